@@ -6,6 +6,11 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Benchmarks
+//!
+//! The transform benchmarks at the end run on the rayon thread pool with the `rayon` feature. The
+//! scaling with the number of threads is measured by comparing
+//! `RAYON_NUM_THREADS=1 cargo bench --features rayon transform_` with runs using more threads
+//! and with a run without the feature.
 
 #![feature(test)]
 
@@ -14,7 +19,14 @@ extern crate test;
 
 #[cfg(test)]
 mod tests {
-    use sphrs::*;
+    use sphrs::basis::{Backend, FixedHarmonicsSet};
+    use sphrs::grid::{self, Healpix};
+    use sphrs::prelude::*;
+    use sphrs::rotation::{rotate, Rotation};
+    use sphrs::sampling::fibonacci;
+    use sphrs::sht::{forward, inverse, Sampling};
+    use sphrs::transform::project;
+    use sphrs::CartesianCoordinates;
     use std::f32::consts::PI as PI32;
     use std::f64::consts::PI;
     use test::{black_box, Bencher};
@@ -271,4 +283,267 @@ mod tests {
             black_box(sh.eval(&p));
         });
     }
+
+    #[bench]
+    fn eval_array_degree_04(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(4, RealSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 2.0, 0.0);
+        b.iter(|| {
+            black_box(sh.eval_array::<_, 25>(&p).unwrap());
+        });
+    }
+
+    #[bench]
+    fn eval_batch_degree_08(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(8, RealSH::Spherical);
+        let points = fibonacci::<f64>(1024, 0);
+        b.iter(|| {
+            black_box(sh.eval_batch(&points));
+        });
+    }
+
+    #[bench]
+    fn eval_simd_8_lanes_degree_08(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(8, RealSH::Spherical);
+        let points: [Coordinates<f32>; 8] =
+            std::array::from_fn(|k| Coordinates::spherical(1.0, 0.3 * k as f32, 0.7 * k as f32));
+        b.iter(|| {
+            black_box(sh.eval_simd(&points));
+        });
+    }
+
+    #[bench]
+    fn eval_scalar_8_points_degree_08(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(8, RealSH::Spherical);
+        let points: [Coordinates<f32>; 8] =
+            std::array::from_fn(|k| Coordinates::spherical(1.0, 0.3 * k as f32, 0.7 * k as f32));
+        b.iter(|| {
+            for p in &points {
+                black_box(sh.eval(p));
+            }
+        });
+    }
+
+    #[bench]
+    fn eval_simd_8_lanes_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let points: [Coordinates<f32>; 8] =
+            std::array::from_fn(|k| Coordinates::spherical(1.0, 0.3 * k as f32, 0.7 * k as f32));
+        b.iter(|| {
+            black_box(sh.eval_simd(&points));
+        });
+    }
+
+    #[bench]
+    fn eval_scalar_8_points_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let points: [Coordinates<f32>; 8] =
+            std::array::from_fn(|k| Coordinates::spherical(1.0, 0.3 * k as f32, 0.7 * k as f32));
+        b.iter(|| {
+            for p in &points {
+                black_box(sh.eval(p));
+            }
+        });
+    }
+
+    #[bench]
+    fn eval_ring_256_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let phis: Vec<f64> = (0..256).map(|k| 2.0 * PI * k as f64 / 256.0).collect();
+        b.iter(|| {
+            black_box(sh.eval_ring(1.0, 0.9, &phis));
+        });
+    }
+
+    #[bench]
+    fn eval_batch_ring_256_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let points: Vec<_> = (0..256)
+            .map(|k| Coordinates::spherical(1.0, 0.9, 2.0 * PI * k as f64 / 256.0))
+            .collect();
+        b.iter(|| {
+            black_box(sh.eval_batch(&points));
+        });
+    }
+
+    #[bench]
+    fn eval_even_degrees_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::even_degrees(30, RealSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_real_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_tabulated_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::builder()
+            .degree(30)
+            .sh_type(RealSH::Spherical)
+            .backend(Backend::Tabulated)
+            .build()
+            .unwrap();
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_fixed_degree_2_f32(b: &mut Bencher) {
+        let sh = FixedHarmonicsSet::<f32, 2, 9>::new(RealSH::Spherical);
+        let p = Coordinates::cartesian(0.3f32, -0.5, 0.8);
+        b.iter(|| {
+            black_box(sh.eval(black_box(&p)));
+        });
+    }
+
+    #[bench]
+    fn eval_cartesian_degree_4_f32(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(4, RealSH::Spherical);
+        b.iter(|| {
+            let p = CartesianCoordinates::new(black_box(0.3f32), -0.5, 0.8);
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_coordinates_degree_4_f32(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(4, RealSH::Spherical);
+        b.iter(|| {
+            let p = Coordinates::cartesian(black_box(0.3f32), -0.5, 0.8);
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_degree_2_f32(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(2, RealSH::Spherical);
+        let p = Coordinates::cartesian(0.3f32, -0.5, 0.8);
+        b.iter(|| {
+            black_box(sh.eval(black_box(&p)));
+        });
+    }
+
+    #[bench]
+    fn eval_complex_degree_8(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(8, ComplexSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_complex_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, ComplexSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_grad_degree_30(b: &mut Bencher) {
+        let sh = HarmonicsSet::new(30, RealSH::Spherical);
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            black_box(sh.eval_grad(&p));
+        });
+    }
+
+    #[bench]
+    fn eval_grad_single_degree_30(b: &mut Bencher) {
+        let p = Coordinates::spherical(1.0, PI / 3.0, 0.7);
+        b.iter(|| {
+            for l in 0..=30 {
+                for m in -l..=l {
+                    black_box(RealSH::Spherical.eval_grad(l, m, &p));
+                }
+            }
+        });
+    }
+
+    #[bench]
+    fn eval_real_degree_100(b: &mut Bencher) {
+        let sh_type = RealSH::Spherical;
+        let degree = 100;
+        let sh = HarmonicsSet::new(degree, sh_type);
+        let p = Coordinates::spherical(1.0, PI / 2.0, 0.0);
+        b.iter(|| {
+            black_box(sh.eval(&p));
+        });
+    }
+
+    fn transform_coefficients(degree: usize) -> SHCoefficients<f64> {
+        let n = (degree + 1) * (degree + 1);
+        SHCoefficients::from_vec(degree, (0..n).map(|i| (i as f64 * 0.37).sin()).collect())
+    }
+
+    #[bench]
+    fn transform_sht_inverse_bandlimit_128(b: &mut Bencher) {
+        let coeffs = transform_coefficients(127);
+        b.iter(|| {
+            black_box(inverse(Sampling::GaussLegendre, &coeffs));
+        });
+    }
+
+    #[bench]
+    fn transform_sht_forward_bandlimit_128(b: &mut Bencher) {
+        let values = inverse(Sampling::GaussLegendre, &transform_coefficients(127));
+        b.iter(|| {
+            black_box(forward(Sampling::GaussLegendre, 128, &values).unwrap());
+        });
+    }
+
+    #[bench]
+    fn transform_grid_analyze_healpix_8(b: &mut Bencher) {
+        let grid = Healpix::new(8).unwrap();
+        let values = grid::synthesize(&grid, &transform_coefficients(16));
+        b.iter(|| {
+            black_box(grid::analyze(&grid, 16, &values).unwrap());
+        });
+    }
+
+    #[bench]
+    fn transform_project_points(b: &mut Bencher) {
+        let directions = fibonacci::<f64>(4096, 0);
+        b.iter(|| {
+            black_box(project(8, &directions, |p| p.x() * p.y() + p.z()));
+        });
+    }
+
+    #[bench]
+    fn rotate_ivanic_ruedenberg_degree_16(b: &mut Bencher) {
+        let coeffs = transform_coefficients(16);
+        let rotation = Rotation::from_euler(0.7, 1.2, -0.4);
+        b.iter(|| {
+            black_box(coeffs.rotate(&rotation));
+        });
+    }
+
+    #[bench]
+    fn rotate_wigner_degree_16(b: &mut Bencher) {
+        let coeffs = transform_coefficients(16);
+        b.iter(|| {
+            black_box(rotate(&coeffs, 0.7, 1.2, -0.4));
+        });
+    }
+
+    #[bench]
+    fn rotate_z_degree_16(b: &mut Bencher) {
+        let mut coeffs = transform_coefficients(16);
+        b.iter(|| {
+            coeffs.rotate_z_in_place(black_box(0.7));
+        });
+    }
 }
@@ -0,0 +1,252 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Generates the closed-form expressions of the real spherical harmonics
+//!
+//! The harmonics up to degree `SPHRS_HARDCODED_DEGREE` (default 8, at least 3) are written to
+//! `$OUT_DIR/hardcoded.rs` as polynomials in the components of the unit vector, which is included
+//! by `src/basis/hardcoded.rs`. For m >= 0 (m < 0) the real harmonic of degree l and order m is
+//!
+//! Y_lm = P_l^|m|(z) A_|m|(x, y) (P_l^|m|(z) B_|m|(x, y)),
+//!
+//! where P_l^m is a polynomial in z of degree l - m with all normalization factors folded into its
+//! coefficients and A_m + i B_m = (x + i y)^m. Both are evaluated with unrolled recurrences,
+//! which are stable, unlike sums of monomials.
+//!
+//! The unrolled evaluation of these degrees by `HarmonicsSet` is written to
+//! `$OUT_DIR/unrolled_set.rs`, which is included by `src/basis/harmonicsset.rs`, and the one by
+//! `FixedHarmonicsSet` to `$OUT_DIR/unrolled_fixed.rs`, included by `src/basis/fixed.rs`.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Default maximum degree of the closed-form expressions
+const DEFAULT_DEGREE: u32 = 8;
+
+/// Largest supported degree, beyond which the expressions are neither shorter nor more accurate
+/// than the recurrences
+const MAX_DEGREE: u32 = 20;
+
+fn factorial(n: u32) -> f64 {
+    (1..=n).map(f64::from).product()
+}
+
+/// Function name of the harmonic (l, m), e.g. `sh3n2` for (3, -2)
+fn name(l: u32, m: i64) -> String {
+    match m {
+        0 => format!("sh{l}0"),
+        m if m > 0 => format!("sh{l}p{m}"),
+        m => format!("sh{l}n{}", -m),
+    }
+}
+
+/// Closed-form expression of the real harmonic (l, m) in the unit vector components x, y and z
+///
+/// Returns the statements computing intermediate values, the final expression and which of the
+/// components are used.
+fn expression(l: u32, m: i64) -> (Vec<String>, String, [bool; 3]) {
+    let a = m.unsigned_abs() as u32;
+    let n = l - a;
+    let mut used = [false, false, n > 0];
+    let constant = |v: f64| format!("T::from_f64({v:?}).unwrap()");
+
+    // Polynomial in z via the recurrence of the orthonormalized Legendre functions of order a
+    // divided by (1 - z^2)^(a/2), starting from
+    // sqrt((2 a + 1) (2 - delta_a0) / (4 pi (2 a)!)) (2 a - 1)!!.
+    let two = if a == 0 { 1.0 } else { 2.0 };
+    let double_factorial: f64 = (1..=a).map(|k| f64::from(2 * k - 1)).product();
+    let start = (f64::from(2 * a + 1) * two / (4.0 * std::f64::consts::PI) / factorial(2 * a))
+        .sqrt()
+        * double_factorial;
+    let mut statements = Vec::new();
+    let mut z_poly = constant(start);
+    if n >= 1 {
+        z_poly = format!("{} * z", constant(start * f64::from(2 * a + 3).sqrt()));
+    }
+    for i in 2..=n {
+        let k = f64::from(a + i);
+        let af = f64::from(a);
+        let c_a = ((4.0 * k * k - 1.0) / (k * k - af * af)).sqrt();
+        let c_b = ((2.0 * k + 1.0) * ((k - 1.0) * (k - 1.0) - af * af)
+            / ((2.0 * k - 3.0) * (k * k - af * af)))
+            .sqrt();
+        if i == 2 {
+            statements.push(format!("let p0 = {};", constant(start)));
+        }
+        statements.push(format!("let p{} = {z_poly};", i - 1));
+        z_poly = format!(
+            "{} * z * p{} - {} * p{}",
+            constant(c_a),
+            i - 1,
+            constant(c_b),
+            i - 2
+        );
+    }
+
+    // Real (m >= 0) or imaginary (m < 0) part of (x + i y)^a by repeated multiplication
+    used[0] |= a >= 2 || (a == 1 && m > 0);
+    used[1] |= a >= 2 || (a == 1 && m < 0);
+    let re = |k: u32| {
+        if k == 1 {
+            "x".to_string()
+        } else {
+            format!("c{k}")
+        }
+    };
+    let im = |k: u32| {
+        if k == 1 {
+            "y".to_string()
+        } else {
+            format!("s{k}")
+        }
+    };
+    for k in 2..a {
+        statements.push(format!(
+            "let ({}, {}) = (x * {} - y * {}, x * {} + y * {});",
+            re(k),
+            im(k),
+            re(k - 1),
+            im(k - 1),
+            im(k - 1),
+            re(k - 1)
+        ));
+    }
+    let xy = match a {
+        0 => String::new(),
+        1 if m > 0 => "x".to_string(),
+        1 => "y".to_string(),
+        _ if m > 0 => format!("(x * {} - y * {})", re(a - 1), im(a - 1)),
+        _ => format!("(x * {} + y * {})", im(a - 1), re(a - 1)),
+    };
+
+    let expr = if a == 0 {
+        z_poly
+    } else if n >= 2 {
+        format!("({z_poly}) * {xy}")
+    } else {
+        format!("{z_poly} * {xy}")
+    };
+    (statements, expr, used)
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SPHRS_HARDCODED_DEGREE");
+    let degree = match env::var("SPHRS_HARDCODED_DEGREE") {
+        Ok(v) => v
+            .parse::<u32>()
+            .expect("SPHRS_HARDCODED_DEGREE must be a nonnegative integer"),
+        Err(_) => DEFAULT_DEGREE,
+    };
+    assert!(
+        (3..=MAX_DEGREE).contains(&degree),
+        "SPHRS_HARDCODED_DEGREE must be between 3 and {MAX_DEGREE}, got {degree}"
+    );
+
+    let mut out = String::new();
+    writeln!(out, "/// Highest degree of the closed-form expressions").unwrap();
+    writeln!(out, "#[cfg_attr(feature = \"compact\", allow(dead_code))]").unwrap();
+    writeln!(
+        out,
+        "pub(crate) const CLOSED_FORM_DEGREE: usize = {degree};"
+    )
+    .unwrap();
+    for l in 0..=degree {
+        for m in -(l as i64)..=l as i64 {
+            let (statements, expr, used) = expression(l, m);
+            let vars: Vec<&str> = ["x", "y", "z"]
+                .iter()
+                .zip(used)
+                .map(|(v, u)| if u { *v } else { "_" })
+                .collect();
+            writeln!(out).unwrap();
+            writeln!(out, "/// SH (l={l},m={m})").unwrap();
+            writeln!(out, "#[inline]").unwrap();
+            if l <= 3 {
+                writeln!(out, "pub fn {}<T: SphrsFloat>(", name(l, m)).unwrap();
+            } else {
+                writeln!(out, "#[cfg_attr(feature = \"compact\", allow(dead_code))]").unwrap();
+                writeln!(out, "pub(crate) fn {}<T: SphrsFloat>(", name(l, m)).unwrap();
+            }
+            if used.iter().any(|&u| u) {
+                writeln!(out, "    p: &impl SHCoordinates<T>,\n) -> T {{").unwrap();
+                writeln!(out, "    let ({}) = unit_vector(p);", vars.join(", ")).unwrap();
+            } else {
+                writeln!(out, "    _p: &impl SHCoordinates<T>,\n) -> T {{").unwrap();
+            }
+            for statement in statements {
+                writeln!(out, "    {statement}").unwrap();
+            }
+            writeln!(out, "    {expr}").unwrap();
+            writeln!(out, "}}").unwrap();
+        }
+    }
+
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "/// Closed-form expression of the real SH (l, m), if available"
+    )
+    .unwrap();
+    writeln!(out, "#[cfg(not(feature = \"compact\"))]").unwrap();
+    writeln!(out, "#[inline(always)]").unwrap();
+    writeln!(
+        out,
+        "pub(crate) fn real_sh_closed_form<T: SphrsFloat>(\n    l: i64,\n    m: i64,\n    \
+         p: &impl SHCoordinates<T>,\n) -> Option<T> {{"
+    )
+    .unwrap();
+    writeln!(out, "    let v = match (l, m) {{").unwrap();
+    for l in 0..=degree {
+        for m in -(l as i64)..=l as i64 {
+            writeln!(out, "        ({l}, {m}) => {}(p),", name(l, m)).unwrap();
+        }
+    }
+    writeln!(out, "        _ => return None,").unwrap();
+    writeln!(out, "    }};").unwrap();
+    writeln!(out, "    Some(v)").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("hardcoded.rs");
+    fs::write(path, out).unwrap();
+
+    // Unrolled evaluation of the degrees `self.min_degree..=closed` of a `HarmonicsSet` into `sh`,
+    // included into the body of `HarmonicsSet::eval_hardcoded`
+    let mut out = String::from("{\n");
+    for l in 0..=degree {
+        if l == 0 {
+            writeln!(out, "    if self.min_degree == 0 {{").unwrap();
+        } else {
+            writeln!(out, "    if self.min_degree <= {l} && closed >= {l} {{").unwrap();
+        }
+        for m in -(l as i64)..=l as i64 {
+            writeln!(out, "        sh.push(self.sh.eval({l}, {m}, p));").unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+    }
+    out.push_str("}\n");
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("unrolled_set.rs");
+    fs::write(path, out).unwrap();
+
+    // Unrolled evaluation of the degrees up to `L` of a `FixedHarmonicsSet` into the array `out`,
+    // included into the body of `FixedHarmonicsSet::eval`
+    let mut out = String::from("{\n");
+    writeln!(out, "    out[0] = self.sh.eval(0, 0, p);").unwrap();
+    for l in 1..=degree {
+        writeln!(out, "    if L >= {l} {{").unwrap();
+        for m in -(l as i64)..=l as i64 {
+            let i = (l * l) as i64 + l as i64 + m;
+            writeln!(out, "        out[{i}] = self.sh.eval({l}, {m}, p);").unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+    }
+    out.push_str("}\n");
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("unrolled_fixed.rs");
+    fs::write(path, out).unwrap();
+}
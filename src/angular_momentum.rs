@@ -0,0 +1,200 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Orbital angular momentum operators in coefficient space
+//!
+//! The operators act on the coefficients c_lm of a function f = sum_lm c_lm Y_lm in the complex
+//! spherical harmonics [`ComplexSH::Spherical`](`crate::basis::ComplexSH::Spherical`) and return
+//! the coefficients of the function L f, in units of hbar. With the Condon-Shortley phase of the
+//! harmonics, the ladder operators act as
+//!
+//! L_z Y_lm = m Y_lm, L_+- Y_lm = sqrt((l -+ m) (l +- m + 1)) Y_l,m+-1 and
+//! L^2 Y_lm = l (l + 1) Y_lm.
+//!
+//! All operators conserve the degree and are sparse: every output coefficient depends on at most
+//! two input coefficients of the same degree.
+//!
+//! # Example
+//!
+//! ```
+//! use num_complex::Complex;
+//! use sphrs::angular_momentum::{l_minus, l_plus, l_z};
+//! use sphrs::SHCoefficients;
+//!
+//! // Commutator [L_+, L_-] = 2 L_z
+//! let data = [(0.5f64, 0.0), (1.0, 2.0), (0.0, 1.0), (-1.0, 0.5)];
+//! let c = SHCoefficients::from_vec(1, data.iter().map(|&(re, im)| Complex::new(re, im)).collect());
+//! let a = l_plus(&l_minus(&c));
+//! let b = l_minus(&l_plus(&c));
+//! let z = l_z(&c);
+//! for i in 0..4 {
+//!     assert!((a.as_slice()[i] - b.as_slice()[i] - z.as_slice()[i] * 2.0).norm() < 1e-14);
+//! }
+//! ```
+
+use num_complex::Complex;
+
+use crate::coefficients::sh_index;
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Apply the operator that maps the coefficient of (l, m) to `f(l, m)` times the coefficient of
+/// (l, m + `shift`)
+fn apply<T: SphrsFloat>(
+    coeffs: &SHCoefficients<Complex<T>>,
+    shift: i64,
+    f: impl Fn(i64, i64) -> T,
+) -> SHCoefficients<Complex<T>> {
+    let c = coeffs.as_slice();
+    let mut out = SHCoefficients::zeros(coeffs.degree());
+    for l in 0..=coeffs.degree() as i64 {
+        for m in -l..=l {
+            let source = m + shift;
+            if source.abs() <= l {
+                out.as_mut_slice()[sh_index(l, m)] = c[sh_index(l, source)] * f(l, m);
+            }
+        }
+    }
+    out
+}
+
+/// sqrt((l - m) (l + m + 1)), the factor of the raising operator L_+ Y_lm
+#[inline(always)]
+fn raising_factor<T: SphrsFloat>(l: i64, m: i64) -> T {
+    T::from_i64((l - m) * (l + m + 1)).unwrap().sqrt()
+}
+
+/// L_z = -i d/dphi
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::angular_momentum::l_z;
+/// use sphrs::SHCoefficients;
+///
+/// let c = SHCoefficients::from_vec(1, vec![Complex::new(1.0f64, 0.0); 4]);
+/// let z = l_z(&c);
+/// assert_eq!(z.get(1, -1), Some(&Complex::new(-1.0, 0.0)));
+/// assert_eq!(z.get(1, 0), Some(&Complex::new(0.0, 0.0)));
+/// ```
+pub fn l_z<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    apply(coeffs, 0, |_, m| T::from_i64(m).unwrap())
+}
+
+/// Raising operator L_+ = L_x + i L_y
+pub fn l_plus<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    // (L_+ c)_lm = sqrt((l - m + 1) (l + m)) c_l,m-1
+    apply(coeffs, -1, |l, m| raising_factor(l, m - 1))
+}
+
+/// Lowering operator L_- = L_x - i L_y
+pub fn l_minus<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    // (L_- c)_lm = sqrt((l + m + 1) (l - m)) c_l,m+1
+    apply(coeffs, 1, raising_factor)
+}
+
+/// L_x = (L_+ + L_-) / 2
+pub fn l_x<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    let half = T::from_f64(0.5).unwrap();
+    let mut out = l_plus(coeffs);
+    for (o, m) in out
+        .as_mut_slice()
+        .iter_mut()
+        .zip(l_minus(coeffs).as_slice())
+    {
+        *o = (*o + m) * half;
+    }
+    out
+}
+
+/// L_y = (L_+ - L_-) / (2 i)
+pub fn l_y<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    let factor = Complex::new(T::zero(), -T::from_f64(0.5).unwrap());
+    let mut out = l_plus(coeffs);
+    for (o, m) in out
+        .as_mut_slice()
+        .iter_mut()
+        .zip(l_minus(coeffs).as_slice())
+    {
+        *o = (*o - m) * factor;
+    }
+    out
+}
+
+/// L^2 = L_x^2 + L_y^2 + L_z^2, which is diagonal with the eigenvalues l (l + 1)
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::angular_momentum::l_squared;
+/// use sphrs::SHCoefficients;
+///
+/// let c = SHCoefficients::from_vec(1, vec![Complex::new(1.0f64, 0.0); 4]);
+/// assert_eq!(l_squared(&c).get(1, 1), Some(&Complex::new(2.0, 0.0)));
+/// ```
+pub fn l_squared<T: SphrsFloat>(coeffs: &SHCoefficients<Complex<T>>) -> SHCoefficients<Complex<T>> {
+    apply(coeffs, 0, |l, _| T::from_i64(l * (l + 1)).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, HarmonicsSet, SHGrad};
+    use crate::Coordinates;
+
+    fn test_coefficients(degree: usize) -> SHCoefficients<Complex<f64>> {
+        let n = (degree + 1) * (degree + 1);
+        SHCoefficients::from_vec(
+            degree,
+            (0..n)
+                .map(|i| Complex::new((i as f64 * 0.37).sin(), (i as f64 * 1.3).cos()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn differential_operators() {
+        let degree = 6;
+        let c = test_coefficients(degree);
+        let (theta, phi) = (0.9f64, -1.4);
+        let p = Coordinates::spherical(1.0, theta, phi);
+        let sh = HarmonicsSet::new(degree, ComplexSH::Spherical);
+        let eval = |c: &SHCoefficients<Complex<f64>>| -> Complex<f64> {
+            sh.eval_sum(&p, c.as_slice()).unwrap()
+        };
+        let (mut d_theta, mut d_phi) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+        for l in 0..=degree as i64 {
+            for m in -l..=l {
+                let (_, dt, dp) = ComplexSH::Spherical.eval_grad(l, m, &p);
+                d_theta += c.get(l, m).unwrap() * dt;
+                d_phi += c.get(l, m).unwrap() * dp;
+            }
+        }
+        let i = Complex::new(0.0, 1.0);
+        let cot = theta.cos() / theta.sin();
+        // L_z = -i d/dphi, L_+- = e^{+-i phi} (+-d/dtheta + i cot(theta) d/dphi)
+        assert!((eval(&l_z(&c)) + i * d_phi).norm() < 1e-12);
+        let expected = Complex::from_polar(1.0, phi) * (d_theta + i * cot * d_phi);
+        assert!((eval(&l_plus(&c)) - expected).norm() < 1e-12);
+        let expected = Complex::from_polar(1.0, -phi) * (-d_theta + i * cot * d_phi);
+        assert!((eval(&l_minus(&c)) - expected).norm() < 1e-12);
+    }
+
+    #[test]
+    fn l_squared_from_components() {
+        let c = test_coefficients(5);
+        let x = l_x(&l_x(&c));
+        let y = l_y(&l_y(&c));
+        let z = l_z(&l_z(&c));
+        let l2 = l_squared(&c);
+        for k in 0..c.len() {
+            let sum = x.as_slice()[k] + y.as_slice()[k] + z.as_slice()[k];
+            assert!((sum - l2.as_slice()[k]).norm() < 1e-12, "{k}");
+        }
+    }
+}
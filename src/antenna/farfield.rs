@@ -0,0 +1,250 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use num_complex::Complex;
+
+use crate::basis::{VectorHarmonicsSet, VectorSHCoefficients};
+use crate::sampling::fibonacci;
+use crate::{Coordinates, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Project a sampled far-field pattern onto vector spherical harmonics up to degree `degree`
+///
+/// `e_theta` and `e_phi` are the components of the electric far field along `directions`,
+/// `weights` are the quadrature weights of the directions (summing to 4 pi, e.g. 4 pi / N for
+/// quasi-Monte Carlo directions from [`sampling`](`crate::sampling`)). Returns
+/// [`SphrsError::InvalidInput`] if the lengths of the inputs differ.
+pub fn project_far_field<T, C>(
+    degree: usize,
+    directions: &[C],
+    weights: &[T],
+    e_theta: &[Complex<T>],
+    e_phi: &[Complex<T>],
+) -> Result<VectorSHCoefficients<T>, SphrsError>
+where
+    T: SphrsFloat,
+    C: SHCoordinates<T>,
+{
+    let n = directions.len();
+    if weights.len() != n || e_theta.len() != n || e_phi.len() != n {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} directions, {} weights, {} theta and {} phi components",
+            n,
+            weights.len(),
+            e_theta.len(),
+            e_phi.len()
+        )));
+    }
+    let set = VectorHarmonicsSet::new(degree);
+    let mut coeffs = VectorSHCoefficients::zeros(degree);
+    for (k, p) in directions.iter().enumerate() {
+        let (psi, phi) = set.eval(p);
+        let (et, ep) = (e_theta[k] * weights[k], e_phi[k] * weights[k]);
+        for (c, y) in coeffs.psi_mut().iter_mut().zip(psi.iter()) {
+            *c = *c + y.theta.conj() * et + y.phi.conj() * ep;
+        }
+        for (c, y) in coeffs.phi_mut().iter_mut().zip(phi.iter()) {
+            *c = *c + y.theta.conj() * et + y.phi.conj() * ep;
+        }
+    }
+    Ok(coeffs)
+}
+
+/// Squared magnitude of the far field at `p`
+fn intensity<T: SphrsFloat>(coeffs: &VectorSHCoefficients<T>, p: &impl SHCoordinates<T>) -> T {
+    let e = coeffs.eval(p);
+    e.theta.norm_sqr() + e.phi.norm_sqr()
+}
+
+/// Directivity (linear scale) of the pattern `coeffs` in direction `p`
+///
+/// The directivity is the radiation intensity in direction `p` relative to the mean radiation
+/// intensity over all directions.
+pub fn directivity<T: SphrsFloat>(
+    coeffs: &VectorSHCoefficients<T>,
+    p: &impl SHCoordinates<T>,
+) -> T {
+    T::from_f64(4.0).unwrap() * T::PI() * intensity(coeffs, p) / coeffs.power()
+}
+
+/// Point on the unit sphere reached by moving from `p` by the angle `t` along the great circle
+/// with tangent direction `psi` (measured from the theta unit vector towards the phi unit vector)
+fn walk<T: SphrsFloat>(p: &Coordinates<T>, psi: T, t: T) -> Coordinates<T> {
+    let (st, ct) = p.theta().sin_cos();
+    let (sp, cp) = p.phi().sin_cos();
+    let d = [st * cp, st * sp, ct];
+    let e_theta = [ct * cp, ct * sp, -st];
+    let e_phi = [-sp, cp, T::zero()];
+    let (s, c) = psi.sin_cos();
+    let (sw, cw) = t.sin_cos();
+    let v: Vec<T> = (0..3)
+        .map(|i| cw * d[i] + sw * (c * e_theta[i] + s * e_phi[i]))
+        .collect();
+    Coordinates::cartesian(v[0], v[1], v[2])
+}
+
+/// Direction and value of the maximum directivity of the pattern `coeffs`
+///
+/// The maximum is located on a dense set of directions first and then refined locally.
+pub fn max_directivity<T: SphrsFloat>(coeffs: &VectorSHCoefficients<T>) -> (Coordinates<T>, T) {
+    let n = 2048.max(64 * (coeffs.degree() + 1) * (coeffs.degree() + 1));
+    let mut best = Coordinates::spherical(T::one(), T::zero(), T::zero());
+    let mut best_value = T::neg_infinity();
+    for p in fibonacci::<T>(n, 0) {
+        let v = intensity(coeffs, &p);
+        if v > best_value {
+            best_value = v;
+            best = p;
+        }
+    }
+    // Pattern search along four tangent directions with shrinking step size
+    let mut step = (T::from_f64(4.0).unwrap() * T::PI() / T::from_usize(n).unwrap()).sqrt();
+    let half_pi = T::FRAC_PI_2();
+    let tol = T::epsilon().sqrt() * T::from_f64(1e-2).unwrap();
+    while step > tol {
+        let mut improved = false;
+        for k in 0..4 {
+            let q = walk(&best, half_pi * T::from_usize(k).unwrap(), step);
+            let v = intensity(coeffs, &q);
+            if v > best_value {
+                best_value = v;
+                best = q;
+                improved = true;
+                break;
+            }
+        }
+        if !improved {
+            step = step / T::from_f64(2.0).unwrap();
+        }
+    }
+    let d = T::from_f64(4.0).unwrap() * T::PI() * best_value / coeffs.power();
+    (best, d)
+}
+
+/// Beamwidth (in radians) of the pattern `coeffs` around the direction `p`
+///
+/// The beamwidth is measured in the plane cut through `p` whose tangent at `p` encloses the angle
+/// `cut` with the theta unit vector (towards the phi unit vector), i.e. `cut = 0` is the cut with
+/// constant phi and `cut = pi / 2` the cut perpendicular to it. It is the angle between the two
+/// closest points within the cut at which the radiation intensity drops to `level` times the
+/// intensity at `p` (`level = 0.5` yields the half-power beamwidth). Returns `None` if the
+/// intensity does not drop to `level` on one of the sides.
+pub fn beamwidth<T: SphrsFloat>(
+    coeffs: &VectorSHCoefficients<T>,
+    p: &impl SHCoordinates<T>,
+    cut: T,
+    level: T,
+) -> Option<T> {
+    let p = Coordinates::spherical(T::one(), p.theta(), p.phi());
+    let target = level * intensity(coeffs, &p);
+    let f = |t: T| intensity(coeffs, &walk(&p, cut, t)) - target;
+    let step = T::PI() / T::from_usize(8 * (coeffs.degree() + 1)).unwrap();
+    let mut total = T::zero();
+    for side in [T::one(), -T::one()] {
+        let mut lo = T::zero();
+        let mut hi = step;
+        while f(side * hi) > T::zero() {
+            lo = hi;
+            hi = hi + step;
+            if hi > T::PI() {
+                return None;
+            }
+        }
+        for _ in 0..60 {
+            let mid = (lo + hi) / T::from_f64(2.0).unwrap();
+            if f(side * mid) > T::zero() {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        total = total + (lo + hi) / T::from_f64(2.0).unwrap();
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quadrature::gauss_legendre;
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    /// Gauss-Legendre product grid with weights
+    fn grid(n: usize) -> (Vec<Coordinates<f64>>, Vec<f64>) {
+        let (x, w) = gauss_legendre::<f64>(n);
+        let mut points = Vec::new();
+        let mut weights = Vec::new();
+        for (&x, &w) in x.iter().zip(w.iter()) {
+            for k in 0..2 * n {
+                points.push(Coordinates::spherical(
+                    1.0,
+                    x.acos(),
+                    PI * k as f64 / n as f64,
+                ));
+                weights.push(w * PI / n as f64);
+            }
+        }
+        (points, weights)
+    }
+
+    fn project(
+        degree: usize,
+        field: impl Fn(&Coordinates<f64>) -> (Complex<f64>, Complex<f64>),
+    ) -> VectorSHCoefficients<f64> {
+        let (points, weights) = grid(16);
+        let (e_theta, e_phi): (Vec<_>, Vec<_>) = points.iter().map(field).unzip();
+        project_far_field(degree, &points, &weights, &e_theta, &e_phi).unwrap()
+    }
+
+    #[test]
+    fn hertzian_dipole() {
+        let coeffs = project(4, |p| {
+            (Complex::new(p.theta().sin(), 0.0), Complex::new(0.0, 0.0))
+        });
+        assert_relative_eq!(coeffs.power(), 8.0 * PI / 3.0, epsilon = 1e-12);
+        let (p, d) = max_directivity(&coeffs);
+        assert_relative_eq!(d, 1.5, epsilon = 1e-10);
+        assert_relative_eq!(p.theta(), PI / 2.0, epsilon = 1e-4);
+        let hpbw = beamwidth(&coeffs, &p, 0.0, 0.5).unwrap();
+        assert_relative_eq!(hpbw, PI / 2.0, epsilon = 1e-8);
+        // Omnidirectional in azimuth
+        assert!(beamwidth(&coeffs, &p, PI / 2.0, 0.5).is_none());
+    }
+
+    #[test]
+    fn reconstructs_field() {
+        // Circularly polarized field of a tilted dipole pair (band limited)
+        let field = |p: &Coordinates<f64>| {
+            let (t, f) = (p.theta(), p.phi());
+            (
+                Complex::new(t.cos() * f.cos(), 0.3 * t.sin()),
+                Complex::new(-f.sin(), 0.0),
+            )
+        };
+        let coeffs = project(6, field);
+        let p = Coordinates::spherical(1.0, 0.8, 2.2);
+        let e = coeffs.eval(&p);
+        let (et, ep) = field(&p);
+        assert_relative_eq!(e.theta, et, epsilon = 1e-10);
+        assert_relative_eq!(e.phi, ep, epsilon = 1e-10);
+        assert_relative_eq!(
+            directivity(&coeffs, &p),
+            4.0 * PI * (et.norm_sqr() + ep.norm_sqr()) / coeffs.power(),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        let (points, weights) = grid(4);
+        let e = vec![Complex::new(1.0, 0.0); points.len() - 1];
+        assert!(matches!(
+            project_far_field(2, &points, &weights, &e, &e),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Antenna radiation patterns
+//!
+//! Far-field patterns are tangential vector fields on the sphere. They are expanded into the
+//! vector spherical harmonics of [`VectorHarmonicsSet`](`crate::basis::VectorHarmonicsSet`),
+//! from which derived quantities such as the directivity and beamwidths are computed.
+//! Scalar patterns (e.g. power patterns) can be projected with
+//! [`transform::project`](`crate::transform::project`).
+//!
+//! Antenna measurements commonly describe the radiated field with the Q-coefficients of a
+//! spherical wave expansion ([`SWECoefficients`]), which convert to and from the vector spherical
+//! harmonics representation and can be read from TICRA `.sph` files. The coefficients of an
+//! antenna measured on a sphere in its near field are obtained with [`SphericalNearField`], which
+//! yields the far-field pattern without a far-field range.
+
+mod farfield;
+mod nearfield;
+mod swe;
+
+pub use farfield::{beamwidth, directivity, max_directivity, project_far_field};
+pub use nearfield::SphericalNearField;
+pub use swe::{read_ticra_sph, swe_index, SWECoefficients};
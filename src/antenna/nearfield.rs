@@ -0,0 +1,296 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use num_complex::Complex;
+
+use crate::antenna::{project_far_field, SWECoefficients};
+use crate::basis::{vsh_index, Tangential, VectorSHCoefficients};
+use crate::{SHCoordinates, SphrsError, SphrsFloat};
+
+/// Spherical Hankel functions of the first kind h_0(x), ..., h_nmax(x) for x > 0
+///
+/// Computed with the upward recurrence, which is stable for h_n because the growing Neumann part
+/// dominates for n > x.
+pub(crate) fn spherical_hankel1<T: SphrsFloat>(nmax: usize, x: T) -> Vec<Complex<T>> {
+    let i = Complex::new(T::zero(), T::one());
+    let e = (i * x).exp() / x;
+    let mut h = Vec::with_capacity(nmax + 1);
+    // h_0 = -i e^{ix} / x
+    h.push(-i * e);
+    if nmax >= 1 {
+        // h_1 = -(1 + i / x) e^{ix} / x
+        h.push(-(Complex::new(T::one(), T::one() / x)) * e);
+    }
+    for n in 1..nmax {
+        let c = T::from_usize(2 * n + 1).unwrap() / x;
+        h.push(h[n] * c - h[n - 1]);
+    }
+    h
+}
+
+/// Spherical near-field to far-field transformation
+///
+/// Measurements of the tangential electric field on a sphere of radius `radius` enclosing the
+/// antenna determine the Q-coefficients of its spherical wave expansion (see
+/// [`SWECoefficients`]), from which the far field follows with
+/// [`SWECoefficients::far_field`]. Outside the minimum sphere, the field is
+/// E = k / sqrt(eta) sum_smn Q_smn F_smn(r, theta, phi) with Hansen's outgoing spherical vector
+/// wave functions F_smn, whose tangential parts on the sphere are
+///
+/// * F_1mn = -h_n(kr) Phi_nm and
+/// * F_2mn = 1 / (kr) d(kr h_n(kr)) / d(kr) Psi_nm,
+///
+/// with the spherical Hankel functions of the first kind h_n and the vector spherical harmonics
+/// of [`VectorHarmonicsSet`](`crate::basis::VectorHarmonicsSet`). The measurement is assumed to
+/// be probe corrected, i.e. to be samples of the electric field itself.
+///
+/// The expansion of an antenna with minimum sphere radius r_0 is truncated at degree
+/// nmax = k r_0 + 10, which must be resolved by the sampling.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::antenna::{SWECoefficients, SphericalNearField};
+/// use sphrs::sampling::fibonacci;
+/// use sphrs::Coordinates;
+/// use num_complex::Complex;
+///
+/// let nf = SphericalNearField::new(2, 2.0f64, 1.5);
+/// let mut q = SWECoefficients::zeros(2);
+/// *q.get_mut(2, 0, 1).unwrap() = Complex::new(1.0, 0.0);
+///
+/// // Samples of the near field radiated by the known expansion
+/// let directions: Vec<Coordinates<f64>> = fibonacci(2000, 0);
+/// let weights = vec![4.0 * std::f64::consts::PI / 2000.0; 2000];
+/// let field: Vec<_> = directions.iter().map(|p| nf.eval(&q, p)).collect();
+/// let e_theta: Vec<_> = field.iter().map(|e| e.theta).collect();
+/// let e_phi: Vec<_> = field.iter().map(|e| e.phi).collect();
+///
+/// let fitted = nf.fit(&directions, &weights, &e_theta, &e_phi).unwrap();
+/// assert!((fitted.get(2, 0, 1).unwrap() - 1.0).norm() < 1e-2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SphericalNearField<T> {
+    /// Maximum degree of the expansion
+    nmax: usize,
+    /// Wavenumber
+    wavenumber: T,
+    /// Radius of the measurement sphere
+    radius: T,
+    /// Intrinsic admittance of the medium
+    admittance: T,
+}
+
+impl<T: SphrsFloat> SphericalNearField<T> {
+    /// Create a transformation up to degree `nmax` for measurements at wavenumber `wavenumber` on
+    /// a sphere of radius `radius`
+    ///
+    /// The admittance defaults to the one of free space.
+    pub fn new(nmax: usize, wavenumber: T, radius: T) -> Self {
+        SphericalNearField {
+            nmax,
+            wavenumber,
+            radius,
+            admittance: T::one() / T::from_f64(376.730313412).unwrap(),
+        }
+    }
+
+    /// Intrinsic admittance eta of the medium
+    #[must_use]
+    pub fn admittance(mut self, admittance: T) -> Self {
+        self.admittance = admittance;
+        self
+    }
+
+    /// Returns [`SphrsError::InvalidConfiguration`] unless kr and the admittance are positive
+    fn validate(&self) -> Result<(), SphrsError> {
+        let x = self.wavenumber * self.radius;
+        if x.is_nan() || x <= T::zero() || self.admittance.is_nan() || self.admittance <= T::zero()
+        {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "wavenumber, radius and admittance must be positive, got kr = {:?} and eta = {:?}",
+                x, self.admittance
+            )));
+        }
+        Ok(())
+    }
+
+    /// Radial factors of F_1mn and F_2mn for n = 1, ..., nmax, including k / sqrt(eta)
+    fn radial(&self) -> Vec<(Complex<T>, Complex<T>)> {
+        let x = self.wavenumber * self.radius;
+        let c = self.wavenumber / self.admittance.sqrt();
+        let h = spherical_hankel1(self.nmax, x);
+        (1..=self.nmax)
+            .map(|n| {
+                let r2 = h[n - 1] - h[n] * (T::from_usize(n).unwrap() / x);
+                (-h[n] * c, r2 * c)
+            })
+            .collect()
+    }
+
+    /// Fit the Q-coefficients to the tangential electric field sampled on the measurement sphere
+    ///
+    /// `e_theta` and `e_phi` are the field components along `directions`, `weights` are the
+    /// quadrature weights of the directions on the unit sphere as in [`project_far_field`].
+    /// Returns [`SphrsError::InvalidInput`] if the lengths of the inputs differ and
+    /// [`SphrsError::InvalidConfiguration`] if the wavenumber, radius or admittance is not
+    /// positive.
+    pub fn fit<C>(
+        &self,
+        directions: &[C],
+        weights: &[T],
+        e_theta: &[Complex<T>],
+        e_phi: &[Complex<T>],
+    ) -> Result<SWECoefficients<T>, SphrsError>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.validate()?;
+        let radial = self.radial();
+        let vsh = project_far_field(self.nmax, directions, weights, e_theta, e_phi)?;
+        let mut q = SWECoefficients::zeros(self.nmax);
+        for (n, &(r1, r2)) in (1..=self.nmax as i64).zip(radial.iter()) {
+            for m in -n..=n {
+                let j = vsh_index(n, m);
+                *q.get_mut(1, m, n).unwrap() = vsh.phi()[j] / r1;
+                *q.get_mut(2, m, n).unwrap() = vsh.psi()[j] / r2;
+            }
+        }
+        Ok(q)
+    }
+
+    /// Evaluate the tangential electric field of the expansion `q` on the measurement sphere in
+    /// direction `p`
+    ///
+    /// Degrees of `q` beyond `nmax` are ignored. Panics if the wavenumber, radius or admittance
+    /// is not positive.
+    pub fn eval<C>(&self, q: &SWECoefficients<T>, p: &C) -> Tangential<Complex<T>>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.validate().unwrap();
+        let radial = self.radial();
+        let nmax = self.nmax.min(q.nmax());
+        let mut vsh = VectorSHCoefficients::zeros(nmax);
+        for (n, &(r1, r2)) in (1..=nmax as i64).zip(radial.iter()) {
+            for m in -n..=n {
+                let j = vsh_index(n, m);
+                vsh.phi_mut()[j] = *q.get(1, m, n).unwrap() * r1;
+                vsh.psi_mut()[j] = *q.get(2, m, n).unwrap() * r2;
+            }
+        }
+        vsh.eval(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antenna::swe_index;
+    use crate::quadrature::gauss_legendre;
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    /// Gauss-Legendre grid in theta and equiangular grid in phi with weights
+    fn grid(n: usize) -> (Vec<Coordinates<f64>>, Vec<f64>) {
+        let (x, w) = gauss_legendre::<f64>(n);
+        let n_phi = 2 * n;
+        let mut directions = Vec::new();
+        let mut weights = Vec::new();
+        for (&x, &w) in x.iter().zip(w.iter()) {
+            for k in 0..n_phi {
+                let phi = 2.0 * PI * k as f64 / n_phi as f64;
+                directions.push(Coordinates::spherical(1.0, x.acos(), phi));
+                weights.push(w * 2.0 * PI / n_phi as f64);
+            }
+        }
+        (directions, weights)
+    }
+
+    #[test]
+    fn hankel_functions() {
+        for &x in &[0.3f64, 1.0, 4.5, 20.0] {
+            let h = spherical_hankel1(3, x);
+            let (s, c) = x.sin_cos();
+            let j2 = (3.0 / x.powi(3) - 1.0 / x) * s - 3.0 * c / (x * x);
+            let y2 = -(3.0 / x.powi(3) - 1.0 / x) * c - 3.0 * s / (x * x);
+            let j3 = (15.0 / x.powi(4) - 6.0 / (x * x)) * s - (15.0 / x.powi(3) - 1.0 / x) * c;
+            let y3 = -(15.0 / x.powi(4) - 6.0 / (x * x)) * c - (15.0 / x.powi(3) - 1.0 / x) * s;
+            let expected = [
+                Complex::new(s / x, -c / x),
+                Complex::new(j2, y2),
+                Complex::new(j3, y3),
+            ];
+            for (&h, e) in [h[0], h[2], h[3]].iter().zip(expected) {
+                assert!((h - e).norm() < 1e-12 * e.norm());
+            }
+        }
+    }
+
+    #[test]
+    fn hertzian_dipole() {
+        // z-directed Hertzian dipole with current moment I l = 1 in a medium with eta = 1:
+        // E_theta = -i k sin(theta) / (4 pi r) (1 + i / (kr) - 1 / (kr)^2) e^{ikr}
+        let (k, r) = (2.0f64, 0.8);
+        let kr = k * r;
+        let i = Complex::new(0.0, 1.0);
+        let radial = -i * k / (4.0 * PI * r) * (1.0 + i / kr - 1.0 / (kr * kr)) * (i * kr).exp();
+        let (directions, weights) = grid(10);
+        let e_theta: Vec<_> = directions
+            .iter()
+            .map(|p| radial * p.theta().sin())
+            .collect();
+        let e_phi = vec![Complex::new(0.0, 0.0); directions.len()];
+        let nf = SphericalNearField::new(4, k, r).admittance(1.0);
+        let q = nf.fit(&directions, &weights, &e_theta, &e_phi).unwrap();
+        for (j, c) in q.as_slice().iter().enumerate() {
+            if j != swe_index(2, 0, 1) {
+                assert_relative_eq!(c.norm(), 0.0, epsilon = 1e-12);
+            }
+        }
+        for &theta in &[0.3, 1.0, 2.0] {
+            let p = Coordinates::spherical(1.0, theta, 0.4);
+            let far = q.far_field(&p);
+            // E e^{-ikr} kr sqrt(eta) / k = -i k sin(theta) / (4 pi)
+            assert_relative_eq!(
+                far.theta,
+                -i * k * theta.sin() / (4.0 * PI),
+                epsilon = 1e-12
+            );
+            assert_relative_eq!(far.phi.norm(), 0.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let q: Vec<Complex<f64>> = (0..30)
+            .map(|j| Complex::new((j as f64 * 0.7).sin(), (j as f64 * 0.2).cos()))
+            .collect();
+        let q = SWECoefficients::from_vec(3, q);
+        let nf = SphericalNearField::new(3, 3.0, 0.5);
+        let (directions, weights) = grid(8);
+        let field: Vec<_> = directions.iter().map(|p| nf.eval(&q, p)).collect();
+        let e_theta: Vec<_> = field.iter().map(|e| e.theta).collect();
+        let e_phi: Vec<_> = field.iter().map(|e| e.phi).collect();
+        let fitted = nf.fit(&directions, &weights, &e_theta, &e_phi).unwrap();
+        for (a, b) in q.as_slice().iter().zip(fitted.as_slice()) {
+            assert_relative_eq!(a, b, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn invalid_configuration() {
+        let nf = SphericalNearField::new(2, 1.0f64, 0.0);
+        let p = [Coordinates::spherical(1.0, 0.5, 0.5)];
+        let e = [Complex::new(1.0, 0.0)];
+        assert!(matches!(
+            nf.fit(&p, &[1.0], &e, &e),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+    }
+}
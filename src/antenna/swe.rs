@@ -0,0 +1,338 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io::BufRead;
+
+use num_complex::Complex;
+
+use crate::basis::{vsh_index, Tangential, VectorSHCoefficients};
+use crate::{SHCoordinates, SphrsError, SphrsFloat};
+
+/// Index of the Q-coefficient of type `s` (1: TE, 2: TM), order `m` and degree `n` in the
+/// single index ordering of Hansen, j = 2 (n (n + 1) + m - 1) + s, counted from zero
+#[inline(always)]
+pub fn swe_index(s: u8, m: i64, n: i64) -> usize {
+    debug_assert!(s == 1 || s == 2);
+    debug_assert!(n >= 1 && m.abs() <= n);
+    (2 * (n * (n + 1) + m - 1) + s as i64 - 1) as usize
+}
+
+/// Coefficients Q_smn of a spherical wave expansion of the field radiated by an antenna
+///
+/// The coefficients follow the convention of J. E. Hansen, "Spherical Near-Field Antenna
+/// Measurements" (time dependence exp(-i omega t), eta the intrinsic admittance of the medium).
+/// The far field is E = k / sqrt(eta) e^{ikr} / (kr) 1 / sqrt(4 pi) sum_smn Q_smn K_smn(theta, phi),
+/// with the far-field pattern functions K_smn of the TE (s = 1) and TM (s = 2) modes, such that
+/// the radiated power is 1/2 sum |Q_smn|^2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SWECoefficients<T> {
+    /// Maximum degree
+    nmax: usize,
+    /// Coefficients ordered as described in [`swe_index`]
+    q: Vec<Complex<T>>,
+}
+
+impl<T: SphrsFloat> SWECoefficients<T> {
+    /// Create `SWECoefficients` from coefficients ordered as described in [`swe_index`]
+    ///
+    /// Panics if the length of `q` does not equal `2 ((nmax + 1)^2 - 1)`.
+    pub fn from_vec(nmax: usize, q: Vec<Complex<T>>) -> Self {
+        assert_eq!(q.len(), 2 * ((nmax + 1) * (nmax + 1) - 1));
+        SWECoefficients { nmax, q }
+    }
+
+    /// Create `SWECoefficients` up to degree `nmax` with all coefficients set to zero
+    pub fn zeros(nmax: usize) -> Self {
+        let zero = Complex::new(T::zero(), T::zero());
+        SWECoefficients::from_vec(nmax, vec![zero; 2 * ((nmax + 1) * (nmax + 1) - 1)])
+    }
+
+    /// Returns the maximum degree
+    pub fn nmax(&self) -> usize {
+        self.nmax
+    }
+
+    /// Returns the coefficient Q_smn, if present
+    pub fn get(&self, s: u8, m: i64, n: i64) -> Option<&Complex<T>> {
+        if !(s == 1 || s == 2) || n < 1 || n as usize > self.nmax || m.abs() > n {
+            return None;
+        }
+        self.q.get(swe_index(s, m, n))
+    }
+
+    /// Returns a mutable reference to the coefficient Q_smn, if present
+    pub fn get_mut(&mut self, s: u8, m: i64, n: i64) -> Option<&mut Complex<T>> {
+        if !(s == 1 || s == 2) || n < 1 || n as usize > self.nmax || m.abs() > n {
+            return None;
+        }
+        self.q.get_mut(swe_index(s, m, n))
+    }
+
+    /// Returns the coefficients as a slice
+    pub fn as_slice(&self) -> &[Complex<T>] {
+        &self.q
+    }
+
+    /// Returns the radiated power 1/2 sum |Q_smn|^2
+    pub fn power(&self) -> T {
+        self.q.iter().fold(T::zero(), |acc, q| acc + q.norm_sqr()) / T::from_f64(2.0).unwrap()
+    }
+
+    /// Factors f1, f2 with K_1mn = f1 Phi_nm and K_2mn = f2 Psi_nm
+    fn factors(n: i64) -> (Complex<T>, Complex<T>) {
+        let s = (T::from_f64(4.0).unwrap() * T::PI()).sqrt();
+        // (-i)^n
+        let mi_n = match n.rem_euclid(4) {
+            0 => Complex::new(s, T::zero()),
+            1 => Complex::new(T::zero(), -s),
+            2 => Complex::new(-s, T::zero()),
+            _ => Complex::new(T::zero(), s),
+        };
+        let f2 = mi_n;
+        let f1 = -(mi_n * Complex::new(T::zero(), -T::one()));
+        (f1, f2)
+    }
+
+    /// Convert to the coefficients of the far-field pattern sum_smn Q_smn K_smn(theta, phi) with
+    /// respect to the vector spherical harmonics of
+    /// [`VectorHarmonicsSet`](`crate::basis::VectorHarmonicsSet`)
+    pub fn to_vector_sh(&self) -> VectorSHCoefficients<T> {
+        let mut out = VectorSHCoefficients::zeros(self.nmax);
+        for n in 1..=self.nmax as i64 {
+            let (f1, f2) = Self::factors(n);
+            for m in -n..=n {
+                let j = vsh_index(n, m);
+                out.psi_mut()[j] = f2 * self.q[swe_index(2, m, n)];
+                out.phi_mut()[j] = f1 * self.q[swe_index(1, m, n)];
+            }
+        }
+        out
+    }
+
+    /// Evaluate the far-field pattern 1 / sqrt(4 pi) sum_smn Q_smn K_smn in direction `p`
+    ///
+    /// This is the far field E with the factor k / sqrt(eta) e^{ikr} / (kr) removed.
+    pub fn far_field<C>(&self, p: &C) -> Tangential<Complex<T>>
+    where
+        C: SHCoordinates<T>,
+    {
+        let s = T::one() / (T::from_f64(4.0).unwrap() * T::PI()).sqrt();
+        self.to_vector_sh().eval(p) * Complex::new(s, T::zero())
+    }
+
+    /// Create from the vector spherical harmonics coefficients of a far-field pattern, inverse
+    /// of [`to_vector_sh`](`SWECoefficients::to_vector_sh`)
+    pub fn from_vector_sh(coeffs: &VectorSHCoefficients<T>) -> Self {
+        let mut out = SWECoefficients::zeros(coeffs.degree());
+        for n in 1..=coeffs.degree() as i64 {
+            let (f1, f2) = Self::factors(n);
+            for m in -n..=n {
+                let j = vsh_index(n, m);
+                out.q[swe_index(2, m, n)] = coeffs.psi()[j] / f2;
+                out.q[swe_index(1, m, n)] = coeffs.phi()[j] / f1;
+            }
+        }
+        out
+    }
+}
+
+/// Parse a line of floats, accepting Fortran style exponents
+fn parse_floats(line: &str, expected: usize) -> Result<Vec<f64>, SphrsError> {
+    let values = line
+        .split_whitespace()
+        .map(|v| v.replace(['D', 'd'], "E").parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|e| SphrsError::InvalidFormat(format!("invalid number in \"{line}\": {e}")))?;
+    if values.len() < expected {
+        return Err(SphrsError::InvalidFormat(format!(
+            "expected {expected} values in \"{line}\""
+        )));
+    }
+    Ok(values)
+}
+
+/// Read spherical wave coefficients from a TICRA (GRASP) `.sph` file
+///
+/// The file stores the coefficients Q'_smn = conj(Q_s,-m,n) / sqrt(8 pi) for the time dependence
+/// exp(+j omega t) used by TICRA, which are converted to the convention of
+/// [`SWECoefficients`]. Coefficients of orders beyond the MMAX of the file are zero.
+pub fn read_ticra_sph<T: SphrsFloat, R: BufRead>(
+    reader: R,
+) -> Result<SWECoefficients<T>, SphrsError> {
+    let mut lines = reader.lines();
+    let mut next = || -> Result<String, SphrsError> {
+        lines
+            .next()
+            .ok_or_else(|| SphrsError::InvalidFormat("unexpected end of file".to_string()))?
+            .map_err(SphrsError::from)
+    };
+    next()?;
+    next()?;
+    let header = next()?;
+    let sizes = header
+        .split_whitespace()
+        .map(|v| v.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|e| SphrsError::InvalidFormat(format!("invalid header \"{header}\": {e}")))?;
+    if sizes.len() < 4 {
+        return Err(SphrsError::InvalidFormat(format!(
+            "invalid header \"{header}\""
+        )));
+    }
+    let (nmax, mmax) = (sizes[2], sizes[3]);
+    if mmax > nmax {
+        return Err(SphrsError::InvalidFormat(format!(
+            "MMAX {mmax} exceeds NMAX {nmax}"
+        )));
+    }
+    // Text and dummy records
+    for _ in 0..6 {
+        next()?;
+    }
+
+    let scale = T::from_f64((8.0 * std::f64::consts::PI).sqrt()).unwrap();
+    let mut out = SWECoefficients::zeros(nmax);
+    let mut store = |m: i64, n: i64, v: &[f64]| {
+        // Q_smn = sqrt(8 pi) conj(Q'_s,-m,n)
+        let q1 = Complex::new(T::from_f64(v[0]).unwrap(), -T::from_f64(v[1]).unwrap());
+        let q2 = Complex::new(T::from_f64(v[2]).unwrap(), -T::from_f64(v[3]).unwrap());
+        *out.get_mut(1, -m, n).unwrap() = q1 * scale;
+        *out.get_mut(2, -m, n).unwrap() = q2 * scale;
+    };
+    for m in 0..=mmax {
+        let record = next()?;
+        let found = record
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<usize>().ok());
+        if found != Some(m) {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected block of order {m}, found \"{record}\""
+            )));
+        }
+        for n in m.max(1)..=nmax {
+            let (m, n) = (m as i64, n as i64);
+            if m == 0 {
+                store(0, n, &parse_floats(&next()?, 4)?);
+            } else {
+                store(-m, n, &parse_floats(&next()?, 4)?);
+                store(m, n, &parse_floats(&next()?, 4)?);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn indexing() {
+        let mut j = 0;
+        for n in 1..=4i64 {
+            for m in -n..=n {
+                for s in 1..=2u8 {
+                    assert_eq!(swe_index(s, m, n), j);
+                    j += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn far_field_pattern_functions() {
+        // Hansen's K functions of degree 1, written out explicitly
+        let (theta, phi) = (0.7f64, 1.3);
+        let p = Coordinates::spherical(1.0, theta, phi);
+        let i = Complex::new(0.0, 1.0);
+        let e = |m: f64| Complex::new(0.0, m * phi).exp();
+        let s3 = 3f64.sqrt() / 2.0;
+        let cases = [
+            // (s, m, K_theta, K_phi)
+            (
+                2u8,
+                1i64,
+                -e(1.0) * -i * s3 * theta.cos(),
+                -e(1.0) * -i * i * s3,
+            ),
+            (1, -1, -e(-1.0) * -i * s3, -e(-1.0) * -s3 * theta.cos()),
+            (
+                2,
+                0,
+                -i * -(1.5f64).sqrt() * theta.sin(),
+                Complex::new(0.0, 0.0),
+            ),
+        ];
+        for (s, m, k_theta, k_phi) in cases {
+            let mut q = SWECoefficients::<f64>::zeros(1);
+            *q.get_mut(s, m, 1).unwrap() = Complex::new(1.0, 0.0);
+            let field = q.to_vector_sh().eval(&p);
+            assert_relative_eq!(field.theta, k_theta, epsilon = 1e-14);
+            assert_relative_eq!(field.phi, k_phi, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn vector_sh_roundtrip_and_power() {
+        let q: Vec<Complex<f64>> = (0..16)
+            .map(|j| Complex::new((j as f64).sin(), (j as f64 * 0.3).cos()))
+            .collect();
+        let q = SWECoefficients::from_vec(2, q);
+        let vsh = q.to_vector_sh();
+        // K_smn have norm sqrt(4 pi)
+        assert_relative_eq!(
+            vsh.power(),
+            8.0 * std::f64::consts::PI * q.power(),
+            epsilon = 1e-12
+        );
+        let back = SWECoefficients::from_vector_sh(&vsh);
+        for (a, b) in q.as_slice().iter().zip(back.as_slice()) {
+            assert_relative_eq!(a, b, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn ticra_file() {
+        let file = "\
+Created by test
+Dummy antenna
+    18    36     2     1
+Dummy text
+   0.000000E+00   0.000000E+00   0.000000E+00   0.000000E+00   0.000000E+00   0.000000E+00   0.000000E+00   0.000000E+00
+Dummy text
+Dummy text
+Dummy text
+Dummy text
+     0   0.100000E+01
+   0.1000000E+01   0.2000000E+01   0.3000000E+01   0.4000000E+01
+   0.5000000E+01   0.6000000E+01   0.7000000E+01   0.8000000E+01
+     1   0.100000E+01
+   1.0D+00   0.0D+00   0.0D+00   2.0D+00
+   0.0E+00   1.0E+00   0.0E+00   0.0E+00
+   0.0E+00   0.0E+00   0.0E+00   0.0E+00
+   0.0E+00   0.0E+00   0.0E+00   0.0E+00
+";
+        let q = read_ticra_sph::<f64, _>(file.as_bytes()).unwrap();
+        let s = (8.0 * std::f64::consts::PI).sqrt();
+        assert_eq!(q.nmax(), 2);
+        assert_relative_eq!(*q.get(1, 0, 1).unwrap(), Complex::new(s, -2.0 * s));
+        assert_relative_eq!(*q.get(2, 0, 2).unwrap(), Complex::new(7.0 * s, -8.0 * s));
+        // Records of order -1 are stored as order 1 and vice versa
+        assert_relative_eq!(*q.get(1, 1, 1).unwrap(), Complex::new(s, 0.0));
+        assert_relative_eq!(*q.get(2, 1, 1).unwrap(), Complex::new(0.0, -2.0 * s));
+        assert_relative_eq!(*q.get(1, -1, 1).unwrap(), Complex::new(0.0, -s));
+        assert_eq!(*q.get(1, 2, 2).unwrap(), Complex::new(0.0, 0.0));
+
+        assert!(matches!(
+            read_ticra_sph::<f64, _>(&file.as_bytes()[..400]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::legendre::legendre_p_all;
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Factor (2 l + 1) / (4 pi) of the addition theorem
+#[inline(always)]
+fn factor<T: SphrsFloat>(l: usize) -> T {
+    T::from_usize(2 * l + 1).unwrap() / (T::from_f64(4.0).unwrap() * T::PI())
+}
+
+/// Cosine of the angle between the directions of `p` and `q`
+fn cos_angle<T: SphrsFloat>(p: &impl SHCoordinates<T>, q: &impl SHCoordinates<T>) -> T {
+    let c = p.theta_cos() * q.theta_cos()
+        + p.theta().sin() * q.theta().sin() * (p.phi() - q.phi()).cos();
+    c.max(-T::one()).min(T::one())
+}
+
+/// Sum of the products of the spherical harmonics of degree `l` at `p` and `q` over all orders
+///
+/// By the addition theorem, the sum of Y_lm(p) conj(Y_lm(q)) over m = -l, ..., l equals
+/// (2 l + 1) / (4 pi) P_l(cos gamma), where gamma is the angle between `p` and `q`. The sum is
+/// real and the same for the real harmonics [`RealSH::Spherical`](`crate::basis::RealSH`), for
+/// which it is the sum of Y_lm(p) Y_lm(q). Only the directions of `p` and `q` are used.
+///
+/// This costs a single Legendre recurrence instead of the evaluation of 2 (2 l + 1) harmonics.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{addition_theorem, RealSH, SHEval};
+/// use sphrs::Coordinates;
+///
+/// let p = Coordinates::spherical(1.0, 0.4, 1.2);
+/// let q = Coordinates::spherical(2.0, 1.9, -0.3);
+/// let sum: f64 = (-3..=3)
+///     .map(|m| RealSH::Spherical.eval(3, m, &p) * RealSH::Spherical.eval(3, m, &q))
+///     .sum();
+/// assert!((addition_theorem(3, &p, &q) - sum).abs() < 1e-15);
+/// ```
+pub fn addition_theorem<T: SphrsFloat>(
+    l: usize,
+    p: &impl SHCoordinates<T>,
+    q: &impl SHCoordinates<T>,
+) -> T {
+    factor::<T>(l) * legendre_p_all(l, cos_angle(p, q))[l]
+}
+
+/// [`addition_theorem`] for all degrees l = 0, ..., `lmax`
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::addition_theorem_all;
+/// use sphrs::Coordinates;
+///
+/// // Coinciding directions: (2 l + 1) / (4 pi)
+/// let p = Coordinates::cartesian(1.0, 2.0, 3.0);
+/// let sums = addition_theorem_all(4, &p, &p);
+/// for (l, s) in sums.iter().enumerate() {
+///     let expected = (2 * l + 1) as f64 / (4.0 * std::f64::consts::PI);
+///     assert!((s - expected).abs() < 1e-14);
+/// }
+/// ```
+pub fn addition_theorem_all<T: SphrsFloat>(
+    lmax: usize,
+    p: &impl SHCoordinates<T>,
+    q: &impl SHCoordinates<T>,
+) -> Vec<T> {
+    let mut legendre = legendre_p_all(lmax, cos_angle(p, q));
+    for (l, v) in legendre.iter_mut().enumerate() {
+        *v = factor::<T>(l) * *v;
+    }
+    legendre
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, HarmonicsSet, RealSH};
+    use crate::Coordinates;
+    use num_complex::Complex;
+
+    #[test]
+    fn compare_with_sums_over_orders() {
+        let lmax = 12;
+        let points = [
+            Coordinates::spherical(1.0, 0.3, 0.2),
+            Coordinates::spherical(0.5, 2.8, -2.5),
+            Coordinates::cartesian(0.0, 0.0, 1.0),
+            Coordinates::cartesian(1.0, -1.0, 0.0),
+        ];
+        for p in &points {
+            for q in &points {
+                let sums = addition_theorem_all(lmax, p, q);
+                let real_p: Vec<f64> = HarmonicsSet::new(lmax, RealSH::Spherical).eval(p);
+                let real_q: Vec<f64> = HarmonicsSet::new(lmax, RealSH::Spherical).eval(q);
+                let complex_p: Vec<Complex<f64>> =
+                    HarmonicsSet::new(lmax, ComplexSH::Spherical).eval(p);
+                let complex_q: Vec<Complex<f64>> =
+                    HarmonicsSet::new(lmax, ComplexSH::Spherical).eval(q);
+                for (l, &sum) in sums.iter().enumerate() {
+                    let range = l * l..(l + 1) * (l + 1);
+                    let real: f64 = range.clone().map(|i| real_p[i] * real_q[i]).sum();
+                    let complex: Complex<f64> =
+                        range.map(|i| complex_p[i] * complex_q[i].conj()).sum();
+                    assert!((sum - real).abs() < 1e-13, "{l}");
+                    assert!((sum - complex.re).abs() < 1e-13, "{l}");
+                    assert!(complex.im.abs() < 1e-13);
+                    assert_eq!(addition_theorem(l, p, q), sum);
+                }
+            }
+        }
+    }
+}
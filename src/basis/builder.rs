@@ -0,0 +1,610 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::marker::PhantomData;
+
+use crate::basis::tabulated::LegendreGrid;
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::names::named_enum;
+use crate::{SphrsError, SphrsFloat};
+
+/// Evaluation backend of a [`HarmonicsSet`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// Use hardcoded closed-form expressions where available and the recursive implementation
+    /// otherwise
+    ///
+    /// Equivalent to [`Backend::Recursive`] if the `compact` feature is enabled.
+    #[default]
+    Hardcoded,
+    /// Use the recursive implementation for all degrees
+    Recursive,
+    /// Use the recursive implementation for all degrees and evaluate it in `f64`
+    ///
+    /// Only the results are cast to the float type of the set. For `f32` sets, this gives the
+    /// accuracy of `f64` evaluation while the values are stored and processed in `f32`.
+    RecursiveF64,
+    /// Interpolate the Legendre functions in a table computed when the set is created
+    ///
+    /// The Legendre functions of the set are tabulated on a uniform grid of 32 (L + 1)
+    /// colatitudes for degree L and interpolated by cubic polynomials, which replaces the
+    /// recurrences by four multiply-adds per function. This takes (L + 1)(L + 2) / 2 values per
+    /// grid point, or about (L + 1)(M + 1) for sets truncated at order M, e.g. 4 MB in `f64` for
+    /// L = 30, and gives the spherical harmonics with absolute errors of about 1e-6, enough for
+    /// lighting and other approximate uses. Tables larger than [`MAX_TABULATED_BYTES`] are
+    /// rejected by [`HarmonicsSetBuilder::build`], which limits full `f64` sets to degree 100.
+    /// Harmonics evaluated one at a time, e.g. by [`HarmonicsSet::iter_eval`], are computed with
+    /// the recursive implementation.
+    Tabulated,
+    /// Use the recursive implementation with extended exponent arithmetic for all degrees
+    ///
+    /// The recurrences are evaluated in `f64` with a separate integer exponent, which avoids the
+    /// underflow of the intermediate values for degrees beyond a few thousand at the cost of
+    /// slower evaluation. Requires the `xnumber` feature.
+    #[cfg(feature = "xnumber")]
+    Extended,
+}
+
+/// Largest table of [`Backend::Tabulated`] accepted by [`HarmonicsSetBuilder::build`], in bytes
+pub const MAX_TABULATED_BYTES: usize = 128 << 20;
+
+named_enum!(Backend, "backend", {
+    Hardcoded => "hardcoded",
+    Recursive => "recursive",
+    RecursiveF64 => "recursive_f64",
+    Tabulated => "tabulated",
+    #[cfg(feature = "xnumber")]
+    Extended => "extended",
+});
+
+/// Handling of coefficient slices whose length differs from the number of harmonics of a
+/// [`HarmonicsSet`]
+///
+/// Applies to [`HarmonicsSet::eval_with_coefficients`] and [`HarmonicsSet::eval_sum`], which
+/// return [`SphrsError::LengthMismatch`] for lengths the policy does not accept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LengthPolicy {
+    /// The number of coefficients must equal the number of harmonics
+    #[default]
+    Exact,
+    /// Fewer coefficients are accepted, the missing coefficients are treated as zero
+    AllowShorter,
+    /// More coefficients are accepted, the coefficients beyond the set are ignored
+    AllowLonger,
+    /// Both fewer and more coefficients are accepted
+    AllowAny,
+}
+
+named_enum!(LengthPolicy, "length policy", {
+    Exact => "exact",
+    AllowShorter => "allow_shorter",
+    AllowLonger => "allow_longer",
+    AllowAny => "allow_any",
+});
+
+impl LengthPolicy {
+    /// Check a slice of `found` coefficients for a set of `expected` harmonics
+    pub(crate) fn check(&self, expected: usize, found: usize) -> Result<(), SphrsError> {
+        let accepted = match self {
+            LengthPolicy::Exact => found == expected,
+            LengthPolicy::AllowShorter => found <= expected,
+            LengthPolicy::AllowLonger => found >= expected,
+            LengthPolicy::AllowAny => true,
+        };
+        if accepted {
+            Ok(())
+        } else {
+            Err(SphrsError::LengthMismatch { expected, found })
+        }
+    }
+}
+
+/// Use of threads by the batch evaluations of a [`HarmonicsSet`]
+///
+/// Applies to [`HarmonicsSet::eval_batch`], [`HarmonicsSet::eval_batch_into`] and
+/// [`HarmonicsSet::eval_chunked`]. The results are the same in both cases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Parallelism {
+    /// Evaluate blocks of points on the rayon thread pool if the `rayon` feature is enabled, and
+    /// on the calling thread otherwise
+    #[default]
+    Parallel,
+    /// Always evaluate on the calling thread, e.g. for sets used from within tasks that are
+    /// already running on a thread pool
+    Sequential,
+}
+
+named_enum!(Parallelism, "parallelism", {
+    Parallel => "parallel",
+    Sequential => "sequential",
+});
+
+/// Builder for [`HarmonicsSet`]
+///
+/// Created via [`HarmonicsSet::builder`]. The options are validated when calling
+/// [`build`](`HarmonicsSetBuilder::build`). The output container is not an option of the set, it
+/// is chosen by the evaluation method, e.g. [`HarmonicsSet::eval`] for a `Vec`,
+/// [`HarmonicsSet::eval_batch_into`] for a caller-provided slice.
+#[derive(Clone, Debug)]
+pub struct HarmonicsSetBuilder<T, E> {
+    /// Lowest degree
+    min_degree: usize,
+    /// Highest degree
+    degree: Option<usize>,
+    /// Highest order |m|
+    max_order: Option<usize>,
+    /// Type of harmonic
+    sh_type: Option<E>,
+    /// Evaluation backend
+    backend: Backend,
+    /// Handling of coefficient slices of a different length
+    length_policy: LengthPolicy,
+    /// Use of threads by batch evaluations
+    parallelism: Parallelism,
+    /// Float
+    _ttt: PhantomData<T>,
+}
+
+impl<T, E> HarmonicsSetBuilder<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+{
+    pub(crate) fn new() -> Self {
+        HarmonicsSetBuilder {
+            min_degree: 0,
+            degree: None,
+            max_order: None,
+            sh_type: None,
+            backend: Backend::default(),
+            length_policy: LengthPolicy::default(),
+            parallelism: Parallelism::default(),
+            _ttt: PhantomData,
+        }
+    }
+
+    /// Include all degrees up to (and including) `degree`
+    #[must_use]
+    pub fn degree(mut self, degree: usize) -> Self {
+        self.min_degree = 0;
+        self.degree = Some(degree);
+        self
+    }
+
+    /// Only include the degrees `min_degree..=max_degree`
+    #[must_use]
+    pub fn degree_range(mut self, min_degree: usize, max_degree: usize) -> Self {
+        self.min_degree = min_degree;
+        self.degree = Some(max_degree);
+        self
+    }
+
+    /// Only include the orders |m| <= `max_order` of each degree
+    ///
+    /// Sets truncated in order are much cheaper to evaluate for high degrees, as only
+    /// O(L `max_order`) harmonics and Legendre functions are computed. Defaults to all orders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// // Near-zonal set: degrees up to 100, orders up to 2
+    /// let sh = HarmonicsSet::builder()
+    ///     .degree(100)
+    ///     .max_order(2)
+    ///     .sh_type(RealSH::Spherical)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(sh.num_sh(), 1 + 3 + 99 * 5);
+    /// let full = HarmonicsSet::new(100, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let (y, y_full): (Vec<f64>, Vec<f64>) = (sh.eval(&p), full.eval(&p));
+    /// let i = sh.index(50, -2).unwrap();
+    /// assert!((y[i] - y_full[full.index(50, -2).unwrap()]).abs() < 1e-12);
+    /// ```
+    #[must_use]
+    pub fn max_order(mut self, max_order: usize) -> Self {
+        self.max_order = Some(max_order);
+        self
+    }
+
+    /// Type of harmonics (e.g. [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`))
+    #[must_use]
+    pub fn sh_type(mut self, sh_type: E) -> Self {
+        self.sh_type = Some(sh_type);
+        self
+    }
+
+    /// Evaluation backend (defaults to [`Backend::Hardcoded`])
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Handling of coefficient slices of a different length (defaults to
+    /// [`LengthPolicy::Exact`])
+    #[must_use]
+    pub fn length_policy(mut self, length_policy: LengthPolicy) -> Self {
+        self.length_policy = length_policy;
+        self
+    }
+
+    /// Use of threads by batch evaluations (defaults to [`Parallelism::Parallel`])
+    #[must_use]
+    pub fn parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Validate the options and create the [`HarmonicsSet`]
+    ///
+    /// Returns [`SphrsError::InvalidConfiguration`] if the degree or the type of harmonics is
+    /// missing, if the lowest degree exceeds the highest degree, if the number of harmonics does
+    /// not fit into `usize`, or if the limits of the backend are exceeded, i.e. the table of
+    /// [`Backend::Tabulated`] would be larger than [`MAX_TABULATED_BYTES`].
+    pub fn build(self) -> Result<HarmonicsSet<T, E>, SphrsError> {
+        let degree = self.degree.ok_or_else(|| {
+            SphrsError::InvalidConfiguration("degree of the set must be given".to_string())
+        })?;
+        let sh_type = self.sh_type.ok_or_else(|| {
+            SphrsError::InvalidConfiguration("type of harmonics must be given".to_string())
+        })?;
+        if self.min_degree > degree {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "lowest degree {} is larger than highest degree {degree}",
+                self.min_degree
+            )));
+        }
+        let max_order = self.max_order.unwrap_or(degree);
+        if num_sh(self.min_degree, degree, max_order).is_none() {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "number of harmonics of degree {degree} does not fit into usize"
+            )));
+        }
+        if self.backend == Backend::Tabulated {
+            let bytes = LegendreGrid::<T>::size(degree, max_order)
+                .and_then(|n| n.checked_mul(std::mem::size_of::<T>()));
+            if bytes.is_none_or(|b| b > MAX_TABULATED_BYTES) {
+                return Err(SphrsError::InvalidConfiguration(format!(
+                    "table of backend {} for degree {degree} exceeds {MAX_TABULATED_BYTES} bytes",
+                    self.backend
+                )));
+            }
+        }
+        Ok(HarmonicsSet::from_parts(
+            self.min_degree,
+            degree,
+            self.max_order,
+            sh_type,
+            self.backend,
+            self.length_policy,
+            self.parallelism,
+        ))
+    }
+}
+
+/// Number of harmonics of the degrees `min_degree..=degree` and orders |m| <= `max_order`, or
+/// `None` if it overflows
+fn num_sh(min_degree: usize, degree: usize, max_order: usize) -> Option<usize> {
+    let max_order = max_order.min(degree);
+    // Degrees up to max_order have all 2 l + 1 orders
+    let full = if min_degree <= max_order {
+        max_order.checked_add(1)?.checked_pow(2)? - min_degree * min_degree
+    } else {
+        0
+    };
+    // Higher degrees have 2 max_order + 1 orders
+    let first = min_degree.max(max_order.checked_add(1)?);
+    let truncated = if first <= degree {
+        (degree - first + 1).checked_mul(max_order.checked_mul(2)?.checked_add(1)?)?
+    } else {
+        0
+    };
+    full.checked_add(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, RealSH};
+    use crate::{Coordinates, SphrsError};
+    use approx::assert_relative_eq;
+    use num_complex::Complex;
+
+    #[test]
+    fn validation() {
+        let missing_degree = HarmonicsSet::<f64, RealSH>::builder()
+            .sh_type(RealSH::Spherical)
+            .build();
+        assert!(matches!(
+            missing_degree,
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+        let missing_type = HarmonicsSet::<f64, RealSH>::builder().degree(3).build();
+        assert!(matches!(
+            missing_type,
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+        let wrong_range = HarmonicsSet::<f64, _>::builder()
+            .degree_range(4, 3)
+            .sh_type(ComplexSH::Spherical)
+            .build();
+        assert!(matches!(
+            wrong_range,
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+        let too_many = HarmonicsSet::<f64, _>::builder()
+            .degree(usize::MAX / 2)
+            .sh_type(RealSH::Spherical)
+            .backend(Backend::Recursive)
+            .build();
+        assert!(matches!(too_many, Err(SphrsError::InvalidConfiguration(_))));
+        assert_eq!(num_sh(0, usize::MAX, 0), None);
+        assert_eq!(num_sh(usize::MAX, usize::MAX, 3), Some(7));
+        for (min_degree, degree, max_order) in
+            [(0, 0, 0), (0, 9, 9), (2, 9, 4), (6, 9, 4), (3, 5, 20)]
+        {
+            let set = HarmonicsSet::<f64, _>::builder()
+                .degree_range(min_degree, degree)
+                .max_order(max_order)
+                .sh_type(RealSH::Spherical)
+                .build()
+                .unwrap();
+            assert_eq!(num_sh(min_degree, degree, max_order), Some(set.num_sh()));
+        }
+    }
+
+    #[test]
+    fn f64_evaluation_of_f32_sets() {
+        let degree = 60;
+        let (theta, phi) = (0.3f32, 2.2f32);
+        let reference = HarmonicsSet::new(degree, RealSH::Spherical).eval(&Coordinates::spherical(
+            1.0,
+            f64::from(theta),
+            f64::from(phi),
+        ));
+        let max_error = |backend| {
+            let set = HarmonicsSet::builder()
+                .degree(degree)
+                .sh_type(RealSH::Spherical)
+                .backend(backend)
+                .build()
+                .unwrap();
+            set.eval(&Coordinates::spherical(1.0f32, theta, phi))
+                .iter()
+                .zip(&reference)
+                .fold(0.0f64, |acc, (&a, &b)| acc.max((f64::from(a) - b).abs()))
+        };
+        // Only the rounding of the results to f32 remains
+        let widened = max_error(Backend::RecursiveF64);
+        assert!(widened < 2.0 * f64::from(f32::EPSILON), "{widened}");
+        assert!(max_error(Backend::Recursive) > 4.0 * widened);
+    }
+
+    #[cfg(feature = "xnumber")]
+    #[test]
+    fn extended_backend() {
+        let p = Coordinates::spherical(0.9, 2.1, 0.4);
+        let recursive = HarmonicsSet::builder()
+            .degree(30)
+            .sh_type(ComplexSH::IrregularSolid)
+            .backend(Backend::Recursive)
+            .build()
+            .unwrap();
+        let extended = HarmonicsSet::builder()
+            .degree(30)
+            .sh_type(ComplexSH::IrregularSolid)
+            .backend("extended".parse().unwrap())
+            .build()
+            .unwrap();
+        for (a, b) in recursive.eval(&p).iter().zip(extended.eval(&p)) {
+            assert_relative_eq!(*a, b, max_relative = 1e-12);
+        }
+    }
+
+    #[test]
+    fn degree_range_and_backend() {
+        let p = Coordinates::spherical(1.3, 0.7, -0.3);
+        let full = HarmonicsSet::new(10, RealSH::RegularSolid).eval(&p);
+        for backend in [Backend::Hardcoded, Backend::Recursive] {
+            for (min_degree, max_degree) in [(0, 10), (2, 4), (3, 3), (7, 10)] {
+                let set = HarmonicsSet::builder()
+                    .degree_range(min_degree, max_degree)
+                    .sh_type(RealSH::RegularSolid)
+                    .backend(backend)
+                    .build()
+                    .unwrap();
+                let values = set.eval(&p);
+                assert_eq!(values.len(), set.num_sh());
+                let expected = &full[min_degree * min_degree..(max_degree + 1) * (max_degree + 1)];
+                for (a, b) in values.iter().zip(expected) {
+                    assert_relative_eq!(*a, *b, max_relative = 1e-10);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn order_truncated_sets() {
+        let p = Coordinates::spherical(1.2, 0.9, -2.1);
+        for (min_degree, degree, max_order) in [(0, 12, 2), (3, 30, 5), (0, 4, 0), (2, 6, 9)] {
+            let full = HarmonicsSet::builder()
+                .degree_range(min_degree, degree)
+                .sh_type(RealSH::RegularSolid)
+                .build()
+                .unwrap();
+            let all: Vec<f64> = full.eval(&p);
+            for backend in [
+                Backend::Hardcoded,
+                Backend::Recursive,
+                Backend::RecursiveF64,
+            ] {
+                let set = HarmonicsSet::builder()
+                    .degree_range(min_degree, degree)
+                    .max_order(max_order)
+                    .sh_type(RealSH::RegularSolid)
+                    .backend(backend)
+                    .build()
+                    .unwrap();
+                assert_eq!(set.max_order(), max_order.min(degree));
+                let values = set.eval(&p);
+                assert_eq!(values.len(), set.num_sh());
+                let mut count = 0;
+                for (l, m) in full.harmonics() {
+                    match set.index(l, m) {
+                        Some(i) => {
+                            assert_eq!(i, count);
+                            let expected = all[full.index(l, m).unwrap()];
+                            assert_relative_eq!(values[i], expected, max_relative = 1e-12);
+                            count += 1;
+                        }
+                        None => assert!(m.unsigned_abs() as usize > max_order),
+                    }
+                }
+                assert_eq!(count, set.num_sh());
+                let ring = set.eval_ring(1.2, 0.9, &[-2.1]);
+                for (a, b) in ring.iter().zip(&values) {
+                    assert_relative_eq!(*a, *b, max_relative = 1e-12);
+                }
+            }
+        }
+        let set = HarmonicsSet::builder()
+            .degree(4)
+            .max_order(1)
+            .sh_type(ComplexSH::Spherical)
+            .build()
+            .unwrap();
+        let values: [_; 13] = set.eval_array(&p).unwrap();
+        assert_eq!(&values[..], &set.eval(&p)[..]);
+    }
+
+    #[test]
+    fn tabulated_backend() {
+        fn max_error<E: SHEval<f64> + Copy>(degree: usize, max_order: usize, sh_type: E) -> f64
+        where
+            E::Output: std::ops::Sub<Output = E::Output> + num::Zero + Copy,
+            E::Output: Into<Complex<f64>>,
+        {
+            let build = |backend| {
+                HarmonicsSet::builder()
+                    .degree(degree)
+                    .max_order(max_order)
+                    .sh_type(sh_type)
+                    .backend(backend)
+                    .build()
+                    .unwrap()
+            };
+            let (tabulated, exact) = (build(Backend::Tabulated), build(Backend::Recursive));
+            let mut error: f64 = 0.0;
+            for i in 0..=200 {
+                let theta = std::f64::consts::PI * i as f64 / 200.0;
+                let p = Coordinates::spherical(0.9, theta, 0.37 * i as f64);
+                for (a, b) in tabulated.eval(&p).into_iter().zip(exact.eval(&p)) {
+                    error = error.max((a - b).into().norm());
+                }
+            }
+            error
+        }
+        assert!(max_error(0, 0, RealSH::Spherical) < 1e-6);
+        assert!(max_error(6, 6, RealSH::Spherical) < 2e-6);
+        assert!(max_error(30, 30, RealSH::Spherical) < 4e-6);
+        assert!(max_error(20, 3, RealSH::RegularSolid) < 1e-6);
+        assert!(max_error(12, 12, ComplexSH::Spherical) < 2e-6);
+
+        let set = HarmonicsSet::builder()
+            .degree(5)
+            .sh_type(RealSH::Spherical)
+            .backend(Backend::Tabulated)
+            .build()
+            .unwrap();
+        let p = Coordinates::spherical(1.0, 1.1, 0.4);
+        let single: Vec<f64> = set.iter_eval(&p).map(|(_, _, y)| y).collect();
+        let exact: Vec<f64> = HarmonicsSet::new(5, RealSH::Spherical).eval(&p);
+        for (a, b) in single.iter().zip(&exact) {
+            assert_relative_eq!(*a, *b, epsilon = 1e-14);
+        }
+
+        // Tables beyond the memory cap are rejected, truncation in order reduces the table
+        let tabulated = |degree: usize, max_order: usize| {
+            HarmonicsSet::<f64, _>::builder()
+                .degree(degree)
+                .max_order(max_order)
+                .sh_type(RealSH::Spherical)
+                .backend(Backend::Tabulated)
+                .build()
+        };
+        assert!(LegendreGrid::<f64>::size(100, 100).unwrap() * 8 <= MAX_TABULATED_BYTES);
+        assert!(matches!(
+            tabulated(101, 101),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+        assert!(matches!(
+            tabulated(usize::MAX / 2, 0),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+        assert!(tabulated(300, 2).is_ok());
+    }
+
+    #[test]
+    fn parallelism() {
+        let points: Vec<_> = (0..700)
+            .map(|i| Coordinates::spherical(1.0, 0.004 * i as f64, 0.3 * i as f64))
+            .collect();
+        let parallel = HarmonicsSet::new(6, RealSH::Spherical);
+        assert_eq!(parallel.parallelism(), Parallelism::Parallel);
+        let sequential = HarmonicsSet::builder()
+            .degree(6)
+            .sh_type(RealSH::Spherical)
+            .parallelism(Parallelism::Sequential)
+            .build()
+            .unwrap();
+        assert_eq!(sequential.parallelism(), Parallelism::Sequential);
+        let table: Vec<f64> = sequential.eval_batch(&points);
+        assert_eq!(table, parallel.eval_batch(&points));
+        let mut into = vec![0.0; table.len()];
+        sequential.eval_batch_into(&points, &mut into).unwrap();
+        assert_eq!(into, table);
+    }
+
+    #[test]
+    fn length_policy() {
+        let policies = [
+            LengthPolicy::Exact,
+            LengthPolicy::AllowShorter,
+            LengthPolicy::AllowLonger,
+            LengthPolicy::AllowAny,
+        ];
+        let accepted = [
+            [false, true, false],
+            [true, true, false],
+            [false, true, true],
+            [true, true, true],
+        ];
+        for (policy, accepted) in policies.iter().zip(accepted) {
+            for (found, ok) in [3, 4, 5].into_iter().zip(accepted) {
+                let result = policy.check(4, found);
+                if ok {
+                    assert!(result.is_ok());
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(SphrsError::LengthMismatch {
+                            expected: 4,
+                            found: f
+                        }) if f == found
+                    ));
+                }
+            }
+        }
+    }
+}
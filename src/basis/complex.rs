@@ -0,0 +1,513 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use num::Complex;
+
+#[cfg(feature = "xnumber")]
+use crate::basis::sh_extended;
+use crate::basis::{
+    grad_tables, irregular_solid_sh, multiple_angles, regular_solid_sh, set_tables, sh,
+    sh_from_trig, sh_grad, sh_valid, to_f64_coordinates, SHEval, SHGrad,
+};
+use crate::legendre::{normalized_legendre_table_orders, table_index};
+use crate::names::named_enum;
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Available types of complex spherical harmonics and solid harmonics
+///
+/// The plain variants use the [`PhaseConvention::Quantum`] convention, the `*Acoustics` variants
+/// [`PhaseConvention::Acoustics`]. The names used by `Display` and `FromStr` are the same as for
+/// [`RealSH`](`crate::basis::RealSH`), with an `_acoustics` suffix for the latter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComplexSH {
+    /// Spherical harmonics
+    Spherical,
+    /// Regular solid harmonics
+    RegularSolid,
+    /// Irregular solid harmonics
+    IrregularSolid,
+    /// Spherical harmonics in the acoustics convention
+    SphericalAcoustics,
+    /// Regular solid harmonics in the acoustics convention
+    RegularSolidAcoustics,
+    /// Irregular solid harmonics in the acoustics convention
+    IrregularSolidAcoustics,
+}
+
+named_enum!(ComplexSH, "complex SH type", {
+    Spherical => "spherical",
+    RegularSolid => "regular_solid",
+    IrregularSolid => "irregular_solid",
+    SphericalAcoustics => "spherical_acoustics",
+    RegularSolidAcoustics => "regular_solid_acoustics",
+    IrregularSolidAcoustics => "irregular_solid_acoustics",
+});
+
+/// Phase convention of complex spherical harmonics
+///
+/// Both conventions use orthonormal harmonics with the azimuthal dependency e^{i m phi} and only
+/// differ in the sign of the harmonics of positive, odd order m.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PhaseConvention {
+    /// Convention of quantum mechanics (and SciPy): the associated Legendre functions include the
+    /// Condon-Shortley phase (-1)^m, and Y_l^{-m} = (-1)^m conj(Y_l^m)
+    #[default]
+    Quantum,
+    /// Convention common in acoustics and engineering: no Condon-Shortley phase, and
+    /// Y_l^{-m} = conj(Y_l^m), i.e. the harmonics of negative order have the azimuthal dependency
+    /// e^{-i |m| phi}
+    Acoustics,
+}
+
+named_enum!(PhaseConvention, "phase convention", {
+    Quantum => "quantum",
+    Acoustics => "acoustics",
+});
+
+impl PhaseConvention {
+    /// Convert the value `v` of the harmonic of order `m` in the quantum mechanics convention to
+    /// this convention
+    #[inline(always)]
+    fn apply<T: SphrsFloat>(&self, m: i64, v: Complex<T>) -> Complex<T> {
+        match self {
+            PhaseConvention::Acoustics if m > 0 && m % 2 == 1 => -v,
+            _ => v,
+        }
+    }
+
+    /// Value of the harmonic of order -`m` from the value `v` of the harmonic of the same degree
+    /// and order `m` >= 0 in this convention
+    #[inline(always)]
+    fn negative_order<T: SphrsFloat>(&self, m: usize, v: Complex<T>) -> Complex<T> {
+        match self {
+            PhaseConvention::Quantum if m % 2 == 1 => -v.conj(),
+            _ => v.conj(),
+        }
+    }
+}
+
+impl ComplexSH {
+    /// Phase convention of the harmonics
+    pub fn phase_convention(&self) -> PhaseConvention {
+        match self {
+            Self::Spherical | Self::RegularSolid | Self::IrregularSolid => PhaseConvention::Quantum,
+            Self::SphericalAcoustics
+            | Self::RegularSolidAcoustics
+            | Self::IrregularSolidAcoustics => PhaseConvention::Acoustics,
+        }
+    }
+
+    /// The same kind of harmonics in the phase convention `convention`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{ComplexSH, PhaseConvention};
+    ///
+    /// let sh = ComplexSH::RegularSolid.with_phase_convention(PhaseConvention::Acoustics);
+    /// assert_eq!(sh, ComplexSH::RegularSolidAcoustics);
+    /// assert_eq!(sh.phase_convention(), PhaseConvention::Acoustics);
+    /// ```
+    #[must_use]
+    pub fn with_phase_convention(self, convention: PhaseConvention) -> Self {
+        match (self, convention) {
+            (Self::Spherical | Self::SphericalAcoustics, PhaseConvention::Quantum) => {
+                Self::Spherical
+            }
+            (Self::RegularSolid | Self::RegularSolidAcoustics, PhaseConvention::Quantum) => {
+                Self::RegularSolid
+            }
+            (Self::IrregularSolid | Self::IrregularSolidAcoustics, PhaseConvention::Quantum) => {
+                Self::IrregularSolid
+            }
+            (Self::Spherical | Self::SphericalAcoustics, PhaseConvention::Acoustics) => {
+                Self::SphericalAcoustics
+            }
+            (Self::RegularSolid | Self::RegularSolidAcoustics, PhaseConvention::Acoustics) => {
+                Self::RegularSolidAcoustics
+            }
+            (Self::IrregularSolid | Self::IrregularSolidAcoustics, PhaseConvention::Acoustics) => {
+                Self::IrregularSolidAcoustics
+            }
+        }
+    }
+}
+
+impl<T> SHEval<T> for ComplexSH
+where
+    T: SphrsFloat,
+{
+    type Output = Complex<T>;
+
+    /// Evaluate complex SH (l, m) at position `p`
+    #[inline(always)]
+    fn eval(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+        assert!(m.abs() <= l);
+        let v = match self {
+            Self::Spherical | Self::SphericalAcoustics => sh(l, m, p),
+            Self::RegularSolid | Self::RegularSolidAcoustics => regular_solid_sh(l, m, p),
+            Self::IrregularSolid | Self::IrregularSolidAcoustics => irregular_solid_sh(l, m, p),
+        };
+        self.phase_convention().apply(m, v)
+    }
+
+    /// Evaluate complex SH (l, m) with |m| <= l at position `p` without checking (l, m)
+    #[inline(always)]
+    fn eval_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+        let v = sh_valid(l, m, p);
+        let scaling =
+            || ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        let v = match self {
+            Self::Spherical | Self::SphericalAcoustics => v,
+            Self::RegularSolid | Self::RegularSolidAcoustics => {
+                v * (scaling() * p.r().powi(l as i32))
+            }
+            Self::IrregularSolid | Self::IrregularSolidAcoustics => {
+                v * (scaling() / p.r().powi((l + 1) as i32))
+            }
+        };
+        self.phase_convention().apply(m, v)
+    }
+
+    /// Complex SH are evaluated by recurrences, see [`eval_valid`](`SHEval::eval_valid`)
+    #[inline(always)]
+    fn eval_recursive_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+        self.eval_valid(l, m, p)
+    }
+
+    /// Complex SH are always evaluated by recurrences
+    fn has_closed_forms(&self) -> bool {
+        false
+    }
+
+    /// Evaluate complex SH (l, m) at position `p` using the recursive implementation in `f64`
+    fn eval_recursive_f64(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+        let v: Complex<f64> = self.eval_recursive(l, m, &to_f64_coordinates(p));
+        Complex::new(T::from_f64(v.re).unwrap(), T::from_f64(v.im).unwrap())
+    }
+
+    /// Evaluate complex SH (l, m) at position `p` with extended exponent arithmetic
+    #[cfg(feature = "xnumber")]
+    fn eval_extended(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+        assert!(m.abs() <= l);
+        let scaling =
+            ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        let v = sh_extended(l, m, p);
+        let v = match self {
+            Self::Spherical | Self::SphericalAcoustics => v,
+            Self::RegularSolid | Self::RegularSolidAcoustics => {
+                v * (scaling * p.r().powi(l as i32))
+            }
+            Self::IrregularSolid | Self::IrregularSolidAcoustics => {
+                v * (scaling / p.r().powi((l + 1) as i32))
+            }
+        };
+        self.phase_convention().apply(m, v)
+    }
+
+    /// Evaluate all complex SH of the degrees `min_degree..=degree` at position `p` in one pass
+    /// of recurrences
+    fn eval_recursive_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let (legendre, trig) = set_tables(degree, max_order, p);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        self.push_from_tables(min_degree, &legendre, &trig, &factors, out);
+    }
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` given the Legendre functions, with
+    /// the multiple angles and radial factors of `eval_recursive_set`
+    fn eval_set_from_legendre(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        legendre: &[T],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let max_order = max_order.min(degree);
+        let mut trig = Vec::with_capacity(max_order + 1);
+        multiple_angles(max_order, p.phi(), &mut trig);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        self.push_from_tables(min_degree, legendre, &trig, &factors, out);
+    }
+
+    /// Evaluate the complex SH in `harmonics` at position `p` with the tables of
+    /// `eval_recursive_set`
+    fn eval_recursive_selection(
+        &self,
+        degree: usize,
+        max_order: usize,
+        harmonics: &[(i64, i64)],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let (legendre, trig) = set_tables(degree, max_order, p);
+        let factors = self.radial_factors(0, degree, p.r());
+        let convention = self.phase_convention();
+        for &(l, m) in harmonics {
+            let (l, abs_m) = (l as usize, m.unsigned_abs() as usize);
+            let v = sh_from_trig(m, trig[abs_m], legendre[table_index(l, abs_m)]);
+            out.push(convention.apply(m, v * factors[l]));
+        }
+    }
+
+    /// Evaluate all complex SH of the degrees `min_degree..=degree` on a ring with the Legendre
+    /// functions of the ring computed once
+    fn eval_recursive_ring(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        (r, theta): (T, T),
+        phis: &[T],
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let max_order = max_order.min(degree);
+        let legendre =
+            normalized_legendre_table_orders(degree, max_order, theta.cos(), theta.sin());
+        let factors = self.radial_factors(min_degree, degree, r);
+        let mut trig = Vec::with_capacity(max_order + 1);
+        for &phi in phis {
+            multiple_angles(max_order, phi, &mut trig);
+            self.push_from_tables(min_degree, &legendre, &trig, &factors, out);
+        }
+    }
+}
+
+impl ComplexSH {
+    /// Factors of the degrees `min_degree..=degree` relative to the spherical harmonics at radius
+    /// `r`
+    fn radial_factors<T: SphrsFloat>(&self, min_degree: usize, degree: usize, r: T) -> Vec<T> {
+        (min_degree..=degree)
+            .map(|l| {
+                let scaling = ((T::from_f64(4.0).unwrap() * T::PI())
+                    / T::from_usize(2 * l + 1).unwrap())
+                .sqrt();
+                match self {
+                    Self::Spherical | Self::SphericalAcoustics => T::one(),
+                    Self::RegularSolid | Self::RegularSolidAcoustics => scaling * r.powi(l as i32),
+                    Self::IrregularSolid | Self::IrregularSolidAcoustics => {
+                        scaling / r.powi((l + 1) as i32)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Push the harmonics of the degrees from `min_degree` on given the Legendre functions, the
+    /// multiple angles (see `set_tables`) and the radial factors of the degrees, for the orders
+    /// covered by the multiple angles
+    ///
+    /// Only the harmonics of orders m >= 0 are computed. Those of negative order follow by
+    /// conjugation, Y_l^{-m} = (-1)^m conj(Y_l^m) (without the sign in the acoustics convention),
+    /// which gives the same values as evaluating them.
+    fn push_from_tables<T: SphrsFloat>(
+        &self,
+        min_degree: usize,
+        legendre: &[T],
+        trig: &[(T, T)],
+        factors: &[T],
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let convention = self.phase_convention();
+        let max_order = trig.len() - 1;
+        for (l, &factor) in (min_degree..).zip(factors) {
+            let orders = l.min(max_order);
+            let zero = out.len() + orders;
+            out.resize(zero, Complex::new(T::zero(), T::zero()));
+            self.push_nonnegative_orders(l, legendre, &trig[..=orders], factor, out);
+            for m in 1..=orders {
+                out[zero - m] = convention.negative_order(m, out[zero + m]);
+            }
+        }
+    }
+
+    /// Push the harmonics of degree `l` and the orders 0 <= m < `trig.len()` given the Legendre
+    /// functions, the multiple angles and the radial factor of the degree
+    #[inline(always)]
+    fn push_nonnegative_orders<T: SphrsFloat>(
+        &self,
+        l: usize,
+        legendre: &[T],
+        trig: &[(T, T)],
+        factor: T,
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let convention = self.phase_convention();
+        for (m, &(cos, sin)) in trig.iter().enumerate() {
+            let v = legendre[table_index(l, m)];
+            out.push(convention.apply(m as i64, Complex::new(v * cos, v * sin) * factor));
+        }
+    }
+
+    /// Evaluate the complex SH of the degrees `min_degree..=degree` and the orders
+    /// 0 <= m <= `max_order` at position `p` in one pass of recurrences and append them to `out`,
+    /// ordered by degree and then by order
+    pub(crate) fn eval_nonnegative_orders<T: SphrsFloat>(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Complex<T>>,
+    ) {
+        let (legendre, trig) = set_tables(degree, max_order, p);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        for (l, &factor) in (min_degree..).zip(&factors) {
+            let orders = l.min(trig.len() - 1);
+            self.push_nonnegative_orders(l, &legendre, &trig[..=orders], factor, out);
+        }
+    }
+}
+
+impl<T> SHGrad<T> for ComplexSH
+where
+    T: SphrsFloat,
+{
+    /// Evaluate complex SH (l, m) and its derivatives with respect to theta and phi at position
+    /// `p`
+    fn eval_grad(
+        &self,
+        l: i64,
+        m: i64,
+        p: &impl SHCoordinates<T>,
+    ) -> (Complex<T>, Complex<T>, Complex<T>) {
+        assert!(m.abs() <= l);
+        let scaling =
+            ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        let factor = match self {
+            Self::Spherical | Self::SphericalAcoustics => T::one(),
+            Self::RegularSolid | Self::RegularSolidAcoustics => scaling * p.r().powi(l as i32),
+            Self::IrregularSolid | Self::IrregularSolidAcoustics => {
+                scaling / p.r().powi((l + 1) as i32)
+            }
+        };
+        let (y, d_theta, d_phi) = sh_grad(l, m, p);
+        let convention = self.phase_convention();
+        (
+            convention.apply(m, y * factor),
+            convention.apply(m, d_theta * factor),
+            convention.apply(m, d_phi * factor),
+        )
+    }
+
+    /// Evaluate all complex SH of the degrees `min_degree..=degree` and their derivatives at
+    /// position `p` in one pass of recurrences
+    fn eval_grad_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        (values, d_theta, d_phi): (
+            &mut Vec<Complex<T>>,
+            &mut Vec<Complex<T>>,
+            &mut Vec<Complex<T>>,
+        ),
+    ) {
+        let (legendre, trig) = grad_tables(degree, max_order, p);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        let convention = self.phase_convention();
+        let max_order = trig.len() - 1;
+        for (l, &factor) in (min_degree..).zip(&factors) {
+            let orders = l.min(max_order) as i64;
+            for m in -orders..=orders {
+                let abs_m = m.unsigned_abs() as usize;
+                let y = sh_from_trig(m, trig[abs_m], legendre.p[table_index(l, abs_m)]);
+                let dy = sh_from_trig(m, trig[abs_m], legendre.dp[table_index(l, abs_m)]);
+                let y = convention.apply(m, y * factor);
+                values.push(y);
+                d_theta.push(convention.apply(m, dy * factor));
+                d_phi.push(y * Complex::new(T::zero(), T::from_i64(m).unwrap()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coordinates;
+
+    #[test]
+    fn conjugate_symmetry() {
+        // The harmonics of negative order derived by conjugation are the same as those evaluated
+        // from the tables
+        let p = Coordinates::spherical(1.3, 2.2, -0.9);
+        let degree = 25;
+        for sh_type in [
+            ComplexSH::Spherical,
+            ComplexSH::IrregularSolid,
+            ComplexSH::RegularSolidAcoustics,
+        ] {
+            for max_order in [degree, 7] {
+                let (legendre, trig) = set_tables(degree, max_order, &p);
+                let factors = sh_type.radial_factors(2, degree, p.r());
+                let mut set = Vec::new();
+                sh_type.eval_recursive_set(2, degree, max_order, &p, &mut set);
+                let mut half = Vec::new();
+                sh_type.eval_nonnegative_orders(2, degree, max_order, &p, &mut half);
+                let (mut set, mut half) = (set.into_iter(), half.into_iter());
+                for l in 2..=degree {
+                    let orders = l.min(max_order) as i64;
+                    for m in -orders..=orders {
+                        let abs_m = m.unsigned_abs() as usize;
+                        let v = sh_from_trig(m, trig[abs_m], legendre[table_index(l, abs_m)]);
+                        let expected = sh_type.phase_convention().apply(m, v * factors[l - 2]);
+                        assert_eq!(set.next(), Some(expected), "{l} {m}");
+                        if m >= 0 {
+                            assert_eq!(half.next(), Some(expected), "{l} {m}");
+                        }
+                    }
+                }
+                assert!(set.next().is_none() && half.next().is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn acoustics_phase_convention() {
+        let p = Coordinates::spherical(1.3, 0.7, 0.4);
+        for sh_type in [
+            ComplexSH::Spherical,
+            ComplexSH::RegularSolid,
+            ComplexSH::IrregularSolid,
+        ] {
+            let acoustics = sh_type.with_phase_convention(PhaseConvention::Acoustics);
+            assert_eq!(
+                acoustics.with_phase_convention(PhaseConvention::Quantum),
+                sh_type
+            );
+            for l in 0..=6i64 {
+                for m in -l..=l {
+                    let q: Complex<f64> = sh_type.eval(l, m, &p);
+                    let a: Complex<f64> = acoustics.eval(l, m, &p);
+                    let sign = if m > 0 { (-1f64).powi(m as i32) } else { 1.0 };
+                    assert!((a - q * sign).norm() < 1e-14, "{l} {m}");
+                    // Y_l^{-m} = conj(Y_l^m)
+                    let b: Complex<f64> = acoustics.eval(l, -m, &p);
+                    assert!((b - a.conj()).norm() < 1e-14, "{l} {m}");
+                }
+            }
+        }
+
+        // No Condon-Shortley phase: Y_1^1 = sqrt(3 / (8 pi)) sin(theta) e^{i phi}
+        let y11: Complex<f64> = ComplexSH::SphericalAcoustics.eval(1, 1, &p);
+        let expected = Complex::from_polar(
+            (3.0 / (8.0 * std::f64::consts::PI)).sqrt() * 0.7f64.sin(),
+            0.4,
+        );
+        assert!((y11 - expected).norm() < 1e-15);
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use num::Zero;
+
+use crate::basis::{HarmonicsSet, SHEval, SHGrad};
+use crate::{SHCoordinates, SphrsError, SphrsFloat};
+
+/// Function on the sphere given by a [`HarmonicsSet`] and one coefficient per harmonic
+///
+/// The expansion f(p) = sum_lm c_lm Y_lm(p) can be evaluated with
+/// [`value_at`](`HarmonicsExpansion::value_at`) and differentiated with
+/// [`gradient_at`](`HarmonicsExpansion::gradient_at`). Expansions of the same type of harmonics
+/// can be added and subtracted, also if they cover different degrees, and multiplied by a
+/// coefficient. The operators panic for expansions of different types of harmonics, which
+/// [`try_add`](`HarmonicsExpansion::try_add`) and [`try_sub`](`HarmonicsExpansion::try_sub`)
+/// return as errors.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsExpansion, HarmonicsSet, RealSH};
+/// use sphrs::Coordinates;
+///
+/// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+/// let set = HarmonicsSet::new(1, RealSH::Spherical);
+/// let f = HarmonicsExpansion::new(set, vec![1.0f64, 0.0, 2.0, 0.0]).unwrap();
+/// let g = HarmonicsExpansion::new(HarmonicsSet::new(2, RealSH::Spherical), vec![0.5; 9]).unwrap();
+/// let h = &f + &(g.clone() * 2.0);
+/// assert_eq!(h.degree(), 2);
+/// assert!((h.value_at(&p) - f.value_at(&p) - 2.0 * g.value_at(&p)).abs() < 1e-14);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HarmonicsExpansion<T, E>
+where
+    E: SHEval<T>,
+{
+    /// Basis of the expansion
+    set: HarmonicsSet<T, E>,
+    /// Coefficients in the order of the harmonics of the set
+    coefficients: Vec<E::Output>,
+}
+
+impl<T, E> HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+{
+    /// Create the expansion with the harmonics of `set` and their `coefficients`
+    ///
+    /// Returns [`SphrsError::LengthMismatch`] unless there is exactly one coefficient per
+    /// harmonic of the set.
+    pub fn new(set: HarmonicsSet<T, E>, coefficients: Vec<E::Output>) -> Result<Self, SphrsError> {
+        if coefficients.len() != set.num_sh() {
+            return Err(SphrsError::LengthMismatch {
+                expected: set.num_sh(),
+                found: coefficients.len(),
+            });
+        }
+        Ok(HarmonicsExpansion { set, coefficients })
+    }
+
+    /// Returns the basis of the expansion
+    pub fn set(&self) -> &HarmonicsSet<T, E> {
+        &self.set
+    }
+
+    /// Returns the coefficients in the order of the harmonics of the set
+    pub fn coefficients(&self) -> &[E::Output] {
+        &self.coefficients
+    }
+
+    /// Returns the coefficients, consuming the expansion
+    pub fn into_coefficients(self) -> Vec<E::Output> {
+        self.coefficients
+    }
+
+    /// Returns the highest degree of the expansion
+    pub fn degree(&self) -> usize {
+        self.set.degree()
+    }
+
+    /// Returns the coefficient of harmonic (`l`, `m`), or `None` if the set does not contain it
+    pub fn get(&self, l: i64, m: i64) -> Option<&E::Output> {
+        self.set.index(l, m).map(|i| &self.coefficients[i])
+    }
+}
+
+impl<T, E> HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: Copy + Zero + Mul<Output = E::Output>,
+{
+    /// Value of the expansion at position `p`
+    pub fn value_at<C: SHCoordinates<T>>(&self, p: &C) -> E::Output {
+        self.set
+            .eval(p)
+            .into_iter()
+            .zip(&self.coefficients)
+            .fold(E::Output::zero(), |acc, (y, &c)| acc + c * y)
+    }
+
+    /// Derivatives of the expansion with respect to theta and phi at position `p`
+    ///
+    /// The derivatives are taken at a fixed radius, as in [`SHGrad::eval_grad`]. The surface
+    /// gradient follows as (d_theta e_theta + d_phi / sin(theta) e_phi) / r.
+    pub fn gradient_at<C: SHCoordinates<T>>(&self, p: &C) -> (E::Output, E::Output)
+    where
+        E: SHGrad<T>,
+    {
+        let (_, y_theta, y_phi) = self.set.eval_grad(p);
+        let (mut d_theta, mut d_phi) = (E::Output::zero(), E::Output::zero());
+        for ((y_theta, y_phi), &c) in y_theta.into_iter().zip(y_phi).zip(&self.coefficients) {
+            d_theta = d_theta + c * y_theta;
+            d_phi = d_phi + c * y_phi;
+        }
+        (d_theta, d_phi)
+    }
+}
+
+impl<T, E> HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T> + Copy + PartialEq,
+    E::Output: Copy + Zero,
+{
+    /// Combine the coefficients of `self` and `other` with `op` on the union of their degrees,
+    /// treating missing coefficients as zero
+    fn zip_with(
+        &self,
+        other: &Self,
+        op: impl Fn(E::Output, E::Output) -> E::Output,
+    ) -> Result<HarmonicsExpansion<T, E>, SphrsError> {
+        if self.set.sh_type() != other.set.sh_type() {
+            return Err(SphrsError::InvalidInput(
+                "expansions of different types of harmonics".to_string(),
+            ));
+        }
+        let set = HarmonicsSet::builder()
+            .degree_range(
+                self.set.min_degree().min(other.set.min_degree()),
+                self.degree().max(other.degree()),
+            )
+            .max_order(self.set.max_order().max(other.set.max_order()))
+            .sh_type(*self.set.sh_type())
+            .backend(self.set.backend())
+            .length_policy(self.set.length_policy())
+            .parallelism(self.set.parallelism())
+            .build()?;
+        // The combined set is a band of degrees and orders, whose indices are computed in
+        // constant time, unlike those of sets of selected harmonics
+        let realign = |expansion: &Self| {
+            let mut out = vec![E::Output::zero(); set.num_sh()];
+            for ((l, m), &c) in expansion.set.harmonics().zip(&expansion.coefficients) {
+                out[set.band_index(l, m).expect("combined set covers both sets")] = c;
+            }
+            out
+        };
+        let coefficients = realign(self)
+            .into_iter()
+            .zip(realign(other))
+            .map(|(a, b)| op(a, b))
+            .collect();
+        Ok(HarmonicsExpansion { set, coefficients })
+    }
+
+    /// Sum of two expansions
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if the expansions use different types of harmonics,
+    /// and the errors of [`HarmonicsSetBuilder::build`](`crate::basis::HarmonicsSetBuilder::build`)
+    /// for the set covering both expansions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsExpansion, HarmonicsSet, RealSH};
+    ///
+    /// let f = HarmonicsExpansion::new(HarmonicsSet::new(1, RealSH::Spherical), vec![1.0; 4]);
+    /// let g = HarmonicsExpansion::new(HarmonicsSet::new(1, RealSH::RegularSolid), vec![1.0; 4]);
+    /// assert!(f.unwrap().try_add(&g.unwrap()).is_err());
+    /// ```
+    pub fn try_add(&self, other: &Self) -> Result<HarmonicsExpansion<T, E>, SphrsError> {
+        self.zip_with(other, |a, b| a + b)
+    }
+
+    /// Difference of two expansions
+    ///
+    /// Returns the same errors as [`try_add`](`HarmonicsExpansion::try_add`).
+    pub fn try_sub(&self, other: &Self) -> Result<HarmonicsExpansion<T, E>, SphrsError>
+    where
+        E::Output: Sub<Output = E::Output>,
+    {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+impl<T, E> Add for &HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T> + Copy + PartialEq,
+    E::Output: Copy + Zero,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    /// Sum of two expansions
+    ///
+    /// Panics in the cases where [`HarmonicsExpansion::try_add`] returns an error.
+    fn add(self, other: Self) -> HarmonicsExpansion<T, E> {
+        self.try_add(other).unwrap_or_else(|e| panic!("{e}"))
+    }
+}
+
+impl<T, E> Add for HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T> + Copy + PartialEq,
+    E::Output: Copy + Zero,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    /// Sum of two expansions
+    ///
+    /// Panics in the cases where [`HarmonicsExpansion::try_add`] returns an error.
+    fn add(self, other: Self) -> HarmonicsExpansion<T, E> {
+        &self + &other
+    }
+}
+
+impl<T, E> Sub for &HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T> + Copy + PartialEq,
+    E::Output: Copy + Zero + Sub<Output = E::Output>,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    /// Difference of two expansions
+    ///
+    /// Panics in the cases where [`HarmonicsExpansion::try_sub`] returns an error.
+    fn sub(self, other: Self) -> HarmonicsExpansion<T, E> {
+        self.try_sub(other).unwrap_or_else(|e| panic!("{e}"))
+    }
+}
+
+impl<T, E> Sub for HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T> + Copy + PartialEq,
+    E::Output: Copy + Zero + Sub<Output = E::Output>,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    /// Difference of two expansions
+    ///
+    /// Panics in the cases where [`HarmonicsExpansion::try_sub`] returns an error.
+    fn sub(self, other: Self) -> HarmonicsExpansion<T, E> {
+        &self - &other
+    }
+}
+
+impl<T, E> Neg for HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: Neg<Output = E::Output>,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    fn neg(mut self) -> HarmonicsExpansion<T, E> {
+        self.coefficients = self.coefficients.into_iter().map(|c| -c).collect();
+        self
+    }
+}
+
+impl<T, E> Mul<E::Output> for HarmonicsExpansion<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: Copy + Mul<Output = E::Output>,
+{
+    type Output = HarmonicsExpansion<T, E>;
+
+    /// Expansion scaled by `factor`
+    fn mul(mut self, factor: E::Output) -> HarmonicsExpansion<T, E> {
+        for c in &mut self.coefficients {
+            *c = *c * factor;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, RealSH};
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+    use num::Complex;
+
+    fn expansion(min_degree: usize, degree: usize) -> HarmonicsExpansion<f64, RealSH> {
+        let set = HarmonicsSet::builder()
+            .degree_range(min_degree, degree)
+            .sh_type(RealSH::Spherical)
+            .build()
+            .unwrap();
+        let coefficients = (0..set.num_sh()).map(|i| (i as f64 * 0.7).sin()).collect();
+        HarmonicsExpansion::new(set, coefficients).unwrap()
+    }
+
+    #[test]
+    fn value_and_gradient() {
+        let f = expansion(1, 6);
+        let (theta, phi) = (1.1, -0.4);
+        let p = Coordinates::spherical(1.0, theta, phi);
+        let expected: f64 = f.set().eval_sum(&p, f.coefficients()).unwrap();
+        assert_relative_eq!(f.value_at(&p), expected, epsilon = 1e-14);
+
+        let h = 1e-6;
+        let value = |t, ph| f.value_at(&Coordinates::spherical(1.0, t, ph));
+        let (d_theta, d_phi) = f.gradient_at(&p);
+        let fd_theta = (value(theta + h, phi) - value(theta - h, phi)) / (2.0 * h);
+        let fd_phi = (value(theta, phi + h) - value(theta, phi - h)) / (2.0 * h);
+        assert_relative_eq!(d_theta, fd_theta, epsilon = 1e-7);
+        assert_relative_eq!(d_phi, fd_phi, epsilon = 1e-7);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let f = expansion(0, 3);
+        let g = expansion(2, 5);
+        let p = Coordinates::spherical(1.0, 0.6, 2.2);
+        let sum = &f + &g;
+        assert_eq!((sum.set().min_degree(), sum.degree()), (0, 5));
+        assert_relative_eq!(
+            sum.value_at(&p),
+            f.value_at(&p) + g.value_at(&p),
+            epsilon = 1e-14
+        );
+        assert_eq!(sum.get(4, -1), g.get(4, -1));
+        assert_eq!(sum.get(1, 1), f.get(1, 1));
+        let difference = f.clone() - g.clone();
+        assert_relative_eq!(
+            difference.value_at(&p),
+            f.value_at(&p) - g.value_at(&p),
+            epsilon = 1e-14
+        );
+        let scaled = -(g.clone() * 3.0);
+        assert_relative_eq!(scaled.value_at(&p), -3.0 * g.value_at(&p), epsilon = 1e-14);
+        assert_eq!(g.get(1, 0), None);
+        assert_eq!(g.get(2, 3), None);
+    }
+
+    #[test]
+    fn complex_expansion() {
+        let set = HarmonicsSet::new(2, ComplexSH::Spherical);
+        let coefficients: Vec<_> = (0..9).map(|i| Complex::new(i as f64, 1.0)).collect();
+        let f = HarmonicsExpansion::new(set, coefficients).unwrap();
+        let p = Coordinates::spherical(1.0, 0.3, 0.9);
+        let doubled = f.clone() * Complex::new(2.0, 0.0);
+        assert_relative_eq!(doubled.value_at(&p), f.value_at(&p) * 2.0, epsilon = 1e-14);
+    }
+
+    #[test]
+    #[should_panic(expected = "different types of harmonics")]
+    fn different_types() {
+        let f = HarmonicsExpansion::new(HarmonicsSet::new(0, RealSH::Spherical), vec![1.0]);
+        let g = HarmonicsExpansion::new(HarmonicsSet::new(0, RealSH::RegularSolid), vec![1.0]);
+        let _ = f.unwrap() + g.unwrap();
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let f = HarmonicsExpansion::new(HarmonicsSet::new(0, RealSH::Spherical), vec![1.0]);
+        let g = HarmonicsExpansion::new(HarmonicsSet::new(0, RealSH::RegularSolid), vec![1.0]);
+        let (f, g) = (f.unwrap(), g.unwrap());
+        assert!(matches!(f.try_add(&g), Err(SphrsError::InvalidInput(_))));
+        assert!(matches!(f.try_sub(&g), Err(SphrsError::InvalidInput(_))));
+        assert_eq!(f.try_sub(&f).unwrap().coefficients(), &[0.0]);
+    }
+
+    #[test]
+    fn selected_harmonics() {
+        let p = Coordinates::spherical(1.0, 0.6, 2.2);
+        let indices = [(4, -3), (1, 0), (6, 2), (2, 2)];
+        let set = HarmonicsSet::from_indices(&indices, RealSH::Spherical).unwrap();
+        let f = HarmonicsExpansion::new(set, vec![0.5, -1.0, 2.0, 0.25]).unwrap();
+        let g = expansion(0, 3);
+        let sum = f.try_add(&g).unwrap();
+        assert_eq!((sum.set().min_degree(), sum.degree()), (0, 6));
+        assert_relative_eq!(
+            sum.value_at(&p),
+            f.value_at(&p) + g.value_at(&p),
+            epsilon = 1e-14
+        );
+        assert_eq!(sum.get(6, 2), Some(&2.0));
+        assert_eq!(*sum.get(2, 2).unwrap(), 0.25 + g.get(2, 2).unwrap());
+    }
+
+    #[test]
+    fn length_mismatch() {
+        assert!(matches!(
+            HarmonicsExpansion::new(HarmonicsSet::new(1, RealSH::Spherical), vec![1.0; 3]),
+            Err(SphrsError::LengthMismatch {
+                expected: 4,
+                found: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::marker::PhantomData;
+
+#[cfg(not(feature = "compact"))]
+use crate::basis::CLOSED_FORM_DEGREE;
+use crate::basis::{RealSH, SHEval};
+use crate::{sh_index, SHCoordinates, SphrsFloat};
+
+/// Set of real harmonics of all degrees up to the compile-time degree `L`, evaluated into arrays
+/// of `N` = (`L` + 1)^2 values
+///
+/// Meant for small, fixed degrees as in lighting with spherical harmonics. The degree is known at
+/// compile time, such that the loops over the harmonics can be fully unrolled and the closed-form
+/// expressions inlined, and nothing is allocated. `N` has to be given explicitly since it cannot
+/// be computed from `L` in the type; a wrong `N` fails to compile. The harmonics are ordered as
+/// in a [`HarmonicsSet`](`crate::basis::HarmonicsSet`) of degree `L`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{FixedHarmonicsSet, HarmonicsSet, RealSH};
+/// use sphrs::Coordinates;
+///
+/// let sh = FixedHarmonicsSet::<f32, 2, 9>::new(RealSH::Spherical);
+/// let p = Coordinates::cartesian(0.3f32, -0.5, 0.8);
+/// let values: [f32; 9] = sh.eval(&p);
+/// for (a, b) in values.iter().zip(HarmonicsSet::new(2, RealSH::Spherical).eval(&p)) {
+///     assert!((a - b).abs() < 1e-6);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FixedHarmonicsSet<T, const L: usize, const N: usize> {
+    sh: RealSH,
+    _ttt: PhantomData<T>,
+}
+
+impl<T, const L: usize, const N: usize> FixedHarmonicsSet<T, L, N>
+where
+    T: SphrsFloat,
+{
+    /// Number of harmonics `N`, checked at compile time
+    const NUM_SH: usize = {
+        assert!(N == (L + 1) * (L + 1), "N must be (L + 1)^2");
+        N
+    };
+
+    /// Create a set of the harmonics `sh_type`
+    pub fn new(sh_type: RealSH) -> Self {
+        let _ = Self::NUM_SH;
+        FixedHarmonicsSet {
+            sh: sh_type,
+            _ttt: PhantomData,
+        }
+    }
+
+    /// Returns the degree `L`
+    pub fn degree(&self) -> usize {
+        L
+    }
+
+    /// Returns the number of harmonics `N`
+    pub fn num_sh(&self) -> usize {
+        N
+    }
+
+    /// Returns the type of the harmonics
+    pub fn sh_type(&self) -> RealSH {
+        self.sh
+    }
+
+    /// Evaluate the harmonics at position `p`
+    #[inline]
+    pub fn eval<C>(&self, p: &C) -> [T; N]
+    where
+        C: SHCoordinates<T>,
+    {
+        let mut out = [T::zero(); N];
+        #[cfg(not(feature = "compact"))]
+        let next = {
+            // One `if L >= l` per degree of the closed-form expressions with one assignment per
+            // harmonic, generated by the build script. The conditions are resolved at compile
+            // time and the closed-form expressions of the constant (l, m) inlined.
+            include!(concat!(env!("OUT_DIR"), "/unrolled_fixed.rs"));
+            L.min(CLOSED_FORM_DEGREE) + 1
+        };
+        #[cfg(feature = "compact")]
+        let next = 0;
+        for l in next..=L {
+            for m in -(l as i64)..=l as i64 {
+                out[sh_index(l as i64, m)] = self.sh.eval(l as i64, m, p);
+            }
+        }
+        out
+    }
+
+    /// Evaluate the expansion with the coefficients `coefficients` at position `p`
+    #[inline]
+    pub fn eval_sum<C>(&self, p: &C, coefficients: &[T; N]) -> T
+    where
+        C: SHCoordinates<T>,
+    {
+        self.eval(p)
+            .iter()
+            .zip(coefficients)
+            .fold(T::zero(), |acc, (&y, &c)| acc + y * c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::HarmonicsSet;
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn same_as_harmonics_set() {
+        fn check<const L: usize, const N: usize>(sh_type: RealSH) {
+            let fixed = FixedHarmonicsSet::<f64, L, N>::new(sh_type);
+            let set = HarmonicsSet::new(L, sh_type);
+            assert_eq!(fixed.num_sh(), set.num_sh());
+            for p in [
+                Coordinates::spherical(1.3, 0.4, 2.2),
+                Coordinates::cartesian(-0.2, 0.7, -0.4),
+            ] {
+                let values = fixed.eval(&p);
+                for (a, b) in values.iter().zip(set.eval(&p)) {
+                    assert_relative_eq!(*a, b, max_relative = 1e-13, epsilon = 1e-15);
+                }
+                let coeffs: [f64; N] = std::array::from_fn(|i| (i as f64 * 0.6).sin());
+                let expected: f64 = set.eval_sum(&p, &coeffs).unwrap();
+                assert_relative_eq!(fixed.eval_sum(&p, &coeffs), expected, epsilon = 1e-13);
+            }
+        }
+        check::<0, 1>(RealSH::Spherical);
+        check::<2, 9>(RealSH::RegularSolid);
+        check::<4, 25>(RealSH::Spherical);
+        check::<10, 121>(RealSH::IrregularSolid);
+    }
+}
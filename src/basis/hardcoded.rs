@@ -0,0 +1,33 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Closed-form expressions of the real spherical harmonics
+//!
+//! The functions are generated by the build script up to the degree given by the environment
+//! variable `SPHRS_HARDCODED_DEGREE` (default 8). Those up to degree 3 (`sh00`, ..., `sh3p3`) are
+//! part of the public API, the remaining ones are only used via [`real_sh_closed_form`].
+
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Components of the unit vector in the direction of `p`
+///
+/// At the origin, the direction is given by `theta` and `phi` (the z-axis for
+/// [`Coordinates::cartesian`](`crate::Coordinates::cartesian`) of the origin), as in the recursive
+/// implementation.
+#[inline(always)]
+fn unit_vector<T: SphrsFloat>(p: &impl SHCoordinates<T>) -> (T, T, T) {
+    let r = p.r();
+    if r.is_zero() {
+        let (sin_theta, cos_theta) = p.theta().sin_cos();
+        let (sin_phi, cos_phi) = p.phi().sin_cos();
+        return (sin_theta * cos_phi, sin_theta * sin_phi, cos_theta);
+    }
+    let r_inv = r.recip();
+    (p.x() * r_inv, p.y() * r_inv, p.z() * r_inv)
+}
+
+include!(concat!(env!("OUT_DIR"), "/hardcoded.rs"));
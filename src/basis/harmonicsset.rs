@@ -0,0 +1,1918 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::marker::PhantomData;
+
+use num::{Complex, Zero};
+
+use crate::basis::tabulated::LegendreGrid;
+#[cfg(not(feature = "compact"))]
+use crate::basis::CLOSED_FORM_DEGREE;
+use crate::basis::{
+    Backend, ComplexSH, HarmonicsSetBuilder, LengthPolicy, Parallelism, SHEval, SHGrad,
+};
+use crate::parallel;
+use crate::reduce::neumaier_sum;
+use crate::{SHCoordinates, SphrsError, SphrsFloat};
+
+/// Number of consecutive points evaluated by one task of [`HarmonicsSet::eval_batch`]
+const BATCH_BLOCK_SIZE: usize = 256;
+
+/// A set of spherical/solid harmonics up to a given degree
+///
+/// A set covering all degrees up to a given degree with the default options can be created with
+/// [`HarmonicsSet::new`], all other configurations are created with [`HarmonicsSet::builder`].
+///
+/// Sets are immutable once created: evaluation only takes `&self` and keeps no caches or other
+/// interior mutability. A set of [`RealSH`](`crate::basis::RealSH`) or
+/// [`ComplexSH`](`crate::basis::ComplexSH`) is therefore `Send` and `Sync` and can be stored in
+/// application state and evaluated from several threads at once, e.g. behind an `Arc` or a plain
+/// reference. Sets are `Clone` and `Debug` as well.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::Coordinates;
+///
+/// let sh = HarmonicsSet::new(4, RealSH::Spherical);
+/// let sums: Vec<f64> = std::thread::scope(|s| {
+///     let handles: Vec<_> = (0..4)
+///         .map(|i| {
+///             let (sh, p) = (&sh, Coordinates::spherical(1.0, 0.3 * i as f64, 0.5));
+///             s.spawn(move || sh.eval(&p).iter().sum())
+///         })
+///         .collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+/// assert_eq!(sums.len(), 4);
+/// assert_eq!(sh.degree(), 4);
+/// assert_eq!(*sh.sh_type(), RealSH::Spherical);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HarmonicsSet<T, E> {
+    /// Lowest degree
+    min_degree: usize,
+    /// degree
+    degree: usize,
+    /// Highest order |m|, at most `degree`
+    max_order: usize,
+    /// Total number of harmonics
+    num_sh: usize,
+    /// Type of harmonic
+    sh: E,
+    /// Evaluation backend
+    backend: Backend,
+    /// Handling of coefficient slices of a different length
+    length_policy: LengthPolicy,
+    /// Use of threads by batch evaluations
+    parallelism: Parallelism,
+    /// Harmonics of a set created from a list of indices
+    selection: Option<Selection>,
+    /// Tabulated Legendre functions of [`Backend::Tabulated`]
+    table: Option<LegendreGrid<T>>,
+    /// Float
+    _ttt: PhantomData<T>,
+}
+
+/// Harmonics of a [`HarmonicsSet::from_indices`] set
+#[derive(Clone, Debug)]
+struct Selection {
+    /// Degree and order of the harmonics in the order of evaluation
+    harmonics: Vec<(i64, i64)>,
+    /// Indices of the harmonics in the band of degrees and orders covering the selection
+    positions: Vec<usize>,
+    /// Number of harmonics of the covering band
+    covering: usize,
+    /// Highest degree evaluated by closed-form expressions with [`Backend::Hardcoded`], if any
+    closed: Option<usize>,
+    /// Harmonics of higher degrees, which are evaluated by recurrences
+    recursive: Vec<(i64, i64)>,
+}
+
+impl Selection {
+    /// Returns `true` if evaluating the covering band with one pass of recurrences is cheaper
+    /// than evaluating the harmonics one by one, which takes O(l) operations each
+    fn is_dense(&self) -> bool {
+        let single: i64 = self.harmonics.iter().map(|&(l, _)| l + 1).sum();
+        single as usize > self.covering
+    }
+
+    /// Returns `true` if the harmonics of degree `l` are evaluated by recurrences with
+    /// [`Backend::Hardcoded`]
+    fn is_recursive(&self, l: i64) -> bool {
+        self.closed.is_none_or(|closed| l as usize > closed)
+    }
+
+    /// Append the selected harmonics of the evaluated covering band `band` to `out`
+    fn gather<O>(&self, band: Vec<O>, out: &mut Vec<O>) {
+        let mut band: Vec<Option<O>> = band.into_iter().map(Some).collect();
+        out.extend(self.positions.iter().map(|&i| band[i].take().unwrap()));
+    }
+}
+
+impl<T, E> HarmonicsSet<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+{
+    /// Create new `HarmonicsSet` struct
+    pub fn new(degree: usize, sh_type: E) -> HarmonicsSet<T, E> {
+        HarmonicsSet::from_parts(
+            0,
+            degree,
+            None,
+            sh_type,
+            Backend::default(),
+            LengthPolicy::default(),
+            Parallelism::default(),
+        )
+    }
+
+    /// Create a builder for a `HarmonicsSet`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{Backend, HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::builder()
+    ///     .degree_range(2, 4)
+    ///     .sh_type(RealSH::Spherical)
+    ///     .backend(Backend::Recursive)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(sh.num_sh(), 21);
+    ///
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let set: Vec<f64> = sh.eval(&p);
+    /// assert_eq!(set.len(), 21);
+    /// ```
+    pub fn builder() -> HarmonicsSetBuilder<T, E> {
+        HarmonicsSetBuilder::new()
+    }
+
+    /// Create a set of exactly the harmonics (l, m) in `indices`, in the given order
+    ///
+    /// Only the selected harmonics are evaluated, e.g. only even degrees or only the sectoral
+    /// harmonics. Evaluated vectors and coefficient slices follow the order of `indices`. Sparse
+    /// selections are evaluated one harmonic at a time, dense selections by one pass of
+    /// recurrences over the band of degrees and orders covering them. The set uses the default
+    /// [`Backend`] and [`LengthPolicy`]. Returns
+    /// [`SphrsError::InvalidConfiguration`] if `indices` is empty, contains an index with
+    /// |m| > l or contains an index twice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// // Sectoral harmonics of degrees 1 to 3
+    /// let sectoral = [(1, -1), (1, 1), (2, -2), (2, 2), (3, -3), (3, 3)];
+    /// let sh = HarmonicsSet::from_indices(&sectoral, RealSH::Spherical).unwrap();
+    /// assert_eq!(sh.num_sh(), 6);
+    /// assert_eq!(sh.index(2, 2), Some(3));
+    ///
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let full = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let (y, y_full): (Vec<f64>, Vec<f64>) = (sh.eval(&p), full.eval(&p));
+    /// assert!((y[3] - y_full[full.index(2, 2).unwrap()]).abs() < 1e-15);
+    /// ```
+    pub fn from_indices(indices: &[(i64, i64)], sh_type: E) -> Result<Self, SphrsError> {
+        if indices.is_empty() {
+            return Err(SphrsError::InvalidConfiguration(
+                "set must contain at least one harmonic".to_string(),
+            ));
+        }
+        if let Some(&(l, m)) = indices.iter().find(|&&(l, m)| m.abs() > l) {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "invalid harmonic (l, m) = ({l}, {m})"
+            )));
+        }
+        let min_degree = indices.iter().map(|&(l, _)| l).min().unwrap() as usize;
+        let degree = indices.iter().map(|&(l, _)| l).max().unwrap() as usize;
+        let max_order = indices
+            .iter()
+            .map(|&(_, m)| m.unsigned_abs())
+            .max()
+            .unwrap() as usize;
+        let mut set = HarmonicsSet::from_parts(
+            min_degree,
+            degree,
+            Some(max_order),
+            sh_type,
+            Backend::default(),
+            LengthPolicy::default(),
+            Parallelism::default(),
+        );
+        let positions: Vec<usize> = indices
+            .iter()
+            .map(|&(l, m)| set.band_index(l, m).unwrap())
+            .collect();
+        let mut seen = vec![false; set.num_sh];
+        for (&i, &(l, m)) in positions.iter().zip(indices) {
+            if std::mem::replace(&mut seen[i], true) {
+                return Err(SphrsError::InvalidConfiguration(format!(
+                    "harmonic (l, m) = ({l}, {m}) is selected twice"
+                )));
+            }
+        }
+        // As in `eval_hardcoded`, such that the harmonics equal those of the covering band
+        #[cfg(not(feature = "compact"))]
+        let closed = set
+            .sh
+            .has_closed_forms()
+            .then(|| max_order.min(CLOSED_FORM_DEGREE));
+        #[cfg(feature = "compact")]
+        let closed = None;
+        let mut selection = Selection {
+            harmonics: indices.to_vec(),
+            positions,
+            covering: set.num_sh,
+            closed,
+            recursive: Vec::new(),
+        };
+        selection.recursive = indices
+            .iter()
+            .copied()
+            .filter(|&(l, _)| selection.is_recursive(l))
+            .collect();
+        set.selection = Some(selection);
+        set.num_sh = indices.len();
+        Ok(set)
+    }
+
+    /// Create a set of the harmonics of the even degrees up to `degree`
+    ///
+    /// Real functions which are symmetric under inversion, f(-p) = f(p), such as diffusion ODFs
+    /// in dMRI or ambient occlusion, only have coefficients of even degree. The set only evaluates
+    /// the harmonics of even degree, about half of all harmonics, ordered by degree and then by
+    /// order. This is a shortcut for [`from_indices`](`HarmonicsSet::from_indices`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::even_degrees(4, RealSH::Spherical);
+    /// assert_eq!(sh.num_sh(), 1 + 5 + 9);
+    /// assert_eq!(sh.index(1, 0), None);
+    ///
+    /// // The expansion is antipodally symmetric
+    /// let coeffs: Vec<f64> = (0..sh.num_sh()).map(|i| 1.0 / (1.0 + i as f64)).collect();
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let q = Coordinates::spherical(1.0, std::f64::consts::PI - 0.8, 0.4 + std::f64::consts::PI);
+    /// let a: f64 = sh.eval_sum(&p, &coeffs).unwrap();
+    /// let b: f64 = sh.eval_sum(&q, &coeffs).unwrap();
+    /// assert!((a - b).abs() < 1e-12);
+    /// ```
+    pub fn even_degrees(degree: usize, sh_type: E) -> Self {
+        let indices: Vec<(i64, i64)> = (0..=degree as i64)
+            .step_by(2)
+            .flat_map(|l| (-l..=l).map(move |m| (l, m)))
+            .collect();
+        HarmonicsSet::from_indices(&indices, sh_type).expect("even degrees form a valid set")
+    }
+
+    pub(crate) fn from_parts(
+        min_degree: usize,
+        degree: usize,
+        max_order: Option<usize>,
+        sh_type: E,
+        backend: Backend,
+        length_policy: LengthPolicy,
+        parallelism: Parallelism,
+    ) -> HarmonicsSet<T, E> {
+        let max_order = max_order.map_or(degree, |m| m.min(degree));
+        let num_sh = (min_degree..=degree)
+            .map(|o| 2 * o.min(max_order) + 1)
+            .sum();
+
+        HarmonicsSet {
+            min_degree,
+            degree,
+            max_order,
+            num_sh,
+            sh: sh_type,
+            backend,
+            length_policy,
+            parallelism,
+            selection: None,
+            table: matches!(backend, Backend::Tabulated)
+                .then(|| LegendreGrid::new(degree, max_order)),
+            _ttt: PhantomData,
+        }
+    }
+
+    /// Returns the lowest degree of the set
+    ///
+    /// This is zero unless the set was built for a band of degrees with
+    /// [`HarmonicsSetBuilder::degree_range`].
+    pub fn min_degree(&self) -> usize {
+        self.min_degree
+    }
+
+    /// Returns the highest degree of the set
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the highest order |m| of the set
+    ///
+    /// This is the degree of the set unless the orders were truncated with
+    /// [`HarmonicsSetBuilder::max_order`].
+    pub fn max_order(&self) -> usize {
+        self.max_order
+    }
+
+    /// Index of harmonic (`l`, `m`) in the evaluated vectors and coefficient slices of the set, or
+    /// `None` if the set does not contain it
+    ///
+    /// The harmonics are ordered by degree and then by order from -min(l, M) to min(l, M) for the
+    /// highest order M of the set, starting at the lowest degree of the set. Sets created with
+    /// [`from_indices`](`HarmonicsSet::from_indices`) follow the order of the indices instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    ///
+    /// // Degrees 2 to 8 without the monopole and dipole
+    /// let sh = HarmonicsSet::<f64, _>::builder()
+    ///     .degree_range(2, 8)
+    ///     .sh_type(RealSH::Spherical)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(sh.num_sh(), 81 - 4);
+    /// assert_eq!(sh.index(2, -2), Some(0));
+    /// assert_eq!(sh.index(3, 0), Some(8));
+    /// assert_eq!(sh.index(1, 0), None);
+    /// ```
+    pub fn index(&self, l: i64, m: i64) -> Option<usize> {
+        match &self.selection {
+            Some(selection) => selection.harmonics.iter().position(|&h| h == (l, m)),
+            None => self.band_index(l, m),
+        }
+    }
+
+    /// Index of harmonic (`l`, `m`) in the band of degrees and orders of the set, which covers
+    /// all selected harmonics of a [`from_indices`](`HarmonicsSet::from_indices`) set
+    pub(crate) fn band_index(&self, l: i64, m: i64) -> Option<usize> {
+        let (min_degree, max_order) = (self.min_degree as i64, self.max_order as i64);
+        if l < min_degree || l > self.degree as i64 || m.abs() > l.min(max_order) {
+            return None;
+        }
+        // Number of harmonics of the degrees below l, the degrees up to the highest order are
+        // complete
+        let count = |l: i64| {
+            let full = l.min(max_order + 1);
+            full * full + (l - full) * (2 * max_order + 1)
+        };
+        Some((count(l) - count(min_degree) + l.min(max_order) + m) as usize)
+    }
+
+    /// Degree and order (l, m) of the harmonics of the set from degree `from` on, in the order of
+    /// evaluation
+    pub(crate) fn harmonics_from(&self, from: usize) -> impl Iterator<Item = (i64, i64)> {
+        let max_order = self.max_order as i64;
+        (from as i64..=self.degree as i64).flat_map(move |l| {
+            let orders = l.min(max_order);
+            (-orders..=orders).map(move |m| (l, m))
+        })
+    }
+
+    /// Degree and order (l, m) of all harmonics of the set in the order of evaluation
+    pub(crate) fn harmonics(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let selected = self.selection.as_ref().map(|s| s.harmonics.iter().copied());
+        let band = self
+            .selection
+            .is_none()
+            .then(|| self.harmonics_from(self.min_degree));
+        selected
+            .into_iter()
+            .flatten()
+            .chain(band.into_iter().flatten())
+    }
+
+    /// Index in the evaluated vectors per harmonic of the band of degrees and orders of the set,
+    /// `None` for the harmonics not selected by a [`from_indices`](`HarmonicsSet::from_indices`)
+    /// set
+    pub(crate) fn band_slots(&self) -> Vec<Option<usize>> {
+        match &self.selection {
+            Some(selection) => {
+                let mut slots = vec![None; selection.covering];
+                for (i, &j) in selection.positions.iter().enumerate() {
+                    slots[j] = Some(i);
+                }
+                slots
+            }
+            None => (0..self.num_sh).map(Some).collect(),
+        }
+    }
+
+    /// Returns the type of the harmonics
+    pub fn sh_type(&self) -> &E {
+        &self.sh
+    }
+
+    /// Returns the evaluation backend
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Returns the handling of coefficient slices of a different length
+    pub fn length_policy(&self) -> LengthPolicy {
+        self.length_policy
+    }
+
+    /// Returns the use of threads by batch evaluations
+    pub fn parallelism(&self) -> Parallelism {
+        self.parallelism
+    }
+
+    /// Returns the total number of spherical harmonics in the set
+    pub fn num_sh(&self) -> usize {
+        self.num_sh
+    }
+
+    /// Evaluate harmonics at position `p` without coefficients.
+    pub fn eval<C>(&self, p: &C) -> Vec<E::Output>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.eval_internal(p)
+    }
+
+    /// Lazily evaluate harmonics at position `p`
+    ///
+    /// Yields (l, m, value) in the order of [`eval`](`HarmonicsSet::eval`). Each harmonic is
+    /// evaluated when it is requested, one at a time with the backend of the set, and nothing is
+    /// allocated. This suits consumers which stop early or store the harmonics in their own
+    /// containers; the full set is evaluated faster with [`eval`](`HarmonicsSet::eval`), which
+    /// shares the recurrences of all harmonics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(10, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0f64, 0.8, 0.4);
+    /// // First harmonic with a magnitude above 0.5
+    /// let (l, m, y) = sh.iter_eval(&p).find(|&(_, _, y)| y.abs() > 0.5).unwrap();
+    /// assert_eq!(y, sh.eval(&p)[sh.index(l, m).unwrap()]);
+    /// ```
+    pub fn iter_eval<'a, C>(&'a self, p: &'a C) -> impl Iterator<Item = (i64, i64, E::Output)> + 'a
+    where
+        C: SHCoordinates<T>,
+    {
+        self.harmonics()
+            .map(move |(l, m)| (l, m, self.eval_single(l, m, p)))
+    }
+
+    /// Evaluate harmonics at position `p` into an array on the stack
+    ///
+    /// Same as [`eval`](`HarmonicsSet::eval`) without allocating, for small sets evaluated at
+    /// many points, e.g. `N = 9` for degree 2 or `N = 25` for degree 4. Degrees beyond the
+    /// closed-form expressions are evaluated one harmonic at a time, such that large sets are
+    /// faster with [`eval`](`HarmonicsSet::eval`). Returns [`SphrsError::InvalidInput`] if `N`
+    /// differs from [`num_sh`](`HarmonicsSet::num_sh`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(4, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let values: [f64; 25] = sh.eval_array(&p).unwrap();
+    /// for (a, b) in values.iter().zip(sh.eval(&p)) {
+    ///     assert!((a - b).abs() < 1e-15);
+    /// }
+    /// assert!(sh.eval_array::<_, 16>(&p).is_err());
+    /// ```
+    pub fn eval_array<C, const N: usize>(&self, p: &C) -> Result<[E::Output; N], SphrsError>
+    where
+        C: SHCoordinates<T>,
+        E::Output: Copy + Default,
+    {
+        if N != self.num_sh {
+            return Err(SphrsError::InvalidInput(format!(
+                "array of length {N} for a set of {} harmonics",
+                self.num_sh
+            )));
+        }
+        let mut data = [E::Output::default(); N];
+        self.eval_per_harmonic(
+            p,
+            &mut SliceBuffer {
+                data: &mut data,
+                len: 0,
+            },
+        );
+        Ok(data)
+    }
+
+    /// Evaluate harmonics at position `p` into `out` without allocating, panicking or blocking
+    ///
+    /// Meant for real-time threads such as the audio callbacks of ambisonics renderers: all
+    /// memory is provided by the caller, nothing is allocated, no lock is taken and the degree
+    /// and order of the harmonics, which are valid by construction of the set, are not checked
+    /// again (see [`SHEval::eval_valid`]). The number of operations is bounded by the set.
+    /// The harmonics are computed as by [`eval_array`](`HarmonicsSet::eval_array`), i.e. degrees
+    /// beyond the closed-form expressions one harmonic at a time, with the same values as
+    /// [`eval`](`HarmonicsSet::eval`) up to rounding. Returns [`SphrsError::LengthMismatch`],
+    /// which does not allocate either, if `out` does not hold exactly
+    /// [`num_sh`](`HarmonicsSet::num_sh`) values.
+    ///
+    /// Only sets with [`Backend::Hardcoded`] or [`Backend::Recursive`] can be evaluated in real
+    /// time. The other backends interpolate tables or convert between float types per harmonic,
+    /// and result in [`SphrsError::InvalidConfiguration`], whose message is allocated; check the
+    /// result once before the real-time thread starts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// // Created before the audio thread starts
+    /// let sh = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let mut gains = vec![0.0f32; sh.num_sh()];
+    ///
+    /// // In the audio callback
+    /// let source = Coordinates::spherical(1.0, 1.2, 0.3);
+    /// sh.eval_realtime(&source, &mut gains).unwrap();
+    /// for (a, b) in gains.iter().zip(sh.eval(&source)) {
+    ///     assert!((a - b).abs() < 1e-6);
+    /// }
+    /// assert!(sh.eval_realtime(&source, &mut gains[1..]).is_err());
+    /// ```
+    pub fn eval_realtime<C>(&self, p: &C, out: &mut [E::Output]) -> Result<(), SphrsError>
+    where
+        C: SHCoordinates<T>,
+    {
+        if !matches!(self.backend, Backend::Hardcoded | Backend::Recursive) {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "backend {} does not support real-time evaluation",
+                self.backend
+            )));
+        }
+        if out.len() != self.num_sh {
+            return Err(SphrsError::LengthMismatch {
+                expected: self.num_sh,
+                found: out.len(),
+            });
+        }
+        self.eval_per_harmonic(p, &mut SliceBuffer { data: out, len: 0 });
+        Ok(())
+    }
+
+    /// Evaluate harmonics at all `points`
+    ///
+    /// Returns the harmonics of all points in one row-major buffer of `points.len()` rows of
+    /// [`num_sh`](`HarmonicsSet::num_sh`) values, i.e. harmonic `j` at point `i` is at index
+    /// `i * num_sh + j`. The buffer is allocated once and the harmonics of each point are written
+    /// into their row in place, as by [`eval_batch_into`](`HarmonicsSet::eval_batch_into`). With
+    /// the `rayon` feature, blocks of points are evaluated in parallel unless the set was built
+    /// with [`Parallelism::Sequential`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let points = [
+    ///     Coordinates::spherical(1.0, 0.8, 0.4),
+    ///     Coordinates::spherical(1.0, 2.1, -1.3),
+    /// ];
+    /// let table: Vec<f64> = sh.eval_batch(&points);
+    /// assert_eq!(table.len(), 2 * sh.num_sh());
+    /// assert_eq!(&table[sh.num_sh()..], &sh.eval(&points[1])[..]);
+    /// ```
+    pub fn eval_batch<C>(&self, points: &[C]) -> Vec<E::Output>
+    where
+        C: SHCoordinates<T> + Sync,
+        E: Sync,
+        E::Output: Clone + Send,
+    {
+        let mut table = Vec::with_capacity(points.len() * self.num_sh);
+        self.fill_table(points, &mut table);
+        table
+    }
+
+    /// Evaluate harmonics at all `points` into the row-major buffer `out`
+    ///
+    /// Same as [`eval_batch`](`HarmonicsSet::eval_batch`), but the harmonics are written into a
+    /// buffer of the caller, which has to hold exactly `points.len() * num_sh` values. Otherwise,
+    /// [`SphrsError::InvalidInput`] is returned. The buffer can be any slice, e.g. a memory-mapped
+    /// file, such that tables larger than the available memory can be written without copies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let points = [
+    ///     Coordinates::spherical(1.0, 0.8, 0.4),
+    ///     Coordinates::spherical(1.0, 2.1, -1.3),
+    /// ];
+    /// let mut table = vec![0.0f64; 2 * sh.num_sh()];
+    /// sh.eval_batch_into(&points, &mut table).unwrap();
+    /// assert_eq!(table, sh.eval_batch(&points));
+    /// assert!(sh.eval_batch_into(&points[..1], &mut table).is_err());
+    /// ```
+    pub fn eval_batch_into<C>(&self, points: &[C], out: &mut [E::Output]) -> Result<(), SphrsError>
+    where
+        C: SHCoordinates<T> + Sync,
+        E: Sync,
+        E::Output: Send,
+    {
+        if out.len() != points.len() * self.num_sh {
+            return Err(SphrsError::InvalidInput(format!(
+                "buffer of length {} for {} points of {} harmonics",
+                out.len(),
+                points.len(),
+                self.num_sh
+            )));
+        }
+        self.eval_rows(points, out);
+        Ok(())
+    }
+
+    /// Evaluate harmonics at the positions of `points` in chunks of `chunk_len` positions
+    ///
+    /// Driver for point clouds too large to hold all harmonics in memory at once, e.g. tens of
+    /// millions of directions of LIDAR scans or rendering bakes. The positions are taken from the
+    /// iterator in chunks, each chunk is evaluated as by
+    /// [`eval_batch`](`HarmonicsSet::eval_batch`), in parallel according to the [`Parallelism`],
+    /// and passed to `sink` together with the index of its first position. Only one chunk of
+    /// positions and its `chunk_len * num_sh` harmonics are held in memory, with the buffers
+    /// reused between chunks. The first error returned by `sink` stops the evaluation and is
+    /// returned; a `chunk_len` of zero results in [`SphrsError::InvalidInput`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    /// use std::io::Write;
+    ///
+    /// let sh = HarmonicsSet::new(4, RealSH::Spherical);
+    /// // Directions generated on the fly and tables written to any `Write`, e.g. a file
+    /// let directions = (0..1000).map(|i| Coordinates::spherical(1.0, 0.003 * i as f64, 0.7));
+    /// let mut file = Vec::new();
+    /// sh.eval_chunked(directions, 256, |first, table: &[f64]| {
+    ///     assert_eq!(first % 256, 0);
+    ///     for v in table {
+    ///         file.write_all(&v.to_le_bytes())?;
+    ///     }
+    ///     Ok(())
+    /// })
+    /// .unwrap();
+    /// assert_eq!(file.len(), 1000 * sh.num_sh() * 8);
+    /// ```
+    pub fn eval_chunked<C, I, F>(
+        &self,
+        points: I,
+        chunk_len: usize,
+        mut sink: F,
+    ) -> Result<(), SphrsError>
+    where
+        I: IntoIterator<Item = C>,
+        C: SHCoordinates<T> + Sync,
+        E: Sync,
+        E::Output: Clone + Send,
+        F: FnMut(usize, &[E::Output]) -> Result<(), SphrsError>,
+    {
+        if chunk_len == 0 {
+            return Err(SphrsError::InvalidInput(
+                "chunk length must be positive".to_string(),
+            ));
+        }
+        let mut points = points.into_iter();
+        let mut chunk = Vec::with_capacity(chunk_len);
+        let mut table = Vec::with_capacity(chunk_len * self.num_sh);
+        let mut first = 0;
+        loop {
+            chunk.clear();
+            chunk.extend(points.by_ref().take(chunk_len));
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            self.fill_table(&chunk, &mut table);
+            sink(first, &table)?;
+            first += chunk.len();
+        }
+    }
+
+    /// Replace the contents of `table` by the harmonics at all `points`, reusing its allocation
+    fn fill_table<C>(&self, points: &[C], table: &mut Vec<E::Output>)
+    where
+        C: SHCoordinates<T> + Sync,
+        E: Sync,
+        E::Output: Clone + Send,
+    {
+        table.clear();
+        let Some((first, rest)) = points.split_first() else {
+            return;
+        };
+        self.eval_into(first, table);
+        if let Some(value) = table.first().cloned() {
+            // Placeholders for the remaining rows, which are overwritten in place
+            table.resize(points.len() * self.num_sh, value);
+            self.eval_rows(rest, &mut table[self.num_sh..]);
+        }
+    }
+
+    /// Evaluate harmonics at all `points` into the rows of `rows` in blocks of
+    /// [`BATCH_BLOCK_SIZE`] points, in parallel according to the [`Parallelism`] of the set
+    fn eval_rows<C>(&self, points: &[C], rows: &mut [E::Output])
+    where
+        C: SHCoordinates<T> + Sync,
+        E: Sync,
+        E::Output: Send,
+    {
+        if rows.is_empty() {
+            return;
+        }
+        self.for_each_block_mut(rows, BATCH_BLOCK_SIZE * self.num_sh, |i, block| {
+            let points = &points[i * BATCH_BLOCK_SIZE..];
+            let mut values = Vec::with_capacity(self.num_sh);
+            for (p, row) in points.iter().zip(block.chunks_mut(self.num_sh)) {
+                self.eval_into(p, &mut values);
+                for (slot, value) in row.iter_mut().zip(values.drain(..)) {
+                    *slot = value;
+                }
+            }
+        });
+    }
+
+    /// `f(i, chunk)` for the consecutive chunks of `chunk_len` elements of `data`, in parallel
+    /// according to the [`Parallelism`] of the set
+    fn for_each_block_mut<U, F>(&self, data: &mut [U], chunk_len: usize, f: F)
+    where
+        U: Send,
+        F: Fn(usize, &mut [U]) + Sync + Send,
+    {
+        match self.parallelism {
+            Parallelism::Parallel => parallel::for_each_chunk_mut(data, chunk_len, f),
+            Parallelism::Sequential => data
+                .chunks_mut(chunk_len)
+                .enumerate()
+                .for_each(|(i, chunk)| f(i, chunk)),
+        }
+    }
+
+    /// Evaluate harmonics on the ring of radius `r` and colatitude `theta` at the azimuths `phis`
+    ///
+    /// Returns a row-major buffer like [`eval_batch`](`HarmonicsSet::eval_batch`) with one row of
+    /// [`num_sh`](`HarmonicsSet::num_sh`) values per azimuth. The associated Legendre functions
+    /// and radial factors only depend on `r` and `theta` and are computed once for the ring; per
+    /// azimuth, only cos(m phi) and sin(m phi) are updated by a recurrence. This is much cheaper
+    /// than evaluating each position on its own, as on the rings of equiangular or
+    /// Gauss-Legendre grids. The harmonics are always computed with the recursive
+    /// implementation, independent of the [`Backend`] of the set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let phis = [0.0, 0.5, 1.0, 1.5];
+    /// let ring: Vec<f64> = sh.eval_ring(1.0, 0.8, &phis);
+    /// assert_eq!(ring.len(), phis.len() * sh.num_sh());
+    /// let p = Coordinates::spherical(1.0, 0.8, 1.0);
+    /// for (a, b) in ring[2 * sh.num_sh()..3 * sh.num_sh()].iter().zip(sh.eval(&p)) {
+    ///     assert!((a - b).abs() < 1e-14);
+    /// }
+    /// ```
+    pub fn eval_ring(&self, r: T, theta: T, phis: &[T]) -> Vec<E::Output> {
+        let mut table = Vec::with_capacity(phis.len() * self.num_sh);
+        self.sh.eval_recursive_ring(
+            self.min_degree,
+            self.degree,
+            self.max_order,
+            (r, theta),
+            phis,
+            &mut table,
+        );
+        match &self.selection {
+            Some(selection) => {
+                let mut bands = table.into_iter();
+                let mut out = Vec::with_capacity(phis.len() * self.num_sh);
+                for _ in phis {
+                    let band = bands.by_ref().take(selection.covering).collect();
+                    selection.gather(band, &mut out);
+                }
+                out
+            }
+            None => table,
+        }
+    }
+
+    /// Evaluate harmonics at position `p` with a given vector of coefficients.
+    ///
+    /// Returns the harmonics multiplied by their coefficients. Coefficient slices of a length
+    /// different from [`num_sh`](`HarmonicsSet::num_sh`) are handled according to the
+    /// [`LengthPolicy`] of the set; a length the policy does not accept results in
+    /// [`SphrsError::LengthMismatch`]. The returned vector always has one entry per harmonic. Use
+    /// [`eval_sum`](`HarmonicsSet::eval_sum`) for the value of the expansion, i.e. the sum of
+    /// these products.
+    ///
+    /// The coefficients are either of the type of the harmonics or, for complex harmonics, real
+    /// scalars of type `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{ComplexSH, HarmonicsSet, LengthPolicy, RealSH};
+    /// use sphrs::{Coordinates, SphrsError};
+    ///
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let coeffs = vec![1.0f64; 4];
+    /// let exact = HarmonicsSet::new(2, RealSH::Spherical);
+    /// assert!(matches!(
+    ///     exact.eval_with_coefficients(&p, &coeffs),
+    ///     Err(SphrsError::LengthMismatch { expected: 9, found: 4 })
+    /// ));
+    ///
+    /// // Treat the coefficients of degree 2 as zero
+    /// let padded = HarmonicsSet::builder()
+    ///     .degree(2)
+    ///     .sh_type(RealSH::Spherical)
+    ///     .length_policy(LengthPolicy::AllowShorter)
+    ///     .build()
+    ///     .unwrap();
+    /// let values = padded.eval_with_coefficients(&p, &coeffs).unwrap();
+    /// assert_eq!(values.len(), 9);
+    /// assert_eq!(values[8], 0.0);
+    ///
+    /// // Real coefficients with complex harmonics
+    /// let complex = HarmonicsSet::new(1, ComplexSH::Spherical);
+    /// let values = complex.eval_with_coefficients(&p, &coeffs).unwrap();
+    /// assert_eq!(values[2], complex.eval(&p)[2]);
+    /// ```
+    pub fn eval_with_coefficients<C, I>(
+        &self,
+        p: &C,
+        coefficients: &[I],
+    ) -> Result<Vec<E::Output>, SphrsError>
+    where
+        C: SHCoordinates<T>,
+        I: Copy,
+        E::Output: std::ops::Mul<I, Output = E::Output> + Zero,
+    {
+        self.length_policy.check(self.num_sh, coefficients.len())?;
+        Ok(self
+            .eval_internal(p)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| match coefficients.get(i) {
+                Some(&c) => value * c,
+                None => E::Output::zero(),
+            })
+            .collect())
+    }
+
+    /// Evaluate the expansion with the given coefficients at position `p`, i.e. the sum of the
+    /// harmonics multiplied by their coefficients
+    ///
+    /// Coefficient slices of a different length and real coefficients for complex harmonics are
+    /// handled as in [`eval_with_coefficients`](`HarmonicsSet::eval_with_coefficients`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(2, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let coeffs: Vec<f64> = (0..sh.num_sh()).map(|i| i as f64).collect();
+    /// let value: f64 = sh.eval_sum(&p, &coeffs).unwrap();
+    /// let products = sh.eval_with_coefficients(&p, &coeffs).unwrap();
+    /// assert!((value - products.iter().sum::<f64>()).abs() < 1e-14);
+    /// ```
+    pub fn eval_sum<C, I, O>(&self, p: &C, coefficients: &[I]) -> Result<O, SphrsError>
+    where
+        C: SHCoordinates<T>,
+        I: Copy,
+        E::Output: std::ops::Mul<I, Output = O>,
+        O: Zero,
+    {
+        self.length_policy.check(self.num_sh, coefficients.len())?;
+        Ok(self
+            .eval_internal(p)
+            .into_iter()
+            .zip(coefficients.iter())
+            .fold(O::zero(), |acc, (a, &b)| acc + a * b))
+    }
+
+    /// Evaluate harmonics at position `p`. If available, hardcoded SH functions will be used.
+    #[inline]
+    fn eval_internal<C>(&self, p: &C) -> Vec<E::Output>
+    where
+        C: SHCoordinates<T>,
+    {
+        let mut sh = Vec::with_capacity(self.num_sh);
+        self.eval_into(p, &mut sh);
+        sh
+    }
+
+    /// Evaluate harmonics at position `p` and append them to `sh`
+    #[inline]
+    fn eval_into<C>(&self, p: &C, sh: &mut Vec<E::Output>)
+    where
+        C: SHCoordinates<T>,
+    {
+        if let Some(selection) = &self.selection {
+            self.eval_selection(selection, p, sh);
+            return;
+        }
+        match self.backend {
+            Backend::Hardcoded => self.eval_hardcoded(p, sh),
+            Backend::Recursive => {
+                self.sh
+                    .eval_recursive_set(self.min_degree, self.degree, self.max_order, p, sh);
+            }
+            Backend::RecursiveF64 => {
+                for (l, m) in self.harmonics() {
+                    sh.push(self.sh.eval_recursive_f64(l, m, p));
+                }
+            }
+            Backend::Tabulated => match &self.table {
+                Some(table) => self.sh.eval_set_from_legendre(
+                    self.min_degree,
+                    self.degree,
+                    self.max_order,
+                    &table.interpolate(p.theta()),
+                    p,
+                    sh,
+                ),
+                None => {
+                    self.sh
+                        .eval_recursive_set(self.min_degree, self.degree, self.max_order, p, sh)
+                }
+            },
+            #[cfg(feature = "xnumber")]
+            Backend::Extended => {
+                for (l, m) in self.harmonics() {
+                    sh.push(self.sh.eval_extended(l, m, p));
+                }
+            }
+        }
+    }
+
+    /// Evaluate harmonics at position `p` one harmonic at a time, apart from the unrolled
+    /// closed-form expressions, and push them to `sh` without allocating
+    #[inline(always)]
+    fn eval_per_harmonic<C, B>(&self, p: &C, sh: &mut B)
+    where
+        C: SHCoordinates<T>,
+        B: Push<E::Output>,
+    {
+        if self.selection.is_some() {
+            for (l, m) in self.harmonics() {
+                sh.push(self.eval_single(l, m, p));
+            }
+            return;
+        }
+        #[allow(unused_mut)]
+        let mut next = self.min_degree;
+        #[cfg(not(feature = "compact"))]
+        if matches!(self.backend, Backend::Hardcoded) {
+            // The unrolled evaluation covers whole degrees
+            next = next.max(self.eval_unrolled(p, sh) + 1);
+        }
+        for (l, m) in self.harmonics_from(next) {
+            sh.push(self.eval_single(l, m, p));
+        }
+    }
+
+    /// Evaluate harmonic (`l`, `m`) of the set at position `p` with the backend of the set
+    #[inline(always)]
+    fn eval_single<C>(&self, l: i64, m: i64, p: &C) -> E::Output
+    where
+        C: SHCoordinates<T>,
+    {
+        match self.backend {
+            Backend::Hardcoded => self.sh.eval_valid(l, m, p),
+            Backend::Recursive | Backend::Tabulated => self.sh.eval_recursive_valid(l, m, p),
+            Backend::RecursiveF64 => self.sh.eval_recursive_f64(l, m, p),
+            #[cfg(feature = "xnumber")]
+            Backend::Extended => self.sh.eval_extended(l, m, p),
+        }
+    }
+
+    /// Evaluate the harmonics of a [`from_indices`](`HarmonicsSet::from_indices`) set at position
+    /// `p` and append them to `sh`
+    fn eval_selection<C>(&self, selection: &Selection, p: &C, sh: &mut Vec<E::Output>)
+    where
+        C: SHCoordinates<T>,
+    {
+        let dense = selection.is_dense();
+        match self.backend {
+            Backend::Recursive if dense => {
+                self.sh.eval_recursive_selection(
+                    self.degree,
+                    self.max_order,
+                    &selection.harmonics,
+                    p,
+                    sh,
+                );
+            }
+            Backend::Hardcoded if dense => {
+                // Closed-form expressions for the same degrees as for the other sets
+                let mut recursive = Vec::with_capacity(selection.recursive.len());
+                self.sh.eval_recursive_selection(
+                    self.degree,
+                    self.max_order,
+                    &selection.recursive,
+                    p,
+                    &mut recursive,
+                );
+                let mut recursive = recursive.into_iter();
+                for &(l, m) in &selection.harmonics {
+                    sh.push(if selection.is_recursive(l) {
+                        recursive.next().unwrap()
+                    } else {
+                        self.sh.eval(l, m, p)
+                    });
+                }
+            }
+            _ => {
+                for &(l, m) in &selection.harmonics {
+                    sh.push(self.eval_single(l, m, p));
+                }
+            }
+        }
+    }
+
+    /// Evaluate harmonics at position `p` via [`SHEval::eval_recursive_set`] (`compact` feature:
+    /// no closed-form expressions).
+    #[cfg(feature = "compact")]
+    #[inline]
+    fn eval_hardcoded<C>(&self, p: &C, sh: &mut Vec<E::Output>)
+    where
+        C: SHCoordinates<T>,
+    {
+        self.sh
+            .eval_recursive_set(self.min_degree, self.degree, self.max_order, p, sh);
+    }
+
+    /// Evaluate harmonics at position `p` via [`SHEval::eval`], unrolled up to the degree of the
+    /// closed-form expressions, and via [`SHEval::eval_recursive_set`] for higher degrees and
+    /// harmonics without closed-form expressions.
+    #[cfg(not(feature = "compact"))]
+    #[inline]
+    fn eval_hardcoded<C>(&self, p: &C, sh: &mut Vec<E::Output>)
+    where
+        C: SHCoordinates<T>,
+    {
+        if !self.sh.has_closed_forms() {
+            self.sh
+                .eval_recursive_set(self.min_degree, self.degree, self.max_order, p, sh);
+            return;
+        }
+        let closed = self.eval_unrolled(p, sh);
+        let recursive = self.min_degree.max(closed + 1);
+        if recursive <= self.degree {
+            self.sh
+                .eval_recursive_set(recursive, self.degree, self.max_order, p, sh);
+        }
+    }
+
+    /// Evaluate the harmonics of the degrees up to the degree of the closed-form expressions at
+    /// position `p` via [`SHEval::eval`] and push them to `sh`. Returns the highest degree
+    /// evaluated, which is at most the highest order of the set, such that all evaluated degrees
+    /// are complete.
+    #[cfg(not(feature = "compact"))]
+    #[inline(always)]
+    fn eval_unrolled<C, B>(&self, p: &C, sh: &mut B) -> usize
+    where
+        C: SHCoordinates<T>,
+        B: Push<E::Output>,
+    {
+        let closed = self.max_order.min(CLOSED_FORM_DEGREE);
+        // One `if` per degree with one `push` per harmonic, generated by the build script up to
+        // the degree of the closed-form expressions. With constant (l, m), the compiler inlines
+        // the closed-form expression of each harmonic, which makes this about twice as fast as a
+        // loop.
+        include!(concat!(env!("OUT_DIR"), "/unrolled_set.rs"));
+        closed
+    }
+}
+
+/// Destination of evaluated harmonics
+trait Push<O> {
+    fn push(&mut self, value: O);
+}
+
+impl<O> Push<O> for Vec<O> {
+    #[inline(always)]
+    fn push(&mut self, value: O) {
+        Vec::push(self, value);
+    }
+}
+
+/// Slice filled from the front, see [`HarmonicsSet::eval_array`] and
+/// [`HarmonicsSet::eval_realtime`]
+///
+/// Values beyond the end of the slice are dropped instead of panicking; the callers check the
+/// length beforehand.
+struct SliceBuffer<'a, O> {
+    data: &'a mut [O],
+    len: usize,
+}
+
+impl<O> Push<O> for SliceBuffer<'_, O> {
+    #[inline(always)]
+    fn push(&mut self, value: O) {
+        if let Some(slot) = self.data.get_mut(self.len) {
+            *slot = value;
+        }
+        self.len += 1;
+    }
+}
+impl<T, E> HarmonicsSet<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T, Output = T>,
+{
+    /// Evaluate the expansion with the given coefficients at position `p` with compensated
+    /// summation
+    ///
+    /// Same as [`eval_sum`](`HarmonicsSet::eval_sum`), but the products of harmonics and
+    /// coefficients are summed with Neumaier's compensated summation, which keeps the rounding
+    /// error of the sum independent of the number of coefficients. This is noticeably more
+    /// accurate for long expansions of high degree, in particular in `f32`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let set = HarmonicsSet::new(8, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0f32, 0.3, 1.2);
+    /// let coeffs = vec![0.25f32; set.num_sh()];
+    /// let value = set.eval_sum_compensated(&p, &coeffs).unwrap();
+    /// let plain: f32 = set.eval_sum(&p, &coeffs).unwrap();
+    /// assert!((value - plain).abs() < 1e-5);
+    /// ```
+    pub fn eval_sum_compensated<C>(&self, p: &C, coefficients: &[T]) -> Result<T, SphrsError>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.length_policy.check(self.num_sh, coefficients.len())?;
+        Ok(neumaier_sum(
+            self.eval_internal(p)
+                .into_iter()
+                .zip(coefficients.iter())
+                .map(|(a, &b)| b * a),
+        ))
+    }
+
+    /// Evaluate harmonics at position `p` and round them to half precision (`half::f16`)
+    ///
+    /// The harmonics are computed in `T` (e.g. `f32` or `f64`) and only the results are rounded,
+    /// which is meant for storing precomputed tables compactly, e.g. for GPUs. Real harmonics of
+    /// degree l are bounded by sqrt((2 l + 1) / (4 pi)), which is well within the range of `f16`
+    /// for any degree; the precision is limited to about three significant digits.
+    #[cfg(feature = "half")]
+    pub fn eval_f16<C>(&self, p: &C) -> Vec<half::f16>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.eval_internal(p)
+            .into_iter()
+            .map(|v| half::f16::from_f64(v.to_f64().unwrap()))
+            .collect()
+    }
+
+    /// Evaluate harmonics at all `points` and store them in half precision, one row of
+    /// [`num_sh`](`HarmonicsSet::num_sh`) values per point
+    ///
+    /// See [`eval_f16`](`HarmonicsSet::eval_f16`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH};
+    /// use sphrs::Coordinates;
+    ///
+    /// let set = HarmonicsSet::new(3, RealSH::Spherical);
+    /// let points = [
+    ///     Coordinates::spherical(1.0f32, 0.3, 1.2),
+    ///     Coordinates::spherical(1.0f32, 2.1, 4.0),
+    /// ];
+    /// let table = set.eval_table_f16(&points);
+    /// assert_eq!(table.len(), 2 * set.num_sh());
+    /// assert!((table[0].to_f32() - 0.2820948).abs() < 1e-3);
+    /// ```
+    #[cfg(feature = "half")]
+    pub fn eval_table_f16<C>(&self, points: &[C]) -> Vec<half::f16>
+    where
+        C: SHCoordinates<T>,
+    {
+        let mut table = Vec::with_capacity(points.len() * self.num_sh);
+        for p in points {
+            table.extend(
+                self.eval_internal(p)
+                    .into_iter()
+                    .map(|v| half::f16::from_f64(v.to_f64().unwrap())),
+            );
+        }
+        table
+    }
+}
+
+impl<T, E> HarmonicsSet<T, E>
+where
+    T: SphrsFloat,
+    E: SHGrad<T>,
+{
+    /// Evaluate harmonics and their derivatives with respect to theta and phi at position `p`
+    ///
+    /// Returns the values, the derivatives with respect to theta and the derivatives with respect
+    /// to phi, each with one entry per harmonic in the order of [`eval`](`HarmonicsSet::eval`).
+    /// For sets of whole degrees and dense selections, all Legendre functions, their derivatives
+    /// and the multiple angles are computed in one pass of recurrences (see
+    /// [`SHGrad::eval_grad_set`]), instead of once per harmonic as with [`SHGrad::eval_grad`]. As
+    /// for [`SHGrad::eval_grad`], the derivatives of solid harmonics are taken at a fixed radius
+    /// and are accurate at the poles. The values are always computed by recurrences, independent
+    /// of the [`Backend`], and agree with [`eval`](`HarmonicsSet::eval`) up to rounding.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{HarmonicsSet, RealSH, SHGrad};
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(4, RealSH::Spherical);
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let (values, d_theta, d_phi): (Vec<f64>, _, _) = sh.eval_grad(&p);
+    /// assert_eq!(values.len(), sh.num_sh());
+    /// let i = sh.index(3, -2).unwrap();
+    /// let (y, dt, dp) = RealSH::Spherical.eval_grad(3, -2, &p);
+    /// assert!((values[i] - y).abs() < 1e-14);
+    /// assert!((d_theta[i] - dt).abs() < 1e-14);
+    /// assert!((d_phi[i] - dp).abs() < 1e-14);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn eval_grad<C>(&self, p: &C) -> (Vec<E::Output>, Vec<E::Output>, Vec<E::Output>)
+    where
+        C: SHCoordinates<T>,
+    {
+        let mut values = Vec::with_capacity(self.num_sh);
+        let mut d_theta = Vec::with_capacity(self.num_sh);
+        let mut d_phi = Vec::with_capacity(self.num_sh);
+        match &self.selection {
+            Some(selection) if selection.is_dense() => {
+                let mut band = (Vec::new(), Vec::new(), Vec::new());
+                self.sh.eval_grad_set(
+                    self.min_degree,
+                    self.degree,
+                    self.max_order,
+                    p,
+                    (&mut band.0, &mut band.1, &mut band.2),
+                );
+                selection.gather(band.0, &mut values);
+                selection.gather(band.1, &mut d_theta);
+                selection.gather(band.2, &mut d_phi);
+            }
+            Some(selection) => {
+                for &(l, m) in &selection.harmonics {
+                    let (y, dt, dp) = self.sh.eval_grad(l, m, p);
+                    values.push(y);
+                    d_theta.push(dt);
+                    d_phi.push(dp);
+                }
+            }
+            None => self.sh.eval_grad_set(
+                self.min_degree,
+                self.degree,
+                self.max_order,
+                p,
+                (&mut values, &mut d_theta, &mut d_phi),
+            ),
+        }
+        (values, d_theta, d_phi)
+    }
+}
+
+impl<T> HarmonicsSet<T, ComplexSH>
+where
+    T: SphrsFloat,
+{
+    /// Evaluate the complex harmonics of orders m >= 0 at position `p`
+    ///
+    /// The harmonics of negative order follow from Y_l^{-m} = (-1)^m conj(Y_l^m) (without the
+    /// sign in the acoustics convention), which is how [`eval`](`HarmonicsSet::eval`) computes
+    /// them. Returns the harmonics of the degrees and orders 0 <= m <= min(l,
+    /// [`max_order`](`HarmonicsSet::max_order`)) of the set, ordered by degree and then by order,
+    /// i.e. at [`legendre::index`](`crate::legendre::index`) for sets of all degrees and orders.
+    /// For sets created with [`HarmonicsSet::from_indices`], this is the band of degrees and
+    /// orders covering the selection. The values are always computed by recurrences, independent
+    /// of the [`Backend`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{ComplexSH, HarmonicsSet};
+    /// use sphrs::legendre::index;
+    /// use sphrs::Coordinates;
+    ///
+    /// let sh = HarmonicsSet::new(5, ComplexSH::Spherical);
+    /// let p = Coordinates::spherical(1.0f64, 0.8, 0.4);
+    /// let half = sh.eval_half_spectrum(&p);
+    /// assert_eq!(half.len(), 21);
+    /// let full = sh.eval(&p);
+    /// let y = half[index(3, 2)];
+    /// assert!((full[sh.index(3, 2).unwrap()] - y).norm() < 1e-14);
+    /// assert!((full[sh.index(3, -2).unwrap()] - y.conj()).norm() < 1e-14);
+    /// ```
+    pub fn eval_half_spectrum<C>(&self, p: &C) -> Vec<Complex<T>>
+    where
+        C: SHCoordinates<T>,
+    {
+        let mut out = Vec::new();
+        self.sh
+            .eval_nonnegative_orders(self.min_degree, self.degree, self.max_order, p, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::RealSH;
+    use crate::{CartesianCoordinates, Coordinates, SphrsError};
+    use approx::assert_relative_eq;
+    use num_complex::Complex;
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_precision_tables() {
+        let set = HarmonicsSet::new(12, RealSH::Spherical);
+        let points = [
+            Coordinates::spherical(1.0, 0.2, 0.1),
+            Coordinates::spherical(2.0, 1.7, 5.9),
+        ];
+        let table = set.eval_table_f16(&points);
+        assert_eq!(table.len(), 2 * set.num_sh());
+        for (row, p) in table.chunks(set.num_sh()).zip(&points) {
+            assert_eq!(row, set.eval_f16(p));
+            for (h, v) in row.iter().zip(set.eval(p)) {
+                let v: f64 = v;
+                assert!(
+                    (h.to_f64() - v).abs() <= 1e-3 * v.abs().max(1e-2),
+                    "{h} {v}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unrolled_set_is_complete() {
+        // Every (l, m) in order, across the closed-form degree and for all lower bounds
+        let p = &Coordinates::spherical(1.2, 0.4, 2.9);
+        let degree = 22;
+        for min_degree in 0..=degree {
+            let set = HarmonicsSet::builder()
+                .degree_range(min_degree, degree)
+                .sh_type(RealSH::Spherical)
+                .build()
+                .unwrap();
+            let values: Vec<f64> = set.eval(p);
+            let single: Vec<f64> = (min_degree as i64..=degree as i64)
+                .flat_map(|l| (-l..=l).map(move |m| RealSH::Spherical.eval(l, m, p)))
+                .collect();
+            assert_eq!(values.len(), single.len());
+            for (a, b) in values.iter().zip(&single) {
+                assert_relative_eq!(*a, *b, max_relative = 1e-12, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn array_evaluation() {
+        let p = Coordinates::spherical(0.7, 2.0, -0.9);
+        for backend in [Backend::Hardcoded, Backend::Recursive] {
+            let set = HarmonicsSet::builder()
+                .degree_range(2, 4)
+                .sh_type(ComplexSH::RegularSolid)
+                .backend(backend)
+                .build()
+                .unwrap();
+            let values: [_; 21] = set.eval_array(&p).unwrap();
+            for (a, b) in values.iter().zip(set.eval(&p)) {
+                assert_relative_eq!(*a, b, max_relative = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn batch_evaluation() {
+        let set = HarmonicsSet::new(5, ComplexSH::Spherical);
+        let points: Vec<_> = (0..600)
+            .map(|i| Coordinates::spherical(1.0, 0.005 * i as f64, 0.37 * i as f64))
+            .collect();
+        let table = set.eval_batch(&points);
+        assert_eq!(table.len(), points.len() * set.num_sh());
+        assert_eq!(table.capacity(), table.len());
+        for (row, p) in table.chunks(set.num_sh()).zip(&points) {
+            assert_eq!(row, &set.eval(p)[..]);
+        }
+        assert!(set.eval_batch::<Coordinates<f64>>(&[]).is_empty());
+    }
+
+    #[test]
+    fn recursive_set_matches_single_harmonics() {
+        let p = &Coordinates::spherical(0.8, 2.3, -1.4);
+        let (min_degree, degree) = (5, 50);
+        for sh in [
+            RealSH::Spherical,
+            RealSH::RegularSolid,
+            RealSH::IrregularSolid,
+        ] {
+            let mut set = Vec::new();
+            sh.eval_recursive_set(min_degree, degree, degree, p, &mut set);
+            let single: Vec<f64> = (min_degree as i64..=degree as i64)
+                .flat_map(|l| (-l..=l).map(move |m| sh.eval_recursive(l, m, p)))
+                .collect();
+            assert_eq!(set.len(), single.len());
+            for (a, b) in set.iter().zip(&single) {
+                assert_relative_eq!(*a, *b, max_relative = 1e-11, epsilon = 1e-14);
+            }
+        }
+        for sh in [
+            ComplexSH::Spherical,
+            ComplexSH::RegularSolidAcoustics,
+            ComplexSH::IrregularSolid,
+        ] {
+            let mut set = Vec::new();
+            sh.eval_recursive_set(min_degree, degree, degree, p, &mut set);
+            let single: Vec<_> = (min_degree as i64..=degree as i64)
+                .flat_map(|l| (-l..=l).map(move |m| sh.eval_recursive(l, m, p)))
+                .collect();
+            assert_eq!(set.len(), single.len());
+            for (a, b) in set.iter().zip(&single) {
+                assert_relative_eq!(*a, *b, max_relative = 1e-11, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn degree_band_indexing() {
+        let p = Coordinates::spherical(1.0, 0.9, -2.1);
+        let full = HarmonicsSet::new(8, RealSH::Spherical);
+        let band = HarmonicsSet::builder()
+            .degree_range(2, 8)
+            .sh_type(RealSH::Spherical)
+            .build()
+            .unwrap();
+        assert_eq!((band.min_degree(), band.degree()), (2, 8));
+        let (all, part): (Vec<f64>, Vec<f64>) = (full.eval(&p), band.eval(&p));
+        for l in 0..=8 {
+            for m in -l..=l {
+                let i = full.index(l, m).unwrap();
+                match band.index(l, m) {
+                    Some(j) => assert_eq!(part[j], all[i]),
+                    None => assert!(l < 2),
+                }
+            }
+        }
+        assert_eq!(band.index(8, 8), Some(band.num_sh() - 1));
+        assert_eq!(band.index(9, 0), None);
+        assert_eq!(band.index(3, 4), None);
+    }
+
+    #[test]
+    fn selected_harmonics() {
+        let p = Coordinates::spherical(0.7, 2.3, 0.4);
+        let full = HarmonicsSet::new(16, RealSH::IrregularSolid);
+        let all: Vec<f64> = full.eval(&p);
+        let even: Vec<(i64, i64)> = (0..=16)
+            .step_by(2)
+            .flat_map(|l| (-l..=l).map(move |m| (l, m)))
+            .collect();
+        let sparse = vec![(16, 3), (2, -1), (9, 0)];
+        for indices in [even, sparse] {
+            let set = HarmonicsSet::from_indices(&indices, RealSH::IrregularSolid).unwrap();
+            assert_eq!(set.num_sh(), indices.len());
+            let values = set.eval(&p);
+            assert_eq!(values.len(), indices.len());
+            for (i, &(l, m)) in indices.iter().enumerate() {
+                assert_eq!(set.index(l, m), Some(i));
+                let expected = all[full.index(l, m).unwrap()];
+                assert_relative_eq!(values[i], expected, max_relative = 1e-12, epsilon = 1e-14);
+            }
+            let ring = set.eval_ring(0.7, 2.3, &[0.4, 0.4]);
+            assert_eq!(ring.len(), 2 * values.len());
+            for (a, b) in ring.iter().zip(values.iter().chain(&values)) {
+                assert_relative_eq!(*a, *b, max_relative = 1e-12, epsilon = 1e-14);
+            }
+            let packets = set.eval_simd(&[p.clone(), p.clone(), p.clone(), p.clone()]);
+            for (a, b) in packets.iter().zip(&values) {
+                assert_relative_eq!(a[3], *b, max_relative = 1e-12, epsilon = 1e-14);
+            }
+        }
+        let set = HarmonicsSet::from_indices(&[(1, 1), (0, 0)], ComplexSH::Spherical).unwrap();
+        let values: [_; 2] = set.eval_array(&p).unwrap();
+        assert_eq!(&values[..], &set.eval(&p)[..]);
+        assert_eq!(set.index(1, 0), None);
+
+        for indices in [vec![], vec![(1, 2)], vec![(2, 1), (3, 0), (2, 1)]] {
+            assert!(matches!(
+                HarmonicsSet::<f64, _>::from_indices(&indices, RealSH::Spherical),
+                Err(SphrsError::InvalidConfiguration(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn even_degree_sets() {
+        let p = Coordinates::spherical(1.0, 0.4, 2.9);
+        for degree in [0, 4, 31] {
+            let all: Vec<f64> = HarmonicsSet::new(degree, RealSH::Spherical).eval(&p);
+            let values = HarmonicsSet::even_degrees(degree, RealSH::Spherical).eval(&p);
+            let expected: Vec<f64> = (0..=degree)
+                .step_by(2)
+                .flat_map(|l| all[l * l..(l + 1) * (l + 1)].iter().copied())
+                .collect();
+            // Same evaluation as the full set
+            assert_eq!(values, expected);
+        }
+        let indices = [(7, -3), (2, 0), (7, 7), (5, -5)];
+        for sh in [RealSH::Spherical, RealSH::IrregularSolid] {
+            let (mut band, mut selected) = (Vec::new(), Vec::new());
+            sh.eval_recursive_set(0, 7, 7, &p, &mut band);
+            sh.eval_recursive_selection(7, 7, &indices, &p, &mut selected);
+            for (&(l, m), y) in indices.iter().zip(selected) {
+                assert_eq!(y, band[(l * l + l + m) as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_evaluation() {
+        let p = Coordinates::spherical(1.3, 2.0, 0.1);
+        let truncated = HarmonicsSet::builder()
+            .degree_range(1, 14)
+            .max_order(6)
+            .sh_type(ComplexSH::RegularSolid)
+            .build()
+            .unwrap();
+        let selected = HarmonicsSet::from_indices(&[(3, 1), (0, 0)], ComplexSH::RegularSolid);
+        for set in [truncated, selected.unwrap()] {
+            let values = set.eval(&p);
+            let lazy: Vec<_> = set.iter_eval(&p).collect();
+            assert_eq!(lazy.len(), values.len());
+            for ((l, m, y), v) in lazy.into_iter().zip(values) {
+                assert_eq!(set.index(l, m).map(|i| i < set.num_sh()), Some(true));
+                assert_relative_eq!(y, v, max_relative = 1e-12, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn sets_are_shareable() {
+        fn assert_shareable<S: Clone + std::fmt::Debug + Send + Sync>() {}
+        assert_shareable::<HarmonicsSet<f32, RealSH>>();
+        assert_shareable::<HarmonicsSet<f64, ComplexSH>>();
+        let set = HarmonicsSet::<f64, _>::builder()
+            .degree_range(1, 6)
+            .sh_type(ComplexSH::RegularSolid)
+            .backend(Backend::RecursiveF64)
+            .length_policy(LengthPolicy::AllowShorter)
+            .build()
+            .unwrap();
+        assert_eq!(*set.sh_type(), ComplexSH::RegularSolid);
+        assert_eq!(set.backend(), Backend::RecursiveF64);
+        assert_eq!(set.length_policy(), LengthPolicy::AllowShorter);
+        assert_eq!((set.min_degree(), set.degree()), (1, 6));
+        let shared = std::sync::Arc::new(set);
+        let p = Coordinates::spherical(1.2, 0.4, -0.3);
+        let expected = shared.eval(&p);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (set, p) = (shared.clone(), p.clone());
+                std::thread::spawn(move || set.eval(&p))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn cartesian_coordinates() {
+        let points = [
+            (0.3, -0.5, 0.8),
+            (-2.0, 0.1, -0.7),
+            (0.0, 0.0, 1.5),
+            (1e-3, 0.0, 0.0),
+        ];
+        for sh_type in [
+            RealSH::Spherical,
+            RealSH::RegularSolid,
+            RealSH::IrregularSolid,
+        ] {
+            let set = HarmonicsSet::new(12, sh_type);
+            for (x, y, z) in points {
+                let fast: Vec<f64> = set.eval(&CartesianCoordinates::new(x, y, z));
+                let slow: Vec<f64> = set.eval(&Coordinates::cartesian(x, y, z));
+                // cos(theta) = z / r is exact where theta.cos() leaves a residue of about 1e-16
+                let scale = slow.iter().fold(1.0f64, |s, b| s.max(b.abs()));
+                for (a, b) in fast.iter().zip(&slow) {
+                    assert_relative_eq!(*a, *b, max_relative = 1e-13, epsilon = 1e-14 * scale);
+                }
+            }
+        }
+        let set = HarmonicsSet::new(5, RealSH::Spherical);
+        let origin: Vec<f64> = set.eval(&CartesianCoordinates::new(0.0, 0.0, 0.0));
+        assert_eq!(origin, set.eval(&Coordinates::cartesian(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn realtime_evaluation() {
+        fn check<E: SHEval<f64>>(sh: HarmonicsSet<f64, E>)
+        where
+            E::Output: Copy + std::fmt::Debug + PartialEq + Default,
+            E::Output: approx::RelativeEq<Epsilon = f64>,
+        {
+            let mut out = vec![E::Output::default(); sh.num_sh()];
+            let points = [
+                Coordinates::spherical(1.3, 0.4, 2.2),
+                Coordinates::spherical(0.7, 0.0, 0.0),
+                Coordinates::cartesian(-0.2, 0.7, -0.4),
+            ];
+            for p in &points {
+                sh.eval_realtime(p, &mut out).unwrap();
+                for (a, b) in out.iter().zip(sh.eval(p)) {
+                    assert_relative_eq!(*a, b, max_relative = 1e-12, epsilon = 1e-14);
+                }
+            }
+            assert!(matches!(
+                sh.eval_realtime(&points[0], &mut out[1..]),
+                Err(SphrsError::LengthMismatch { .. })
+            ));
+        }
+        check(HarmonicsSet::new(3, RealSH::Spherical));
+        check(HarmonicsSet::new(12, RealSH::RegularSolid));
+        check(HarmonicsSet::new(7, ComplexSH::Spherical));
+        check(
+            HarmonicsSet::builder()
+                .degree_range(2, 15)
+                .max_order(4)
+                .backend(Backend::Recursive)
+                .sh_type(RealSH::IrregularSolid)
+                .build()
+                .unwrap(),
+        );
+        check(HarmonicsSet::from_indices(&[(9, -3), (1, 0), (4, 4)], RealSH::Spherical).unwrap());
+    }
+
+    #[test]
+    fn chunked_evaluation() {
+        let sh = HarmonicsSet::new(5, ComplexSH::Spherical);
+        let points: Vec<Coordinates<f64>> = (0..1000)
+            .map(|i| Coordinates::spherical(1.0, 0.003 * i as f64, 0.01 * i as f64))
+            .collect();
+        let expected = sh.eval_batch(&points);
+
+        let mut table = vec![Complex::new(0.0, 0.0); expected.len()];
+        sh.eval_batch_into(&points, &mut table).unwrap();
+        assert_eq!(table, expected);
+        assert!(sh.eval_batch_into(&points[1..], &mut table).is_err());
+
+        let mut firsts = Vec::new();
+        let mut chunked = Vec::new();
+        sh.eval_chunked(points.iter().cloned(), 300, |first, table| {
+            assert!(table.len() <= 300 * sh.num_sh());
+            firsts.push(first);
+            chunked.extend_from_slice(table);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(firsts, vec![0, 300, 600, 900]);
+        assert_eq!(chunked, expected);
+
+        let mut calls = 0;
+        let result = sh.eval_chunked(points.iter().cloned(), 300, |_, _| {
+            calls += 1;
+            Err(SphrsError::InvalidInput("full".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert!(sh.eval_chunked(points, 0, |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn complex_sets_without_closed_forms() {
+        // Complex sets evaluate the low degrees by the same recurrences as the higher degrees
+        let p = Coordinates::spherical(0.9, 1.1, 2.5);
+        let sh_type = ComplexSH::IrregularSolidAcoustics;
+        let mut expected = Vec::new();
+        sh_type.eval_recursive_set(0, 12, 12, &p, &mut expected);
+        for backend in [Backend::Hardcoded, Backend::Recursive] {
+            let set = HarmonicsSet::builder()
+                .degree(12)
+                .sh_type(sh_type)
+                .backend(backend)
+                .build()
+                .unwrap();
+            assert_eq!(set.eval(&p), expected, "{backend:?}");
+        }
+        let even = HarmonicsSet::even_degrees(12, sh_type);
+        for (y, (l, m)) in even.eval(&p).into_iter().zip(even.harmonics()) {
+            assert_eq!(y, expected[crate::sh_index(l, m)]);
+        }
+    }
+
+    #[test]
+    fn gradient_sets() {
+        // Compare against the derivatives of each harmonic, including a point at the pole
+        for p in [
+            Coordinates::spherical(1.3, 0.7, -2.3),
+            Coordinates::spherical(0.8, 0.0, 0.4),
+        ] {
+            for (degree_range, max_order) in [((0, 12), None), ((3, 20), Some(5))] {
+                let mut builder = HarmonicsSet::builder()
+                    .degree_range(degree_range.0, degree_range.1)
+                    .sh_type(RealSH::IrregularSolid);
+                if let Some(max_order) = max_order {
+                    builder = builder.max_order(max_order);
+                }
+                let set = builder.build().unwrap();
+                let (values, d_theta, d_phi) = set.eval_grad(&p);
+                assert_eq!(values.len(), set.num_sh());
+                for (i, (l, m)) in set.harmonics().enumerate() {
+                    let (y, dt, dp) = RealSH::IrregularSolid.eval_grad(l, m, &p);
+                    assert_relative_eq!(values[i], y, max_relative = 1e-12, epsilon = 1e-14);
+                    assert_relative_eq!(d_theta[i], dt, max_relative = 1e-12, epsilon = 1e-13);
+                    assert_relative_eq!(d_phi[i], dp, max_relative = 1e-12, epsilon = 1e-13);
+                }
+            }
+            let sparse = HarmonicsSet::from_indices(&[(7, -3), (2, 1)], ComplexSH::RegularSolid);
+            for set in [
+                HarmonicsSet::new(10, ComplexSH::SphericalAcoustics),
+                HarmonicsSet::even_degrees(10, ComplexSH::RegularSolid),
+                sparse.unwrap(),
+            ] {
+                let (values, d_theta, d_phi) = set.eval_grad(&p);
+                for (i, (l, m)) in set.harmonics().enumerate() {
+                    let (y, dt, dp) = set.sh_type().eval_grad(l, m, &p);
+                    assert_relative_eq!(values[i], y, max_relative = 1e-12, epsilon = 1e-14);
+                    assert_relative_eq!(d_theta[i], dt, max_relative = 1e-12, epsilon = 1e-13);
+                    assert_relative_eq!(d_phi[i], dp, max_relative = 1e-12, epsilon = 1e-13);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ring_evaluation() {
+        let phis: Vec<f64> = (0..37).map(|i| -3.0 + 0.17 * i as f64).collect();
+        let (r, theta) = (1.3, 0.7);
+        let real = HarmonicsSet::builder()
+            .degree_range(2, 25)
+            .sh_type(RealSH::RegularSolid)
+            .build()
+            .unwrap();
+        let table = real.eval_ring(r, theta, &phis);
+        assert_eq!(table.len(), phis.len() * real.num_sh());
+        for (row, &phi) in table.chunks(real.num_sh()).zip(&phis) {
+            let p = Coordinates::spherical(r, theta, phi);
+            for (a, b) in row.iter().zip(real.eval(&p)) {
+                assert_relative_eq!(*a, b, max_relative = 1e-11, epsilon = 1e-13);
+            }
+        }
+        let complex = HarmonicsSet::new(12, ComplexSH::IrregularSolidAcoustics);
+        let table = complex.eval_ring(r, theta, &phis);
+        for (row, &phi) in table.chunks(complex.num_sh()).zip(&phis) {
+            let p = Coordinates::spherical(r, theta, phi);
+            for (a, b) in row.iter().zip(complex.eval(&p)) {
+                assert_relative_eq!(*a, b, max_relative = 1e-11, epsilon = 1e-13);
+            }
+        }
+        assert!(complex.eval_ring(r, theta, &[]).is_empty());
+    }
+
+    #[test]
+    fn compensated_eval_sum() {
+        let set = HarmonicsSet::new(40, RealSH::Spherical);
+        let p = Coordinates::spherical(1.0f32, 1.1, 0.6);
+        // Large coefficients of alternating sign such that the sum suffers from cancellation
+        let coeffs: Vec<f32> = (0..set.num_sh())
+            .map(|i| if i % 2 == 0 { 1e4 } else { -1e4 } * (1.0 + (i % 7) as f32))
+            .collect();
+        // Summing the f32 products in f64 is exact enough to serve as reference
+        let exact: f64 = set
+            .eval(&p)
+            .iter()
+            .zip(&coeffs)
+            .map(|(&y, &c)| f64::from(y * c))
+            .sum();
+        let compensated = set.eval_sum_compensated(&p, &coeffs).unwrap();
+        let plain: f32 = set.eval_sum(&p, &coeffs).unwrap();
+        let compensated_error = (f64::from(compensated) - exact).abs();
+        let plain_error = (f64::from(plain) - exact).abs();
+        assert!(compensated_error <= f64::from(f32::EPSILON) * exact.abs());
+        assert!(compensated_error < plain_error);
+    }
+
+    #[test]
+    fn eval_sum_with_truncated_models() {
+        let p = Coordinates::spherical(1.0, 0.9, 2.1);
+        let coeffs: Vec<f64> = (0..16).map(|j| (j as f64 * 0.4).sin()).collect();
+        let full = HarmonicsSet::new(3, RealSH::Spherical);
+        let expected: f64 = full
+            .eval_with_coefficients(&p, &coeffs[..])
+            .unwrap()
+            .iter()
+            .sum();
+        assert_relative_eq!(full.eval_sum(&p, &coeffs).unwrap(), expected);
+
+        // A lower degree set ignores the tail, a higher degree set pads with zeros
+        for (degree, policy) in [
+            (1, LengthPolicy::AllowLonger),
+            (5, LengthPolicy::AllowShorter),
+        ] {
+            let set = HarmonicsSet::builder()
+                .degree(degree)
+                .sh_type(RealSH::Spherical)
+                .length_policy(policy)
+                .build()
+                .unwrap();
+            let n = set.num_sh().min(coeffs.len());
+            let expected: f64 = full.eval(&p)[..n]
+                .iter()
+                .zip(&coeffs)
+                .map(|(y, c)| y * c)
+                .sum();
+            assert_relative_eq!(
+                set.eval_sum(&p, &coeffs).unwrap(),
+                expected,
+                epsilon = 1e-14
+            );
+            assert_eq!(
+                set.eval_with_coefficients(&p, &coeffs).unwrap().len(),
+                set.num_sh()
+            );
+        }
+        assert!(matches!(
+            full.eval_sum(&p, &coeffs[..15]),
+            Err(SphrsError::LengthMismatch {
+                expected: 16,
+                found: 15
+            })
+        ));
+    }
+
+    #[test]
+    fn real_coefficients_with_complex_harmonics() {
+        // Generic over the float type, which requires the bounds to hold for any `T`
+        fn expand<T: SphrsFloat>(
+            set: &HarmonicsSet<T, ComplexSH>,
+            p: &Coordinates<T>,
+            coeffs: &[T],
+        ) -> (Vec<Complex<T>>, Complex<T>) {
+            (
+                set.eval_with_coefficients(p, coeffs).unwrap(),
+                set.eval_sum(p, coeffs).unwrap(),
+            )
+        }
+        let set = HarmonicsSet::builder()
+            .degree(4)
+            .sh_type(ComplexSH::Spherical)
+            .length_policy(LengthPolicy::AllowShorter)
+            .build()
+            .unwrap();
+        let p = Coordinates::spherical(1.0, 0.7, -1.9);
+        let real: Vec<f64> = (0..20).map(|j| (j as f64 * 0.3).cos()).collect();
+        let complex: Vec<Complex<f64>> = real.iter().map(|&c| Complex::new(c, 0.0)).collect();
+        let (products, sum) = expand(&set, &p, &real);
+        assert_eq!(products, set.eval_with_coefficients(&p, &complex).unwrap());
+        let expected: Complex<f64> = set.eval_sum(&p, &complex).unwrap();
+        assert_relative_eq!(sum.re, expected.re, epsilon = 1e-14);
+        assert_relative_eq!(sum.im, expected.im, epsilon = 1e-14);
+    }
+}
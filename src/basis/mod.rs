@@ -0,0 +1,809 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Real and complex spherical/solid harmonics
+//!
+//! The enums [`RealSH`] and [`ComplexSH`] select the kind of harmonic, [`HarmonicsSet`] evaluates
+//! all harmonics up to a given degree at once, [`FixedHarmonicsSet`] does so without allocating
+//! for a degree known at compile time, and [`HarmonicsExpansion`] combines a set with
+//! coefficients to a function on the sphere. The low level functions in this module compute
+//! individual harmonics directly. [`VectorHarmonicsSet`] evaluates the vector spherical harmonics
+//! used to expand tangential vector fields.
+
+// Allow comparison chains because benchmarking shows that they are much faster than match
+// expressions.
+#![allow(clippy::comparison_chain)]
+
+mod addition;
+mod builder;
+mod complex;
+mod expansion;
+mod fixed;
+mod hardcoded;
+mod harmonicsset;
+mod packet;
+mod polynomial;
+mod real;
+mod tabulated;
+mod unitary;
+mod vector;
+mod zernike;
+mod zonal;
+
+pub use addition::{addition_theorem, addition_theorem_all};
+pub use builder::{Backend, HarmonicsSetBuilder, LengthPolicy, Parallelism, MAX_TABULATED_BYTES};
+pub use complex::{ComplexSH, PhaseConvention};
+pub use expansion::HarmonicsExpansion;
+pub use fixed::FixedHarmonicsSet;
+#[cfg(not(feature = "compact"))]
+use hardcoded::{real_sh_closed_form, CLOSED_FORM_DEGREE};
+pub use hardcoded::{
+    sh00, sh10, sh1n1, sh1p1, sh20, sh2n1, sh2n2, sh2p1, sh2p2, sh30, sh3n1, sh3n2, sh3n3, sh3p1,
+    sh3p2, sh3p3,
+};
+pub use harmonicsset::HarmonicsSet;
+pub use polynomial::{real_regular_solid_polynomial, Monomial};
+pub use real::RealSH;
+pub use unitary::{
+    complex_from_real_matrix, complex_to_real_coefficients, real_from_complex_matrix,
+    real_to_complex_coefficients,
+};
+pub use vector::{
+    vsh_degree_order, vsh_index, Tangential, VectorHarmonicsSet, VectorSHCoefficients,
+};
+pub use zernike::{zernike_index, zernike_radial, ZernikeSet};
+pub use zonal::{zonal, zonal_set};
+
+use crate::coordinates::{Coordinates, SHCoordinates};
+use crate::legendre::{
+    normalized_legendre, normalized_legendre_dtheta, normalized_legendre_table_orders,
+    LegendreTable,
+};
+#[cfg(feature = "xnumber")]
+use crate::xnumber::normalized_legendre_x;
+use crate::SphrsFloat;
+use num_complex::Complex;
+
+/// Harmonics evaluation trait
+///
+/// Every kind of harmonics needs to implement this in order to be usable with [`HarmonicsSet`].
+pub trait SHEval<T> {
+    /// Output type
+    type Output;
+
+    /// Evaluate SH (l, m) at position `p`
+    fn eval(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output;
+
+    /// Evaluate SH (l, m) with |m| <= l at position `p` without checking (l, m)
+    ///
+    /// Used by [`HarmonicsSet`] for its own harmonics, which are valid by construction, e.g. on
+    /// the path of [`HarmonicsSet::eval_realtime`]. Defaults to [`eval`](`SHEval::eval`).
+    /// [`RealSH`] and [`ComplexSH`] skip the assertions of [`eval`](`SHEval::eval`), with the same
+    /// results.
+    fn eval_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        self.eval(l, m, p)
+    }
+
+    /// Returns `true` if [`eval`](`SHEval::eval`) uses closed-form expressions for low degrees
+    ///
+    /// [`HarmonicsSet`]s with [`Backend::Hardcoded`] evaluate the degrees of the closed-form
+    /// expressions one harmonic at a time with [`eval`](`SHEval::eval`) if this is `true`, and all
+    /// degrees with [`eval_recursive_set`](`SHEval::eval_recursive_set`) otherwise. Defaults to
+    /// `true`. [`ComplexSH`] has no closed-form expressions, and evaluating its harmonics one at a
+    /// time would repeat the Legendre recurrence and compute cos(m phi) and sin(m phi) for every
+    /// harmonic.
+    fn has_closed_forms(&self) -> bool {
+        true
+    }
+
+    /// Evaluate SH (l, m) at position `p` without using hardcoded closed-form expressions.
+    ///
+    /// Used by [`Backend::Recursive`]. Defaults to [`eval`](`SHEval::eval`).
+    fn eval_recursive(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        self.eval(l, m, p)
+    }
+
+    /// Evaluate SH (l, m) with |m| <= l at position `p` as
+    /// [`eval_recursive`](`SHEval::eval_recursive`) without checking (l, m)
+    ///
+    /// See [`eval_valid`](`SHEval::eval_valid`). Defaults to
+    /// [`eval_recursive`](`SHEval::eval_recursive`).
+    fn eval_recursive_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        self.eval_recursive(l, m, p)
+    }
+
+    /// Evaluate SH (l, m) at position `p` with the recursive implementation in `f64`, casting only
+    /// the result to the output type.
+    ///
+    /// Used by [`Backend::RecursiveF64`]. Defaults to [`eval_recursive`](`SHEval::eval_recursive`).
+    fn eval_recursive_f64(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        self.eval_recursive(l, m, p)
+    }
+
+    /// Evaluate SH (l, m) at position `p` with extended exponent arithmetic.
+    ///
+    /// Used by [`Backend::Extended`]. Defaults to [`eval_recursive`](`SHEval::eval_recursive`).
+    #[cfg(feature = "xnumber")]
+    fn eval_extended(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        self.eval_recursive(l, m, p)
+    }
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` and the orders |m| <= `max_order` at
+    /// position `p` with the recursive implementation and append them to `out`, ordered by degree
+    /// and then by order
+    ///
+    /// Used by [`HarmonicsSet`] for [`Backend::Recursive`] and for the degrees without
+    /// closed-form expressions. Defaults to [`eval_recursive`](`SHEval::eval_recursive`) for each
+    /// harmonic, which repeats the Legendre recurrence for every (l, m) and needs O(L^3)
+    /// operations. [`RealSH`] and [`ComplexSH`] compute all Legendre functions and all
+    /// cos(m phi) and sin(m phi) in a single pass of recurrences instead, which needs O(L^2), or
+    /// O(L M) for M = `max_order`.
+    fn eval_recursive_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Self::Output>,
+    ) {
+        for l in min_degree as i64..=degree as i64 {
+            let orders = l.min(max_order as i64);
+            for m in -orders..=orders {
+                out.push(self.eval_recursive(l, m, p));
+            }
+        }
+    }
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` and the orders |m| <= `max_order` at
+    /// position `p` given the orthonormalized Legendre functions at its colatitude and append
+    /// them to `out`, ordered by degree and then by order
+    ///
+    /// `legendre` holds K_lm P_l^m(cos theta) for 0 <= m <= l <= `degree` at
+    /// [`legendre::index`](`crate::legendre::index`)(l, m). Used by [`Backend::Tabulated`] with
+    /// interpolated Legendre functions. Defaults to
+    /// [`eval_recursive_set`](`SHEval::eval_recursive_set`), which ignores `legendre`. [`RealSH`]
+    /// and [`ComplexSH`] only compute the multiple angles and the radial factors.
+    fn eval_set_from_legendre(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        legendre: &[T],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Self::Output>,
+    ) {
+        let _ = legendre;
+        self.eval_recursive_set(min_degree, degree, max_order, p, out);
+    }
+
+    /// Evaluate the SH (l, m) in `harmonics` at position `p` with the recursive implementation and
+    /// append them to `out` in the given order
+    ///
+    /// All harmonics must have l <= `degree` and |m| <= `max_order`. Used by [`HarmonicsSet`]
+    /// for sets created with [`HarmonicsSet::from_indices`]. Defaults to
+    /// [`eval_recursive`](`SHEval::eval_recursive`) for each harmonic. [`RealSH`] and
+    /// [`ComplexSH`] compute the Legendre functions and the multiple angles as in
+    /// [`eval_recursive_set`](`SHEval::eval_recursive_set`), but only the selected harmonics, with
+    /// the same results.
+    fn eval_recursive_selection(
+        &self,
+        degree: usize,
+        max_order: usize,
+        harmonics: &[(i64, i64)],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<Self::Output>,
+    ) {
+        let _ = (degree, max_order);
+        for &(l, m) in harmonics {
+            out.push(self.eval_recursive(l, m, p));
+        }
+    }
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` and the orders |m| <= `max_order`
+    /// with the recursive implementation at the positions on the ring of radius `r` and
+    /// colatitude `theta` with the azimuths `phis` and append them to `out`, position by position
+    ///
+    /// Used by [`HarmonicsSet::eval_ring`]. Defaults to
+    /// [`eval_recursive_set`](`SHEval::eval_recursive_set`) at each position. [`RealSH`] and
+    /// [`ComplexSH`] compute the Legendre functions and the radial factors only once for the whole
+    /// ring.
+    fn eval_recursive_ring(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        (r, theta): (T, T),
+        phis: &[T],
+        out: &mut Vec<Self::Output>,
+    ) where
+        T: SphrsFloat,
+    {
+        for &phi in phis {
+            let p = Coordinates::spherical(r, theta, phi);
+            self.eval_recursive_set(min_degree, degree, max_order, &p, out);
+        }
+    }
+}
+
+/// Evaluation of harmonics together with their derivatives with respect to the angles
+///
+/// Implemented by [`RealSH`] and [`ComplexSH`] for all their variants. The derivatives of the
+/// solid harmonics are taken at a fixed radius. Division by sin(theta) is avoided, such that the
+/// derivatives are accurate at and close to the poles.
+pub trait SHGrad<T>: SHEval<T> {
+    /// Evaluate SH (l, m) at position `p`, returning the value and its derivatives with respect
+    /// to `theta` and `phi`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{RealSH, SHGrad};
+    /// use sphrs::Coordinates;
+    ///
+    /// // Y_10 = sqrt(3 / (4 pi)) cos(theta)
+    /// let p = Coordinates::spherical(1.0, 0.5, 0.2);
+    /// let (y, d_theta, d_phi) = RealSH::Spherical.eval_grad(1, 0, &p);
+    /// let c = (3.0 / (4.0 * std::f64::consts::PI)).sqrt();
+    /// assert!((y - c * 0.5f64.cos()).abs() < 1e-15);
+    /// assert!((d_theta + c * 0.5f64.sin()).abs() < 1e-15);
+    /// assert_eq!(d_phi, 0.0);
+    /// ```
+    fn eval_grad(
+        &self,
+        l: i64,
+        m: i64,
+        p: &impl SHCoordinates<T>,
+    ) -> (Self::Output, Self::Output, Self::Output);
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` and the orders |m| <= `max_order` at
+    /// position `p` together with their derivatives and append the values, the derivatives with
+    /// respect to `theta` and the derivatives with respect to `phi` to the three vectors of `out`,
+    /// ordered by degree and then by order
+    ///
+    /// Used by [`HarmonicsSet::eval_grad`]. Defaults to [`eval_grad`](`SHGrad::eval_grad`) for
+    /// each harmonic. [`RealSH`] and [`ComplexSH`] compute the Legendre functions, their
+    /// derivatives and the multiple angles in a single pass of recurrences, as in
+    /// [`SHEval::eval_recursive_set`].
+    #[allow(clippy::type_complexity)]
+    fn eval_grad_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        (values, d_theta, d_phi): (
+            &mut Vec<Self::Output>,
+            &mut Vec<Self::Output>,
+            &mut Vec<Self::Output>,
+        ),
+    ) {
+        for l in min_degree as i64..=degree as i64 {
+            let orders = l.min(max_order as i64);
+            for m in -orders..=orders {
+                let (y, dt, dp) = self.eval_grad(l, m, p);
+                values.push(y);
+                d_theta.push(dt);
+                d_phi.push(dp);
+            }
+        }
+    }
+}
+
+/// Complex spherical harmonic of order `m` from the orthonormalized Legendre function `v` of
+/// order |m|
+#[inline(always)]
+fn sh_from_legendre<T: SphrsFloat>(m: i64, p: &impl SHCoordinates<T>, v: T) -> Complex<T> {
+    let trig = (T::from_i64(m.abs()).unwrap() * p.phi()).sin_cos();
+    sh_from_trig(m, (trig.1, trig.0), v)
+}
+
+/// Complex spherical harmonic of order `m` from the orthonormalized Legendre function `v` of
+/// order |m| and (cos(|m| phi), sin(|m| phi))
+#[inline(always)]
+fn sh_from_trig<T: SphrsFloat>(m: i64, (cos, sin): (T, T), v: T) -> Complex<T> {
+    let (sign, sin) = if m < 0 {
+        (T::from_f64((-1f64).powi(m.abs() as i32)).unwrap(), -sin)
+    } else {
+        (T::from_f64(1.0).unwrap(), sin)
+    };
+    Complex::new(sign * v * cos, sign * v * sin)
+}
+
+/// Real spherical harmonic of order `m` from the orthonormalized Legendre function `v` of
+/// order |m|
+#[inline(always)]
+fn real_sh_from_legendre<T: SphrsFloat>(m: i64, p: &impl SHCoordinates<T>, v: T) -> T {
+    let trig = (T::from_i64(m.abs()).unwrap() * p.phi()).sin_cos();
+    real_sh_from_trig(m, (trig.1, trig.0), v)
+}
+
+/// Real spherical harmonic of order `m` from the orthonormalized Legendre function `v` of
+/// order |m| and (cos(|m| phi), sin(|m| phi))
+#[inline(always)]
+fn real_sh_from_trig<T: SphrsFloat>(m: i64, (cos, sin): (T, T), v: T) -> T {
+    let y = if m == 0 {
+        v
+    } else if m > 0 {
+        T::SQRT_2() * cos * v
+    } else {
+        T::SQRT_2() * sin * v
+    };
+    // (-1)^m
+    if m % 2 == 0 {
+        y
+    } else {
+        -y
+    }
+}
+
+/// Orthonormalized associated Legendre functions and their derivatives up to degree `degree` and
+/// order `max_order` (see [`LegendreTable::with_orders`]) and (cos(m phi), sin(m phi)) for
+/// 0 <= m <= `max_order` at `p` (see [`multiple_angles`])
+fn grad_tables<T: SphrsFloat>(
+    degree: usize,
+    max_order: usize,
+    p: &impl SHCoordinates<T>,
+) -> (LegendreTable<T>, Vec<(T, T)>) {
+    let max_order = max_order.min(degree);
+    let legendre = LegendreTable::with_orders(degree, max_order, p.theta_cos(), p.theta().sin());
+    let mut trig = Vec::with_capacity(max_order + 1);
+    multiple_angles(max_order, p.phi(), &mut trig);
+    (legendre, trig)
+}
+
+/// Orthonormalized associated Legendre functions up to degree `degree` and order `max_order`
+/// (see [`normalized_legendre_table_orders`]) and (cos(m phi), sin(m phi)) for
+/// 0 <= m <= `max_order` at `p` (see [`multiple_angles`])
+fn set_tables<T: SphrsFloat>(
+    degree: usize,
+    max_order: usize,
+    p: &impl SHCoordinates<T>,
+) -> (Vec<T>, Vec<(T, T)>) {
+    let max_order = max_order.min(degree);
+    let legendre =
+        normalized_legendre_table_orders(degree, max_order, p.theta_cos(), p.theta().sin());
+    let mut trig = Vec::with_capacity(max_order + 1);
+    multiple_angles(max_order, p.phi(), &mut trig);
+    (legendre, trig)
+}
+
+/// Replace the contents of `trig` by (cos(m phi), sin(m phi)) for 0 <= m <= `max_order`, computed
+/// by the recurrence e^{i m phi} = e^{i (m - 1) phi} e^{i phi}
+fn multiple_angles<T: SphrsFloat>(max_order: usize, phi: T, trig: &mut Vec<(T, T)>) {
+    trig.clear();
+    let (s, c) = phi.sin_cos();
+    let (mut cm, mut sm) = (T::one(), T::zero());
+    for _ in 0..=max_order {
+        trig.push((cm, sm));
+        (cm, sm) = (cm * c - sm * s, sm * c + cm * s);
+    }
+}
+
+/// Coordinates `p` converted to `f64`
+#[inline(always)]
+fn to_f64_coordinates<T: SphrsFloat>(p: &impl SHCoordinates<T>) -> Coordinates<f64> {
+    Coordinates::spherical(
+        p.r().to_f64().unwrap(),
+        p.theta().to_f64().unwrap(),
+        p.phi().to_f64().unwrap(),
+    )
+}
+
+/// Complex spherical harmonics
+#[inline(always)]
+pub fn sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+    assert!(l >= 0);
+    assert!(m.abs() <= l);
+    sh_valid(l, m, p)
+}
+
+/// Complex spherical harmonics for 0 <= |m| <= l, without checking (l, m)
+#[inline(always)]
+pub(crate) fn sh_valid<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+    let v: T = normalized_legendre(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    sh_from_legendre(m, p, v)
+}
+
+/// Real spherical harmonics (recursive implementation)
+#[inline(always)]
+pub fn real_sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    let v: T = normalized_legendre(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    real_sh_from_legendre(m, p, v)
+}
+
+/// Complex spherical harmonics and their derivatives with respect to theta and phi
+#[inline(always)]
+pub(crate) fn sh_grad<T: SphrsFloat>(
+    l: i64,
+    m: i64,
+    p: &impl SHCoordinates<T>,
+) -> (Complex<T>, Complex<T>, Complex<T>) {
+    assert!(l >= 0);
+    assert!(m.abs() <= l);
+    let (v, dv): (T, T) = normalized_legendre_dtheta(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    let y = sh_from_legendre(m, p, v);
+    let d_phi = y * Complex::new(T::zero(), T::from_i64(m).unwrap());
+    (y, sh_from_legendre(m, p, dv), d_phi)
+}
+
+/// Real spherical harmonics and their derivatives with respect to theta and phi
+#[inline(always)]
+pub(crate) fn real_sh_grad<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> (T, T, T) {
+    let (v, dv): (T, T) = normalized_legendre_dtheta(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    // cos(m phi) and sin(|m| phi) are exchanged by the derivative with respect to phi
+    let d_phi = match m {
+        0 => T::zero(),
+        m if m > 0 => -T::from_i64(m).unwrap() * real_sh_from_legendre(-m, p, v),
+        m => T::from_i64(-m).unwrap() * real_sh_from_legendre(-m, p, v),
+    };
+    (
+        real_sh_from_legendre(m, p, v),
+        real_sh_from_legendre(m, p, dv),
+        d_phi,
+    )
+}
+
+/// Complex spherical harmonics computed with extended exponent arithmetic
+#[cfg(feature = "xnumber")]
+pub(crate) fn sh_extended<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+    assert!(l >= 0);
+    assert!(m.abs() <= l);
+    let v: T = normalized_legendre_x(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    sh_from_legendre(m, p, v)
+}
+
+/// Real spherical harmonics computed with extended exponent arithmetic
+#[cfg(feature = "xnumber")]
+pub(crate) fn real_sh_extended<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    let v: T = normalized_legendre_x(
+        l as usize,
+        m.unsigned_abs() as usize,
+        p.theta_cos(),
+        p.theta().sin(),
+    );
+    real_sh_from_legendre(m, p, v)
+}
+
+/// Accelerated spherical harmonics.
+///
+/// This will use the closed-form expressions up to the degree given by the environment variable
+/// `SPHRS_HARDCODED_DEGREE` at build time (default 8) and the recursive implementation for
+/// higher degrees.
+///
+/// With the `compact` feature enabled, this is the same as [`real_sh`].
+#[cfg(feature = "compact")]
+#[inline(always)]
+pub fn real_sh_hardcoded<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    real_sh(l, m, p)
+}
+
+/// Accelerated spherical harmonics.
+///
+/// This will use the closed-form expressions generated up to the degree given by the environment
+/// variable `SPHRS_HARDCODED_DEGREE` at build time (default 8) and the recursive implementation
+/// for higher degrees. At the origin, both use the direction given by `theta` and `phi`.
+///
+/// With the `compact` feature enabled, this is the same as [`real_sh`].
+#[cfg(not(feature = "compact"))]
+#[inline(always)]
+pub fn real_sh_hardcoded<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    match real_sh_closed_form(l, m, p) {
+        Some(v) => v,
+        None => real_sh(l, m, p),
+    }
+}
+
+/// Complex regular solid harmonics
+///
+/// Finite everywhere; at the origin only the harmonic of degree 0 is nonzero.
+#[inline(always)]
+pub fn regular_solid_sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+    let scaling = ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt()
+        * p.r().powi(l as i32);
+    let sh = sh(l, m, p);
+    Complex::new(sh.re * scaling, sh.im * scaling)
+}
+
+/// Complex irregular solid harmonics
+///
+/// Singular at the origin.
+#[inline(always)]
+pub fn irregular_solid_sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> Complex<T> {
+    let scaling = ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt()
+        / p.r().powi((l + 1) as i32);
+    let sh = sh(l, m, p);
+    Complex::new(sh.re * scaling, sh.im * scaling)
+}
+
+/// Real regular solid harmonics
+///
+/// Finite everywhere; at the origin only the harmonic of degree 0 is nonzero.
+#[inline(always)]
+pub fn real_regular_solid_sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt()
+        * p.r().powi(l as i32)
+        * real_sh_hardcoded(l, m, p)
+}
+
+/// Real irregular solid harmonics
+///
+/// Singular at the origin.
+#[inline(always)]
+pub fn real_irregular_solid_sh<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> T {
+    ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt()
+        / p.r().powi(l as i32)
+        * real_sh_hardcoded(l, m, p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coordinates;
+    use std::f64::consts::PI;
+
+    macro_rules! comp {
+        ($l:expr, $m:expr, $p:tt, $hcf:expr, $tol:tt) => {
+            let rsh: f64 = real_sh($l, $m, $p);
+            let hsh: f64 = $hcf($p);
+            assert!((rsh - hsh).abs() < $tol);
+        };
+    }
+
+    #[cfg(not(feature = "compact"))]
+    #[test]
+    fn compare_generated_and_recursive() {
+        let points = [
+            Coordinates::spherical(1.0, 0.3, 2.0),
+            Coordinates::spherical(2.5, 1.9, -0.6),
+            Coordinates::cartesian(0.0, 0.0, -1.0),
+            Coordinates::cartesian(1.0, 1.0, 0.3),
+        ];
+        for p in &points {
+            let mut l = 0;
+            while let Some(v) = real_sh_closed_form::<f64>(l, 0, p) {
+                for m in -l..=l {
+                    let v = if m == 0 {
+                        v
+                    } else {
+                        real_sh_closed_form(l, m, p).unwrap()
+                    };
+                    let expected: f64 = real_sh(l, m, p);
+                    assert!((v - expected).abs() < 1e-13, "{l} {m} {v} {expected}");
+                }
+                l += 1;
+            }
+            assert!(l > 3);
+        }
+    }
+
+    #[test]
+    fn compare_hardcoded_and_recursive() {
+        let tol = 10.0 * f64::EPSILON;
+        let c = [
+            Coordinates::spherical(1.0, PI / 4.0, PI / 2.0),
+            Coordinates::spherical(2.0, PI / 4.0, PI / 2.0),
+            Coordinates::spherical(2.0, PI / 2.0, PI / 4.0),
+            Coordinates::spherical(0.5, 0.0, PI / 4.0),
+            Coordinates::spherical(0.75, PI / 2.0, 0.0),
+            Coordinates::cartesian(1.0, 1.0, 0.3),
+            Coordinates::cartesian(1.0, 0.0, 0.0),
+            Coordinates::cartesian(0.0, 1.0, 0.0),
+            Coordinates::cartesian(0.0, 0.0, 1.0),
+            Coordinates::cartesian(0.0, 0.0, 0.0),
+            Coordinates::spherical(0.0, 1.2, 0.3),
+        ];
+
+        for p in c.iter() {
+            // println!("{:?}", p);
+            // println!("{:?} | {:?}", real_SH(2, 2, p), sh2p2(p));
+            // println!("{:?}", (real_SH(2, 2, p) - sh2p2(p)).abs());
+            // 0th degree
+            comp!(0, 0, p, sh00, tol);
+            // 1st degree
+            comp!(1, -1, p, sh1n1, tol);
+            comp!(1, 0, p, sh10, tol);
+            comp!(1, 1, p, sh1p1, tol);
+            // 2nd degree
+            comp!(2, -2, p, sh2n2, tol);
+            comp!(2, -1, p, sh2n1, tol);
+            comp!(2, 0, p, sh20, tol);
+            comp!(2, 1, p, sh2p1, tol);
+            comp!(2, 2, p, sh2p2, tol);
+            // 3rd degree
+            comp!(3, -3, p, sh3n3, tol);
+            comp!(3, -2, p, sh3n2, tol);
+            comp!(3, -1, p, sh3n1, tol);
+            comp!(3, 0, p, sh30, tol);
+            comp!(3, 1, p, sh3p1, tol);
+            comp!(3, 2, p, sh3p2, tol);
+            comp!(3, 3, p, sh3p3, tol);
+        }
+    }
+
+    #[test]
+    fn origin_and_poles() {
+        use crate::basis::{ComplexSH, HarmonicsSet, RealSH};
+
+        let points = [
+            Coordinates::cartesian(0.0, 0.0, 0.0),
+            Coordinates::spherical(0.0, 1.2, 0.3),
+            Coordinates::cartesian(0.0, 0.0, 3.0),
+            Coordinates::cartesian(-0.0, 0.0, -3.0),
+            Coordinates::spherical(1.0, PI, 2.0),
+        ];
+        for p in &points {
+            for sh_type in [RealSH::Spherical, RealSH::RegularSolid] {
+                let values: Vec<f64> = HarmonicsSet::new(8, sh_type).eval(p);
+                assert!(values.iter().all(|v| v.is_finite()), "{sh_type} {p:?}");
+            }
+            for sh_type in [ComplexSH::Spherical, ComplexSH::RegularSolid] {
+                let values = HarmonicsSet::new(8, sh_type).eval(p);
+                let values: Vec<Complex<f64>> = values;
+                assert!(values.iter().all(|v| v.is_finite()), "{sh_type} {p:?}");
+            }
+        }
+
+        // Regular solid harmonics vanish at the origin except for degree 0
+        let values: Vec<f64> = HarmonicsSet::new(4, RealSH::RegularSolid).eval(&points[0]);
+        assert!((values[0] - 1.0).abs() < 1e-15);
+        assert!(values[1..].iter().all(|&v| v == 0.0));
+
+        // At the north pole only the zonal harmonics are nonzero
+        let values: Vec<f64> = HarmonicsSet::new(4, RealSH::Spherical).eval(&points[2]);
+        for l in 0..=4i64 {
+            for m in -l..=l {
+                let v = values[(l * l + l + m) as usize];
+                let expected = if m == 0 {
+                    ((2 * l + 1) as f64 / (4.0 * PI)).sqrt()
+                } else {
+                    0.0
+                };
+                assert!((v - expected).abs() < 1e-14, "{l} {m} {v}");
+            }
+        }
+    }
+
+    #[test]
+    fn angular_derivatives() {
+        let h = 1e-6;
+        let points = [(0.7, 0.4), (2.3, -1.9), (0.0, 0.6), (PI, 1.1)];
+        for &(theta, phi) in &points {
+            let at = |t: f64, f: f64| Coordinates::spherical(1.5, t, f);
+            let p = at(theta, phi);
+            for sh_type in [
+                RealSH::Spherical,
+                RealSH::RegularSolid,
+                RealSH::IrregularSolid,
+            ] {
+                for l in 0..=7i64 {
+                    for m in -l..=l {
+                        let (y, d_theta, d_phi) = sh_type.eval_grad(l, m, &p);
+                        assert!((y - sh_type.eval(l, m, &p)).abs() < 1e-13);
+                        let fd_theta = (sh_type.eval(l, m, &at(theta + h, phi))
+                            - sh_type.eval(l, m, &at(theta - h, phi)))
+                            / (2.0 * h);
+                        let fd_phi = (sh_type.eval(l, m, &at(theta, phi + h))
+                            - sh_type.eval(l, m, &at(theta, phi - h)))
+                            / (2.0 * h);
+                        assert!(
+                            (d_theta - fd_theta).abs() < 1e-7,
+                            "{sh_type} {l} {m} {theta}"
+                        );
+                        assert!((d_phi - fd_phi).abs() < 1e-7, "{sh_type} {l} {m} {theta}");
+                    }
+                }
+            }
+            for sh_type in [ComplexSH::Spherical, ComplexSH::IrregularSolidAcoustics] {
+                for l in 0..=7i64 {
+                    for m in -l..=l {
+                        let (y, d_theta, d_phi) = sh_type.eval_grad(l, m, &p);
+                        let value: Complex<f64> = sh_type.eval(l, m, &p);
+                        assert!((y - value).norm() < 1e-13);
+                        let fd_theta: Complex<f64> = (sh_type.eval(l, m, &at(theta + h, phi))
+                            - sh_type.eval(l, m, &at(theta - h, phi)))
+                            / (2.0 * h);
+                        assert!((d_theta - fd_theta).norm() < 1e-7, "{sh_type} {l} {m}");
+                        assert!((d_phi - value * Complex::new(0.0, m as f64)).norm() < 1e-14);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compare_recursive_complex_and_scipy() {
+        use csv;
+        use std::fs::File;
+
+        let tol = 10.0 * f64::EPSILON;
+        let file = File::open("test_helpers/scipy.csv").unwrap();
+        let mut rdr = csv::Reader::from_reader(file);
+        for result in rdr.records() {
+            let record = result.unwrap();
+            let l: i64 = record[0].parse().ok().unwrap();
+            let m: i64 = record[1].parse().ok().unwrap();
+            let phi: f64 = record[2].parse().ok().unwrap();
+            let theta: f64 = record[3].parse().ok().unwrap();
+            let scipy_res: Complex<f64> = Complex::new(
+                record[4].parse().ok().unwrap(),
+                record[5].parse().ok().unwrap(),
+            );
+            let coords = Coordinates::spherical(1.0, theta, phi);
+            let sphrs_res: Complex<f64> = sh(l, m, &coords);
+            // println!(
+            //     "{:?} | l: {:?}, m: {:?}, phi: {:?}, theta: {:?}, {:?} - {:?}",
+            //     idx, l, m, phi, theta, scipy_res, sphrs_res
+            // );
+            assert!((sphrs_res.re - scipy_res.re).abs() < tol);
+            assert!((sphrs_res.im - scipy_res.im).abs() < tol);
+        }
+    }
+
+    #[test]
+    fn compare_high_degree_and_mpmath() {
+        use csv;
+        use std::fs::File;
+
+        let file = File::open("test_helpers/high_degree.csv").unwrap();
+        let mut rdr = csv::Reader::from_reader(file);
+        for result in rdr.records() {
+            let record = result.unwrap();
+            let l: i64 = record[0].parse().unwrap();
+            let m: i64 = record[1].parse().unwrap();
+            let phi: f64 = record[2].parse().unwrap();
+            let theta: f64 = record[3].parse().unwrap();
+            let expected = Complex::new(
+                record[4].parse::<f64>().unwrap(),
+                record[5].parse::<f64>().unwrap(),
+            );
+            // The rounding errors of the recurrences grow linearly with the degree, relative to the
+            // maximum sqrt((2l + 1) / (4 pi)) of the harmonic
+            let max = ((2 * l + 1) as f64 / (4.0 * PI)).sqrt();
+            let tol = 4.0 * l as f64 * f64::EPSILON * max;
+            let coords = Coordinates::spherical(1.0, theta, phi);
+            let complex: Complex<f64> = sh(l, m, &coords);
+            assert!((complex - expected).norm() < tol, "{l} {m} {theta} {phi}");
+
+            let sign = if m % 2 == 0 { 1.0 } else { -1.0 };
+            let real: f64 = real_sh(l, m, &coords);
+            let expected_real = match m {
+                0 => expected.re,
+                m if m > 0 => sign * 2f64.sqrt() * expected.re,
+                _ => -2f64.sqrt() * expected.im,
+            };
+            assert!(
+                (real - expected_real).abs() < 2.0 * tol,
+                "{l} {m} {theta} {phi}"
+            );
+        }
+    }
+}
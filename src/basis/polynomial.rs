@@ -0,0 +1,169 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::collections::BTreeMap;
+
+use crate::SphrsFloat;
+
+/// Monomial `coefficient * x^a * y^b * z^c` with the exponents `powers = [a, b, c]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Monomial<T> {
+    /// Coefficient
+    pub coefficient: T,
+    /// Exponents of x, y and z
+    pub powers: [u32; 3],
+}
+
+impl<T: SphrsFloat> Monomial<T> {
+    /// Evaluate the monomial at (x, y, z)
+    pub fn eval(&self, x: T, y: T, z: T) -> T {
+        let [a, b, c] = self.powers;
+        self.coefficient * x.powi(a as i32) * y.powi(b as i32) * z.powi(c as i32)
+    }
+}
+
+/// Binomial coefficient C(n, k) as float
+fn binomial<T: SphrsFloat>(n: u32, k: u32) -> T {
+    (0..k).fold(T::one(), |acc, i| {
+        acc * T::from_u32(n - i).unwrap() / T::from_u32(i + 1).unwrap()
+    })
+}
+
+/// Real regular solid harmonic R_lm as a polynomial in the Cartesian coordinates
+///
+/// Returns the monomials of the homogeneous polynomial of degree `l` that equals
+/// [`RealSH::RegularSolid`](`crate::basis::RealSH::RegularSolid`), i.e.
+/// sqrt(4 pi / (2 l + 1)) r^l Y_lm, in ascending lexicographic order of the exponents (x, y, z).
+/// Monomials with a zero coefficient are omitted. The polynomial is
+///
+/// R_lm = N_lm P_l^|m|(z, r) A_|m|(x, y) for m >= 0 and N_lm P_l^|m|(z, r) B_|m|(x, y) for m < 0
+///
+/// with N_lm = sqrt((2 - delta_m0) (l - |m|)! / (l + |m|)!), the z-dependent part
+/// P_l^m(z, r) = 2^-l sum_k (-1)^k C(l, k) C(2 l - 2 k, l) (l - 2 k)! / (l - 2 k - m)!
+/// r^(2 k) z^(l - 2 k - m) and the real and imaginary part A_m + i B_m of (x + i y)^m. The
+/// coefficients are computed exactly up to the final normalization, which makes them suitable for
+/// code generation.
+///
+/// # Panics
+///
+/// Panics if |m| > l.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{real_regular_solid_polynomial, Monomial};
+///
+/// // R_20 = z^2 - (x^2 + y^2) / 2
+/// let poly = real_regular_solid_polynomial::<f64>(2, 0);
+/// assert_eq!(
+///     poly,
+///     vec![
+///         Monomial { coefficient: 1.0, powers: [0, 0, 2] },
+///         Monomial { coefficient: -0.5, powers: [0, 2, 0] },
+///         Monomial { coefficient: -0.5, powers: [2, 0, 0] },
+///     ]
+/// );
+/// ```
+pub fn real_regular_solid_polynomial<T: SphrsFloat>(l: u32, m: i64) -> Vec<Monomial<T>> {
+    assert!(m.unsigned_abs() <= l as u64, "order {m} exceeds degree {l}");
+    let a = m.unsigned_abs() as u32;
+    let factorial = |n: u32| (1..=n).fold(T::one(), |acc, i| acc * T::from_u32(i).unwrap());
+    let half_l = T::from_f64(0.5).unwrap().powi(l as i32);
+
+    // x and y dependency: real (m >= 0) or imaginary part (m < 0) of (x + i y)^a
+    let xy: Vec<(T, u32, u32)> = (0..=a)
+        .filter_map(|p| {
+            let q = a - p;
+            let keep = (m >= 0) == q.is_multiple_of(2);
+            if !keep {
+                return None;
+            }
+            let sign = if (q / 2).is_multiple_of(2) {
+                T::one()
+            } else {
+                -T::one()
+            };
+            Some((sign * binomial::<T>(a, p), p, q))
+        })
+        .collect();
+
+    let mut terms: BTreeMap<[u32; 3], T> = BTreeMap::new();
+    for k in 0..=(l - a) / 2 {
+        let sign = if k.is_multiple_of(2) {
+            T::one()
+        } else {
+            -T::one()
+        };
+        let zk = sign
+            * half_l
+            * binomial::<T>(l, k)
+            * binomial::<T>(2 * l - 2 * k, l)
+            * factorial(l - 2 * k)
+            / factorial(l - 2 * k - a);
+        let z_power = l - 2 * k - a;
+        // r^2k = (x^2 + y^2 + z^2)^k
+        for i in 0..=k {
+            for j in 0..=(k - i) {
+                let n = k - i - j;
+                let multinomial = factorial(k) / (factorial(i) * factorial(j) * factorial(n));
+                for &(c, p, q) in &xy {
+                    let key = [2 * i + p, 2 * j + q, 2 * n + z_power];
+                    let entry = terms.entry(key).or_insert_with(T::zero);
+                    *entry = *entry + zk * multinomial * c;
+                }
+            }
+        }
+    }
+
+    let two = if a == 0 {
+        T::one()
+    } else {
+        T::from_f64(2.0).unwrap()
+    };
+    let norm = (two * factorial(l - a) / factorial(l + a)).sqrt();
+    terms
+        .into_iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|(powers, c)| Monomial {
+            coefficient: norm * c,
+            powers,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{RealSH, SHEval};
+    use crate::Coordinates;
+
+    #[test]
+    fn compare_with_real_regular_solid_harmonics() {
+        let points = [
+            (0.3f64, -0.8, 1.1),
+            (-1.2, 0.4, 0.2),
+            (0.0, 0.0, 2.0),
+            (0.7, 0.7, -0.7),
+        ];
+        for l in 0..=10u32 {
+            for m in -(l as i64)..=l as i64 {
+                let poly = real_regular_solid_polynomial::<f64>(l, m);
+                assert!(poly.iter().all(|t| t.powers.iter().sum::<u32>() == l));
+                for &(x, y, z) in &points {
+                    let value: f64 = poly.iter().map(|t| t.eval(x, y, z)).sum();
+                    let expected =
+                        RealSH::RegularSolid.eval(l as i64, m, &Coordinates::cartesian(x, y, z));
+                    let scale = (x * x + y * y + z * z).sqrt().powi(l as i32);
+                    assert!(
+                        (value - expected).abs() < 1e-13 * scale.max(1.0),
+                        "{l} {m} {value} {expected}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+#[cfg(feature = "xnumber")]
+use crate::basis::real_sh_extended;
+use crate::basis::{
+    grad_tables, multiple_angles, real_irregular_solid_sh, real_regular_solid_sh, real_sh,
+    real_sh_from_trig, real_sh_grad, real_sh_hardcoded, set_tables, to_f64_coordinates, SHEval,
+    SHGrad,
+};
+use crate::legendre::{normalized_legendre_table_orders, table_index};
+use crate::names::named_enum;
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Available types of real spherical harmonics and solid harmonics
+///
+/// The names used by `Display` and `FromStr` are `spherical`, `regular_solid` and
+/// `irregular_solid`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RealSH {
+    /// Spherical harmonics
+    Spherical,
+    /// Regular solid harmonics
+    RegularSolid,
+    /// Irregular solid harmonics
+    IrregularSolid,
+}
+
+named_enum!(RealSH, "real SH type", {
+    Spherical => "spherical",
+    RegularSolid => "regular_solid",
+    IrregularSolid => "irregular_solid",
+});
+
+impl<T> SHEval<T> for RealSH
+where
+    T: SphrsFloat,
+{
+    type Output = T;
+
+    /// Evaluate real SH (l, m) at position `p`
+    #[inline(always)]
+    fn eval(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        assert!(m.abs() <= l);
+        self.eval_valid(l, m, p)
+    }
+
+    /// Evaluate real SH (l, m) with |m| <= l at position `p` without checking (l, m)
+    #[inline(always)]
+    fn eval_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        match self {
+            Self::Spherical => real_sh_hardcoded(l, m, p),
+            Self::RegularSolid => real_regular_solid_sh(l, m, p),
+            Self::IrregularSolid => real_irregular_solid_sh(l, m, p),
+        }
+    }
+
+    /// Evaluate real SH (l, m) at position `p` using the recursive implementation only
+    #[inline(always)]
+    fn eval_recursive(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        assert!(m.abs() <= l);
+        self.eval_recursive_valid(l, m, p)
+    }
+
+    /// Evaluate real SH (l, m) with |m| <= l at position `p` using the recursive implementation
+    /// only, without checking (l, m)
+    #[inline(always)]
+    fn eval_recursive_valid(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        let scaling =
+            ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        match self {
+            Self::Spherical => real_sh(l, m, p),
+            Self::RegularSolid => scaling * p.r().powi(l as i32) * real_sh(l, m, p),
+            Self::IrregularSolid => scaling / p.r().powi(l as i32) * real_sh(l, m, p),
+        }
+    }
+
+    /// Evaluate real SH (l, m) at position `p` using the recursive implementation in `f64`
+    fn eval_recursive_f64(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        let v: f64 = self.eval_recursive(l, m, &to_f64_coordinates(p));
+        T::from_f64(v).unwrap()
+    }
+
+    /// Evaluate real SH (l, m) at position `p` with extended exponent arithmetic
+    #[cfg(feature = "xnumber")]
+    fn eval_extended(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> Self::Output {
+        assert!(m.abs() <= l);
+        let scaling =
+            ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        let v = real_sh_extended(l, m, p);
+        match self {
+            Self::Spherical => v,
+            Self::RegularSolid => scaling * p.r().powi(l as i32) * v,
+            Self::IrregularSolid => scaling / p.r().powi(l as i32) * v,
+        }
+    }
+
+    /// Evaluate all real SH of the degrees `min_degree..=degree` at position `p` in one pass of
+    /// recurrences
+    fn eval_recursive_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<T>,
+    ) {
+        let (legendre, trig) = set_tables(degree, max_order, p);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        self.push_from_tables(min_degree, &legendre, &trig, &factors, out);
+    }
+
+    /// Evaluate all SH of the degrees `min_degree..=degree` given the Legendre functions, with
+    /// the multiple angles and radial factors of `eval_recursive_set`
+    fn eval_set_from_legendre(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        legendre: &[T],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<T>,
+    ) {
+        let max_order = max_order.min(degree);
+        let mut trig = Vec::with_capacity(max_order + 1);
+        multiple_angles(max_order, p.phi(), &mut trig);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        self.push_from_tables(min_degree, legendre, &trig, &factors, out);
+    }
+
+    /// Evaluate the real SH in `harmonics` at position `p` with the tables of
+    /// `eval_recursive_set`
+    fn eval_recursive_selection(
+        &self,
+        degree: usize,
+        max_order: usize,
+        harmonics: &[(i64, i64)],
+        p: &impl SHCoordinates<T>,
+        out: &mut Vec<T>,
+    ) {
+        let (legendre, trig) = set_tables(degree, max_order, p);
+        let factors = self.radial_factors(0, degree, p.r());
+        for &(l, m) in harmonics {
+            let (l, abs_m) = (l as usize, m.unsigned_abs() as usize);
+            let v = real_sh_from_trig(m, trig[abs_m], legendre[table_index(l, abs_m)]);
+            out.push(factors[l] * v);
+        }
+    }
+
+    /// Evaluate all real SH of the degrees `min_degree..=degree` on a ring with the Legendre
+    /// functions of the ring computed once
+    fn eval_recursive_ring(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        (r, theta): (T, T),
+        phis: &[T],
+        out: &mut Vec<T>,
+    ) {
+        let max_order = max_order.min(degree);
+        let legendre =
+            normalized_legendre_table_orders(degree, max_order, theta.cos(), theta.sin());
+        let factors = self.radial_factors(min_degree, degree, r);
+        let mut trig = Vec::with_capacity(max_order + 1);
+        for &phi in phis {
+            multiple_angles(max_order, phi, &mut trig);
+            self.push_from_tables(min_degree, &legendre, &trig, &factors, out);
+        }
+    }
+}
+
+impl RealSH {
+    /// Factors of the degrees `min_degree..=degree` relative to the spherical harmonics at radius
+    /// `r`
+    fn radial_factors<T: SphrsFloat>(&self, min_degree: usize, degree: usize, r: T) -> Vec<T> {
+        (min_degree..=degree)
+            .map(|l| {
+                let scaling = ((T::from_f64(4.0).unwrap() * T::PI())
+                    / T::from_usize(2 * l + 1).unwrap())
+                .sqrt();
+                match self {
+                    Self::Spherical => T::one(),
+                    Self::RegularSolid => scaling * r.powi(l as i32),
+                    Self::IrregularSolid => scaling / r.powi(l as i32),
+                }
+            })
+            .collect()
+    }
+
+    /// Push the harmonics of the degrees from `min_degree` on given the Legendre functions, the
+    /// multiple angles (see `set_tables`) and the radial factors of the degrees, for the orders
+    /// covered by the multiple angles
+    fn push_from_tables<T: SphrsFloat>(
+        &self,
+        min_degree: usize,
+        legendre: &[T],
+        trig: &[(T, T)],
+        factors: &[T],
+        out: &mut Vec<T>,
+    ) {
+        let max_order = trig.len() - 1;
+        for (l, &factor) in (min_degree..).zip(factors) {
+            let orders = l.min(max_order) as i64;
+            for m in -orders..=orders {
+                let abs_m = m.unsigned_abs() as usize;
+                let v = real_sh_from_trig(m, trig[abs_m], legendre[table_index(l, abs_m)]);
+                out.push(factor * v);
+            }
+        }
+    }
+}
+
+impl<T> SHGrad<T> for RealSH
+where
+    T: SphrsFloat,
+{
+    /// Evaluate real SH (l, m) and its derivatives with respect to theta and phi at position `p`
+    fn eval_grad(&self, l: i64, m: i64, p: &impl SHCoordinates<T>) -> (T, T, T) {
+        assert!(m.abs() <= l);
+        let scaling =
+            ((T::from_f64(4.0).unwrap() * T::PI()) / T::from_i64(2 * l + 1).unwrap()).sqrt();
+        let factor = match self {
+            Self::Spherical => T::one(),
+            Self::RegularSolid => scaling * p.r().powi(l as i32),
+            Self::IrregularSolid => scaling / p.r().powi(l as i32),
+        };
+        let (y, d_theta, d_phi) = real_sh_grad(l, m, p);
+        (factor * y, factor * d_theta, factor * d_phi)
+    }
+
+    /// Evaluate all real SH of the degrees `min_degree..=degree` and their derivatives at
+    /// position `p` in one pass of recurrences
+    fn eval_grad_set(
+        &self,
+        min_degree: usize,
+        degree: usize,
+        max_order: usize,
+        p: &impl SHCoordinates<T>,
+        (values, d_theta, d_phi): (&mut Vec<T>, &mut Vec<T>, &mut Vec<T>),
+    ) {
+        let (legendre, trig) = grad_tables(degree, max_order, p);
+        let factors = self.radial_factors(min_degree, degree, p.r());
+        let max_order = trig.len() - 1;
+        for (l, &factor) in (min_degree..).zip(&factors) {
+            let orders = l.min(max_order) as i64;
+            for m in -orders..=orders {
+                let abs_m = m.unsigned_abs() as usize;
+                let (v, dv) = (
+                    legendre.p[table_index(l, abs_m)],
+                    legendre.dp[table_index(l, abs_m)],
+                );
+                values.push(factor * real_sh_from_trig(m, trig[abs_m], v));
+                d_theta.push(factor * real_sh_from_trig(m, trig[abs_m], dv));
+                // cos(m phi) and sin(|m| phi) are exchanged by the derivative with respect to phi
+                d_phi.push(
+                    factor * -T::from_i64(m).unwrap() * real_sh_from_trig(-m, trig[abs_m], v),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use num::Zero;
+use num_complex::Complex;
+
+use crate::coefficients::sh_index;
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Elements (column m, value) of row `m` of the matrix returned by [`real_from_complex_matrix`]
+///
+/// Row 0 has a single nonzero element, its second element is zero.
+fn real_from_complex_row<T: SphrsFloat>(m: i64) -> [(i64, Complex<T>); 2] {
+    let h = T::FRAC_1_SQRT_2();
+    let sign = if m % 2 == 0 { T::one() } else { -T::one() };
+    match m {
+        0 => [(0, Complex::new(T::one(), T::zero())), (0, Complex::zero())],
+        m if m > 0 => [
+            (-m, Complex::new(h, T::zero())),
+            (m, Complex::new(sign * h, T::zero())),
+        ],
+        m => [
+            (m, Complex::new(T::zero(), h)),
+            (-m, Complex::new(T::zero(), -sign * h)),
+        ],
+    }
+}
+
+/// Unitary matrix U of degree `l` mapping the complex spherical harmonics to the real ones
+///
+/// The real harmonics [`RealSH::Spherical`](`crate::basis::RealSH`) of degree `l` are
+/// Y^R_m = sum_m' U_mm' Y_m' with the complex harmonics
+/// [`ComplexSH::Spherical`](`crate::basis::ComplexSH`) (quantum phase convention). For m > 0
+/// this is Y^R_m = ((-1)^m Y_m + Y_-m) / sqrt(2) and Y^R_-m = i (Y_-m - (-1)^m Y_m) / sqrt(2).
+/// The same matrix maps the complex regular solid harmonics to the real ones.
+///
+/// The matrix is stored row-major with the orders running from -l to l, as the Wigner matrices
+/// (see [`matrix_index`](`crate::rotation::wigner::matrix_index`)). Coefficients transform with
+/// the complex conjugate, see [`complex_to_real_coefficients`].
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::basis::{real_from_complex_matrix, ComplexSH, RealSH, SHEval};
+/// use sphrs::Coordinates;
+///
+/// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+/// let u = real_from_complex_matrix::<f64>(1);
+/// let y: Vec<Complex<f64>> = (-1..=1).map(|m| ComplexSH::Spherical.eval(1, m, &p)).collect();
+/// // Row of m = 1
+/// let y11: Complex<f64> = (0..3).map(|j| u[6 + j] * y[j]).sum();
+/// assert!((y11.re - RealSH::Spherical.eval(1, 1, &p)).abs() < 1e-15);
+/// assert!(y11.im.abs() < 1e-15);
+/// ```
+pub fn real_from_complex_matrix<T: SphrsFloat>(l: usize) -> Vec<Complex<T>> {
+    let li = l as i64;
+    let n = 2 * l + 1;
+    let mut out = vec![Complex::zero(); n * n];
+    for m in -li..=li {
+        for (col, v) in real_from_complex_row::<T>(m) {
+            let idx = ((m + li) as usize) * n + (col + li) as usize;
+            out[idx] = out[idx] + v;
+        }
+    }
+    out
+}
+
+/// Inverse U^H of [`real_from_complex_matrix`], mapping the real spherical harmonics of degree
+/// `l` to the complex ones
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{complex_from_real_matrix, real_from_complex_matrix};
+///
+/// let u = real_from_complex_matrix::<f64>(2);
+/// let v = complex_from_real_matrix::<f64>(2);
+/// // v = u^H
+/// assert_eq!(v[3], u[3 * 5].conj());
+/// ```
+pub fn complex_from_real_matrix<T: SphrsFloat>(l: usize) -> Vec<Complex<T>> {
+    let n = 2 * l + 1;
+    let u = real_from_complex_matrix::<T>(l);
+    let mut out = vec![Complex::zero(); n * n];
+    for i in 0..n {
+        for j in 0..n {
+            out[i * n + j] = u[j * n + i].conj();
+        }
+    }
+    out
+}
+
+/// Convert the coefficients of an expansion in complex spherical harmonics to the coefficients of
+/// the same function in real spherical harmonics
+///
+/// The real coefficients are c^R = conj(U) c per degree, with U from
+/// [`real_from_complex_matrix`]. Only real functions have real coefficients, i.e. coefficients
+/// with c_-m = (-1)^m conj(c_m); the imaginary parts of c^R, which vanish for those, are
+/// discarded. The normalization convention is kept. Also available as
+/// [`SHCoefficients::to_real`].
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::basis::{complex_to_real_coefficients, real_to_complex_coefficients};
+/// use sphrs::SHCoefficients;
+///
+/// let real = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, -0.2, 0.3]);
+/// let complex = real_to_complex_coefficients(&real);
+/// let back = complex_to_real_coefficients(&complex);
+/// for (a, b) in real.as_slice().iter().zip(back.as_slice()) {
+///     assert!((a - b).abs() < 1e-15);
+/// }
+/// ```
+pub fn complex_to_real_coefficients<T: SphrsFloat>(
+    coeffs: &SHCoefficients<Complex<T>>,
+) -> SHCoefficients<T> {
+    let c = coeffs.as_slice();
+    let mut out = SHCoefficients::zeros(coeffs.degree());
+    for l in 0..=coeffs.degree() as i64 {
+        for m in -l..=l {
+            let v = real_from_complex_row::<T>(m)
+                .iter()
+                .fold(Complex::zero(), |acc: Complex<T>, &(col, u)| {
+                    acc + u.conj() * c[sh_index(l, col)]
+                });
+            out.as_mut_slice()[sh_index(l, m)] = v.re;
+        }
+    }
+    out.with_normalization(coeffs.normalization())
+}
+
+/// Convert the coefficients of an expansion in real spherical harmonics to the coefficients of
+/// the same function in complex spherical harmonics
+///
+/// The complex coefficients are c = U^T c^R per degree, with U from
+/// [`real_from_complex_matrix`]. The normalization convention is kept. Also available as
+/// [`SHCoefficients::to_complex`].
+pub fn real_to_complex_coefficients<T: SphrsFloat>(
+    coeffs: &SHCoefficients<T>,
+) -> SHCoefficients<Complex<T>> {
+    let c = coeffs.as_slice();
+    let mut out: SHCoefficients<Complex<T>> = SHCoefficients::zeros(coeffs.degree());
+    for l in 0..=coeffs.degree() as i64 {
+        for m in -l..=l {
+            for (col, u) in real_from_complex_row::<T>(m) {
+                let v = &mut out.as_mut_slice()[sh_index(l, col)];
+                *v = *v + u * c[sh_index(l, m)];
+            }
+        }
+    }
+    out.with_normalization(coeffs.normalization())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, HarmonicsSet, RealSH};
+    use crate::Coordinates;
+
+    #[test]
+    fn maps_complex_to_real_harmonics() {
+        let lmax = 6;
+        let p = Coordinates::spherical(1.3, 2.1, -0.8);
+        let real: Vec<f64> = HarmonicsSet::new(lmax, RealSH::Spherical).eval(&p);
+        let complex: Vec<Complex<f64>> = HarmonicsSet::new(lmax, ComplexSH::Spherical).eval(&p);
+        for l in 0..=lmax {
+            let n = 2 * l + 1;
+            let u = real_from_complex_matrix::<f64>(l);
+            let v = complex_from_real_matrix::<f64>(l);
+            let start = l * l;
+            for i in 0..n {
+                let y: Complex<f64> = (0..n).map(|j| u[i * n + j] * complex[start + j]).sum();
+                assert!((y.re - real[start + i]).abs() < 1e-14, "{l} {i}");
+                assert!(y.im.abs() < 1e-14);
+                let y: Complex<f64> = (0..n).map(|j| v[i * n + j] * real[start + j]).sum();
+                assert!((y - complex[start + i]).norm() < 1e-14, "{l} {i}");
+                for j in 0..n {
+                    let dot: Complex<f64> = (0..n).map(|k| u[i * n + k] * v[k * n + j]).sum();
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!((dot - expected).norm() < 1e-15);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coefficients_represent_the_same_function() {
+        let lmax = 5;
+        let real = SHCoefficients::from_vec(
+            lmax,
+            (0..36)
+                .map(|i| (i as f64 * 0.71).sin())
+                .collect::<Vec<f64>>(),
+        );
+        let complex = real_to_complex_coefficients(&real);
+        let back = complex_to_real_coefficients(&complex);
+        for (a, b) in real.as_slice().iter().zip(back.as_slice()) {
+            assert!((a - b).abs() < 1e-15);
+        }
+        let p = Coordinates::spherical(1.0, 0.4, 2.6);
+        let f_real: f64 = HarmonicsSet::new(lmax, RealSH::Spherical)
+            .eval_sum(&p, real.as_slice())
+            .unwrap();
+        let f_complex: Complex<f64> = HarmonicsSet::new(lmax, ComplexSH::Spherical)
+            .eval_sum(&p, complex.as_slice())
+            .unwrap();
+        assert!((f_complex.re - f_real).abs() < 1e-14);
+        assert!(f_complex.im.abs() < 1e-14);
+    }
+}
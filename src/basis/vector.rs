@@ -0,0 +1,315 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::marker::PhantomData;
+use std::ops::{Add, Mul};
+
+use num_complex::Complex;
+
+use crate::legendre::{table_index, LegendreTable};
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Tangential vector on the unit sphere, given by its components along the unit vectors of the
+/// polar angle (`theta`) and the azimuthal angle (`phi`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tangential<T> {
+    /// Component along the theta unit vector
+    pub theta: T,
+    /// Component along the phi unit vector
+    pub phi: T,
+}
+
+impl<T> Tangential<T> {
+    /// Create a tangential vector from its components
+    pub fn new(theta: T, phi: T) -> Self {
+        Tangential { theta, phi }
+    }
+}
+
+impl<T: Add<Output = T>> Add for Tangential<T> {
+    type Output = Tangential<T>;
+
+    fn add(self, other: Self) -> Self::Output {
+        Tangential::new(self.theta + other.theta, self.phi + other.phi)
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Tangential<T> {
+    type Output = Tangential<T>;
+
+    fn mul(self, factor: T) -> Self::Output {
+        Tangential::new(self.theta * factor, self.phi * factor)
+    }
+}
+
+/// Index of (l, m) in a vector of vector spherical harmonics (l >= 1)
+///
+/// The ordering is the same as the one of [`HarmonicsSet`](`crate::basis::HarmonicsSet`), but
+/// without l = 0, for which the vector spherical harmonics vanish.
+#[inline(always)]
+pub fn vsh_index(l: i64, m: i64) -> usize {
+    debug_assert!(l >= 1 && m.abs() <= l);
+    (l * l + l + m - 1) as usize
+}
+
+/// Degree and order (l, m) of the vector spherical harmonic with index `idx`, the inverse of
+/// [`vsh_index`]
+#[inline]
+pub fn vsh_degree_order(idx: usize) -> (i64, i64) {
+    crate::sh_degree_order(idx + 1)
+}
+
+/// Set of the complex vector spherical harmonics up to a given degree
+///
+/// For each degree l >= 1 and order m, the orthonormal vector spherical harmonics are
+///
+/// * Psi_lm = r grad Y_lm / sqrt(l (l + 1)) (gradient or "electric" type) and
+/// * Phi_lm = r_hat x Psi_lm (curl or "magnetic" type),
+///
+/// where Y_lm are the complex spherical harmonics as computed by
+/// [`ComplexSH::Spherical`](`crate::basis::ComplexSH::Spherical`). Any tangential vector field on
+/// the sphere can be expanded into these harmonics.
+pub struct VectorHarmonicsSet<T> {
+    /// degree
+    degree: usize,
+    /// Total number of harmonics of each type
+    num_sh: usize,
+    /// Float
+    _ttt: PhantomData<T>,
+}
+
+impl<T> VectorHarmonicsSet<T>
+where
+    T: SphrsFloat,
+{
+    /// Create new `VectorHarmonicsSet` struct
+    pub fn new(degree: usize) -> VectorHarmonicsSet<T> {
+        VectorHarmonicsSet {
+            degree,
+            num_sh: (degree + 1) * (degree + 1) - 1,
+            _ttt: PhantomData,
+        }
+    }
+
+    /// Returns the degree of the set
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the number of harmonics of each type (Psi and Phi) in the set
+    pub fn num_sh(&self) -> usize {
+        self.num_sh
+    }
+
+    /// Evaluate the harmonics at position `p`
+    ///
+    /// Returns the Psi harmonics and the Phi harmonics, each ordered as described in
+    /// [`vsh_index`].
+    #[allow(clippy::type_complexity)]
+    pub fn eval<C>(&self, p: &C) -> (Vec<Tangential<Complex<T>>>, Vec<Tangential<Complex<T>>>)
+    where
+        C: SHCoordinates<T>,
+    {
+        let table = LegendreTable::new(self.degree, p.theta_cos(), p.theta().sin());
+        let mut psi = Vec::with_capacity(self.num_sh);
+        let mut phi = Vec::with_capacity(self.num_sh);
+        for l in 1..=self.degree {
+            let norm = T::one() / T::from_usize(l * (l + 1)).unwrap().sqrt();
+            for m in -(l as i64)..=l as i64 {
+                let i = table_index(l, m.unsigned_abs() as usize);
+                // Y_l,-m = (-1)^m conj(Y_lm)
+                let sign = if m < 0 && m % 2 != 0 { -norm } else { norm };
+                let e = Complex::new(T::zero(), T::from_i64(m).unwrap() * p.phi()).exp() * sign;
+                let d_theta = e * table.dp[i];
+                let d_phi = e * Complex::new(T::zero(), T::from_i64(m).unwrap() * table.p_sin[i]);
+                psi.push(Tangential::new(d_theta, d_phi));
+                phi.push(Tangential::new(-d_phi, d_theta));
+            }
+        }
+        (psi, phi)
+    }
+}
+
+/// Coefficients of a tangential vector field expanded into vector spherical harmonics
+///
+/// The field is the sum of `psi[j]` Psi_j and `phi[j]` Phi_j over all harmonics of
+/// [`VectorHarmonicsSet`] up to the degree of the expansion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorSHCoefficients<T> {
+    /// Maximum degree
+    degree: usize,
+    /// Coefficients of the Psi harmonics
+    psi: Vec<Complex<T>>,
+    /// Coefficients of the Phi harmonics
+    phi: Vec<Complex<T>>,
+}
+
+impl<T: SphrsFloat> VectorSHCoefficients<T> {
+    /// Create `VectorSHCoefficients` from the coefficients of the Psi and Phi harmonics.
+    ///
+    /// Panics if the length of `psi` or `phi` does not equal `(degree + 1)^2 - 1`.
+    pub fn from_vecs(degree: usize, psi: Vec<Complex<T>>, phi: Vec<Complex<T>>) -> Self {
+        let n = (degree + 1) * (degree + 1) - 1;
+        assert_eq!(psi.len(), n);
+        assert_eq!(phi.len(), n);
+        VectorSHCoefficients { degree, psi, phi }
+    }
+
+    /// Create `VectorSHCoefficients` up to degree `degree` with all coefficients set to zero.
+    pub fn zeros(degree: usize) -> Self {
+        let n = (degree + 1) * (degree + 1) - 1;
+        let zero = Complex::new(T::zero(), T::zero());
+        VectorSHCoefficients::from_vecs(degree, vec![zero; n], vec![zero; n])
+    }
+
+    /// Returns the maximum degree
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the coefficients of the Psi harmonics
+    pub fn psi(&self) -> &[Complex<T>] {
+        &self.psi
+    }
+
+    /// Returns the coefficients of the Psi harmonics as a mutable slice
+    pub fn psi_mut(&mut self) -> &mut [Complex<T>] {
+        &mut self.psi
+    }
+
+    /// Returns the coefficients of the Phi harmonics
+    pub fn phi(&self) -> &[Complex<T>] {
+        &self.phi
+    }
+
+    /// Returns the coefficients of the Phi harmonics as a mutable slice
+    pub fn phi_mut(&mut self) -> &mut [Complex<T>] {
+        &mut self.phi
+    }
+
+    /// Returns the integral of the squared magnitude of the field over the unit sphere
+    pub fn power(&self) -> T {
+        self.psi
+            .iter()
+            .chain(self.phi.iter())
+            .fold(T::zero(), |acc, c| acc + c.norm_sqr())
+    }
+
+    /// Evaluate the vector field at position `p`
+    pub fn eval<C>(&self, p: &C) -> Tangential<Complex<T>>
+    where
+        C: SHCoordinates<T>,
+    {
+        let zero = Complex::new(T::zero(), T::zero());
+        let (psi, phi) = VectorHarmonicsSet::new(self.degree).eval(p);
+        psi.into_iter()
+            .zip(self.psi.iter())
+            .chain(phi.into_iter().zip(self.phi.iter()))
+            .fold(Tangential::new(zero, zero), |acc, (y, &c)| acc + y * c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, SHEval};
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn index_round_trip() {
+        for l in 1..=20i64 {
+            for m in -l..=l {
+                assert_eq!(vsh_degree_order(vsh_index(l, m)), (l, m));
+            }
+        }
+    }
+
+    #[test]
+    fn gradient_of_scalar_harmonics() {
+        // Compare against finite differences of the complex scalar harmonics
+        let (theta, phi) = (0.9f64, -2.1);
+        let h = 1e-6;
+        let (psi, curl) = VectorHarmonicsSet::new(5).eval(&Coordinates::spherical(1.0, theta, phi));
+        for l in 1..=5i64 {
+            let norm = ((l * (l + 1)) as f64).sqrt();
+            for m in -l..=l {
+                let y = |t: f64, f: f64| {
+                    ComplexSH::Spherical.eval(l, m, &Coordinates::spherical(1.0, t, f))
+                };
+                let d_theta = (y(theta + h, phi) - y(theta - h, phi)) / (2.0 * h) / norm;
+                let d_phi =
+                    (y(theta, phi + h) - y(theta, phi - h)) / (2.0 * h) / norm / theta.sin();
+                let j = vsh_index(l, m);
+                assert_relative_eq!(psi[j].theta, d_theta, epsilon = 1e-7);
+                assert_relative_eq!(psi[j].phi, d_phi, epsilon = 1e-7);
+                assert_relative_eq!(curl[j].theta, -d_phi, epsilon = 1e-7);
+                assert_relative_eq!(curl[j].phi, d_theta, epsilon = 1e-7);
+            }
+        }
+    }
+
+    #[test]
+    fn orthonormality() {
+        // Gauss-Legendre in theta, trapezoidal in phi
+        let degree = 4;
+        let (x, w) = crate::quadrature::gauss_legendre::<f64>(12);
+        let n_phi = 16;
+        let set = VectorHarmonicsSet::new(degree);
+        let n = set.num_sh();
+        let mut gram = vec![Complex::new(0.0, 0.0); 4 * n * n];
+        for (&x, &w) in x.iter().zip(w.iter()) {
+            for k in 0..n_phi {
+                let p = Coordinates::spherical(
+                    1.0,
+                    x.acos(),
+                    2.0 * std::f64::consts::PI * k as f64 / n_phi as f64,
+                );
+                let (psi, phi) = set.eval(&p);
+                let all: Vec<_> = psi.into_iter().chain(phi).collect();
+                let dw = w * 2.0 * std::f64::consts::PI / n_phi as f64;
+                for i in 0..2 * n {
+                    for j in 0..2 * n {
+                        gram[i * 2 * n + j] += (all[i].theta.conj() * all[j].theta
+                            + all[i].phi.conj() * all[j].phi)
+                            * dw;
+                    }
+                }
+            }
+        }
+        for i in 0..2 * n {
+            for j in 0..2 * n {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert_relative_eq!(gram[i * 2 * n + j].re, expected, epsilon = 1e-12);
+                assert_relative_eq!(gram[i * 2 * n + j].im, 0.0, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn power_and_eval() {
+        let mut coeffs = VectorSHCoefficients::<f64>::zeros(2);
+        coeffs.psi_mut()[vsh_index(1, 0)] = Complex::new(2.0, 0.0);
+        coeffs.phi_mut()[vsh_index(2, -1)] = Complex::new(0.0, 1.0);
+        assert_relative_eq!(coeffs.power(), 5.0);
+        // Psi_10 = -sqrt(3 / 8 pi) sin(theta) theta_hat
+        let p = Coordinates::spherical(1.0, std::f64::consts::FRAC_PI_2, 0.3);
+        let e = VectorSHCoefficients::from_vecs(
+            1,
+            vec![
+                Complex::new(0.0, 0.0),
+                Complex::new(1.0, 0.0),
+                Complex::new(0.0, 0.0),
+            ],
+            vec![Complex::new(0.0, 0.0); 3],
+        )
+        .eval(&p);
+        let expected = -(3.0 / (8.0 * std::f64::consts::PI)).sqrt();
+        assert_relative_eq!(e.theta.re, expected, epsilon = 1e-14);
+        assert_relative_eq!(e.phi.norm(), 0.0, epsilon = 1e-14);
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::ops::Mul;
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Index of (n, l, m) in a set of 3D Zernike functions
+///
+/// The functions are ordered by n, then by l (with n - l even) and then by m from -l to l, e.g.
+/// (0, 0, 0), (1, 1, -1), (1, 1, 0), (1, 1, 1), (2, 0, 0), (2, 2, -2), ...
+#[inline(always)]
+pub fn zernike_index(n: usize, l: usize, m: i64) -> usize {
+    debug_assert!(l <= n && (n - l).is_multiple_of(2) && m.unsigned_abs() as usize <= l);
+    let l0 = n % 2;
+    let j = (l - l0) / 2;
+    // All functions of lower n, then all functions of lower l of the same n
+    n * (n + 1) * (n + 2) / 6 + j * (l + l0).saturating_sub(1) + (m + l as i64) as usize
+}
+
+/// Radial 3D Zernike polynomials R_nl(r) of degree `l` for n = l, l + 2, ..., <= `nmax`
+///
+/// Evaluated with the three-term recurrence of the Jacobi polynomials P_k^(0, l + 1/2) in
+/// 2 r^2 - 1, see [`zernike_radial`]. Element k of the result is R_(l+2k),l(r).
+fn zernike_radial_all<T: SphrsFloat>(nmax: usize, l: usize, r: T) -> Vec<T> {
+    if l > nmax {
+        return Vec::new();
+    }
+    let kmax = (nmax - l) / 2;
+    let two = T::from_f64(2.0).unwrap();
+    let x = two * r * r - T::one();
+    let beta = T::from_usize(l).unwrap() + T::from_f64(0.5).unwrap();
+    let r_l = r.powi(l as i32);
+
+    let mut out = Vec::with_capacity(kmax + 1);
+    let (mut prev, mut cur) = (T::zero(), T::one());
+    for k in 0..=kmax {
+        if k == 1 {
+            // P_1^(0, b)(x) = 1 + (b + 2) (x - 1) / 2
+            prev = cur;
+            cur = T::one() + (beta + two) * (x - T::one()) / two;
+        } else if k >= 2 {
+            let kf = T::from_usize(k).unwrap();
+            let s = two * kf + beta;
+            let next = ((s - T::one()) * (s * (s - two) * x - beta * beta) * cur
+                - two * (kf - T::one()) * (kf + beta - T::one()) * s * prev)
+                / (two * kf * (kf + beta) * (s - two));
+            prev = cur;
+            cur = next;
+        }
+        let n = l + 2 * k;
+        out.push(T::from_usize(2 * n + 3).unwrap().sqrt() * r_l * cur);
+    }
+    out
+}
+
+/// Radial 3D Zernike polynomial R_nl(r)
+///
+/// R_nl(r) = sqrt(2 n + 3) r^l P_k^(0, l + 1/2)(2 r^2 - 1) with k = (n - l) / 2 and the Jacobi
+/// polynomial P_k^(a, b), i.e. a polynomial of degree n in r with only the powers r^l, r^(l + 2),
+/// ..., r^n. The polynomials are orthonormal on the unit ball, i.e. the integral of
+/// R_nl(r) R_n'l(r) r^2 over [0, 1] is delta_nn', and R_nl(1) = sqrt(2 n + 3).
+///
+/// # Panics
+///
+/// Panics if l > n or n - l is odd.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::zernike_radial;
+///
+/// // R_20(r) = sqrt(7) (5 r^2 - 3) / 2
+/// let r = 0.6f64;
+/// let expected = 7f64.sqrt() * (5.0 * r * r - 3.0) / 2.0;
+/// assert!((zernike_radial(2, 0, r) - expected).abs() < 1e-15);
+/// ```
+pub fn zernike_radial<T: SphrsFloat>(n: usize, l: usize, r: T) -> T {
+    assert!(
+        l <= n && (n - l).is_multiple_of(2),
+        "invalid 3D Zernike indices n = {n}, l = {l}"
+    );
+    zernike_radial_all(n, l, r)[(n - l) / 2]
+}
+
+/// A set of 3D Zernike functions up to a given radial degree
+///
+/// The 3D Zernike functions Z_nlm(r, theta, phi) = R_nl(r) Y_lm(theta, phi) with the radial
+/// polynomials [`zernike_radial`] and n - l even form an orthonormal basis of the functions on the
+/// unit ball. The angular part Y_lm is computed with the given type of harmonic, which should be
+/// [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`) or
+/// [`ComplexSH::Spherical`](`crate::basis::ComplexSH::Spherical`). The set contains all functions
+/// with n <= `nmax`, ordered as described in [`zernike_index`].
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{zernike_index, RealSH, ZernikeSet};
+/// use sphrs::Coordinates;
+///
+/// let set = ZernikeSet::new(4, RealSH::Spherical);
+/// assert_eq!(set.num_functions(), 35);
+///
+/// let p = Coordinates::spherical(0.5, 0.8, 0.4);
+/// let z: Vec<f64> = set.eval(&p);
+/// assert_eq!(z.len(), 35);
+/// // Z_000 = sqrt(3 / (4 pi))
+/// let z000 = (3.0 / (4.0 * std::f64::consts::PI)).sqrt();
+/// assert!((z[zernike_index(0, 0, 0)] - z000).abs() < 1e-15);
+/// ```
+pub struct ZernikeSet<T, E> {
+    /// Maximum radial degree
+    nmax: usize,
+    /// Total number of functions
+    num_functions: usize,
+    /// Angular part
+    sh: HarmonicsSet<T, E>,
+}
+
+impl<T, E> ZernikeSet<T, E>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: Copy + Mul<T, Output = E::Output>,
+{
+    /// Create new `ZernikeSet` struct
+    pub fn new(nmax: usize, sh_type: E) -> ZernikeSet<T, E> {
+        ZernikeSet {
+            nmax,
+            num_functions: (nmax + 1) * (nmax + 2) * (nmax + 3) / 6,
+            sh: HarmonicsSet::new(nmax, sh_type),
+        }
+    }
+
+    /// Returns the maximum radial degree of the set
+    pub fn nmax(&self) -> usize {
+        self.nmax
+    }
+
+    /// Returns the total number of functions in the set
+    pub fn num_functions(&self) -> usize {
+        self.num_functions
+    }
+
+    /// Evaluate the functions at position `p`
+    ///
+    /// The harmonics and the radial polynomials are each evaluated once and combined. Positions
+    /// outside of the unit ball are not rejected, the polynomials are simply extrapolated.
+    pub fn eval<C>(&self, p: &C) -> Vec<E::Output>
+    where
+        C: SHCoordinates<T>,
+    {
+        let y = self.sh.eval(p);
+        let radial: Vec<Vec<T>> = (0..=self.nmax)
+            .map(|l| zernike_radial_all(self.nmax, l, p.r()))
+            .collect();
+        let mut out = Vec::with_capacity(self.num_functions);
+        for n in 0..=self.nmax {
+            for l in (n % 2..=n).step_by(2) {
+                let r = radial[l][(n - l) / 2];
+                out.extend(y[l * l..(l + 1) * (l + 1)].iter().map(|&y| y * r));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, RealSH};
+    use crate::quadrature::gauss_legendre;
+    use crate::Coordinates;
+    use num_complex::Complex;
+
+    #[test]
+    fn index_matches_ordering() {
+        let mut i = 0;
+        for n in 0..=8 {
+            for l in (n % 2..=n).step_by(2) {
+                for m in -(l as i64)..=l as i64 {
+                    assert_eq!(zernike_index(n, l, m), i);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn radial_closed_forms() {
+        for r in [0.0f64, 0.3, 0.75, 1.0] {
+            let cases = [
+                (0, 0, 3f64.sqrt()),
+                (1, 1, 5f64.sqrt() * r),
+                (2, 2, 7f64.sqrt() * r * r),
+                (2, 0, 7f64.sqrt() * (5.0 * r * r - 3.0) / 2.0),
+                (3, 1, 3.0 * (7.0 * r * r * r - 5.0 * r) / 2.0),
+                (
+                    4,
+                    0,
+                    11f64.sqrt() * (63.0 * r.powi(4) - 70.0 * r * r + 15.0) / 8.0,
+                ),
+            ];
+            for (n, l, expected) in cases {
+                let v = zernike_radial(n, l, r);
+                assert!((v - expected).abs() < 1e-14, "{n} {l} {r}: {v} {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn radial_orthonormality() {
+        // Gauss-Legendre on [0, 1] is exact for the polynomial integrands
+        let (nodes, weights) = gauss_legendre::<f64>(20);
+        let nmax = 16;
+        for l in 0..=nmax {
+            let values: Vec<Vec<f64>> = nodes
+                .iter()
+                .map(|&x| zernike_radial_all(nmax, l, (x + 1.0) / 2.0))
+                .collect();
+            for a in 0..values[0].len() {
+                for b in 0..values[0].len() {
+                    let integral: f64 = nodes
+                        .iter()
+                        .zip(&weights)
+                        .zip(&values)
+                        .map(|((&x, &w), v)| {
+                            let r = (x + 1.0) / 2.0;
+                            w / 2.0 * r * r * v[a] * v[b]
+                        })
+                        .sum();
+                    let expected = if a == b { 1.0 } else { 0.0 };
+                    assert!(
+                        (integral - expected).abs() < 1e-12,
+                        "{l} {a} {b} {integral}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_combines_radial_and_angular_parts() {
+        let nmax = 7;
+        let p = Coordinates::spherical(0.7, 1.1, -2.3);
+        let real: Vec<f64> = ZernikeSet::new(nmax, RealSH::Spherical).eval(&p);
+        let complex: Vec<Complex<f64>> = ZernikeSet::new(nmax, ComplexSH::Spherical).eval(&p);
+        assert_eq!(real.len(), 120);
+        for n in 0..=nmax {
+            for l in (n % 2..=n).step_by(2) {
+                let r = zernike_radial(n, l, 0.7);
+                for m in -(l as i64)..=l as i64 {
+                    let i = zernike_index(n, l, m);
+                    let y: f64 = RealSH::Spherical.eval(l as i64, m, &p);
+                    assert!((real[i] - r * y).abs() < 1e-13);
+                    let y: Complex<f64> = ComplexSH::Spherical.eval(l as i64, m, &p);
+                    assert!((complex[i] - y * r).norm() < 1e-13);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::legendre::{legendre_p_all, zonal_norm};
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Zonal spherical harmonic Y_l0 at position `p`
+///
+/// Y_l0 = sqrt((2 l + 1) / (4 pi)) P_l(cos(theta)) is real and the same for the real and the
+/// complex harmonics. It is computed with the recurrence of the Legendre polynomials, which is
+/// much cheaper than evaluating a [`HarmonicsSet`](`crate::basis::HarmonicsSet`) and discarding
+/// the harmonics of nonzero order. No memory is allocated.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{zonal, RealSH, SHEval};
+/// use sphrs::Coordinates;
+///
+/// let p = Coordinates::spherical(1.0, 0.7, 0.2);
+/// let y: f64 = RealSH::Spherical.eval(5, 0, &p);
+/// assert!((zonal(5, &p) - y).abs() < 1e-15);
+/// ```
+pub fn zonal<T: SphrsFloat>(l: usize, p: &impl SHCoordinates<T>) -> T {
+    if l == 0 {
+        return zonal_norm(0);
+    }
+    let x = p.theta_cos();
+    let (mut prev, mut cur) = (T::one(), x);
+    for k in 2..=l {
+        let kf = T::from_usize(k).unwrap();
+        let next = ((kf + kf - T::one()) * x * cur - (kf - T::one()) * prev) / kf;
+        prev = cur;
+        cur = next;
+    }
+    zonal_norm::<T>(l) * cur
+}
+
+/// Zonal spherical harmonics Y_00, Y_10, ..., Y_lmax,0 at position `p`
+///
+/// See [`zonal`]. Element l of the result is Y_l0, i.e. the set is indexed by the degree.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::zonal_set;
+/// use sphrs::Coordinates;
+///
+/// // At the north pole Y_l0 = sqrt((2 l + 1) / (4 pi))
+/// let set = zonal_set(3, &Coordinates::cartesian(0.0, 0.0, 2.0));
+/// assert_eq!(set.len(), 4);
+/// assert!((set[2] - (5.0 / (4.0 * std::f64::consts::PI)).sqrt()).abs() < 1e-15);
+/// ```
+pub fn zonal_set<T: SphrsFloat>(lmax: usize, p: &impl SHCoordinates<T>) -> Vec<T> {
+    let mut out = legendre_p_all(lmax, p.theta_cos());
+    for (l, v) in out.iter_mut().enumerate() {
+        *v = zonal_norm::<T>(l) * *v;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::Coordinates;
+
+    #[test]
+    fn compare_with_harmonics_set() {
+        let lmax = 40;
+        let sh = HarmonicsSet::new(lmax, RealSH::Spherical);
+        for p in [
+            Coordinates::spherical(1.0, 0.3, 0.2),
+            Coordinates::spherical(0.5, 2.8, -2.5),
+            Coordinates::cartesian(0.0, 0.0, -1.0),
+            Coordinates::cartesian(1.0, -1.0, 0.0),
+        ] {
+            let set: Vec<f64> = sh.eval(&p);
+            let zonal_values = zonal_set(lmax, &p);
+            for (l, &v) in zonal_values.iter().enumerate() {
+                let expected = set[l * l + l];
+                assert!((v - expected).abs() < 1e-13, "{l} {v} {expected}");
+                assert_eq!(zonal(l, &p), v);
+            }
+        }
+    }
+}
@@ -0,0 +1,672 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::ops::Mul;
+
+use num_complex::Complex;
+
+use crate::basis::{complex_to_real_coefficients, real_to_complex_coefficients};
+use crate::names::named_enum;
+use crate::rotation::Rotation;
+use crate::spectral::filter_degrees;
+use crate::SphrsFloat;
+
+/// Normalization convention of a set of coefficients
+///
+/// The conventions differ by a positive factor per degree and order (see
+/// [`factor`](`Normalization::factor`)). None of them includes a Condon-Shortley phase in the real
+/// harmonics, like [`RealSH`](`crate::basis::RealSH`), such that real coefficients of the common
+/// geodesy and geomagnetism conventions (e.g. SHTOOLS with `csphase = 1`) can be converted with
+/// [`SHCoefficients::to_normalization`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Normalization {
+    /// Orthonormal harmonics (as computed by [`RealSH`](`crate::basis::RealSH`) and
+    /// [`ComplexSH`](`crate::basis::ComplexSH`))
+    #[default]
+    Orthonormal,
+    /// 4 pi normalized harmonics, whose squares integrate to 4 pi over the sphere, as used in
+    /// geodesy (e.g. the gravity models EGM2008 and the default of SHTOOLS)
+    FourPi,
+    /// Schmidt semi-normalized harmonics, whose squares integrate to 4 pi / (2 l + 1), as used in
+    /// geomagnetism (e.g. IGRF and WMM)
+    Schmidt,
+    /// Harmonics P_l^|m|(cos theta) cos(m phi) for m >= 0 and P_l^|m|(cos theta) sin(|m| phi) for
+    /// m < 0 with the associated Legendre functions P_l^m without normalization
+    Unnormalized,
+}
+
+named_enum!(Normalization, "normalization", {
+    Orthonormal => "orthonormal",
+    FourPi => "four_pi",
+    Schmidt => "schmidt",
+    Unnormalized => "unnormalized",
+});
+
+impl Normalization {
+    /// Factor of the harmonic of degree `l` and order `m` in this convention relative to the
+    /// orthonormal one
+    ///
+    /// The factors are 1 for [`Orthonormal`](`Normalization::Orthonormal`), sqrt(4 pi) for
+    /// [`FourPi`](`Normalization::FourPi`), sqrt(4 pi / (2 l + 1)) for
+    /// [`Schmidt`](`Normalization::Schmidt`) and
+    /// sqrt(4 pi / ((2 l + 1) (2 - delta_m0)) (l + |m|)! / (l - |m|)!) for
+    /// [`Unnormalized`](`Normalization::Unnormalized`), which overflows for orders beyond about
+    /// 85 in `f64`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{RealSH, SHEval};
+    /// use sphrs::{Coordinates, Normalization};
+    ///
+    /// // Schmidt semi-normalized Y_11 = sin(theta) cos(phi)
+    /// let p = Coordinates::spherical(1.0f64, 0.7, 0.3);
+    /// let y = Normalization::Schmidt.factor::<f64>(1, 1) * RealSH::Spherical.eval(1, 1, &p);
+    /// assert!((y - 0.7f64.sin() * 0.3f64.cos()).abs() < 1e-15);
+    /// ```
+    pub fn factor<T: SphrsFloat>(self, l: usize, m: i64) -> T {
+        let four_pi = T::from_f64(4.0).unwrap() * T::PI();
+        let degree = T::from_usize(2 * l + 1).unwrap();
+        match self {
+            Normalization::Orthonormal => T::one(),
+            Normalization::FourPi => four_pi.sqrt(),
+            Normalization::Schmidt => (four_pi / degree).sqrt(),
+            Normalization::Unnormalized => {
+                let a = m.unsigned_abs() as usize;
+                let ratio =
+                    (l - a + 1..=l + a).fold(T::one(), |acc, k| acc * T::from_usize(k).unwrap());
+                let two = if a == 0 {
+                    T::one()
+                } else {
+                    T::from_f64(2.0).unwrap()
+                };
+                (four_pi / (degree * two) * ratio).sqrt()
+            }
+        }
+    }
+}
+
+/// Index of (l, m) in a coefficient vector ordered by degree and then by order
+/// (`(0, 0), (1, -1), (1, 0), (1, 1), (2, -2), ...`).
+///
+/// This is the same ordering as used by [`HarmonicsSet`](`crate::basis::HarmonicsSet`) for sets
+/// of all degrees and orders and by [`SHCoefficients`]. The inverse is [`sh_degree_order`]. The
+/// other orderings of the crate have their own functions: [`sym_index`](`crate::dmri::sym_index`)
+/// for the even degrees of the symmetric basis, [`vsh_index`](`crate::basis::vsh_index`) for the
+/// vector spherical harmonics and [`legendre::index`](`crate::legendre::index`) for the
+/// Legendre functions of orders m >= 0.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::{sh_degree_order, sh_index};
+///
+/// assert_eq!(sh_index(2, -1), 5);
+/// assert_eq!(sh_degree_order(5), (2, -1));
+/// ```
+#[inline(always)]
+pub fn sh_index(l: i64, m: i64) -> usize {
+    (l * l + l + m) as usize
+}
+
+/// Degree and order (l, m) of the coefficient with index `idx`, the inverse of [`sh_index`]
+#[inline]
+pub fn sh_degree_order(idx: usize) -> (i64, i64) {
+    let l = idx.isqrt();
+    (l as i64, idx as i64 - (l * l + l) as i64)
+}
+
+/// Number of coefficients of a set up to (and including) degree `degree`
+#[inline(always)]
+pub(crate) fn num_coefficients(degree: usize) -> usize {
+    (degree + 1) * (degree + 1)
+}
+
+/// Coefficients of a spherical/solid harmonics expansion up to a given degree
+///
+/// The coefficients are ordered by degree and then by order, which is the same ordering as the
+/// output of [`HarmonicsSet::eval`](`crate::basis::HarmonicsSet::eval`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SHCoefficients<T> {
+    /// Maximum degree
+    degree: usize,
+    /// Normalization convention
+    normalization: Normalization,
+    /// Coefficients
+    data: Vec<T>,
+}
+
+impl<T> SHCoefficients<T> {
+    /// Create `SHCoefficients` from a vector of coefficients ordered by degree and order.
+    ///
+    /// Panics if the length of `data` does not equal `(degree + 1)^2`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::SHCoefficients;
+    /// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// assert_eq!(coeffs.get(1, -1), Some(&0.5));
+    /// ```
+    pub fn from_vec(degree: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), num_coefficients(degree));
+        SHCoefficients {
+            degree,
+            normalization: Normalization::default(),
+            data,
+        }
+    }
+
+    /// Set the normalization convention the coefficients refer to.
+    #[must_use]
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Returns the maximum degree
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the normalization convention
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    /// Returns the total number of coefficients
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if there are no coefficients
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the coefficient of degree `l` and order `m`, if present
+    pub fn get(&self, l: i64, m: i64) -> Option<&T> {
+        if l < 0 || l as usize > self.degree || m.abs() > l {
+            return None;
+        }
+        self.data.get(sh_index(l, m))
+    }
+
+    /// Returns a mutable reference to the coefficient of degree `l` and order `m`, if present
+    pub fn get_mut(&mut self, l: i64, m: i64) -> Option<&mut T> {
+        if l < 0 || l as usize > self.degree || m.abs() > l {
+            return None;
+        }
+        self.data.get_mut(sh_index(l, m))
+    }
+
+    /// Returns the coefficients as a slice
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the coefficients as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Consumes `self` and returns the underlying vector of coefficients
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Returns the coefficients of degree `l`, ordered from m = -l to l, if present
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::SHCoefficients;
+    /// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// assert_eq!(coeffs.band(1), Some(&[0.5, 0.2, 0.1][..]));
+    /// assert_eq!(coeffs.band(2), None);
+    /// ```
+    pub fn band(&self, l: usize) -> Option<&[T]> {
+        if l > self.degree {
+            return None;
+        }
+        Some(&self.data[l * l..(l + 1) * (l + 1)])
+    }
+
+    /// Returns the coefficients of degree `l` as a mutable slice, if present
+    pub fn band_mut(&mut self, l: usize) -> Option<&mut [T]> {
+        if l > self.degree {
+            return None;
+        }
+        Some(&mut self.data[l * l..(l + 1) * (l + 1)])
+    }
+
+    /// Multiply the coefficients of each degree l by the transfer function `transfer(l)`
+    ///
+    /// Applies an isotropic filter in one call, e.g. the smoothing by a beam, the correction of a
+    /// pixel window or spectral derivatives like the Laplacian with h_l = -l (l + 1). Same as
+    /// [`filter_degrees`](`crate::spectral::filter_degrees`); the coefficients may be real or
+    /// complex.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::SHCoefficients;
+    /// // Laplacian on the unit sphere
+    /// let mut coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// coeffs.apply_transfer(|l| -((l * (l + 1)) as f64));
+    /// assert_eq!(coeffs.as_slice(), &[0.0, -1.0, -0.4, -0.2]);
+    /// ```
+    pub fn apply_transfer<H, F>(&mut self, transfer: F)
+    where
+        T: Copy + Mul<H, Output = T>,
+        H: Copy,
+        F: Fn(usize) -> H,
+    {
+        filter_degrees(self, transfer);
+    }
+
+    /// Drop the coefficients of the degrees above `degree`
+    ///
+    /// Like [`Vec::truncate`], this has no effect if `degree` is not smaller than the maximum
+    /// degree. The ordering and the normalization convention of the remaining coefficients stay
+    /// the same.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::SHCoefficients;
+    /// let mut coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// coeffs.truncate(0);
+    /// assert_eq!(coeffs.degree(), 0);
+    /// assert_eq!(coeffs.as_slice(), &[1.0]);
+    /// ```
+    pub fn truncate(&mut self, degree: usize) {
+        if degree < self.degree {
+            self.data.truncate(num_coefficients(degree));
+            self.degree = degree;
+        }
+    }
+}
+
+impl<T: SphrsFloat> SHCoefficients<T> {
+    /// Convert the coefficients to the normalization convention `normalization`
+    ///
+    /// The expansion stays the same: each coefficient is multiplied by the
+    /// [`factor`](`Normalization::factor`) of its harmonic in the current convention and divided by
+    /// the one in the new convention. Coefficients of other conventions, e.g. Schmidt
+    /// semi-normalized geomagnetic models, can be converted to
+    /// [`Orthonormal`](`Normalization::Orthonormal`) in order to evaluate them with
+    /// [`RealSH`](`crate::basis::RealSH`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::{Normalization, SHCoefficients};
+    ///
+    /// // Dipole of IGRF-13 at 2020 in nT, Schmidt semi-normalized
+    /// let igrf = SHCoefficients::from_vec(1, vec![0.0f64, 4652.5, -29404.8, -1450.9])
+    ///     .with_normalization(Normalization::Schmidt);
+    /// let coeffs = igrf.to_normalization(Normalization::Orthonormal);
+    /// assert_eq!(coeffs.normalization(), Normalization::Orthonormal);
+    /// let factor = (4.0 * std::f64::consts::PI / 3.0).sqrt();
+    /// assert!((coeffs.get(1, 0).unwrap() - factor * -29404.8).abs() < 1e-9);
+    /// ```
+    pub fn to_normalization(&self, normalization: Normalization) -> Self {
+        let data = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                let (l, m) = sh_degree_order(i);
+                let l = l as usize;
+                c * self.normalization.factor::<T>(l, m) / normalization.factor::<T>(l, m)
+            })
+            .collect();
+        SHCoefficients::from_vec(self.degree, data).with_normalization(normalization)
+    }
+
+    /// Coefficients of the same function in the complex spherical harmonics
+    ///
+    /// Applies the unitary transformation between the real harmonics
+    /// [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`) and the complex harmonics
+    /// [`ComplexSH::Spherical`](`crate::basis::ComplexSH::Spherical`) per degree, see
+    /// [`real_to_complex_coefficients`]. The inverse is [`to_real`](`SHCoefficients::to_real`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::basis::{ComplexSH, HarmonicsSet, RealSH};
+    /// use sphrs::{Coordinates, SHCoefficients};
+    ///
+    /// let real = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, -0.2, 0.3]);
+    /// let complex = real.to_complex();
+    /// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+    /// let a: f64 = HarmonicsSet::new(1, RealSH::Spherical).eval_sum(&p, real.as_slice()).unwrap();
+    /// let b: num_complex::Complex<f64> = HarmonicsSet::new(1, ComplexSH::Spherical)
+    ///     .eval_sum(&p, complex.as_slice())
+    ///     .unwrap();
+    /// assert!((a - b.re).abs() < 1e-15 && b.im.abs() < 1e-15);
+    /// ```
+    pub fn to_complex(&self) -> SHCoefficients<Complex<T>> {
+        real_to_complex_coefficients(self)
+    }
+
+    /// Rotate the expansion by `rotation`
+    ///
+    /// Returns the coefficients of the rotated function f(R^-1 p). The coefficients of each
+    /// degree are transformed with the real rotation matrices of Ivanic and Ruedenberg (see
+    /// [`Rotation::real_sh_matrices`]), which only take real arithmetic and are generated by a
+    /// recursion over the degrees. The result equals the one of
+    /// [`rotation::rotate`](`crate::rotation::rotate`) with the same Euler angles. Coefficients
+    /// in the [`Unnormalized`](`Normalization::Unnormalized`) convention, which does not transform
+    /// per degree like the orthonormal one, are converted for the rotation and back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::rotation::Rotation;
+    /// use sphrs::SHCoefficients;
+    ///
+    /// // Rotating Y_1,0 (along z) by 90 degrees about the y-axis turns it into Y_1,1 (along x)
+    /// let z = SHCoefficients::from_vec(1, vec![0.0f64, 0.0, 1.0, 0.0]);
+    /// let x = z.rotate(&Rotation::from_euler(0.0, std::f64::consts::FRAC_PI_2, 0.0));
+    /// assert!((x.get(1, 1).unwrap() - 1.0).abs() < 1e-14);
+    /// ```
+    pub fn rotate(&self, rotation: &Rotation<T>) -> Self {
+        let r = rotation.matrix();
+        if r[2][2] == T::one() {
+            return self.rotate_z(r[1][0].atan2(r[0][0]));
+        }
+        if self.normalization == Normalization::Unnormalized {
+            return self
+                .to_normalization(Normalization::Orthonormal)
+                .rotate(rotation)
+                .to_normalization(self.normalization);
+        }
+        let matrices = rotation.real_sh_matrices(self.degree);
+        let mut data = Vec::with_capacity(self.data.len());
+        for (l, r) in matrices.iter().enumerate() {
+            let band = &self.data[l * l..(l + 1) * (l + 1)];
+            let width = 2 * l + 1;
+            data.extend(r.chunks_exact(width).map(|row| {
+                row.iter()
+                    .zip(band)
+                    .fold(T::zero(), |acc, (&a, &c)| acc + a * c)
+            }));
+        }
+        SHCoefficients::from_vec(self.degree, data).with_normalization(self.normalization)
+    }
+
+    /// Rotate the expansion by the angle `alpha` about the z-axis
+    ///
+    /// Same as [`rotate`](`SHCoefficients::rotate`) with `Rotation::from_euler(alpha, 0, 0)`, but
+    /// in O(`num_sh`) operations: the rotation only mixes the coefficients of the orders m and -m
+    /// of each degree with cos(m alpha) and sin(m alpha). [`rotate`](`SHCoefficients::rotate`)
+    /// takes this path for all rotations about the z-axis. See
+    /// [`rotate_z_in_place`](`SHCoefficients::rotate_z_in_place`) for a version which does not
+    /// allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::SHCoefficients;
+    ///
+    /// // Rotating Y_1,1 (along x) by 90 degrees about the z-axis turns it into Y_1,-1 (along y)
+    /// let x = SHCoefficients::from_vec(1, vec![0.0f64, 0.0, 0.0, 1.0]);
+    /// let y = x.rotate_z(std::f64::consts::FRAC_PI_2);
+    /// assert!((y.get(1, -1).unwrap() - 1.0).abs() < 1e-15);
+    /// ```
+    pub fn rotate_z(&self, alpha: T) -> Self {
+        let mut out = self.clone();
+        out.rotate_z_in_place(alpha);
+        out
+    }
+
+    /// Rotate the expansion by the angle `alpha` about the z-axis in place
+    ///
+    /// Like [`rotate_z`](`SHCoefficients::rotate_z`), but neither allocates nor panics, such that
+    /// it can be called on real-time threads, e.g. to follow the head yaw in ambisonics.
+    pub fn rotate_z_in_place(&mut self, alpha: T) {
+        let (s, c) = alpha.sin_cos();
+        let (mut cm, mut sm) = (c, s);
+        for m in 1..=self.degree {
+            for l in m..=self.degree {
+                let (l, m) = (l as i64, m as i64);
+                let (pos, neg) = (sh_index(l, m), sh_index(l, -m));
+                let (a, b) = (self.data[pos], self.data[neg]);
+                self.data[pos] = a * cm - b * sm;
+                self.data[neg] = a * sm + b * cm;
+            }
+            (cm, sm) = (cm * c - sm * s, sm * c + cm * s);
+        }
+    }
+}
+
+impl<T: SphrsFloat> SHCoefficients<Complex<T>> {
+    /// Coefficients of the same function in the real spherical harmonics
+    ///
+    /// Inverse of [`to_complex`](`SHCoefficients::to_complex`), see
+    /// [`complex_to_real_coefficients`]. Only real functions, whose coefficients satisfy
+    /// c_-m = (-1)^m conj(c_m), have real coefficients; the imaginary parts, which vanish for
+    /// those, are discarded.
+    pub fn to_real(&self) -> SHCoefficients<T> {
+        complex_to_real_coefficients(self)
+    }
+}
+
+impl<T> SHCoefficients<T>
+where
+    T: num::Zero + Clone,
+{
+    /// Create `SHCoefficients` up to degree `degree` with all coefficients set to zero.
+    pub fn zeros(degree: usize) -> Self {
+        SHCoefficients::from_vec(degree, vec![T::zero(); num_coefficients(degree)])
+    }
+
+    /// Extend the coefficients with zeros for the degrees up to `degree`
+    ///
+    /// The expansion stays the same. Has no effect if `degree` is not larger than the maximum
+    /// degree, see [`truncate`](`SHCoefficients::truncate`) for reducing it. Together, the two
+    /// bring expansions of different band limits to a common degree, e.g. in order to add them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::SHCoefficients;
+    /// let mut coeffs = SHCoefficients::from_vec(0, vec![1.0f64]);
+    /// coeffs.pad_to(1);
+    /// assert_eq!(coeffs.degree(), 1);
+    /// assert_eq!(coeffs.as_slice(), &[1.0, 0.0, 0.0, 0.0]);
+    /// ```
+    pub fn pad_to(&mut self, degree: usize) {
+        if degree > self.degree {
+            self.data.resize(num_coefficients(degree), T::zero());
+            self.degree = degree;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexing() {
+        let coeffs = SHCoefficients::from_vec(2, (0..9).collect::<Vec<i32>>());
+        let mut idx = 0;
+        for l in 0..=2i64 {
+            for m in -l..=l {
+                assert_eq!(coeffs.get(l, m), Some(&idx));
+                idx += 1;
+            }
+        }
+        assert_eq!(coeffs.get(3, 0), None);
+        assert_eq!(coeffs.get(1, 2), None);
+        assert_eq!(coeffs.get(-1, 0), None);
+    }
+
+    #[test]
+    fn index_round_trip() {
+        let mut idx = 0;
+        for l in 0..=40i64 {
+            for m in -l..=l {
+                assert_eq!(sh_index(l, m), idx);
+                assert_eq!(sh_degree_order(idx), (l, m));
+                idx += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn normalization_conversion() {
+        use crate::basis::{RealSH, SHEval};
+        use crate::Coordinates;
+        use approx::assert_relative_eq;
+
+        // Unnormalized harmonics of degree 2 without Condon-Shortley phase
+        let (theta, phi) = (0.7f64, -1.1f64);
+        let p = Coordinates::spherical(1.0, theta, phi);
+        let (c, s) = (theta.cos(), theta.sin());
+        let expected = [
+            (0, 1.5 * c * c - 0.5),
+            (1, 3.0 * c * s * phi.cos()),
+            (-1, 3.0 * c * s * phi.sin()),
+            (2, 3.0 * s * s * (2.0 * phi).cos()),
+            (-2, 3.0 * s * s * (2.0 * phi).sin()),
+        ];
+        for (m, y) in expected {
+            let factor: f64 = Normalization::Unnormalized.factor(2, m);
+            assert_relative_eq!(
+                factor * RealSH::Spherical.eval(2, m, &p),
+                y,
+                epsilon = 1e-14
+            );
+        }
+        let four_pi: f64 = Normalization::FourPi.factor(0, 0);
+        assert_relative_eq!(four_pi * RealSH::Spherical.eval(0, 0, &p), 1.0);
+
+        // The expansion does not change
+        let coeffs = SHCoefficients::from_vec(6, (0..49).map(|i| (i as f64 * 0.7).cos()).collect());
+        let sum = |coeffs: &SHCoefficients<f64>| -> f64 {
+            coeffs
+                .as_slice()
+                .iter()
+                .enumerate()
+                .fold(0.0, |acc, (i, &c)| {
+                    let (l, m) = sh_degree_order(i);
+                    let factor: f64 = coeffs.normalization().factor(l as usize, m);
+                    acc + c * factor * RealSH::Spherical.eval(l, m, &p)
+                })
+        };
+        for normalization in [
+            Normalization::FourPi,
+            Normalization::Schmidt,
+            Normalization::Unnormalized,
+        ] {
+            let converted = coeffs.to_normalization(normalization);
+            assert_eq!(converted.normalization(), normalization);
+            assert_relative_eq!(sum(&converted), sum(&coeffs), epsilon = 1e-12);
+            let back = converted.to_normalization(Normalization::Orthonormal);
+            for (a, b) in back.as_slice().iter().zip(coeffs.as_slice()) {
+                assert_relative_eq!(a, b, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn real_complex_round_trip() {
+        let real = SHCoefficients::from_vec(7, (0..64).map(|i| (i as f64 * 1.3).sin()).collect())
+            .with_normalization(Normalization::Schmidt);
+        let complex = real.to_complex();
+        assert_eq!(complex.normalization(), Normalization::Schmidt);
+        for l in 0..=7i64 {
+            for m in 0..=l {
+                // Real function
+                let (a, b) = (complex.get(l, m).unwrap(), complex.get(l, -m).unwrap());
+                let sign = if m % 2 == 0 { 1.0 } else { -1.0 };
+                assert!((*b - a.conj() * sign).norm() < 1e-15);
+            }
+        }
+        let back = complex.to_real();
+        assert_eq!(back.normalization(), Normalization::Schmidt);
+        for (a, b) in back.as_slice().iter().zip(real.as_slice()) {
+            assert!((a - b).abs() < 1e-15);
+        }
+
+        // Complex coefficients of a real function
+        let mut complex = SHCoefficients::<Complex<f64>>::zeros(5);
+        for l in 0..=5i64 {
+            for m in 0..=l {
+                let c = Complex::new((l * 3 + m) as f64 * 0.1, if m == 0 { 0.0 } else { 0.2 });
+                let sign = if m % 2 == 0 { 1.0 } else { -1.0 };
+                *complex.get_mut(l, m).unwrap() = c;
+                *complex.get_mut(l, -m).unwrap() = c.conj() * sign;
+            }
+        }
+        for (a, b) in complex
+            .to_real()
+            .to_complex()
+            .as_slice()
+            .iter()
+            .zip(complex.as_slice())
+        {
+            assert!((a - b).norm() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn truncate_and_pad() {
+        let mut coeffs = SHCoefficients::from_vec(3, (0..16).map(f64::from).collect())
+            .with_normalization(Normalization::Schmidt);
+        coeffs.truncate(5);
+        assert_eq!(coeffs.degree(), 3);
+        coeffs.truncate(2);
+        assert_eq!(coeffs.len(), 9);
+        assert_eq!(coeffs.get(2, 2), Some(&8.0));
+        assert_eq!(coeffs.get(3, 0), None);
+        coeffs.pad_to(1);
+        assert_eq!(coeffs.degree(), 2);
+        coeffs.pad_to(4);
+        assert_eq!(coeffs.degree(), 4);
+        assert_eq!(coeffs.normalization(), Normalization::Schmidt);
+        for l in 0..=4 {
+            let band = coeffs.band(l).unwrap();
+            assert_eq!(band.len(), 2 * l + 1);
+            for (m, &c) in (-(l as i64)..=l as i64).zip(band) {
+                assert_eq!(Some(&c), coeffs.get(l as i64, m));
+                let expected = if l <= 2 {
+                    sh_index(l as i64, m) as f64
+                } else {
+                    0.0
+                };
+                assert_eq!(c, expected);
+            }
+        }
+        coeffs.band_mut(3).unwrap()[0] = 1.0;
+        assert_eq!(coeffs.get(3, -3), Some(&1.0));
+        assert!(coeffs.band(5).is_none());
+    }
+
+    #[test]
+    fn transfer() {
+        let mut coeffs = SHCoefficients::from_vec(2, vec![Complex::new(1.0f64, -1.0); 9]);
+        coeffs.apply_transfer(|l| 0.5f64.powi(l as i32));
+        for l in 0..=2i64 {
+            for m in -l..=l {
+                let h = 0.5f64.powi(l as i32);
+                assert_eq!(coeffs.get(l, m), Some(&Complex::new(h, -h)));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        SHCoefficients::from_vec(2, vec![1.0f64; 8]);
+    }
+}
@@ -30,7 +30,8 @@ pub trait SHCoordinates<T>: Sized {
 /// Representation of coordinates.
 ///
 /// Generic over floats. Can be created with [`cartesian`](`Coordinates::cartesian`) (Cartesian
-/// coordinates) or [`spherical`](`Coordinates::spherical`) (spherical coordinates).
+/// coordinates), [`spherical`](`Coordinates::spherical`) (spherical coordinates with the
+/// colatitude `theta`) or [`geographic`](`Coordinates::geographic`) (latitude and longitude).
 #[derive(Default, Clone, Debug)]
 pub struct Coordinates<T> {
     /// radius (spherical coordinates)
@@ -55,15 +56,23 @@ where
 {
     /// Create `Coordinates` struct from Cartesian coordinates
     ///
+    /// The angles are computed with `atan2`, which is accurate near the poles and does not
+    /// overflow or underflow for very large or small coordinates. The direction of the origin is
+    /// not defined; it is assigned `theta = 0` and `phi = 0` (the positive z-axis), such that
+    /// harmonics evaluated at the origin stay finite.
+    ///
     /// # Example
     ///
     /// ```
-    /// # use sphrs::Coordinates;
+    /// # use sphrs::{Coordinates, SHCoordinates};
     /// let coords = Coordinates::cartesian(1.0f64, 0.5, 12.0);
+    /// let origin = Coordinates::cartesian(0.0f64, 0.0, 0.0);
+    /// assert_eq!((origin.r(), origin.theta(), origin.phi()), (0.0, 0.0, 0.0));
     /// ```
     pub fn cartesian(x: T, y: T, z: T) -> Self {
-        let r = (x.powi(2) + y.powi(2) + z.powi(2)).sqrt();
-        let theta = (z / r).acos();
+        let rho = x.hypot(y);
+        let r = rho.hypot(z);
+        let theta = rho.atan2(z);
         let phi = y.atan2(x);
 
         let theta_cos = theta.cos();
@@ -101,6 +110,37 @@ where
             theta_cos,
         }
     }
+
+    /// Create `Coordinates` struct from geographic coordinates, i.e. the `latitude` (positive
+    /// north of the equator) and the east-positive `longitude`, both in radians
+    ///
+    /// `theta` is the colatitude pi/2 - `latitude` and `phi` the `longitude`; the x-axis points to
+    /// longitude zero and the y-axis to 90 degrees east. Angles given in degrees can be converted
+    /// with `to_radians`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sphrs::{Coordinates, SHCoordinates};
+    /// // Latitude 30 degrees south, longitude 60 degrees west
+    /// let p = Coordinates::geographic(1.0f64, (-30f64).to_radians(), (-60f64).to_radians());
+    /// assert!((p.theta() - 120f64.to_radians()).abs() < 1e-15);
+    /// assert!((p.latitude() + 30f64.to_radians()).abs() < 1e-15);
+    /// assert!(p.y() < 0.0 && p.z() < 0.0);
+    /// ```
+    pub fn geographic(r: T, latitude: T, longitude: T) -> Self {
+        Self::spherical(r, T::FRAC_PI_2() - latitude, longitude)
+    }
+
+    /// Latitude in radians, pi/2 - `theta`
+    pub fn latitude(&self) -> T {
+        T::FRAC_PI_2() - self.theta
+    }
+
+    /// East-positive longitude in radians, the same as `phi`
+    pub fn longitude(&self) -> T {
+        self.phi
+    }
 }
 
 impl<T> SHCoordinates<T> for Coordinates<T>
@@ -150,6 +190,115 @@ where
     }
 }
 
+/// Cartesian coordinates with the angles computed on demand
+///
+/// Fast path for real harmonics of low degree at Cartesian positions, e.g. normals or view
+/// directions in graphics. [`Coordinates::cartesian`] computes `theta`, `phi` and `cos(theta)`
+/// with two `atan2` and a `cos` when it is created, which dominates the evaluation of small sets.
+/// The closed-form expressions of the real harmonics (see [`basis::RealSH`](`crate::basis::RealSH`)
+/// and the build-time degree `SPHRS_HARDCODED_DEGREE`, default 8) are polynomials in
+/// x / r, y / r and z / r and never need the angles, such that sets of
+/// [`RealSH`](`crate::basis::RealSH`) harmonics up to that degree are evaluated without any
+/// trigonometric function. Higher degrees, the other harmonics and the `compact` feature, which
+/// has no closed-form expressions, compute the angles as [`Coordinates::cartesian`] does whenever
+/// they are needed.
+///
+/// The radius is computed as sqrt(x^2 + y^2 + z^2), which overflows for coordinates beyond about
+/// 1e154 (`f64`) or 1e19 (`f32`), unlike the `hypot` of [`Coordinates::cartesian`].
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::{CartesianCoordinates, Coordinates};
+///
+/// let sh = HarmonicsSet::new(4, RealSH::Spherical);
+/// let fast: Vec<f32> = sh.eval(&CartesianCoordinates::new(0.3, -0.5, 0.8));
+/// let slow: Vec<f32> = sh.eval(&Coordinates::cartesian(0.3, -0.5, 0.8));
+/// for (a, b) in fast.iter().zip(&slow) {
+///     assert!((a - b).abs() < 1e-6);
+/// }
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct CartesianCoordinates<T> {
+    /// x (cartesian coordinates)
+    x: T,
+    /// y (cartesian coordinates)
+    y: T,
+    /// z (cartesian coordinates)
+    z: T,
+    /// radius (spherical coordinates)
+    r: T,
+}
+
+impl<T> CartesianCoordinates<T>
+where
+    T: SphrsFloat,
+{
+    /// Create `CartesianCoordinates` struct from Cartesian coordinates
+    pub fn new(x: T, y: T, z: T) -> Self {
+        CartesianCoordinates {
+            x,
+            y,
+            z,
+            r: (x * x + y * y + z * z).sqrt(),
+        }
+    }
+}
+
+impl<T> SHCoordinates<T> for CartesianCoordinates<T>
+where
+    T: SphrsFloat,
+{
+    /// Return angle `theta`, computed as in [`Coordinates::cartesian`]
+    #[inline(always)]
+    fn theta(&self) -> T {
+        self.x.hypot(self.y).atan2(self.z)
+    }
+
+    /// Return angle `phi`, computed as in [`Coordinates::cartesian`]
+    #[inline(always)]
+    fn phi(&self) -> T {
+        self.y.atan2(self.x)
+    }
+
+    /// Return radius `r`
+    #[inline(always)]
+    fn r(&self) -> T {
+        self.r
+    }
+
+    /// Return coordinate `x`
+    #[inline(always)]
+    fn x(&self) -> T {
+        self.x
+    }
+
+    /// Return coordinate `y`
+    #[inline(always)]
+    fn y(&self) -> T {
+        self.y
+    }
+
+    /// Return coordinate `z`
+    #[inline(always)]
+    fn z(&self) -> T {
+        self.z
+    }
+
+    /// Return `cos(theta)`, computed as z / r without any trigonometric function
+    ///
+    /// The origin is assigned `theta = 0` as in [`Coordinates::cartesian`], hence `cos(theta) = 1`.
+    #[inline(always)]
+    fn theta_cos(&self) -> T {
+        if self.r == T::zero() {
+            T::one()
+        } else {
+            self.z / self.r
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,8 +382,8 @@ mod tests {
         let x = x.0;
         let y = y.0;
         let z = z.0;
-        let r = (x.powi(2) + y.powi(2) + z.powi(2)).sqrt();
-        let theta = (z / r).acos();
+        let r = x.hypot(y).hypot(z);
+        let theta = x.hypot(y).atan2(z);
         let phi = y.atan2(x);
         let theta_cos = theta.cos();
 
@@ -248,13 +397,32 @@ mod tests {
         assert_relative_eq!(coords.theta_cos(), theta_cos);
     }
 
+    #[quickcheck]
+    fn cartesian_coordinates_f64(x: Cartesian, y: Cartesian, z: Cartesian) {
+        let coords = Coordinates::cartesian(x.0, y.0, z.0);
+        let lazy = CartesianCoordinates::new(x.0, y.0, z.0);
+        assert_eq!(
+            (lazy.x(), lazy.y(), lazy.z()),
+            (coords.x(), coords.y(), coords.z())
+        );
+        assert_relative_eq!(lazy.r(), coords.r(), max_relative = 1e-15);
+        assert_eq!(lazy.theta(), coords.theta());
+        assert_eq!(lazy.phi(), coords.phi());
+        assert_relative_eq!(
+            lazy.theta_cos(),
+            coords.theta_cos(),
+            epsilon = 1e-15,
+            max_relative = 1e-14
+        );
+    }
+
     #[quickcheck]
     fn shcoordinates_cartesian_f32(x: Cartesian, y: Cartesian, z: Cartesian) {
         let x = x.0 as f32;
         let y = y.0 as f32;
         let z = z.0 as f32;
-        let r = (x.powi(2) + y.powi(2) + z.powi(2)).sqrt();
-        let theta = (z / r).acos();
+        let r = x.hypot(y).hypot(z);
+        let theta = x.hypot(y).atan2(z);
         let phi = y.atan2(x);
         let theta_cos = theta.cos();
 
@@ -267,4 +435,45 @@ mod tests {
         assert_relative_eq!(coords.phi(), phi);
         assert_relative_eq!(coords.theta_cos(), theta_cos);
     }
+
+    #[test]
+    fn geographic() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+        let north = Coordinates::geographic(1.0f64, FRAC_PI_2, 0.3);
+        assert_eq!(north.theta(), 0.0);
+        assert_relative_eq!(north.z(), 1.0);
+
+        // 45 degrees north, 90 degrees east is in the upper half of the y-z plane
+        let p = Coordinates::geographic(2.0f64, FRAC_PI_4, FRAC_PI_2);
+        assert_relative_eq!(p.theta(), FRAC_PI_4);
+        assert!(p.x().abs() < 1e-15);
+        assert_relative_eq!(p.y(), std::f64::consts::SQRT_2);
+        assert_relative_eq!(p.z(), std::f64::consts::SQRT_2);
+
+        let q = Coordinates::cartesian(p.x(), p.y(), p.z());
+        assert_relative_eq!(q.latitude(), FRAC_PI_4);
+        assert_relative_eq!(q.longitude(), FRAC_PI_2);
+    }
+
+    #[test]
+    fn origin_and_axes() {
+        let origin = Coordinates::cartesian(0.0f64, 0.0, 0.0);
+        assert_eq!(origin.r(), 0.0);
+        assert_eq!(origin.theta(), 0.0);
+        assert_eq!(origin.phi(), 0.0);
+        assert_eq!(origin.theta_cos(), 1.0);
+        assert_eq!(CartesianCoordinates::new(0.0f64, 0.0, 0.0).theta_cos(), 1.0);
+
+        let south = Coordinates::cartesian(0.0f64, 0.0, -2.0);
+        assert_eq!(south.r(), 2.0);
+        assert_eq!(south.theta(), std::f64::consts::PI);
+
+        // Neither underflows nor overflows when squared
+        for scale in [1e-200, 1e200] {
+            let p = Coordinates::cartesian(0.0f64, scale, scale);
+            assert_relative_eq!(p.r(), scale * std::f64::consts::SQRT_2);
+            assert_relative_eq!(p.theta(), std::f64::consts::FRAC_PI_4);
+        }
+    }
 }
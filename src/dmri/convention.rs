@@ -0,0 +1,212 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::dmri::symmetric::sym_degree;
+use crate::dmri::{sym_degree_order, sym_index};
+use crate::names::named_enum;
+use crate::{SphrsError, SphrsFloat};
+
+/// Real symmetric SH conventions of neuroimaging software
+///
+/// All conventions order the coefficients like the symmetric basis of this crate (by even degree
+/// `l` and then by order `m`, index `l (l + 1) / 2 + m`), but differ in the definition of the real
+/// basis functions. With the complex harmonics Y_l^m including the Condon-Shortley phase, the
+/// basis functions of degree `l` and order `m` are defined as follows:
+///
+/// | Convention               | m < 0                | m = 0 | m > 0            |
+/// |--------------------------|----------------------|-------|------------------|
+/// | `Mrtrix3`                | sqrt(2) Im Y_l^\|m\| | Y_l^0 | sqrt(2) Re Y_l^m |
+/// | `DipyDescoteaux07`       | sqrt(2) Re Y_l^m     | Y_l^0 | sqrt(2) Im Y_l^m |
+/// | `DipyTournier07Legacy`   | Im Y_l^\|m\|         | Y_l^0 | Re Y_l^m         |
+/// | `DipyDescoteaux07Legacy` | sqrt(2) Re Y_l^\|m\| | Y_l^0 | sqrt(2) Im Y_l^m |
+///
+/// DIPY's non-legacy `tournier07` basis is identical to `Mrtrix3`. The names used by `Display` and
+/// `FromStr` are the variant names in snake case (e.g. `dipy_descoteaux07`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SHConvention {
+    /// MRtrix3 (also DIPY's `tournier07` basis with `legacy=False`)
+    Mrtrix3,
+    /// DIPY's `descoteaux07` basis with `legacy=False`
+    DipyDescoteaux07,
+    /// DIPY's `tournier07` basis with `legacy=True` (not orthonormal)
+    DipyTournier07Legacy,
+    /// DIPY's `descoteaux07` basis with `legacy=True`
+    DipyDescoteaux07Legacy,
+}
+
+named_enum!(SHConvention, "SH convention", {
+    Mrtrix3 => "mrtrix3",
+    DipyDescoteaux07 => "dipy_descoteaux07",
+    DipyTournier07Legacy => "dipy_tournier07_legacy",
+    DipyDescoteaux07Legacy => "dipy_descoteaux07_legacy",
+});
+
+impl SHConvention {
+    /// Index in the symmetric basis of this crate and factor w such that the basis function
+    /// (l, m) of `self` equals w times the basis function of this crate at the returned index
+    fn map<T: SphrsFloat>(&self, l: usize, m: i64) -> (usize, T) {
+        let sign = if m % 2 == 0 { T::one() } else { -T::one() };
+        match (self, m) {
+            (_, 0) => (sym_index(l, 0), T::one()),
+            (SHConvention::Mrtrix3, _) => (sym_index(l, m), sign),
+            (SHConvention::DipyTournier07Legacy, _) => (sym_index(l, m), sign / T::SQRT_2()),
+            (SHConvention::DipyDescoteaux07, m) if m < 0 => (sym_index(l, -m), T::one()),
+            (SHConvention::DipyDescoteaux07Legacy, m) if m < 0 => (sym_index(l, -m), sign),
+            (SHConvention::DipyDescoteaux07 | SHConvention::DipyDescoteaux07Legacy, m) => {
+                (sym_index(l, -m), sign)
+            }
+        }
+    }
+}
+
+/// Convert coefficients given in `convention` to the symmetric basis of this crate
+///
+/// Returns [`SphrsError::InvalidInput`] if the length of `coeffs` does not correspond to a
+/// symmetric basis up to some even degree.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::dmri::{from_convention, to_convention, SHConvention};
+///
+/// // Coefficients up to degree 2 as stored by MRtrix3
+/// let mrtrix = vec![1.0f64, 0.1, -0.2, 0.3, 0.4, 0.5];
+/// let sphrs = from_convention(SHConvention::Mrtrix3, &mrtrix).unwrap();
+/// let back = to_convention(SHConvention::Mrtrix3, &sphrs).unwrap();
+/// assert_eq!(mrtrix, back);
+/// ```
+pub fn from_convention<T: SphrsFloat>(
+    convention: SHConvention,
+    coeffs: &[T],
+) -> Result<Vec<T>, SphrsError> {
+    sym_degree(coeffs.len())?;
+    let mut out = vec![T::zero(); coeffs.len()];
+    for (j, &c) in coeffs.iter().enumerate() {
+        let (l, m) = sym_degree_order(j);
+        let (k, w) = convention.map::<T>(l, m);
+        out[k] = w * c;
+    }
+    Ok(out)
+}
+
+/// Convert coefficients of the symmetric basis of this crate to `convention`
+///
+/// Returns [`SphrsError::InvalidInput`] if the length of `coeffs` does not correspond to a
+/// symmetric basis up to some even degree.
+pub fn to_convention<T: SphrsFloat>(
+    convention: SHConvention,
+    coeffs: &[T],
+) -> Result<Vec<T>, SphrsError> {
+    sym_degree(coeffs.len())?;
+    Ok((0..coeffs.len())
+        .map(|j| {
+            let (l, m) = sym_degree_order(j);
+            let (k, w) = convention.map::<T>(l, m);
+            coeffs[k] / w
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmri::SymmetricHarmonicsSet;
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+
+    const CONVENTIONS: [SHConvention; 4] = [
+        SHConvention::Mrtrix3,
+        SHConvention::DipyDescoteaux07,
+        SHConvention::DipyTournier07Legacy,
+        SHConvention::DipyDescoteaux07Legacy,
+    ];
+
+    /// Evaluate the basis functions of `convention` up to degree 4 at `p`
+    fn basis(convention: SHConvention, p: &Coordinates<f64>) -> Vec<f64> {
+        let set = SymmetricHarmonicsSet::new(4);
+        (0..15)
+            .map(|j| {
+                let mut e = vec![0.0; 15];
+                e[j] = 1.0;
+                let c = from_convention(convention, &e).unwrap();
+                set.eval_sum(p, &c).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reference_values() {
+        // Computed with the definitions of DIPY's `real_sh_descoteaux_from_index` and
+        // `real_sh_tournier_from_index` (legacy=False) at theta = 0.7, phi = 1.9.
+        let descoteaux = [
+            0.28209479177387814,
+            -0.1793224839868262,
+            -0.17403510758916205,
+            0.23810508748746864,
+            -0.5094177308909151,
+            -0.13871599965215867,
+            0.027084075682337064,
+            0.3021427548737338,
+            -0.4806289141075458,
+            -0.11668660546165753,
+            -0.27211267889929447,
+            -0.3415530728430036,
+            -0.3717934237797958,
+            0.1993328123537104,
+            0.10433505562330952,
+        ];
+        let tournier = [
+            0.28209479177387814,
+            -0.13871599965215867,
+            -0.5094177308909151,
+            0.23810508748746864,
+            0.17403510758916205,
+            -0.1793224839868262,
+            0.10433505562330952,
+            0.1993328123537104,
+            -0.3717934237797958,
+            -0.3415530728430036,
+            -0.27211267889929447,
+            0.11668660546165753,
+            -0.4806289141075458,
+            -0.3021427548737338,
+            0.027084075682337064,
+        ];
+        let p = Coordinates::spherical(1.0, 0.7, 1.9);
+        let mrtrix = basis(SHConvention::Mrtrix3, &p);
+        let dipy = basis(SHConvention::DipyDescoteaux07, &p);
+        let legacy = basis(SHConvention::DipyTournier07Legacy, &p);
+        for j in 0..15 {
+            assert_relative_eq!(mrtrix[j], tournier[j], epsilon = 1e-12);
+            assert_relative_eq!(dipy[j], descoteaux[j], epsilon = 1e-12);
+            let (_, m) = sym_degree_order(j);
+            let scale = if m == 0 { 1.0 } else { 2f64.sqrt() };
+            assert_relative_eq!(legacy[j] * scale, tournier[j], epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let coeffs: Vec<f64> = (0..28).map(|i| (i as f64 * 0.37).sin()).collect();
+        for convention in CONVENTIONS {
+            let sphrs = from_convention(convention, &coeffs).unwrap();
+            let back = to_convention(convention, &sphrs).unwrap();
+            for (a, b) in coeffs.iter().zip(back.iter()) {
+                assert_relative_eq!(a, b, epsilon = 1e-15);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_length() {
+        assert!(matches!(
+            from_convention(SHConvention::Mrtrix3, &[1.0f64; 7]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::dmri::symmetric::sym_degree;
+use crate::dmri::{num_sym_coefficients, sym_degree_order, SymmetricHarmonicsSet};
+use crate::linalg::solve_spd;
+use crate::sampling::fibonacci;
+use crate::{Coordinates, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Convolution kernel sqrt(4 pi / (2l + 1)) r_l for every coefficient of the symmetric basis up to
+/// degree `degree`. Degrees not covered by `response` have a kernel of zero.
+fn kernel<T: SphrsFloat>(response: &[T], degree: usize) -> Vec<T> {
+    let four_pi = T::from_f64(4.0).unwrap() * T::PI();
+    (0..num_sym_coefficients(degree))
+        .map(|j| {
+            let (l, _) = sym_degree_order(j);
+            response.get(l / 2).map_or(T::zero(), |&r| {
+                (four_pi / T::from_usize(2 * l + 1).unwrap()).sqrt() * r
+            })
+        })
+        .collect()
+}
+
+/// Spherical convolution of a fiber orientation distribution with an axially symmetric response
+///
+/// `response` holds the coefficients of Y_l0 for the even degrees l = 0, 2, 4, ... of the response
+/// to a single fiber along the z-axis (the same layout as the response files of MRtrix3). `fodf`
+/// contains coefficients of the symmetric basis. Returns the coefficients of the resulting
+/// signal, or [`SphrsError::InvalidInput`] if the length of `fodf` does not correspond to a
+/// symmetric basis.
+pub fn convolve<T: SphrsFloat>(response: &[T], fodf: &[T]) -> Result<Vec<T>, SphrsError> {
+    let degree = sym_degree(fodf.len())?;
+    Ok(kernel(response, degree)
+        .iter()
+        .zip(fodf.iter())
+        .map(|(&h, &f)| h * f)
+        .collect())
+}
+
+/// Unconstrained spherical deconvolution of signal coefficients with an axially symmetric
+/// response
+///
+/// Inverse of [`convolve`]. Returns [`SphrsError::SingularSystem`] if the response has a vanishing
+/// coefficient at one of the degrees of `signal`.
+pub fn deconvolve<T: SphrsFloat>(response: &[T], signal: &[T]) -> Result<Vec<T>, SphrsError> {
+    let degree = sym_degree(signal.len())?;
+    kernel(response, degree)
+        .iter()
+        .zip(signal.iter())
+        .map(|(&h, &s)| {
+            if h == T::zero() {
+                Err(SphrsError::SingularSystem)
+            } else {
+                Ok(s / h)
+            }
+        })
+        .collect()
+}
+
+/// Constrained spherical deconvolution (CSD)
+///
+/// Estimates the coefficients of the fiber orientation distribution (fODF) in the symmetric
+/// basis from diffusion weighted signals, following Tournier et al. (2007): starting from an
+/// unconstrained estimate up to degree 4, the least squares problem is repeatedly solved with an
+/// additional penalty on the fODF amplitudes which fall below `tau` times the mean amplitude of
+/// the initial estimate, until the set of penalized directions no longer changes. The penalty
+/// allows to estimate more coefficients than there are signal samples ("super-resolution").
+///
+/// # Example
+///
+/// ```
+/// use sphrs::dmri::{convolve, ConstrainedSD, SymmetricHarmonicsSet};
+/// use sphrs::sampling::fibonacci;
+///
+/// let response = [1.0f64, -0.4, 0.1];
+/// // Signal of an fODF which is constant (i.e. isotropic)
+/// let signal_coeffs = convolve(&response, &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+/// let set = SymmetricHarmonicsSet::new(2);
+/// let directions = fibonacci::<f64>(30, 0);
+/// let signal: Vec<f64> = directions
+///     .iter()
+///     .map(|p| set.eval_sum(p, &signal_coeffs).unwrap())
+///     .collect();
+///
+/// let fodf = ConstrainedSD::new(4, &response)
+///     .unwrap()
+///     .fit(&directions, &signal)
+///     .unwrap();
+/// assert_eq!(fodf.len(), 15);
+/// assert!((fodf[0] - 1.0).abs() < 1e-8);
+/// ```
+pub struct ConstrainedSD<T> {
+    /// Degree of the fODF
+    degree: usize,
+    /// Zonal coefficients of the response of even degree
+    response: Vec<T>,
+    /// Weight of the non-negativity penalty
+    lambda: T,
+    /// Amplitude threshold relative to the mean amplitude of the initial estimate
+    tau: T,
+    /// Maximum number of iterations
+    max_iter: usize,
+    /// Directions at which the non-negativity constraint is enforced
+    constraint_directions: Vec<Coordinates<T>>,
+}
+
+impl<T: SphrsFloat> ConstrainedSD<T> {
+    /// Create CSD for an fODF up to the even degree `degree` with `response` as described in
+    /// [`convolve`]
+    ///
+    /// Defaults to `lambda = 1`, `tau = 0.1`, 50 iterations and 300 constraint directions
+    /// distributed over the upper hemisphere. Returns [`SphrsError::InvalidConfiguration`] if
+    /// `degree` is odd.
+    pub fn new(degree: usize, response: &[T]) -> Result<Self, SphrsError> {
+        if !degree.is_multiple_of(2) {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "degree {degree} of the fODF must be even"
+            )));
+        }
+        // The fODF is antipodally symmetric, therefore one hemisphere suffices.
+        let constraint_directions = fibonacci(600, 0)
+            .into_iter()
+            .filter(|p: &Coordinates<T>| p.z() > T::zero())
+            .collect();
+        Ok(ConstrainedSD {
+            degree,
+            response: response.to_vec(),
+            lambda: T::one(),
+            tau: T::from_f64(0.1).unwrap(),
+            max_iter: 50,
+            constraint_directions,
+        })
+    }
+
+    /// Weight of the non-negativity penalty
+    ///
+    /// The weight is relative to the response magnitude and the ratio of signal samples to
+    /// constraint directions, such that the default of one is a sensible choice for most data.
+    #[must_use]
+    pub fn lambda(mut self, lambda: T) -> Self {
+        self.lambda = lambda;
+        self
+    }
+
+    /// Amplitude threshold relative to the mean amplitude of the initial estimate
+    #[must_use]
+    pub fn tau(mut self, tau: T) -> Self {
+        self.tau = tau;
+        self
+    }
+
+    /// Maximum number of iterations
+    #[must_use]
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Directions at which non-negativity of the fODF is enforced
+    #[must_use]
+    pub fn constraint_directions(mut self, directions: Vec<Coordinates<T>>) -> Self {
+        self.constraint_directions = directions;
+        self
+    }
+
+    /// Estimate the fODF from the `signal` measured along `directions`
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if `directions` and `signal` differ in length and
+    /// [`SphrsError::SingularSystem`] if the signal samples and constraints do not determine the
+    /// fODF.
+    pub fn fit<C: SHCoordinates<T>>(
+        &self,
+        directions: &[C],
+        signal: &[T],
+    ) -> Result<Vec<T>, SphrsError> {
+        if directions.len() != signal.len() {
+            return Err(SphrsError::InvalidInput(format!(
+                "{} directions but {} signal values",
+                directions.len(),
+                signal.len()
+            )));
+        }
+        let n = num_sym_coefficients(self.degree);
+        let h = kernel(&self.response, self.degree);
+        let set = SymmetricHarmonicsSet::<T>::new(self.degree);
+
+        // Normal equations of the unconstrained problem with design matrix Y diag(h)
+        let mut ata = vec![T::zero(); n * n];
+        let mut atb = vec![T::zero(); n];
+        for (p, &s) in directions.iter().zip(signal.iter()) {
+            let row: Vec<T> = set
+                .eval(p)
+                .iter()
+                .zip(h.iter())
+                .map(|(&y, &h)| y * h)
+                .collect();
+            for i in 0..n {
+                atb[i] = atb[i] + row[i] * s;
+                for j in 0..n {
+                    ata[i * n + j] = ata[i * n + j] + row[i] * row[j];
+                }
+            }
+        }
+
+        // Initial estimate up to degree 4
+        let n0 = num_sym_coefficients(self.degree.min(4));
+        let ata0 = (0..n0 * n0).map(|k| ata[(k / n0) * n + k % n0]).collect();
+        let mut fodf = solve_spd(ata0, n0, atb[..n0].to_vec()).ok_or(SphrsError::SingularSystem)?;
+        fodf.resize(n, T::zero());
+
+        let constraints: Vec<Vec<T>> = self
+            .constraint_directions
+            .iter()
+            .map(|p| set.eval(p))
+            .collect();
+        let amplitude = |fodf: &[T], row: &[T]| -> T {
+            row.iter()
+                .zip(fodf.iter())
+                .fold(T::zero(), |acc, (&y, &f)| acc + y * f)
+        };
+        let mean = constraints
+            .iter()
+            .fold(T::zero(), |acc, row| acc + amplitude(&fodf, row))
+            / T::from_usize(constraints.len().max(1)).unwrap();
+        let threshold = self.tau * mean;
+        let weight = self.lambda
+            * h[0].abs()
+            * (T::from_usize(directions.len()).unwrap()
+                / T::from_usize(constraints.len().max(1)).unwrap())
+            .sqrt();
+        let weight2 = weight * weight;
+
+        let mut active: Option<Vec<usize>> = None;
+        for _ in 0..self.max_iter {
+            let negative: Vec<usize> = (0..constraints.len())
+                .filter(|&i| amplitude(&fodf, &constraints[i]) < threshold)
+                .collect();
+            if active.as_ref() == Some(&negative) {
+                break;
+            }
+            let mut a = ata.clone();
+            for &k in &negative {
+                let row = &constraints[k];
+                for i in 0..n {
+                    for j in 0..n {
+                        a[i * n + j] = a[i * n + j] + weight2 * row[i] * row[j];
+                    }
+                }
+            }
+            fodf = solve_spd(a, n, atb.clone()).ok_or(SphrsError::SingularSystem)?;
+            active = Some(negative);
+        }
+        Ok(fodf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const RESPONSE: [f64; 5] = [1.0, -0.6, 0.3, -0.1, 0.03];
+
+    /// Symmetric coefficients up to degree 8 of a sum of (truncated) delta functions along `axes`
+    fn fibers(axes: &[Coordinates<f64>]) -> Vec<f64> {
+        let set = SymmetricHarmonicsSet::new(8);
+        let mut out = vec![0.0; set.num_sh()];
+        for a in axes {
+            for (o, y) in out.iter_mut().zip(set.eval(a)) {
+                *o += y;
+            }
+        }
+        out
+    }
+
+    fn sample(coeffs: &[f64], directions: &[Coordinates<f64>]) -> Vec<f64> {
+        let set = SymmetricHarmonicsSet::new(8);
+        directions
+            .iter()
+            .map(|p| set.eval_sum(p, coeffs).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn convolution_roundtrip() {
+        let fodf: Vec<f64> = (0..45).map(|j| (j as f64 * 0.3).cos()).collect();
+        let signal = convolve(&RESPONSE, &fodf).unwrap();
+        let back = deconvolve(&RESPONSE, &signal).unwrap();
+        for (a, b) in fodf.iter().zip(back.iter()) {
+            assert_relative_eq!(a, b, epsilon = 1e-12);
+        }
+        assert!(matches!(
+            deconvolve(&RESPONSE[..2], &signal),
+            Err(SphrsError::SingularSystem)
+        ));
+    }
+
+    #[test]
+    fn convolution_of_delta_is_rotated_response() {
+        // The signal of a single fiber along z is the response itself
+        let fodf = fibers(&[Coordinates::cartesian(0.0, 0.0, 1.0)]);
+        let signal = convolve(&RESPONSE, &fodf).unwrap();
+        for l in (0..=8).step_by(2) {
+            assert_relative_eq!(
+                signal[crate::dmri::sym_index(l, 0)],
+                RESPONSE[l / 2],
+                epsilon = 1e-12
+            );
+        }
+    }
+
+    #[test]
+    fn csd_without_active_constraints_is_least_squares() {
+        let mut fodf = vec![0.0; 45];
+        fodf[0] = 1.0;
+        fodf[3] = 0.2;
+        let signal_coeffs = convolve(&RESPONSE, &fodf).unwrap();
+        let directions = fibonacci::<f64>(64, 1);
+        let signal = sample(&signal_coeffs, &directions);
+        let estimate = ConstrainedSD::new(8, &RESPONSE)
+            .unwrap()
+            .fit(&directions, &signal)
+            .unwrap();
+        for (a, b) in fodf.iter().zip(estimate.iter()) {
+            assert_relative_eq!(a, b, epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn csd_resolves_crossing_fibers() {
+        let axes = [
+            Coordinates::cartesian(1.0, 0.0, 0.2),
+            Coordinates::cartesian(0.0, 1.0, -0.2),
+        ];
+        let signal_coeffs = convolve(&RESPONSE, &fibers(&axes)).unwrap();
+        // Fewer samples than coefficients: only solvable due to the constraint
+        let directions = fibonacci::<f64>(40, 2);
+        let signal = sample(&signal_coeffs, &directions);
+        let estimate = ConstrainedSD::new(8, &RESPONSE)
+            .unwrap()
+            .fit(&directions, &signal)
+            .unwrap();
+        let set = SymmetricHarmonicsSet::new(8);
+        let amp = |p: &Coordinates<f64>| -> f64 { set.eval_sum(p, &estimate).unwrap() };
+        let on_axis = amp(&axes[0]).min(amp(&axes[1]));
+        let between = amp(&Coordinates::cartesian(1.0, 1.0, 0.0));
+        let off = amp(&Coordinates::cartesian(0.0, 0.0, 1.0));
+        assert!(on_axis > 2.0 * between, "{on_axis} {between}");
+        assert!(on_axis > 2.0 * off, "{on_axis} {off}");
+    }
+
+    #[test]
+    fn csd_rejects_odd_degree() {
+        assert!(matches!(
+            ConstrainedSD::new(7, &RESPONSE),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spherical harmonics for diffusion MRI
+//!
+//! Diffusion signals and fiber orientation distributions are antipodally symmetric, i.e.
+//! f(-x) = f(x). Only the even degrees of their expansions are non-zero, which is why dMRI
+//! software uses a basis consisting of the real spherical harmonics of even degree only.
+//!
+//! Within this symmetric basis, the harmonic of degree `l` (even) and order `m` has the index
+//! `l (l + 1) / 2 + m` (see [`sym_index`]), and a basis up to degree `L` contains
+//! `(L + 1) (L + 2) / 2` harmonics (see [`num_sym_coefficients`]).
+//!
+//! Coefficients of MRtrix3 and DIPY use the same ordering, but different definitions of the real
+//! basis functions. Use [`from_convention`] and [`to_convention`] to convert between them.
+//!
+//! Fiber orientation distributions are estimated from diffusion weighted signals with
+//! [`ConstrainedSD`].
+
+mod convention;
+mod csd;
+mod symmetric;
+
+pub use convention::{from_convention, to_convention, SHConvention};
+pub use csd::{convolve, deconvolve, ConstrainedSD};
+pub use symmetric::{
+    full_from_symmetric, num_sym_coefficients, sym_degree_order, sym_index, symmetric_from_full,
+    SymmetricHarmonicsSet,
+};
@@ -0,0 +1,234 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Index of (l, m) in the symmetric basis. `l` must be even.
+#[inline(always)]
+pub fn sym_index(l: usize, m: i64) -> usize {
+    debug_assert!(l.is_multiple_of(2));
+    debug_assert!(m.unsigned_abs() as usize <= l);
+    ((l * (l + 1) / 2) as i64 + m) as usize
+}
+
+/// Degree and order of the harmonic with index `j` in the symmetric basis
+pub fn sym_degree_order(j: usize) -> (usize, i64) {
+    let mut l = 0;
+    while num_sym_coefficients(l) <= j {
+        l += 2;
+    }
+    (l, j as i64 - (l * (l + 1) / 2) as i64)
+}
+
+/// Number of harmonics in the symmetric basis up to (and including) the even degree `degree`
+#[inline(always)]
+pub fn num_sym_coefficients(degree: usize) -> usize {
+    debug_assert!(degree.is_multiple_of(2));
+    (degree + 1) * (degree + 2) / 2
+}
+
+/// Degree of a symmetric coefficient vector of length `len`
+pub(crate) fn sym_degree(len: usize) -> Result<usize, SphrsError> {
+    let mut degree = 0;
+    while num_sym_coefficients(degree) < len {
+        degree += 2;
+    }
+    if num_sym_coefficients(degree) != len {
+        return Err(SphrsError::InvalidInput(format!(
+            "{len} is not the number of coefficients of a symmetric basis"
+        )));
+    }
+    Ok(degree)
+}
+
+/// Extract the even degrees of `coeffs` in the ordering of the symmetric basis
+///
+/// If the degree of `coeffs` is odd, the highest degree is dropped.
+pub fn symmetric_from_full<T: Copy>(coeffs: &SHCoefficients<T>) -> Vec<T> {
+    let degree = coeffs.degree() - coeffs.degree() % 2;
+    let mut out = Vec::with_capacity(num_sym_coefficients(degree));
+    for l in (0..=degree as i64).step_by(2) {
+        out.extend((-l..=l).map(|m| *coeffs.get(l, m).unwrap()));
+    }
+    out
+}
+
+/// Expand coefficients of the symmetric basis up to degree `degree` into a full set of
+/// coefficients with all odd degrees set to zero
+///
+/// Panics if `degree` is odd or the length of `sym` does not match `degree`.
+pub fn full_from_symmetric<T: Copy + num::Zero>(degree: usize, sym: &[T]) -> SHCoefficients<T> {
+    assert!(
+        degree.is_multiple_of(2),
+        "degree of the symmetric basis must be even"
+    );
+    assert_eq!(sym.len(), num_sym_coefficients(degree));
+    let mut out = SHCoefficients::zeros(degree);
+    for l in (0..=degree).step_by(2) {
+        for m in -(l as i64)..=l as i64 {
+            *out.get_mut(l as i64, m).unwrap() = sym[sym_index(l, m)];
+        }
+    }
+    out
+}
+
+/// Set of the real spherical harmonics of even degree up to a given (even) degree
+///
+/// The harmonics are the same as computed by [`RealSH::Spherical`], ordered by degree and then by
+/// order, skipping all odd degrees.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::dmri::{sym_index, SymmetricHarmonicsSet};
+/// use sphrs::Coordinates;
+///
+/// let sh = SymmetricHarmonicsSet::new(4);
+/// assert_eq!(sh.num_sh(), 15);
+///
+/// let p = Coordinates::spherical(1.0, 0.8, 0.4);
+/// let set: Vec<f64> = sh.eval(&p);
+/// let y2n1 = set[sym_index(2, -1)];
+/// ```
+pub struct SymmetricHarmonicsSet<T> {
+    /// degree
+    degree: usize,
+    /// Total number of harmonics
+    num_sh: usize,
+    /// Harmonics of even degree
+    set: HarmonicsSet<T, RealSH>,
+}
+
+impl<T> SymmetricHarmonicsSet<T>
+where
+    T: SphrsFloat,
+{
+    /// Create new `SymmetricHarmonicsSet` up to degree `degree`
+    ///
+    /// Panics if `degree` is odd.
+    pub fn new(degree: usize) -> SymmetricHarmonicsSet<T> {
+        assert!(
+            degree.is_multiple_of(2),
+            "degree of the symmetric basis must be even"
+        );
+        SymmetricHarmonicsSet {
+            degree,
+            num_sh: num_sym_coefficients(degree),
+            set: HarmonicsSet::even_degrees(degree, RealSH::Spherical),
+        }
+    }
+
+    /// Returns the degree of the set
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the total number of spherical harmonics in the set
+    pub fn num_sh(&self) -> usize {
+        self.num_sh
+    }
+
+    /// Evaluate harmonics at position `p` without coefficients.
+    pub fn eval<C>(&self, p: &C) -> Vec<T>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.set.eval(p)
+    }
+
+    /// Evaluate harmonics at position `p` with a given vector of coefficients.
+    ///
+    /// Returns [`SphrsError::LengthMismatch`] if the number of coefficients differs from the
+    /// number of harmonics.
+    pub fn eval_with_coefficients<C>(&self, p: &C, coefficients: &[T]) -> Result<Vec<T>, SphrsError>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.check_length(coefficients.len())?;
+        Ok(self
+            .eval(p)
+            .into_iter()
+            .zip(coefficients.iter())
+            .map(|(a, &b)| b * a)
+            .collect())
+    }
+
+    /// Evaluate the expansion with the given coefficients at position `p`
+    ///
+    /// Returns [`SphrsError::LengthMismatch`] if the number of coefficients differs from the
+    /// number of harmonics.
+    pub fn eval_sum<C>(&self, p: &C, coefficients: &[T]) -> Result<T, SphrsError>
+    where
+        C: SHCoordinates<T>,
+    {
+        self.check_length(coefficients.len())?;
+        Ok(self
+            .eval(p)
+            .into_iter()
+            .zip(coefficients.iter())
+            .fold(T::zero(), |acc, (a, &b)| acc + b * a))
+    }
+
+    fn check_length(&self, found: usize) -> Result<(), SphrsError> {
+        if found != self.num_sh {
+            return Err(SphrsError::LengthMismatch {
+                expected: self.num_sh,
+                found,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::HarmonicsSet;
+    use crate::Coordinates;
+
+    #[test]
+    fn indexing() {
+        let mut j = 0;
+        for l in (0..=8).step_by(2) {
+            for m in -(l as i64)..=l as i64 {
+                assert_eq!(sym_index(l, m), j);
+                assert_eq!(sym_degree_order(j), (l, m));
+                j += 1;
+            }
+            assert_eq!(num_sym_coefficients(l), j);
+        }
+    }
+
+    #[test]
+    fn matches_even_degrees_of_full_set() {
+        let p = Coordinates::spherical(1.0, 1.1, -2.3);
+        let full = HarmonicsSet::new(6, RealSH::Spherical).eval(&p);
+        let sym = SymmetricHarmonicsSet::new(6).eval(&p);
+        let full = symmetric_from_full(&SHCoefficients::from_vec(6, full));
+        assert_eq!(sym, full);
+    }
+
+    #[test]
+    fn antipodal_symmetry() {
+        let p = Coordinates::cartesian(0.3f64, -0.4, 0.8);
+        let q = Coordinates::cartesian(-0.3, 0.4, -0.8);
+        let sh = SymmetricHarmonicsSet::new(8);
+        for (a, b) in sh.eval(&p).iter().zip(sh.eval(&q).iter()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn full_roundtrip() {
+        let sym: Vec<f64> = (0..15).map(|i| i as f64).collect();
+        let full = full_from_symmetric(4, &sym);
+        assert_eq!(full.len(), 25);
+        assert_eq!(*full.get(1, 0).unwrap(), 0.0);
+        assert_eq!(symmetric_from_full(&full), sym);
+    }
+}
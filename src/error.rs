@@ -0,0 +1,74 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::fmt;
+
+/// Errors returned by the fallible operations of sphrs
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SphrsError {
+    /// Input does not follow the expected format
+    InvalidFormat(String),
+    /// Format version is not supported by this version of sphrs
+    UnsupportedVersion(u16),
+    /// Stored data type does not match the requested data type
+    DtypeMismatch {
+        /// Data type requested by the caller
+        expected: &'static str,
+        /// Data type found in the input
+        found: &'static str,
+    },
+    /// Invalid configuration of a builder
+    InvalidConfiguration(String),
+    /// Invalid input data
+    InvalidInput(String),
+    /// Number of coefficients does not match the number of harmonics
+    LengthMismatch {
+        /// Number of harmonics
+        expected: usize,
+        /// Number of coefficients given
+        found: usize,
+    },
+    /// Linear system is singular or not sufficiently determined
+    SingularSystem,
+    /// Underlying I/O error
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SphrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SphrsError::InvalidFormat(msg) => write!(f, "invalid format: {msg}"),
+            SphrsError::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            SphrsError::DtypeMismatch { expected, found } => {
+                write!(f, "data type mismatch: expected {expected}, found {found}")
+            }
+            SphrsError::InvalidConfiguration(msg) => write!(f, "invalid configuration: {msg}"),
+            SphrsError::InvalidInput(msg) => write!(f, "invalid input: {msg}"),
+            SphrsError::LengthMismatch { expected, found } => {
+                write!(f, "expected {expected} coefficients, found {found}")
+            }
+            SphrsError::SingularSystem => write!(f, "linear system is singular"),
+            SphrsError::Io(e) => write!(f, "I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SphrsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SphrsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SphrsError {
+    fn from(e: std::io::Error) -> Self {
+        SphrsError::Io(e)
+    }
+}
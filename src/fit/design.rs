@@ -0,0 +1,81 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use ndarray::Array2;
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::{SHCoordinates, SphrsFloat};
+
+/// Design matrix of the harmonics up to degree `degree` at `points`
+///
+/// Row i holds the harmonics of type `basis` evaluated at `points[i]`, in the order of
+/// [`HarmonicsSet`], such that the values of an expansion with coefficients `c` at the points are
+/// the matrix-vector product of the design matrix and `c`. This allows to use the harmonics as
+/// basis functions in other solvers, e.g. for sparse regression or Gaussian process regression.
+/// Requires the `ndarray` feature.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array1;
+/// use sphrs::basis::RealSH;
+/// use sphrs::fit::design_matrix;
+/// use sphrs::Coordinates;
+///
+/// let points = [
+///     Coordinates::spherical(1.0, 0.3, 0.1),
+///     Coordinates::spherical(1.0, 1.7, -2.0),
+///     Coordinates::spherical(1.0, 2.5, 0.9),
+/// ];
+/// let a = design_matrix(2, &points, RealSH::Spherical);
+/// assert_eq!(a.dim(), (3, 9));
+/// let values = a.dot(&Array1::from_elem(9, 1.0f64));
+/// assert_eq!(values.len(), 3);
+/// ```
+pub fn design_matrix<T, E, C>(degree: usize, points: &[C], basis: E) -> Array2<E::Output>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    C: SHCoordinates<T>,
+{
+    let set = HarmonicsSet::new(degree, basis);
+    let n = set.num_sh();
+    let mut data = Vec::with_capacity(points.len() * n);
+    for p in points {
+        data.extend(set.eval(p));
+    }
+    Array2::from_shape_vec((points.len(), n), data).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, RealSH};
+    use crate::sampling::fibonacci;
+    use num_complex::Complex;
+
+    #[test]
+    fn rows_are_harmonics_sets() {
+        let points = fibonacci::<f64>(10, 0);
+        let real = design_matrix(3, &points, RealSH::Spherical);
+        let complex = design_matrix(3, &points, ComplexSH::Spherical);
+        assert_eq!(real.dim(), (10, 16));
+        assert_eq!(complex.dim(), (10, 16));
+        let real_set = HarmonicsSet::new(3, RealSH::Spherical);
+        let complex_set = HarmonicsSet::new(3, ComplexSH::Spherical);
+        for (i, p) in points.iter().enumerate() {
+            let y: Vec<f64> = real_set.eval(p);
+            assert_eq!(real.row(i).to_vec(), y);
+            let y: Vec<Complex<f64>> = complex_set.eval(p);
+            assert_eq!(complex.row(i).to_vec(), y);
+        }
+        assert_eq!(
+            design_matrix(2, &points[..0], RealSH::Spherical).dim(),
+            (0, 9)
+        );
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::fit::least_squares;
+use crate::grid::{analyze, SphericalGrid};
+use crate::sampling::fibonacci;
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Minimum number of probe directions for the covering radius of scattered points
+const PROBES: usize = 1024;
+
+/// Spectral content of sampled data and the degree its sampling supports
+///
+/// Created with [`diagnose_grid`] or [`diagnose_points`]. The data is analyzed up to the lower of
+/// the requested degree and the degree the sampling supports, such that the estimated spectrum
+/// is not itself corrupted by aliasing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SamplingDiagnostics<T> {
+    /// Degree requested by the caller
+    requested_degree: usize,
+    /// Highest degree the sampling supports
+    supported_degree: usize,
+    /// Power per degree of the analysis up to the analyzed degree
+    spectrum: Vec<T>,
+}
+
+impl<T: SphrsFloat> SamplingDiagnostics<T> {
+    /// Create the diagnostics from the coefficients of the analysis
+    fn new(requested_degree: usize, supported_degree: usize, coeffs: &SHCoefficients<T>) -> Self {
+        let spectrum = (0..=coeffs.degree() as i64)
+            .map(|l| {
+                (-l..=l).fold(T::zero(), |acc, m| {
+                    let c = *coeffs.get(l, m).unwrap();
+                    acc + c * c
+                })
+            })
+            .collect();
+        SamplingDiagnostics {
+            requested_degree,
+            supported_degree,
+            spectrum,
+        }
+    }
+
+    /// Degree requested by the caller
+    pub fn requested_degree(&self) -> usize {
+        self.requested_degree
+    }
+
+    /// Highest degree the sampling supports without aliasing
+    pub fn supported_degree(&self) -> usize {
+        self.supported_degree
+    }
+
+    /// Degree up to which the data was analyzed, the lower of the requested and the supported
+    /// degree
+    pub fn analyzed_degree(&self) -> usize {
+        self.spectrum.len() - 1
+    }
+
+    /// Power sum_m c_lm^2 of each degree l up to the analyzed degree
+    pub fn spectrum(&self) -> &[T] {
+        &self.spectrum
+    }
+
+    /// Whether an analysis at the requested degree aliases because the sampling is not dense
+    /// enough
+    pub fn aliasing(&self) -> bool {
+        self.requested_degree > self.supported_degree
+    }
+
+    /// Highest degree whose power exceeds `threshold` times the total power
+    pub fn effective_degree(&self, threshold: T) -> usize {
+        let total = self.spectrum.iter().fold(T::zero(), |acc, &p| acc + p);
+        self.spectrum
+            .iter()
+            .rposition(|&p| p > threshold * total)
+            .unwrap_or(0)
+    }
+
+    /// Whether the spectrum has decayed below `threshold` times the total power before the
+    /// analyzed degree
+    ///
+    /// If not, the data contains frequencies at or beyond the analyzed degree, which are either
+    /// truncated or, if the sampling does not resolve them, aliased into the lower degrees.
+    pub fn resolved(&self, threshold: T) -> bool {
+        self.effective_degree(threshold) < self.analyzed_degree()
+    }
+}
+
+/// Diagnose the analysis of `values` sampled on `grid` up to degree `degree`
+///
+/// The supported degree is the bandlimit of the grid. Returns [`SphrsError::InvalidInput`] if the
+/// number of values does not equal the number of grid directions.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::fit::diagnose_grid;
+/// use sphrs::grid::{GaussLegendre, SphericalGrid};
+/// use sphrs::SHCoordinates;
+///
+/// let grid = GaussLegendre::new(6).unwrap();
+/// let values: Vec<f64> = grid.directions::<f64>().iter().map(|p| p.x() * p.y()).collect();
+/// let diagnostics = diagnose_grid(&grid, 8, &values).unwrap();
+/// // The grid only resolves degrees up to 5
+/// assert!(diagnostics.aliasing());
+/// assert_eq!(diagnostics.analyzed_degree(), 5);
+/// assert_eq!(diagnostics.effective_degree(1e-12), 2);
+/// ```
+pub fn diagnose_grid<T, G>(
+    grid: &G,
+    degree: usize,
+    values: &[T],
+) -> Result<SamplingDiagnostics<T>, SphrsError>
+where
+    T: SphrsFloat,
+    G: SphericalGrid,
+{
+    let supported = grid.max_degree();
+    let coeffs = analyze(grid, degree.min(supported), values)?;
+    Ok(SamplingDiagnostics::new(degree, supported, &coeffs))
+}
+
+/// Degree supported by scattered `points`
+///
+/// Resolving the degree L requires at least (L + 1)^2 samples and no gaps wider than about
+/// pi / L. The gap width is estimated from the covering radius h, the largest angular distance
+/// of any direction to its nearest sample, which is computed over a Fibonacci lattice of probe
+/// directions, and limits the degree to pi / (2 h).
+pub fn supported_degree<T, C>(points: &[C]) -> usize
+where
+    T: SphrsFloat,
+    C: SHCoordinates<T>,
+{
+    if points.is_empty() {
+        return 0;
+    }
+    let unit: Vec<[T; 3]> = points
+        .iter()
+        .map(|p| [p.x() / p.r(), p.y() / p.r(), p.z() / p.r()])
+        .collect();
+    let probes = fibonacci::<T>((4 * points.len()).max(PROBES), 0);
+    let radius = probes
+        .iter()
+        .map(|q| {
+            let nearest = unit
+                .iter()
+                .map(|u| u[0] * q.x() + u[1] * q.y() + u[2] * q.z())
+                .fold(-T::one(), T::max);
+            nearest.min(T::one()).acos()
+        })
+        .fold(T::zero(), T::max);
+    let by_count = ((points.len() as f64).sqrt() as usize).saturating_sub(1);
+    if radius <= T::zero() {
+        return by_count;
+    }
+    let two = T::from_f64(2.0).unwrap();
+    let by_gaps = (T::PI() / (two * radius))
+        .floor()
+        .to_usize()
+        .unwrap_or(usize::MAX);
+    by_count.min(by_gaps)
+}
+
+/// Diagnose the least squares fit of `values` sampled at scattered `points` up to degree
+/// `degree`
+///
+/// The supported degree is estimated with [`supported_degree`]. Returns
+/// [`SphrsError::InvalidInput`] if `points` and `values` differ in length and
+/// [`SphrsError::SingularSystem`] if the points do not determine the coefficients up to the
+/// supported degree.
+pub fn diagnose_points<T, C>(
+    points: &[C],
+    values: &[T],
+    degree: usize,
+) -> Result<SamplingDiagnostics<T>, SphrsError>
+where
+    T: SphrsFloat,
+    C: SHCoordinates<T>,
+{
+    let supported = supported_degree(points);
+    let set = HarmonicsSet::new(degree.min(supported), RealSH::Spherical);
+    let coeffs = least_squares(&set, points, values)?;
+    Ok(SamplingDiagnostics::new(degree, supported, &coeffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::SHEval;
+    use crate::grid::{GaussLegendre, LatLon};
+    use crate::Coordinates;
+
+    fn f(p: &Coordinates<f64>) -> f64 {
+        RealSH::Spherical.eval(1, 0, p) + 0.1 * RealSH::Spherical.eval(4, 2, p)
+    }
+
+    #[test]
+    fn grid_spectrum() {
+        let grid = LatLon::new(19, 20).unwrap();
+        let values: Vec<f64> = grid.directions().iter().map(f).collect();
+        let d = diagnose_grid(&grid, 6, &values).unwrap();
+        assert!(!d.aliasing());
+        assert_eq!(d.analyzed_degree(), 6);
+        assert!((d.spectrum()[1] - 1.0).abs() < 1e-12);
+        assert!((d.spectrum()[4] - 0.01).abs() < 1e-12);
+        assert_eq!(d.effective_degree(1e-12), 4);
+        assert!(d.resolved(1e-12));
+
+        // Analysis at degree 3 truncates the degree 4 content
+        let d = diagnose_grid(&grid, 3, &values).unwrap();
+        assert!(!d.aliasing());
+        assert_eq!(d.effective_degree(1e-12), 1);
+
+        let d = diagnose_grid(&GaussLegendre::new(4).unwrap(), 6, &vec![0.0; 32]).unwrap();
+        assert!(d.aliasing());
+        assert_eq!(d.supported_degree(), 3);
+    }
+
+    #[test]
+    fn scattered_points() {
+        let points = fibonacci::<f64>(100, 0);
+        let supported = supported_degree(&points);
+        assert!((6..=9).contains(&supported), "{supported}");
+        let values: Vec<f64> = points.iter().map(f).collect();
+        let d = diagnose_points(&points, &values, 30).unwrap();
+        assert!(d.aliasing());
+        assert_eq!(d.analyzed_degree(), supported);
+        assert_eq!(d.effective_degree(1e-10), 4);
+
+        // Half of the points on one hemisphere leave a gap
+        let half: Vec<_> = points.iter().filter(|p| p.z() > 0.0).cloned().collect();
+        assert!(supported_degree(&half) < 2);
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::coefficients::num_coefficients;
+use crate::linalg::{cholesky, cholesky_solve};
+use crate::parallel;
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Choice of the truncation degree of an [`Interpolator`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DegreeSelection {
+    /// Fixed degree
+    Fixed(usize),
+    /// Degree up to the given maximum minimizing the leave-one-out cross-validation error, the
+    /// mean of the squared prediction errors at each sample of fits without that sample
+    LeaveOneOut(usize),
+    /// Degree up to the given maximum minimizing the generalized cross-validation score
+    /// N |r|^2 / (N - (L + 1)^2)^2, which replaces the leverages of the leave-one-out error by
+    /// their mean
+    Gcv(usize),
+}
+
+/// Bandlimited interpolation of scattered samples
+///
+/// The interpolator is a least squares fit of the real spherical harmonics
+/// [`RealSH::Spherical`] up to a truncation degree, which is either given or chosen by
+/// cross-validation (see [`DegreeSelection`]). Too low degrees cannot represent the data, too
+/// high ones fit the noise and oscillate between the samples; cross-validation estimates the
+/// prediction error at unseen points and picks the degree in between.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::fit::{DegreeSelection, Interpolator};
+/// use sphrs::sampling::fibonacci;
+/// use sphrs::{Coordinates, SHCoordinates};
+///
+/// let points = fibonacci::<f64>(200, 0);
+/// let values: Vec<f64> = points.iter().map(|p| p.x() * p.y() + p.z()).collect();
+/// let interpolator = Interpolator::fit(&points, &values, DegreeSelection::LeaveOneOut(8)).unwrap();
+/// assert_eq!(interpolator.degree(), 2);
+/// let p = Coordinates::spherical(1.0, 0.3, 2.0);
+/// assert!((interpolator.eval(&p) - (p.x() * p.y() + p.z())).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interpolator<T> {
+    /// Fitted coefficients up to the truncation degree
+    coefficients: SHCoefficients<T>,
+    /// Cross-validation score per candidate degree
+    scores: Vec<T>,
+}
+
+impl<T: SphrsFloat> Interpolator<T> {
+    /// Fit the interpolator to `values` sampled at `points`
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if `points` and `values` differ in length and
+    /// [`SphrsError::SingularSystem`] if the samples do not determine the coefficients of the
+    /// fixed degree or of any candidate degree. Cross-validation only considers degrees with
+    /// fewer harmonics than samples.
+    pub fn fit<C: SHCoordinates<T>>(
+        points: &[C],
+        values: &[T],
+        selection: DegreeSelection,
+    ) -> Result<Self, SphrsError> {
+        if points.len() != values.len() {
+            return Err(SphrsError::InvalidInput(format!(
+                "{} points but {} values",
+                points.len(),
+                values.len()
+            )));
+        }
+        let max_degree = match selection {
+            DegreeSelection::Fixed(degree)
+            | DegreeSelection::LeaveOneOut(degree)
+            | DegreeSelection::Gcv(degree) => degree,
+        };
+        let set = HarmonicsSet::new(max_degree, RealSH::Spherical);
+        let rows: Vec<Vec<T>> = points.iter().map(|p| set.eval(p)).collect();
+
+        // Normal equations of the maximum degree; those of lower degrees are leading blocks
+        let n = num_coefficients(max_degree);
+        let mut ata = vec![T::zero(); n * n];
+        let mut atb = vec![T::zero(); n];
+        for (y, &v) in rows.iter().zip(values) {
+            for i in 0..n {
+                atb[i] = atb[i] + y[i] * v;
+                for j in 0..=i {
+                    ata[i * n + j] = ata[i * n + j] + y[i] * y[j];
+                }
+            }
+        }
+        let system = NestedSystem {
+            rows,
+            values,
+            ata,
+            atb,
+        };
+
+        if let DegreeSelection::Fixed(degree) = selection {
+            let (x, _) = system
+                .solve(degree, false)
+                .ok_or(SphrsError::SingularSystem)?;
+            return Ok(Interpolator {
+                coefficients: SHCoefficients::from_vec(degree, x),
+                scores: Vec::new(),
+            });
+        }
+
+        let leave_one_out = matches!(selection, DegreeSelection::LeaveOneOut(_));
+        let mut scores = Vec::with_capacity(max_degree + 1);
+        let mut best: Option<(T, usize, Vec<T>)> = None;
+        for degree in 0..=max_degree {
+            let score = if num_coefficients(degree) < values.len() {
+                system.solve(degree, leave_one_out)
+            } else {
+                None
+            };
+            match score {
+                Some((x, score)) => {
+                    if best.as_ref().is_none_or(|(b, _, _)| score < *b) {
+                        best = Some((score, degree, x));
+                    }
+                    scores.push(score);
+                }
+                None => scores.push(T::infinity()),
+            }
+        }
+        let (_, degree, x) = best.ok_or(SphrsError::SingularSystem)?;
+        Ok(Interpolator {
+            coefficients: SHCoefficients::from_vec(degree, x),
+            scores,
+        })
+    }
+
+    /// Returns the truncation degree
+    pub fn degree(&self) -> usize {
+        self.coefficients.degree()
+    }
+
+    /// Returns the fitted coefficients
+    pub fn coefficients(&self) -> &SHCoefficients<T> {
+        &self.coefficients
+    }
+
+    /// Consumes `self` and returns the fitted coefficients
+    pub fn into_coefficients(self) -> SHCoefficients<T> {
+        self.coefficients
+    }
+
+    /// Returns the cross-validation score of each candidate degree 0, ..., max_degree
+    ///
+    /// Degrees whose fit is not determined by the samples have an infinite score. Empty for
+    /// [`DegreeSelection::Fixed`].
+    pub fn scores(&self) -> &[T] {
+        &self.scores
+    }
+
+    /// Evaluate the fitted expansion at `p`
+    pub fn eval<C: SHCoordinates<T>>(&self, p: &C) -> T {
+        let set = HarmonicsSet::new(self.degree(), RealSH::Spherical);
+        self.eval_with(&set, p)
+    }
+
+    /// Evaluate the fitted expansion at all `points`
+    ///
+    /// With the `rayon` feature, the points are evaluated in parallel.
+    pub fn eval_many<C: SHCoordinates<T> + Sync>(&self, points: &[C]) -> Vec<T> {
+        let set = HarmonicsSet::new(self.degree(), RealSH::Spherical);
+        parallel::map(points.len(), |i| self.eval_with(&set, &points[i]))
+    }
+
+    /// Evaluate the fitted expansion at `p` with the harmonics of `set`
+    fn eval_with<C: SHCoordinates<T>>(&self, set: &HarmonicsSet<T, RealSH>, p: &C) -> T {
+        set.eval(p)
+            .iter()
+            .zip(self.coefficients.as_slice())
+            .fold(T::zero(), |acc, (&y, &c)| acc + y * c)
+    }
+}
+
+/// Samples and normal equations (lower triangle) of the fit of the maximum degree
+struct NestedSystem<'a, T> {
+    /// Harmonics up to the maximum degree at each sample
+    rows: Vec<Vec<T>>,
+    /// Sampled values
+    values: &'a [T],
+    /// Y^T Y, lower triangle
+    ata: Vec<T>,
+    /// Y^T v
+    atb: Vec<T>,
+}
+
+impl<T: SphrsFloat> NestedSystem<'_, T> {
+    /// Least squares fit up to degree `degree` and its cross-validation score, the leave-one-out
+    /// error if `leave_one_out` is set and the generalized cross-validation score otherwise.
+    /// Returns `None` if the normal equations are singular.
+    fn solve(&self, degree: usize, leave_one_out: bool) -> Option<(Vec<T>, T)> {
+        let stride = self.atb.len();
+        let n = num_coefficients(degree);
+        let mut l = vec![T::zero(); n * n];
+        for i in 0..n {
+            for j in 0..=i {
+                l[i * n + j] = self.ata[i * stride + j];
+                l[j * n + i] = l[i * n + j];
+            }
+        }
+        cholesky(&mut l, n)?;
+        let mut x = self.atb[..n].to_vec();
+        cholesky_solve(&l, n, &mut x);
+
+        let num_samples = T::from_usize(self.values.len()).unwrap();
+        let mut sum = T::zero();
+        for (y, &v) in self.rows.iter().zip(self.values) {
+            let y = &y[..n];
+            let residual = y
+                .iter()
+                .zip(&x)
+                .fold(T::zero(), |acc, (&a, &b)| acc + a * b)
+                - v;
+            if leave_one_out {
+                // Leverage h_ii = y^T (Y^T Y)^-1 y
+                let mut z = y.to_vec();
+                cholesky_solve(&l, n, &mut z);
+                let leverage = y
+                    .iter()
+                    .zip(&z)
+                    .fold(T::zero(), |acc, (&a, &b)| acc + a * b);
+                let e = residual / (T::one() - leverage);
+                sum = sum + e * e;
+            } else {
+                sum = sum + residual * residual;
+            }
+        }
+        let score = if leave_one_out {
+            sum / num_samples
+        } else {
+            let dof = num_samples - T::from_usize(n).unwrap();
+            num_samples * sum / (dof * dof)
+        };
+        Some((x, score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::least_squares;
+    use crate::sampling::fibonacci;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn leave_one_out_matches_refits() {
+        // The leverage formula equals the errors of fits without each sample
+        let points = fibonacci::<f64>(30, 2);
+        let values: Vec<f64> = points.iter().map(|p| (3.0 * p.x()).sin() + p.z()).collect();
+        let interpolator =
+            Interpolator::fit(&points, &values, DegreeSelection::LeaveOneOut(3)).unwrap();
+        let set = HarmonicsSet::new(2, RealSH::Spherical);
+        let mut expected = 0.0;
+        for i in 0..points.len() {
+            let (mut p, mut v) = (points.clone(), values.clone());
+            p.remove(i);
+            v.remove(i);
+            let coeffs = least_squares(&set, &p, &v).unwrap();
+            let prediction: f64 = set.eval_sum(&points[i], coeffs.as_slice()).unwrap();
+            expected += (prediction - values[i]).powi(2);
+        }
+        expected /= points.len() as f64;
+        assert_relative_eq!(interpolator.scores()[2], expected, epsilon = 1e-10);
+        // Degree 5 has more harmonics than samples
+        assert_eq!(interpolator.scores().len(), 4);
+    }
+
+    #[test]
+    fn selects_degree_of_noisy_data() {
+        let points = fibonacci::<f64>(300, 0);
+        let mut state = 7u64;
+        let values: Vec<f64> = points
+            .iter()
+            .map(|p| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let noise = 0.05 * ((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5);
+                1.0 + p.z()
+                    + 0.5 * (p.x() * p.x() - p.y() * p.y())
+                    + 0.3 * p.x() * p.y() * p.z()
+                    + noise
+            })
+            .collect();
+        for selection in [DegreeSelection::LeaveOneOut(12), DegreeSelection::Gcv(12)] {
+            let interpolator = Interpolator::fit(&points, &values, selection).unwrap();
+            assert_eq!(interpolator.degree(), 3, "{selection:?}");
+            assert_eq!(interpolator.scores().len(), 13);
+        }
+    }
+
+    #[test]
+    fn fixed_degree_and_evaluation() {
+        let points = fibonacci::<f64>(50, 0);
+        let values: Vec<f64> = points.iter().map(|p| p.y()).collect();
+        let interpolator = Interpolator::fit(&points, &values, DegreeSelection::Fixed(1)).unwrap();
+        assert!(interpolator.scores().is_empty());
+        let new = fibonacci::<f64>(7, 3);
+        for (v, p) in interpolator.eval_many(&new).iter().zip(&new) {
+            assert_relative_eq!(*v, p.y(), epsilon = 1e-12);
+        }
+        assert!(matches!(
+            Interpolator::fit(&points[..3], &values[..3], DegreeSelection::Fixed(2)),
+            Err(SphrsError::SingularSystem)
+        ));
+        assert!(matches!(
+            Interpolator::fit(&points, &values[..3], DegreeSelection::Gcv(2)),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Fitting of spherical/solid harmonics expansions to samples
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::linalg::{cholesky, cholesky_solve};
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+#[cfg(feature = "ndarray")]
+mod design;
+mod diagnostics;
+mod interpolate;
+mod spline;
+mod tikhonov;
+
+#[cfg(feature = "ndarray")]
+pub use design::design_matrix;
+pub use diagnostics::{diagnose_grid, diagnose_points, supported_degree, SamplingDiagnostics};
+pub use interpolate::{DegreeSelection, Interpolator};
+pub use spline::{smoothing_spline, Smoothing, SplineFit};
+pub use tikhonov::{tikhonov, Damping};
+
+/// Least squares fit of the real harmonics of `set` to `values` sampled at `points`
+///
+/// Solves the normal equations of the least squares problem. The returned coefficients cover all
+/// degrees up to the degree of `set`, coefficients of degrees below the lowest degree of `set`
+/// are zero.
+///
+/// Returns [`SphrsError::InvalidInput`] if `points` and `values` differ in length and
+/// [`SphrsError::SingularSystem`] if the samples do not determine the coefficients, for instance
+/// because there are fewer samples than harmonics in the set.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::fit::least_squares;
+/// use sphrs::sampling::fibonacci;
+/// use sphrs::SHCoordinates;
+///
+/// let points = fibonacci::<f64>(64, 0);
+/// let values: Vec<f64> = points.iter().map(|p| 1.0 + p.z()).collect();
+/// let set = HarmonicsSet::new(2, RealSH::Spherical);
+/// let coeffs = least_squares(&set, &points, &values).unwrap();
+/// assert!(coeffs.get(2, 0).unwrap().abs() < 1e-12);
+/// ```
+pub fn least_squares<T, E, C>(
+    set: &HarmonicsSet<T, E>,
+    points: &[C],
+    values: &[T],
+) -> Result<SHCoefficients<T>, SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T, Output = T>,
+    C: SHCoordinates<T>,
+{
+    let (mut ata, mut atb) = normal_equations(set, points, values)?;
+    let n = set.num_sh();
+    cholesky(&mut ata, n).ok_or(SphrsError::SingularSystem)?;
+    cholesky_solve(&ata, n, &mut atb);
+    Ok(pad_coefficients(set, &atb))
+}
+
+/// Normal equations A^T A x = A^T b of the least squares fit of the harmonics of `set` to
+/// `values` sampled at `points`
+fn normal_equations<T, E, C>(
+    set: &HarmonicsSet<T, E>,
+    points: &[C],
+    values: &[T],
+) -> Result<(Vec<T>, Vec<T>), SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T, Output = T>,
+    C: SHCoordinates<T>,
+{
+    if points.len() != values.len() {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} points but {} values",
+            points.len(),
+            values.len()
+        )));
+    }
+    let n = set.num_sh();
+    let mut ata = vec![T::zero(); n * n];
+    let mut atb = vec![T::zero(); n];
+    for (p, &v) in points.iter().zip(values.iter()) {
+        let y = set.eval(p);
+        for i in 0..n {
+            atb[i] = atb[i] + y[i] * v;
+            for j in 0..=i {
+                ata[i * n + j] = ata[i * n + j] + y[i] * y[j];
+            }
+        }
+    }
+    for i in 0..n {
+        for j in (i + 1)..n {
+            ata[i * n + j] = ata[j * n + i];
+        }
+    }
+    Ok((ata, atb))
+}
+
+/// Coefficients of all degrees up to the degree of `set` from the coefficients `x` of the
+/// harmonics of `set`
+fn pad_coefficients<T, E>(set: &HarmonicsSet<T, E>, x: &[T]) -> SHCoefficients<T>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+{
+    let mut coeffs = SHCoefficients::zeros(set.degree());
+    for ((l, m), &c) in set.harmonics().zip(x) {
+        *coeffs.get_mut(l, m).unwrap() = c;
+    }
+    coeffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::RealSH;
+    use crate::sampling::fibonacci;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn recovers_band_limited_function() {
+        let truth: Vec<f64> = (0..16).map(|i| ((i * 7) % 5) as f64 - 2.0).collect();
+        let set = HarmonicsSet::new(3, RealSH::Spherical);
+        let points = fibonacci::<f64>(100, 3);
+        let values: Vec<f64> = points
+            .iter()
+            .map(|p| set.eval(p).iter().zip(&truth).map(|(y, c)| y * c).sum())
+            .collect();
+        let coeffs = least_squares(&set, &points, &values).unwrap();
+        for (c, t) in coeffs.as_slice().iter().zip(truth.iter()) {
+            assert_relative_eq!(c, t, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn degree_range() {
+        let set = HarmonicsSet::builder()
+            .degree_range(1, 2)
+            .sh_type(RealSH::Spherical)
+            .build()
+            .unwrap();
+        let points = fibonacci::<f64>(50, 0);
+        let values: Vec<f64> = points.iter().map(|p| 0.5 * p.z()).collect();
+        let coeffs = least_squares(&set, &points, &values).unwrap();
+        assert_eq!(coeffs.len(), 9);
+        assert_eq!(*coeffs.get(0, 0).unwrap(), 0.0);
+        let y10 = (3.0 / (4.0 * std::f64::consts::PI)).sqrt();
+        assert_relative_eq!(*coeffs.get(1, 0).unwrap(), 0.5 / y10, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let set = HarmonicsSet::new(2, RealSH::Spherical);
+        let points = fibonacci::<f64>(4, 0);
+        assert!(matches!(
+            least_squares(&set, &points, &[1.0; 3]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            least_squares(&set, &points, &[1.0; 4]),
+            Err(SphrsError::SingularSystem)
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::fit::{normal_equations, pad_coefficients};
+use crate::linalg::{cholesky, cholesky_solve};
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Candidate smoothing parameters per decade of the generalized cross-validation search
+const GCV_STEPS_PER_DECADE: i32 = 4;
+/// Decades below the scale of the normal equations searched by generalized cross-validation
+const GCV_DECADES_BELOW: i32 = 12;
+/// Decades above the scale of the normal equations searched by generalized cross-validation
+const GCV_DECADES_ABOVE: i32 = 3;
+
+/// Choice of the smoothing parameter of [`smoothing_spline`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Smoothing<T> {
+    /// Fixed smoothing parameter lambda >= 0
+    Fixed(T),
+    /// Smoothing parameter minimizing the generalized cross-validation score
+    ///
+    /// The parameter is searched on a logarithmic grid spanning several decades around the
+    /// ratio of the traces of the data and penalty terms.
+    Gcv,
+}
+
+/// Result of [`smoothing_spline`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplineFit<T> {
+    /// Fitted coefficients
+    coefficients: SHCoefficients<T>,
+    /// Smoothing parameter
+    lambda: T,
+    /// Trace of the influence matrix
+    effective_dof: T,
+    /// Generalized cross-validation score
+    gcv: T,
+}
+
+impl<T: SphrsFloat> SplineFit<T> {
+    /// Returns the fitted coefficients
+    pub fn coefficients(&self) -> &SHCoefficients<T> {
+        &self.coefficients
+    }
+
+    /// Consumes `self` and returns the fitted coefficients
+    pub fn into_coefficients(self) -> SHCoefficients<T> {
+        self.coefficients
+    }
+
+    /// Returns the smoothing parameter used for the fit
+    pub fn lambda(&self) -> T {
+        self.lambda
+    }
+
+    /// Returns the effective number of degrees of freedom, the trace of the influence matrix
+    pub fn effective_dof(&self) -> T {
+        self.effective_dof
+    }
+
+    /// Returns the generalized cross-validation score N |r|^2 / (N - dof)^2 of the fit, which is
+    /// infinite if the fit has no residual degrees of freedom
+    pub fn gcv(&self) -> T {
+        self.gcv
+    }
+}
+
+/// Penalized least squares fit of the real harmonics of `set` to `values` sampled at `points`
+///
+/// Minimizes |Y c - v|^2 + lambda R(c), where the roughness R(c) = sum_lm (l (l + 1))^2 c_lm^2
+/// is the integral of the squared surface Laplacian of the expansion over the sphere. Contrary
+/// to [`least_squares`](`crate::fit::least_squares`), `set` may contain more harmonics than the
+/// samples determine, the penalty then suppresses the undetermined high degrees. With
+/// [`Smoothing::Gcv`], the smoothing parameter is chosen by generalized cross-validation, which
+/// balances misfit and roughness without knowledge of the noise level.
+///
+/// Returns [`SphrsError::InvalidInput`] if `points` and `values` differ in length or if a fixed
+/// smoothing parameter is negative, and [`SphrsError::SingularSystem`] if the penalized normal
+/// equations are singular, e.g. for a fixed smoothing parameter of zero and too few samples.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::fit::{smoothing_spline, Smoothing};
+/// use sphrs::sampling::fibonacci;
+/// use sphrs::SHCoordinates;
+///
+/// let points = fibonacci::<f64>(80, 0);
+/// let values: Vec<f64> = points.iter().map(|p| p.z() + 0.01 * (7.0 * p.x()).sin()).collect();
+/// // More harmonics (100) than samples (80)
+/// let set = HarmonicsSet::new(9, RealSH::Spherical);
+/// let fit = smoothing_spline(&set, &points, &values, Smoothing::Gcv).unwrap();
+/// assert!(fit.effective_dof() < 80.0);
+/// ```
+pub fn smoothing_spline<T, E, C>(
+    set: &HarmonicsSet<T, E>,
+    points: &[C],
+    values: &[T],
+    smoothing: Smoothing<T>,
+) -> Result<SplineFit<T>, SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T, Output = T>,
+    C: SHCoordinates<T>,
+{
+    let (ata, atb) = normal_equations(set, points, values)?;
+    let btb = values.iter().fold(T::zero(), |acc, &v| acc + v * v);
+    let penalty: Vec<T> = set
+        .harmonics()
+        .map(|(l, _)| {
+            let w = T::from_i64(l * (l + 1)).unwrap();
+            w * w
+        })
+        .collect();
+    let system = PenalizedSystem {
+        ata,
+        atb,
+        btb,
+        penalty,
+        num_samples: T::from_usize(points.len()).unwrap(),
+    };
+
+    match smoothing {
+        Smoothing::Fixed(lambda) => {
+            if lambda.is_nan() || lambda < T::zero() {
+                return Err(SphrsError::InvalidInput(format!(
+                    "smoothing parameter must be non-negative, got {lambda:?}"
+                )));
+            }
+            let (x, fit) = system.solve(lambda).ok_or(SphrsError::SingularSystem)?;
+            Ok(fit.into_fit(pad_coefficients(set, &x)))
+        }
+        Smoothing::Gcv => {
+            let n = system.atb.len();
+            let trace_ata = (0..n).fold(T::zero(), |acc, i| acc + system.ata[i * n + i]);
+            let trace_penalty = system.penalty.iter().fold(T::zero(), |acc, &p| acc + p);
+            // Without any penalized harmonic (degree 0 only), the search range is irrelevant
+            let scale = if trace_penalty > T::zero() {
+                trace_ata / trace_penalty
+            } else {
+                T::one()
+            };
+            let steps = T::from_i32(GCV_STEPS_PER_DECADE).unwrap();
+            let ten = T::from_f64(10.0).unwrap();
+            let first = -GCV_DECADES_BELOW * GCV_STEPS_PER_DECADE;
+            let last = GCV_DECADES_ABOVE * GCV_STEPS_PER_DECADE;
+            let mut best: Option<(Vec<T>, Candidate<T>)> = None;
+            for k in first..=last {
+                let lambda = scale * ten.powf(T::from_i32(k).unwrap() / steps);
+                if let Some((x, fit)) = system.solve(lambda) {
+                    if best.as_ref().is_none_or(|(_, b)| fit.gcv < b.gcv) {
+                        best = Some((x, fit));
+                    }
+                }
+            }
+            let (x, fit) = best.ok_or(SphrsError::SingularSystem)?;
+            Ok(fit.into_fit(pad_coefficients(set, &x)))
+        }
+    }
+}
+
+/// Normal equations of the fit together with the diagonal roughness penalty
+struct PenalizedSystem<T> {
+    /// Y^T Y
+    ata: Vec<T>,
+    /// Y^T v
+    atb: Vec<T>,
+    /// v^T v
+    btb: T,
+    /// Diagonal of the roughness penalty
+    penalty: Vec<T>,
+    /// Number of samples
+    num_samples: T,
+}
+
+/// Statistics of the fit for one smoothing parameter
+struct Candidate<T> {
+    /// Smoothing parameter
+    lambda: T,
+    /// Trace of the influence matrix
+    effective_dof: T,
+    /// Generalized cross-validation score
+    gcv: T,
+}
+
+impl<T: SphrsFloat> Candidate<T> {
+    /// Combine the statistics with the fitted `coefficients`
+    fn into_fit(self, coefficients: SHCoefficients<T>) -> SplineFit<T> {
+        SplineFit {
+            coefficients,
+            lambda: self.lambda,
+            effective_dof: self.effective_dof,
+            gcv: self.gcv,
+        }
+    }
+}
+
+impl<T: SphrsFloat> PenalizedSystem<T> {
+    /// Solve (Y^T Y + lambda D) x = Y^T v. Returns `None` if the system is singular.
+    fn solve(&self, lambda: T) -> Option<(Vec<T>, Candidate<T>)> {
+        let n = self.atb.len();
+        let mut l = self.ata.clone();
+        for (i, &p) in self.penalty.iter().enumerate() {
+            l[i * n + i] = l[i * n + i] + lambda * p;
+        }
+        cholesky(&mut l, n)?;
+        let mut x = self.atb.clone();
+        cholesky_solve(&l, n, &mut x);
+
+        // The influence matrix H = Y (Y^T Y + lambda D)^-1 Y^T has the same trace as
+        // (Y^T Y + lambda D)^-1 Y^T Y
+        let mut effective_dof = T::zero();
+        let mut column = vec![T::zero(); n];
+        for j in 0..n {
+            for (i, c) in column.iter_mut().enumerate() {
+                *c = self.ata[i * n + j];
+            }
+            cholesky_solve(&l, n, &mut column);
+            effective_dof = effective_dof + column[j];
+        }
+
+        // |Y x - v|^2 = x^T Y^T Y x - 2 x^T Y^T v + v^T v
+        let mut rss = self.btb;
+        for i in 0..n {
+            let ax = (0..n).fold(T::zero(), |acc, j| acc + self.ata[i * n + j] * x[j]);
+            rss = rss + x[i] * (ax - (T::one() + T::one()) * self.atb[i]);
+        }
+        let rss = rss.max(T::zero());
+        let denominator = self.num_samples - effective_dof;
+        let gcv = if denominator > T::zero() {
+            self.num_samples * rss / (denominator * denominator)
+        } else {
+            T::infinity()
+        };
+        Some((
+            x,
+            Candidate {
+                lambda,
+                effective_dof,
+                gcv,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::RealSH;
+    use crate::fit::least_squares;
+    use crate::sampling::fibonacci;
+    use approx::assert_relative_eq;
+
+    /// Smooth test function and noisy samples of it
+    fn noisy_samples(n: usize) -> (Vec<crate::Coordinates<f64>>, Vec<f64>, Vec<f64>) {
+        let points = fibonacci::<f64>(n, 1);
+        let truth: Vec<f64> = points
+            .iter()
+            .map(|p| 1.0 + p.z() - 0.5 * p.x() * p.y())
+            .collect();
+        let mut state = 12345u64;
+        let noisy = truth
+            .iter()
+            .map(|t| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                t + 0.2 * ((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5)
+            })
+            .collect();
+        (points, truth, noisy)
+    }
+
+    fn rms_error(set: &HarmonicsSet<f64, RealSH>, coeffs: &SHCoefficients<f64>) -> f64 {
+        let (points, truth, _) = noisy_samples(500);
+        let err: f64 = points
+            .iter()
+            .zip(&truth)
+            .map(|(p, t)| {
+                let v: f64 = set
+                    .eval(p)
+                    .iter()
+                    .zip(coeffs.as_slice())
+                    .map(|(y, c)| y * c)
+                    .sum();
+                (v - t) * (v - t)
+            })
+            .sum();
+        (err / truth.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn zero_smoothing_is_least_squares() {
+        let (points, _, values) = noisy_samples(60);
+        let set = HarmonicsSet::new(3, RealSH::Spherical);
+        let fit = smoothing_spline(&set, &points, &values, Smoothing::Fixed(0.0)).unwrap();
+        let ls = least_squares(&set, &points, &values).unwrap();
+        for (a, b) in fit.coefficients().as_slice().iter().zip(ls.as_slice()) {
+            assert_relative_eq!(a, b, epsilon = 1e-10);
+        }
+        assert_relative_eq!(fit.effective_dof(), 16.0, epsilon = 1e-8);
+
+        let smoother = smoothing_spline(&set, &points, &values, Smoothing::Fixed(1.0)).unwrap();
+        assert!(smoother.effective_dof() < fit.effective_dof());
+        assert!(matches!(
+            smoothing_spline(&set, &points, &values, Smoothing::Fixed(-1.0)),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn gcv_beats_truncated_least_squares() {
+        let (points, _, values) = noisy_samples(150);
+        let set = HarmonicsSet::new(10, RealSH::Spherical);
+        let fit = smoothing_spline(&set, &points, &values, Smoothing::Gcv).unwrap();
+        let ls = least_squares(&set, &points, &values).unwrap();
+        let spline_error = rms_error(&set, fit.coefficients());
+        let ls_error = rms_error(&set, &ls);
+        assert!(spline_error < 0.5 * ls_error, "{spline_error} {ls_error}");
+        assert!(spline_error < 0.05, "{spline_error}");
+        assert!(fit.lambda() > 0.0);
+        assert!(fit.gcv().is_finite());
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::fit::{normal_equations, pad_coefficients};
+use crate::linalg::{cholesky, cholesky_solve};
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Damping of the coefficients in [`tikhonov`]
+///
+/// Each variant defines a damping d_l >= 0 per degree l, which is applied to all coefficients of
+/// that degree.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Damping<T> {
+    /// The same damping d_l = lambda for all degrees (ridge regression)
+    Ridge(T),
+    /// Kaula-style damping d_l = lambda l^4 for l >= 1, degree 0 is not damped
+    ///
+    /// This corresponds to Kaula's rule of thumb for gravity fields, which states that the
+    /// coefficients of degree l have a variance proportional to l^-4, and suppresses the high
+    /// degrees most.
+    Kaula(T),
+    /// Damping d_l = `damping[l]` per degree, given for all degrees of the set
+    PerDegree(Vec<T>),
+}
+
+impl<T: SphrsFloat> Damping<T> {
+    /// Damping of degree `l`
+    fn degree(&self, l: usize) -> T {
+        match self {
+            Damping::Ridge(lambda) => *lambda,
+            Damping::Kaula(lambda) => *lambda * T::from_usize(l).unwrap().powi(4),
+            Damping::PerDegree(d) => d[l],
+        }
+    }
+}
+
+/// Tikhonov regularized least squares fit of the real harmonics of `set` to `values` sampled at
+/// `points`
+///
+/// Minimizes |Y c - v|^2 + sum_lm d_l c_lm^2 with the damping d_l of `damping`. The damping
+/// stabilizes the fit where the samples cover the sphere unevenly or leave some harmonics
+/// undetermined, where the plain [`least_squares`](`crate::fit::least_squares`) solution is
+/// singular or dominated by large, oscillating coefficients. Statistically, d_l is the ratio of
+/// the noise variance and the prior variance of the coefficients of degree l.
+///
+/// Returns [`SphrsError::InvalidInput`] if `points` and `values` differ in length, if a damping
+/// is negative or NaN, or if [`Damping::PerDegree`] does not cover the degree of `set`.
+/// Returns [`SphrsError::SingularSystem`] if the damped normal equations are singular, e.g.
+/// for zero damping and too few samples.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::fit::{tikhonov, Damping};
+/// use sphrs::sampling::fibonacci;
+/// use sphrs::SHCoordinates;
+///
+/// // Samples on the northern hemisphere only
+/// let points: Vec<_> = fibonacci::<f64>(200, 0)
+///     .into_iter()
+///     .filter(|p| p.z() > 0.0)
+///     .collect();
+/// let values: Vec<f64> = points.iter().map(|p| 1.0 + p.z()).collect();
+/// let set = HarmonicsSet::new(12, RealSH::Spherical);
+/// let coeffs = tikhonov(&set, &points, &values, &Damping::Kaula(1e-6)).unwrap();
+/// // The damped fit stays small at the high degrees
+/// assert!(coeffs.get(12, 0).unwrap().abs() < 1e-2);
+/// ```
+pub fn tikhonov<T, E, C>(
+    set: &HarmonicsSet<T, E>,
+    points: &[C],
+    values: &[T],
+    damping: &Damping<T>,
+) -> Result<SHCoefficients<T>, SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T, Output = T>,
+    C: SHCoordinates<T>,
+{
+    if let Damping::PerDegree(d) = damping {
+        if d.len() <= set.degree() {
+            return Err(SphrsError::InvalidInput(format!(
+                "damping given for {} degrees, but the set has degree {}",
+                d.len(),
+                set.degree()
+            )));
+        }
+    }
+    let (mut ata, mut atb) = normal_equations(set, points, values)?;
+    let n = set.num_sh();
+    for l in set.min_degree()..=set.degree() {
+        let d = damping.degree(l);
+        if d.is_nan() || d < T::zero() {
+            return Err(SphrsError::InvalidInput(format!(
+                "damping must be non-negative, got {d:?} for degree {l}"
+            )));
+        }
+    }
+    for (i, (l, _)) in set.harmonics().enumerate() {
+        let d = damping.degree(l as usize);
+        ata[i * n + i] = ata[i * n + i] + d;
+    }
+    cholesky(&mut ata, n).ok_or(SphrsError::SingularSystem)?;
+    cholesky_solve(&ata, n, &mut atb);
+    Ok(pad_coefficients(set, &atb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::RealSH;
+    use crate::fit::least_squares;
+    use crate::sampling::fibonacci;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn zero_damping_is_least_squares() {
+        let set = HarmonicsSet::new(3, RealSH::Spherical);
+        let points = fibonacci::<f64>(60, 1);
+        let values: Vec<f64> = points
+            .iter()
+            .map(|p| (2.0 * p.x()).sin() + p.y() * p.z())
+            .collect();
+        let expected = least_squares(&set, &points, &values).unwrap();
+        for damping in [
+            Damping::Ridge(0.0),
+            Damping::Kaula(0.0),
+            Damping::PerDegree(vec![0.0; 4]),
+        ] {
+            let coeffs = tikhonov(&set, &points, &values, &damping).unwrap();
+            for (a, b) in coeffs.as_slice().iter().zip(expected.as_slice()) {
+                assert_relative_eq!(a, b, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn damping_per_degree() {
+        // Damping only degree 2 shrinks exactly those coefficients towards zero
+        let set = HarmonicsSet::new(2, RealSH::Spherical);
+        let points = fibonacci::<f64>(100, 0);
+        let values: Vec<f64> = points.iter().map(|p| 1.0 + p.x() * p.y()).collect();
+        let free = tikhonov(&set, &points, &values, &Damping::Ridge(0.0)).unwrap();
+        let damped = tikhonov(
+            &set,
+            &points,
+            &values,
+            &Damping::PerDegree(vec![0.0, 0.0, 1e3]),
+        )
+        .unwrap();
+        assert!(free.get(2, -2).unwrap().abs() > 0.5);
+        assert!(damped.get(2, -2).unwrap().abs() < 0.1 * free.get(2, -2).unwrap().abs());
+        assert_relative_eq!(
+            damped.get(0, 0).unwrap(),
+            free.get(0, 0).unwrap(),
+            epsilon = 1e-2
+        );
+    }
+
+    #[test]
+    fn underdetermined_system() {
+        let set = HarmonicsSet::new(6, RealSH::Spherical);
+        let points = fibonacci::<f64>(20, 0);
+        let values: Vec<f64> = points.iter().map(|p| p.z()).collect();
+        assert!(matches!(
+            tikhonov(&set, &points, &values, &Damping::Ridge(0.0)),
+            Err(SphrsError::SingularSystem)
+        ));
+        assert!(tikhonov(&set, &points, &values, &Damping::Ridge(1e-3)).is_ok());
+    }
+
+    #[test]
+    fn invalid_damping() {
+        let set = HarmonicsSet::new(2, RealSH::Spherical);
+        let points = fibonacci::<f64>(20, 0);
+        let values = vec![1.0; 20];
+        for damping in [
+            Damping::Ridge(-1.0),
+            Damping::Kaula(f64::NAN),
+            Damping::PerDegree(vec![1.0, 1.0]),
+        ] {
+            assert!(matches!(
+                tikhonov(&set, &points, &values, &damping),
+                Err(SphrsError::InvalidInput(_))
+            ));
+        }
+    }
+}
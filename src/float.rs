@@ -10,5 +10,15 @@ use num_traits::float::FloatConst;
 use std::fmt::Debug;
 
 /// Trait alias for trait bounds on floats
-pub trait SphrsFloat: Float + FloatConst + FromPrimitive + Debug {}
-impl<I> SphrsFloat for I where I: Float + FloatConst + FromPrimitive + Debug {}
+///
+/// Implemented for all types implementing the `num-traits` float traits. Arbitrary precision types
+/// like those of `rug` or `astro-float` are not `Copy` and therefore cannot implement
+/// `num_traits::Float`; they are not supported. For very high degrees use
+/// [`Backend::Extended`](`crate::basis::Backend`) (feature `xnumber`), and
+/// [`verify::max_error`](`crate::verify::max_error`) to check the accuracy against a
+/// double-double reference.
+///
+/// The floats must be `Send` and `Sync` such that the transforms can be distributed over threads
+/// (feature `rayon`).
+pub trait SphrsFloat: Float + FloatConst + FromPrimitive + Debug + Send + Sync {}
+impl<I> SphrsFloat for I where I: Float + FloatConst + FromPrimitive + Debug + Send + Sync {}
@@ -0,0 +1,215 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Batch evaluation and reconstruction over direction buffers
+//!
+//! [`GpuBatch`] evaluates real spherical harmonics and expansions of them at large buffers of
+//! directions, given as `[x, y, z]` in `f32` like the storage buffers of GPU compute shaders.
+//! Results are `f32` buffers in the same layout as
+//! [`HarmonicsSet::eval_batch`](`crate::basis::HarmonicsSet::eval_batch`).
+//!
+//! The batches are run on the [`Device`] selected when the [`GpuBatch`] is created. Only the CPU
+//! fallback [`Device::Cpu`] is available so far: the buffers are evaluated with the recurrences
+//! and closed-form expressions of [`HarmonicsSet`], distributed over the rayon thread pool with
+//! the `rayon` feature. A wgpu compute-shader device is not part of this version; it will be
+//! selected by [`GpuBatch::new`] without changes to the API, with results matching the CPU
+//! fallback within the rounding errors of `f32`.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::{parallel, CartesianCoordinates, SphrsError};
+
+/// Device which runs the batches of a [`GpuBatch`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Device {
+    /// Evaluation on the CPU, in parallel with the `rayon` feature
+    #[default]
+    Cpu,
+}
+
+/// Real spherical harmonics up to a given degree, evaluated at buffers of directions
+///
+/// See the [module documentation](`crate::gpu`) for the devices.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::gpu::{Device, GpuBatch};
+///
+/// let batch = GpuBatch::new(3).unwrap();
+/// assert_eq!(batch.device(), Device::Cpu);
+/// let directions = [[0.0f32, 0.0, 1.0], [0.6, 0.0, 0.8], [0.0, -1.0, 0.0]];
+/// let table = batch.eval_batch(&directions);
+/// assert_eq!(table.len(), 3 * batch.num_sh());
+///
+/// // Constant function with mean value 1
+/// let mut coefficients = vec![0.0f32; batch.num_sh()];
+/// coefficients[0] = (4.0 * std::f32::consts::PI).sqrt();
+/// for v in batch.reconstruct(&coefficients, &directions).unwrap() {
+///     assert!((v - 1.0).abs() < 1e-6);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct GpuBatch {
+    /// Harmonics evaluated by the CPU fallback
+    set: HarmonicsSet<f32, RealSH>,
+    /// Device running the batches
+    device: Device,
+}
+
+impl GpuBatch {
+    /// Create the batch evaluation of the real spherical harmonics up to `degree` on the best
+    /// available device
+    ///
+    /// Returns [`SphrsError::InvalidConfiguration`] if the number of harmonics does not fit into
+    /// `usize`.
+    pub fn new(degree: usize) -> Result<Self, SphrsError> {
+        let set = HarmonicsSet::builder()
+            .degree(degree)
+            .sh_type(RealSH::Spherical)
+            .build()?;
+        Ok(GpuBatch {
+            set,
+            device: Device::Cpu,
+        })
+    }
+
+    /// Returns the device running the batches
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    /// Returns the highest degree
+    pub fn degree(&self) -> usize {
+        self.set.degree()
+    }
+
+    /// Returns the number of harmonics, i.e. the length of a row of the tables and of the
+    /// coefficients
+    pub fn num_sh(&self) -> usize {
+        self.set.num_sh()
+    }
+
+    /// Evaluate the harmonics at all `directions`
+    ///
+    /// Returns a row-major table of `directions.len()` rows of [`num_sh`](`GpuBatch::num_sh`)
+    /// values, as [`HarmonicsSet::eval_batch`]. The directions need not be normalized.
+    pub fn eval_batch(&self, directions: &[[f32; 3]]) -> Vec<f32> {
+        let mut table = vec![0.0; directions.len() * self.num_sh()];
+        self.eval_batch_into(directions, &mut table)
+            .expect("table has one row per direction");
+        table
+    }
+
+    /// Evaluate the harmonics at all `directions` into the row-major buffer `out`
+    ///
+    /// Same as [`eval_batch`](`GpuBatch::eval_batch`), but `out` has to hold exactly
+    /// `directions.len() * num_sh` values. Otherwise, [`SphrsError::InvalidInput`] is returned.
+    pub fn eval_batch_into(
+        &self,
+        directions: &[[f32; 3]],
+        out: &mut [f32],
+    ) -> Result<(), SphrsError> {
+        match self.device {
+            Device::Cpu => self.set.eval_batch_into(&coordinates(directions), out),
+        }
+    }
+
+    /// Evaluate the expansion with the given `coefficients` at all `directions`
+    ///
+    /// Returns one value per direction. Returns [`SphrsError::LengthMismatch`] unless there is
+    /// exactly one coefficient per harmonic.
+    pub fn reconstruct(
+        &self,
+        coefficients: &[f32],
+        directions: &[[f32; 3]],
+    ) -> Result<Vec<f32>, SphrsError> {
+        if coefficients.len() != self.num_sh() {
+            return Err(SphrsError::LengthMismatch {
+                expected: self.num_sh(),
+                found: coefficients.len(),
+            });
+        }
+        match self.device {
+            Device::Cpu => Ok(parallel::map(directions.len(), |i| {
+                let [x, y, z] = directions[i];
+                self.set
+                    .eval_sum(&CartesianCoordinates::new(x, y, z), coefficients)
+                    .expect("one coefficient per harmonic")
+            })),
+        }
+    }
+}
+
+/// Coordinates of the CPU fallback for a buffer of directions
+fn coordinates(directions: &[[f32; 3]]) -> Vec<CartesianCoordinates<f32>> {
+    directions
+        .iter()
+        .map(|&[x, y, z]| CartesianCoordinates::new(x, y, z))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::fibonacci;
+    use crate::{Coordinates, SHCoordinates};
+
+    #[test]
+    fn matches_f64_evaluation() {
+        let degree = 10;
+        let batch = GpuBatch::new(degree).unwrap();
+        let directions: Vec<[f32; 3]> = fibonacci::<f64>(500, 3)
+            .iter()
+            .map(|p| [p.x() as f32, 2.0 * p.y() as f32, p.z() as f32])
+            .collect();
+        // The reference is evaluated at the same (rounded) directions
+        let points: Vec<Coordinates<f64>> = directions
+            .iter()
+            .map(|&[x, y, z]| Coordinates::cartesian(x.into(), y.into(), z.into()))
+            .collect();
+        let reference: Vec<f64> = HarmonicsSet::new(degree, RealSH::Spherical).eval_batch(&points);
+
+        let table = batch.eval_batch(&directions);
+        assert_eq!(table.len(), reference.len());
+        for (&a, &b) in table.iter().zip(&reference) {
+            assert!((f64::from(a) - b).abs() < 1e-5, "{a} vs {b}");
+        }
+
+        let coefficients: Vec<f32> = (0..batch.num_sh())
+            .map(|i| (0.3 * i as f32).cos() / (1.0 + i as f32).sqrt())
+            .collect();
+        let values = batch.reconstruct(&coefficients, &directions).unwrap();
+        for (v, row) in values.iter().zip(reference.chunks(batch.num_sh())) {
+            let expected: f64 = row
+                .iter()
+                .zip(&coefficients)
+                .map(|(y, &c)| y * f64::from(c))
+                .sum();
+            assert!((f64::from(*v) - expected).abs() < 1e-4, "{v} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn invalid_buffers() {
+        let batch = GpuBatch::new(2).unwrap();
+        let directions = [[1.0f32, 0.0, 0.0]; 4];
+        let mut out = vec![0.0; 3 * batch.num_sh()];
+        assert!(matches!(
+            batch.eval_batch_into(&directions, &mut out),
+            Err(SphrsError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            batch.reconstruct(&[1.0; 4], &directions),
+            Err(SphrsError::LengthMismatch {
+                expected: 9,
+                found: 4
+            })
+        ));
+        assert!(GpuBatch::new(usize::MAX / 2).is_err());
+    }
+}
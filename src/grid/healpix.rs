@@ -0,0 +1,139 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::grid::SphericalGrid;
+use crate::{Coordinates, SphrsError, SphrsFloat};
+
+/// HEALPix grid with resolution parameter `nside`
+///
+/// The 12 `nside`^2 pixel centers are stored in the RING ordering scheme, i.e. by iso-latitude
+/// rings from north to south and by increasing azimuth within each ring. All pixels have the same
+/// area. Following the HEALPix conventions, the bandlimit is 3 `nside` - 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Healpix {
+    /// Resolution parameter
+    nside: usize,
+}
+
+impl Healpix {
+    /// Create a grid with resolution parameter `nside`
+    ///
+    /// Returns [`SphrsError::InvalidConfiguration`] if `nside` is zero or the number of pixels
+    /// does not fit into `usize`.
+    pub fn new(nside: usize) -> Result<Self, SphrsError> {
+        if nside == 0 {
+            return Err(SphrsError::InvalidConfiguration(
+                "nside must be positive".to_string(),
+            ));
+        }
+        if nside
+            .checked_mul(nside)
+            .and_then(|n| n.checked_mul(12))
+            .is_none()
+        {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "number of pixels of nside {nside} does not fit into usize"
+            )));
+        }
+        Ok(Healpix { nside })
+    }
+
+    /// Number of pixels
+    pub fn num_pixels(&self) -> usize {
+        12 * self.nside * self.nside
+    }
+}
+
+impl SphericalGrid for Healpix {
+    fn directions<T: SphrsFloat>(&self) -> Vec<Coordinates<T>> {
+        let f = |v: usize| T::from_usize(v).unwrap();
+        let nside = self.nside;
+        let half = T::from_f64(0.5).unwrap();
+        let mut out = Vec::with_capacity(self.num_pixels());
+        let mut ring = |z: T, count: usize, offset: T| {
+            let theta = z.acos();
+            for j in 0..count {
+                let phi = T::from_f64(2.0).unwrap() * T::PI() * (f(j) + offset) / f(count);
+                out.push(Coordinates::spherical(T::one(), theta, phi));
+            }
+        };
+        let cap = |i: usize| T::one() - f(i * i) / f(3 * nside * nside);
+        // North polar cap
+        for i in 1..nside {
+            ring(cap(i), 4 * i, half);
+        }
+        // Equatorial belt, shifted by half a pixel in every other ring
+        for i in nside..=3 * nside {
+            let z = T::from_f64(4.0 / 3.0).unwrap() - f(2 * i) / f(3 * nside);
+            let offset = if (i - nside).is_multiple_of(2) {
+                half
+            } else {
+                T::zero()
+            };
+            ring(z, 4 * nside, offset);
+        }
+        // South polar cap
+        for i in (1..nside).rev() {
+            ring(-cap(i), 4 * i, half);
+        }
+        out
+    }
+
+    fn weights<T: SphrsFloat>(&self) -> Vec<T> {
+        let n = self.num_pixels();
+        vec![T::from_f64(4.0).unwrap() * T::PI() / T::from_usize(n).unwrap(); n]
+    }
+
+    fn max_degree(&self) -> usize {
+        3 * self.nside - 1
+    }
+
+    fn exact(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHCoordinates;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn pixel_centers() {
+        let directions: Vec<Coordinates<f64>> = Healpix::new(1).unwrap().directions();
+        assert_eq!(directions.len(), 12);
+        // Three rings of four pixels at z = 2/3, 0 and -2/3
+        let z = [2.0 / 3.0, 0.0, -2.0 / 3.0];
+        for (i, p) in directions.iter().enumerate() {
+            assert_relative_eq!(p.z(), z[i / 4], epsilon = 1e-15);
+        }
+        assert_relative_eq!(directions[0].phi(), std::f64::consts::FRAC_PI_4);
+        assert_relative_eq!(directions[4].phi(), 0.0);
+
+        let grid = Healpix::new(4).unwrap();
+        let directions: Vec<Coordinates<f64>> = grid.directions();
+        assert_eq!(directions.len(), grid.num_pixels());
+        // Centroid of all pixels is the origin
+        let sum = directions.iter().fold([0.0; 3], |acc, p| {
+            [acc[0] + p.x(), acc[1] + p.y(), acc[2] + p.z()]
+        });
+        for s in sum {
+            assert!(s.abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn invalid_nside() {
+        for nside in [0, usize::MAX / 2] {
+            assert!(matches!(
+                Healpix::new(nside),
+                Err(SphrsError::InvalidConfiguration(_))
+            ));
+        }
+    }
+}
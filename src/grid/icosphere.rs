@@ -0,0 +1,173 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::collections::HashMap;
+
+use crate::grid::SphericalGrid;
+use crate::{Coordinates, SphrsFloat};
+
+/// Icosphere grid: the vertices of a subdivided icosahedron projected onto the sphere
+///
+/// Each subdivision splits every triangle into four, such that the grid has
+/// 10 4^`subdivisions` + 2 vertices. The vertices are stored in the order of their creation,
+/// starting with the 12 vertices of the icosahedron. Each vertex is weighted with one third of
+/// the spherical area of the adjacent triangles. The bandlimit is chosen like the one of a
+/// HEALPix grid with the same number of samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Icosphere {
+    /// Number of subdivisions
+    subdivisions: usize,
+}
+
+type Mesh = (Vec<[f64; 3]>, Vec<[usize; 3]>);
+
+impl Icosphere {
+    /// Create a grid of an icosahedron subdivided `subdivisions` times
+    pub fn new(subdivisions: usize) -> Self {
+        Icosphere { subdivisions }
+    }
+
+    /// Number of vertices
+    pub fn num_vertices(&self) -> usize {
+        10 * 4usize.pow(self.subdivisions as u32) + 2
+    }
+
+    /// Vertices and triangles of the subdivided icosahedron
+    fn mesh(&self) -> Mesh {
+        let normalize = |v: [f64; 3]| {
+            let n = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+            [v[0] / n, v[1] / n, v[2] / n]
+        };
+        let t = (1.0 + 5f64.sqrt()) / 2.0;
+        let mut vertices: Vec<[f64; 3]> = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .into_iter()
+        .map(normalize)
+        .collect();
+        let mut faces = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+        for _ in 0..self.subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: usize, b: usize, vertices: &mut Vec<[f64; 3]>| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let (u, v) = (vertices[a], vertices[b]);
+                    vertices.push(normalize([u[0] + v[0], u[1] + v[1], u[2] + v[2]]));
+                    vertices.len() - 1
+                })
+            };
+            let mut next = Vec::with_capacity(4 * faces.len());
+            for [a, b, c] in faces {
+                let ab = midpoint(a, b, &mut vertices);
+                let bc = midpoint(b, c, &mut vertices);
+                let ca = midpoint(c, a, &mut vertices);
+                next.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+            }
+            faces = next;
+        }
+        (vertices, faces)
+    }
+}
+
+impl SphericalGrid for Icosphere {
+    fn directions<T: SphrsFloat>(&self) -> Vec<Coordinates<T>> {
+        let f = |v: f64| T::from_f64(v).unwrap();
+        self.mesh()
+            .0
+            .into_iter()
+            .map(|v| Coordinates::cartesian(f(v[0]), f(v[1]), f(v[2])))
+            .collect()
+    }
+
+    fn weights<T: SphrsFloat>(&self) -> Vec<T> {
+        let (vertices, faces) = self.mesh();
+        let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let mut weights = vec![0.0; vertices.len()];
+        for [a, b, c] in faces {
+            let (u, v, w) = (vertices[a], vertices[b], vertices[c]);
+            // Spherical excess (Van Oosterom and Strackee)
+            let cross = [
+                v[1] * w[2] - v[2] * w[1],
+                v[2] * w[0] - v[0] * w[2],
+                v[0] * w[1] - v[1] * w[0],
+            ];
+            let area = 2.0
+                * dot(u, cross)
+                    .abs()
+                    .atan2(1.0 + dot(u, v) + dot(v, w) + dot(w, u));
+            for i in [a, b, c] {
+                weights[i] += area / 3.0;
+            }
+        }
+        weights
+            .into_iter()
+            .map(|w| T::from_f64(w).unwrap())
+            .collect()
+    }
+
+    fn max_degree(&self) -> usize {
+        // 3 nside - 1 for a HEALPix grid of 12 nside^2 pixels
+        ((3.0 * self.num_vertices() as f64).sqrt() / 2.0) as usize - 1
+    }
+
+    fn exact(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn vertices_and_weights() {
+        for subdivisions in 0..4 {
+            let grid = Icosphere::new(subdivisions);
+            let directions: Vec<Coordinates<f64>> = grid.directions();
+            assert_eq!(directions.len(), grid.num_vertices());
+            let weights: Vec<f64> = grid.weights();
+            assert_relative_eq!(
+                weights.iter().sum::<f64>(),
+                4.0 * std::f64::consts::PI,
+                epsilon = 1e-12
+            );
+        }
+        assert_eq!(Icosphere::new(3).max_degree(), 20);
+    }
+}
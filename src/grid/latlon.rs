@@ -0,0 +1,200 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::grid::SphericalGrid;
+use crate::quadrature::gauss_legendre;
+use crate::{Coordinates, SphrsError, SphrsFloat};
+
+/// Product grid of the polar angles `theta` and `n_phi` equally spaced azimuths offset by
+/// `offset` (in units of the azimuth spacing)
+fn product<T: SphrsFloat>(theta: &[T], n_phi: usize, offset: T) -> Vec<Coordinates<T>> {
+    let d_phi = T::from_f64(2.0).unwrap() * T::PI() / T::from_usize(n_phi).unwrap();
+    let mut out = Vec::with_capacity(theta.len() * n_phi);
+    for &t in theta {
+        for k in 0..n_phi {
+            let phi = (T::from_usize(k).unwrap() + offset) * d_phi;
+            out.push(Coordinates::spherical(T::one(), t, phi));
+        }
+    }
+    out
+}
+
+/// Product weights of the weights `w` in cos(theta) and `n_phi` equal weights in phi
+fn product_weights<T: SphrsFloat>(w: &[T], n_phi: usize) -> Vec<T> {
+    let d_phi = T::from_f64(2.0).unwrap() * T::PI() / T::from_usize(n_phi).unwrap();
+    w.iter()
+        .flat_map(|&w| std::iter::repeat_n(w * d_phi, n_phi))
+        .collect()
+}
+
+/// Equiangular latitude-longitude grid of cell centers
+///
+/// The grid has `n_theta` rows at theta_j = pi (j + 1/2) / `n_theta` from north to south, each
+/// with `n_phi` samples at phi_k = 2 pi (k + 1/2) / `n_phi`, which is the layout of an
+/// equirectangular image. Data is stored row by row. The weights of Fejér's first quadrature
+/// rule are exact up to degree min(`n_theta` - 1, `n_phi` - 1) / 2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LatLon {
+    /// Number of rows
+    n_theta: usize,
+    /// Number of samples per row
+    n_phi: usize,
+}
+
+impl LatLon {
+    /// Create a grid with `n_theta` rows of `n_phi` samples
+    ///
+    /// Returns [`SphrsError::InvalidConfiguration`] if either is zero.
+    pub fn new(n_theta: usize, n_phi: usize) -> Result<Self, SphrsError> {
+        if n_theta == 0 || n_phi == 0 {
+            return Err(SphrsError::InvalidConfiguration(format!(
+                "grid of {n_theta} rows of {n_phi} samples is empty"
+            )));
+        }
+        Ok(LatLon { n_theta, n_phi })
+    }
+
+    /// Polar angles of the rows
+    fn theta<T: SphrsFloat>(&self) -> Vec<T> {
+        let n = T::from_usize(self.n_theta).unwrap();
+        (0..self.n_theta)
+            .map(|j| T::PI() * (T::from_usize(j).unwrap() + T::from_f64(0.5).unwrap()) / n)
+            .collect()
+    }
+}
+
+impl SphericalGrid for LatLon {
+    fn directions<T: SphrsFloat>(&self) -> Vec<Coordinates<T>> {
+        product(&self.theta(), self.n_phi, T::from_f64(0.5).unwrap())
+    }
+
+    fn weights<T: SphrsFloat>(&self) -> Vec<T> {
+        let n = T::from_usize(self.n_theta).unwrap();
+        let two = T::from_f64(2.0).unwrap();
+        let w: Vec<T> = self
+            .theta()
+            .into_iter()
+            .map(|t: T| {
+                let sum = (1..=self.n_theta / 2).fold(T::zero(), |acc, k| {
+                    let kf = T::from_usize(k).unwrap();
+                    acc + (two * kf * t).cos() / (T::from_f64(4.0).unwrap() * kf * kf - T::one())
+                });
+                two / n * (T::one() - two * sum)
+            })
+            .collect();
+        product_weights(&w, self.n_phi)
+    }
+
+    fn max_degree(&self) -> usize {
+        (self.n_theta.min(self.n_phi) - 1) / 2
+    }
+
+    fn exact(&self) -> bool {
+        true
+    }
+}
+
+/// Gauss-Legendre grid
+///
+/// The grid has `n` rows at the Gauss-Legendre nodes in cos(theta) from north to south, each with
+/// `2 n` samples at phi_k = pi k / `n`. Data is stored row by row. The quadrature is exact up to
+/// degree `n - 1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GaussLegendre {
+    /// Number of rows
+    n: usize,
+}
+
+impl GaussLegendre {
+    /// Create a grid with `n` rows
+    ///
+    /// Returns [`SphrsError::InvalidConfiguration`] if `n` is zero.
+    pub fn new(n: usize) -> Result<Self, SphrsError> {
+        if n == 0 {
+            return Err(SphrsError::InvalidConfiguration(
+                "grid of 0 rows is empty".to_string(),
+            ));
+        }
+        Ok(GaussLegendre { n })
+    }
+}
+
+impl SphericalGrid for GaussLegendre {
+    fn directions<T: SphrsFloat>(&self) -> Vec<Coordinates<T>> {
+        let (x, _) = gauss_legendre::<T>(self.n);
+        let theta: Vec<T> = x.into_iter().map(|x| x.acos()).collect();
+        product(&theta, 2 * self.n, T::zero())
+    }
+
+    fn weights<T: SphrsFloat>(&self) -> Vec<T> {
+        let (_, w) = gauss_legendre::<T>(self.n);
+        product_weights(&w, 2 * self.n)
+    }
+
+    fn max_degree(&self) -> usize {
+        self.n - 1
+    }
+
+    fn exact(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::SHCoordinates;
+    use approx::assert_relative_eq;
+
+    /// Gram matrix of the harmonics up to the bandlimit under the quadrature of `grid`
+    fn check_orthonormality<G: SphericalGrid>(grid: &G) {
+        let degree = grid.max_degree();
+        let set = HarmonicsSet::new(degree, RealSH::Spherical);
+        let sh: Vec<Vec<f64>> = grid.directions().iter().map(|p| set.eval(p)).collect();
+        let weights = grid.weights();
+        let total: f64 = weights.iter().sum();
+        assert_relative_eq!(total, 4.0 * std::f64::consts::PI, epsilon = 1e-12);
+        for i in 0..set.num_sh() {
+            for j in 0..set.num_sh() {
+                let g: f64 = sh.iter().zip(&weights).map(|(y, w)| w * y[i] * y[j]).sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert_relative_eq!(g, expected, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn latlon_quadrature() {
+        check_orthonormality(&LatLon::new(13, 16).unwrap());
+        check_orthonormality(&LatLon::new(10, 9).unwrap());
+        let grid = LatLon::new(2, 4).unwrap();
+        let directions: Vec<Coordinates<f64>> = grid.directions();
+        assert_eq!(directions.len(), 8);
+        assert_relative_eq!(directions[5].theta(), 0.75 * std::f64::consts::PI);
+        assert_relative_eq!(directions[5].phi(), 0.75 * std::f64::consts::PI);
+    }
+
+    #[test]
+    fn gauss_legendre_quadrature() {
+        check_orthonormality(&GaussLegendre::new(7).unwrap());
+    }
+
+    #[test]
+    fn empty_grids() {
+        for (n_theta, n_phi) in [(0, 4), (4, 0), (0, 0)] {
+            assert!(matches!(
+                LatLon::new(n_theta, n_phi),
+                Err(SphrsError::InvalidConfiguration(_))
+            ));
+        }
+        assert!(matches!(
+            GaussLegendre::new(0),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spherical grids and bandlimited resampling between them
+//!
+//! Data sampled on one grid is analyzed into real SH coefficients (with respect to
+//! [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`)) and synthesized onto another grid.
+//! [`resample`] truncates the expansion at the lower of the two bandlimits, which removes the
+//! frequencies the target grid cannot represent instead of aliasing them, and
+//! [`resample_to_degree`] transfers the degrees up to an explicitly given bandlimit.
+//!
+//! Gauss-Legendre and latitude-longitude grids have quadratures which are exact up to their
+//! bandlimit. The quadratures of HEALPix and icosphere grids are approximate; their analysis is
+//! refined iteratively.
+//!
+//! Sums over the grid are evaluated blockwise in a fixed order, such that results are
+//! bit-reproducible across runs.
+//!
+//! [`map_pointwise`] uses the same round-trip through a grid to apply nonlinear functions to an
+//! expansion.
+
+mod healpix;
+mod icosphere;
+mod latlon;
+
+pub use healpix::Healpix;
+pub use icosphere::Icosphere;
+pub use latlon::{GaussLegendre, LatLon};
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::parallel;
+use crate::reduce::blockwise_sum;
+use crate::{Coordinates, SHCoefficients, SphrsError, SphrsFloat};
+
+/// Number of refinement iterations of the analysis on grids without exact quadrature
+const REFINEMENT_ITERATIONS: usize = 3;
+
+/// A set of sample directions on the unit sphere with quadrature weights
+pub trait SphericalGrid {
+    /// Sample directions, in the order in which data on the grid is stored
+    fn directions<T: SphrsFloat>(&self) -> Vec<Coordinates<T>>;
+
+    /// Quadrature weights of the directions, summing to 4 pi
+    fn weights<T: SphrsFloat>(&self) -> Vec<T>;
+
+    /// Highest degree of the harmonics the grid represents
+    fn max_degree(&self) -> usize;
+
+    /// Whether the quadrature integrates the product of any two harmonics up to
+    /// [`max_degree`](`SphericalGrid::max_degree`) exactly
+    fn exact(&self) -> bool;
+}
+
+/// Quadrature projection of `values` onto the harmonics evaluated at the grid directions
+fn project<T: SphrsFloat>(sh: &[Vec<T>], weights: &[T], values: &[T], out: &mut [T]) {
+    let sum = blockwise_sum(sh.len(), out.len(), |range, acc| {
+        for i in range {
+            let v = values[i] * weights[i];
+            for (a, &y) in acc.iter_mut().zip(&sh[i]) {
+                *a = *a + v * y;
+            }
+        }
+    });
+    for (o, s) in out.iter_mut().zip(sum) {
+        *o = *o + s;
+    }
+}
+
+/// Dot product of the harmonics at each grid direction with `coeffs`
+fn synthesize_sh<T: SphrsFloat>(sh: &[Vec<T>], coeffs: &[T]) -> Vec<T> {
+    parallel::map(sh.len(), |i| {
+        sh[i]
+            .iter()
+            .zip(coeffs)
+            .fold(T::zero(), |acc, (&y, &c)| acc + y * c)
+    })
+}
+
+/// Harmonics of `set` at each of the `directions`
+fn eval_sh<T: SphrsFloat>(
+    set: &HarmonicsSet<T, RealSH>,
+    directions: &[Coordinates<T>],
+) -> Vec<Vec<T>> {
+    parallel::map(directions.len(), |i| set.eval(&directions[i]))
+}
+
+/// Analyze `values` sampled on `grid` into real SH coefficients up to degree `degree`
+///
+/// On grids without exact quadrature, the quadrature projection is refined with a few Jacobi
+/// iterations on the residual (as done by healpy's `map2alm`). Returns
+/// [`SphrsError::InvalidInput`] if the number of values does not equal the number of grid
+/// directions.
+pub fn analyze<T, G>(grid: &G, degree: usize, values: &[T]) -> Result<SHCoefficients<T>, SphrsError>
+where
+    T: SphrsFloat,
+    G: SphericalGrid,
+{
+    let directions = grid.directions();
+    if values.len() != directions.len() {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} values on a grid of {} directions",
+            values.len(),
+            directions.len()
+        )));
+    }
+    let set = HarmonicsSet::new(degree, RealSH::Spherical);
+    let sh = eval_sh(&set, &directions);
+    let weights = grid.weights();
+    let mut coeffs = vec![T::zero(); set.num_sh()];
+    project(&sh, &weights, values, &mut coeffs);
+    if !grid.exact() {
+        for _ in 0..REFINEMENT_ITERATIONS {
+            let residual: Vec<T> = synthesize_sh(&sh, &coeffs)
+                .into_iter()
+                .zip(values)
+                .map(|(s, &v)| v - s)
+                .collect();
+            project(&sh, &weights, &residual, &mut coeffs);
+        }
+    }
+    Ok(SHCoefficients::from_vec(degree, coeffs))
+}
+
+/// Synthesize the values of the expansion `coeffs` at the directions of `grid`
+pub fn synthesize<T, G>(grid: &G, coeffs: &SHCoefficients<T>) -> Vec<T>
+where
+    T: SphrsFloat,
+    G: SphericalGrid,
+{
+    let set = HarmonicsSet::new(coeffs.degree(), RealSH::Spherical);
+    let sh = eval_sh(&set, &grid.directions());
+    synthesize_sh(&sh, coeffs.as_slice())
+}
+
+/// Resample `values` given on `source` onto `target`
+///
+/// The data is analyzed up to the lower of the bandlimits of both grids and synthesized on the
+/// target grid. Returns [`SphrsError::InvalidInput`] if the number of values does not equal the
+/// number of directions of `source`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::grid::{resample, GaussLegendre, Healpix, LatLon, SphericalGrid};
+/// use sphrs::SHCoordinates;
+///
+/// let source = LatLon::new(16, 32).unwrap();
+/// let values: Vec<f64> = source.directions::<f64>().iter().map(|p| p.x() * p.z()).collect();
+///
+/// // x z is of degree 2 and is therefore resampled exactly
+/// let target = GaussLegendre::new(8).unwrap();
+/// let resampled = resample(&source, &values, &target).unwrap();
+/// for (p, v) in target.directions::<f64>().iter().zip(resampled) {
+///     assert!((v - p.x() * p.z()).abs() < 1e-12);
+/// }
+///
+/// let healpix = resample(&source, &values, &Healpix::new(4).unwrap()).unwrap();
+/// assert_eq!(healpix.len(), 192);
+/// ```
+pub fn resample<T, S, G>(source: &S, values: &[T], target: &G) -> Result<Vec<T>, SphrsError>
+where
+    T: SphrsFloat,
+    S: SphericalGrid,
+    G: SphericalGrid,
+{
+    let degree = source.max_degree().min(target.max_degree());
+    resample_to_degree(source, values, target, degree)
+}
+
+/// Resample `values` given on `source` onto `target` through the expansion up to degree `degree`
+///
+/// Contrary to [`resample`], the bandlimit of the transfer is given explicitly, e.g. to remove
+/// small scales with a degree below those of both grids or to pass the same degrees between
+/// several grids. Returns [`SphrsError::InvalidInput`] if `degree` exceeds the
+/// [`max_degree`](`SphericalGrid::max_degree`) of either grid, since the analysis on `source`
+/// would then alias the higher degrees into the lower ones and the samples on `target` would not
+/// determine the expansion, or if the number of values does not equal the number of directions
+/// of `source`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::grid::{resample_to_degree, GaussLegendre, Healpix, LatLon, SphericalGrid};
+/// use sphrs::SHCoordinates;
+///
+/// let source = LatLon::new(16, 32).unwrap();
+/// let values: Vec<f64> = source
+///     .directions::<f64>()
+///     .iter()
+///     .map(|p| p.z() + p.x() * p.y() * p.z())
+///     .collect();
+///
+/// // Only the degree 1 part z is passed on
+/// let target = GaussLegendre::new(8).unwrap();
+/// let resampled = resample_to_degree(&source, &values, &target, 2).unwrap();
+/// for (p, v) in target.directions::<f64>().iter().zip(resampled) {
+///     assert!((v - p.z()).abs() < 1e-12);
+/// }
+///
+/// assert!(resample_to_degree(&source, &values, &Healpix::new(2).unwrap(), 8).is_err());
+/// ```
+pub fn resample_to_degree<T, S, G>(
+    source: &S,
+    values: &[T],
+    target: &G,
+    degree: usize,
+) -> Result<Vec<T>, SphrsError>
+where
+    T: SphrsFloat,
+    S: SphericalGrid,
+    G: SphericalGrid,
+{
+    for (name, max_degree) in [
+        ("source", source.max_degree()),
+        ("target", target.max_degree()),
+    ] {
+        if degree > max_degree {
+            return Err(SphrsError::InvalidInput(format!(
+                "degree {degree} exceeds the maximum degree {max_degree} of the {name} grid"
+            )));
+        }
+    }
+    let coeffs = analyze(source, degree, values)?;
+    Ok(synthesize(target, &coeffs))
+}
+
+/// Apply the pointwise function `f` to the expansion `coeffs` and analyze the result up to degree
+/// `output_degree`
+///
+/// The expansion is synthesized on a Gauss-Legendre grid, `f` is applied to each value and the
+/// result is analyzed again. The grid is chosen such that the analysis is exact as long as the
+/// composition f(g) contains no frequencies above degree `bandlimit`, which is the case for
+/// `bandlimit = k * coeffs.degree()` if `f` is a polynomial of order k (e.g. `2 * degree` for
+/// squaring). Non-polynomial functions like `exp` or clamping are not bandlimited; a higher
+/// `bandlimit` then reduces the aliasing of their high frequencies into the output.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::grid::map_pointwise;
+/// use sphrs::SHCoefficients;
+///
+/// // The square of Y_1,0 = sqrt(3 / (4 pi)) z has the coefficient 1 / sqrt(4 pi) of Y_0,0
+/// let g = SHCoefficients::from_vec(1, vec![0.0f64, 0.0, 1.0, 0.0]);
+/// let squared = map_pointwise(&g, 2, 2, |v| v * v);
+/// let c00 = 1.0 / (4.0 * std::f64::consts::PI).sqrt();
+/// assert!((squared.get(0, 0).unwrap() - c00).abs() < 1e-12);
+/// ```
+pub fn map_pointwise<T, F>(
+    coeffs: &SHCoefficients<T>,
+    output_degree: usize,
+    bandlimit: usize,
+    f: F,
+) -> SHCoefficients<T>
+where
+    T: SphrsFloat,
+    F: Fn(T) -> T,
+{
+    // The quadrature of f(g) Y_lm is exact up to degree 2 n - 1
+    let n = (bandlimit + output_degree + 1)
+        .div_ceil(2)
+        .max(output_degree + 1)
+        .max(coeffs.degree() + 1);
+    let grid = GaussLegendre::new(n).expect("n is positive");
+    let values: Vec<T> = synthesize(&grid, coeffs).into_iter().map(f).collect();
+    analyze(&grid, output_degree, &values).expect("grid and values have the same size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::SHEval;
+
+    /// Y_2,1 + 0.5 Y_4,-3 - 0.2 Y_7,5
+    fn f(p: &Coordinates<f64>) -> f64 {
+        RealSH::Spherical.eval(2, 1, p) + 0.5 * RealSH::Spherical.eval(4, -3, p)
+            - 0.2 * RealSH::Spherical.eval(7, 5, p)
+    }
+
+    fn sample<G: SphericalGrid>(grid: &G, f: impl Fn(&Coordinates<f64>) -> f64) -> Vec<f64> {
+        grid.directions().iter().map(f).collect()
+    }
+
+    #[test]
+    fn exact_grids_roundtrip() {
+        let gl = GaussLegendre::new(10).unwrap();
+        let latlon = LatLon::new(20, 24).unwrap();
+        assert_eq!(gl.max_degree(), 9);
+        assert_eq!(latlon.max_degree(), 9);
+        let on_latlon = resample(&gl, &sample(&gl, f), &latlon).unwrap();
+        for (a, b) in on_latlon.iter().zip(sample(&latlon, f)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        let back = resample(&latlon, &on_latlon, &gl).unwrap();
+        for (a, b) in back.iter().zip(sample(&gl, f)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn approximate_grids() {
+        let g = |p: &Coordinates<f64>| {
+            RealSH::Spherical.eval(2, 1, p) + 0.5 * RealSH::Spherical.eval(4, -3, p)
+        };
+        // Synthesis on any grid is exact, analysis on HEALPix and icosphere grids is approximate
+        let gl = GaussLegendre::new(8).unwrap();
+        let values = sample(&gl, g);
+        let healpix = Healpix::new(4).unwrap();
+        let on_healpix = resample(&gl, &values, &healpix).unwrap();
+        for (a, b) in on_healpix.iter().zip(sample(&healpix, g)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        let icosphere = Icosphere::new(3);
+        let on_icosphere = resample(&gl, &values, &icosphere).unwrap();
+        for (a, b) in on_icosphere.iter().zip(sample(&icosphere, g)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        let coeffs = analyze(&healpix, 6, &sample(&healpix, g)).unwrap();
+        assert!((coeffs.get(2, 1).unwrap() - 1.0).abs() < 1e-3);
+        assert!((coeffs.get(4, -3).unwrap() - 0.5).abs() < 1e-3);
+        let coeffs = analyze(&icosphere, 6, &sample(&icosphere, g)).unwrap();
+        assert!((coeffs.get(2, 1).unwrap() - 1.0).abs() < 1e-3);
+        assert!((coeffs.get(4, -3).unwrap() - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn truncation_to_target_bandlimit() {
+        // The degree 7 part cannot be represented on the target grid and is removed
+        let source = GaussLegendre::new(10).unwrap();
+        let target = GaussLegendre::new(6).unwrap();
+        let resampled = resample(&source, &sample(&source, f), &target).unwrap();
+        let low = |p: &Coordinates<f64>| {
+            RealSH::Spherical.eval(2, 1, p) + 0.5 * RealSH::Spherical.eval(4, -3, p)
+        };
+        for (a, b) in resampled.iter().zip(sample(&target, low)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn explicit_degree() {
+        // Degree 3 keeps only the degree 2 part of f on a HEALPix grid which resolves all of f
+        let source = GaussLegendre::new(10).unwrap();
+        let target = Healpix::new(4).unwrap();
+        let resampled = resample_to_degree(&source, &sample(&source, f), &target, 3).unwrap();
+        let low = |p: &Coordinates<f64>| RealSH::Spherical.eval(2, 1, p);
+        for (a, b) in resampled.iter().zip(sample(&target, low)) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        assert!(matches!(
+            resample_to_degree(&source, &sample(&source, f), &target, 10),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn pointwise_functions() {
+        let g = SHCoefficients::from_vec(1, vec![0.0, 0.0, 1.0, 0.0]);
+        let squared = map_pointwise(&g, 4, 2, |v| v * v);
+        let pi = std::f64::consts::PI;
+        for (i, &c) in squared.as_slice().iter().enumerate() {
+            let expected = match i {
+                0 => 1.0 / (4.0 * pi).sqrt(),
+                6 => 1.0 / (5.0 * pi).sqrt(),
+                _ => 0.0,
+            };
+            assert!((c - expected).abs() < 1e-12, "{i} {c}");
+        }
+
+        // exp is not bandlimited, oversampling reduces the aliasing into the output
+        let h = analyze(
+            &GaussLegendre::new(4).unwrap(),
+            3,
+            &sample(&GaussLegendre::new(4).unwrap(), f),
+        )
+        .unwrap();
+        let reference = map_pointwise(&h, 3, 40, f64::exp);
+        let oversampled = map_pointwise(&h, 3, 16, f64::exp);
+        let aliased = map_pointwise(&h, 3, 3, f64::exp);
+        let error = |c: &SHCoefficients<f64>| {
+            c.as_slice()
+                .iter()
+                .zip(reference.as_slice())
+                .fold(0.0f64, |acc, (a, b)| acc.max((a - b).abs()))
+        };
+        assert!(error(&oversampled) < 1e-8);
+        assert!(error(&aliased) > 100.0 * error(&oversampled));
+    }
+
+    #[test]
+    fn invalid_length() {
+        assert!(matches!(
+            analyze(&GaussLegendre::new(4).unwrap(), 2, &[1.0f64; 3]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
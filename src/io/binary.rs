@@ -0,0 +1,532 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io::{Read, Write};
+
+use num_complex::Complex;
+
+use crate::coefficients::num_coefficients;
+use crate::{Normalization, SHCoefficients, SphrsError};
+
+/// Magic bytes at the start of every binary coefficient file
+const MAGIC: [u8; 8] = *b"SPHRSCOF";
+/// Current version of the binary format
+const VERSION: u16 = 1;
+/// Size of the header in bytes
+const HEADER_SIZE: usize = 32;
+/// Largest payload buffer allocated before reading, in bytes
+const READ_CAPACITY: usize = 1 << 20;
+
+/// Data type of the coefficients stored in the binary format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dtype {
+    /// `f32`
+    F32,
+    /// `f64`
+    F64,
+    /// `Complex<f32>`
+    ComplexF32,
+    /// `Complex<f64>`
+    ComplexF64,
+}
+
+impl Dtype {
+    /// Returns the size of a single value in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            Dtype::F32 => 4,
+            Dtype::F64 | Dtype::ComplexF32 => 8,
+            Dtype::ComplexF64 => 16,
+        }
+    }
+
+    /// Returns the name of the data type
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dtype::F32 => "f32",
+            Dtype::F64 => "f64",
+            Dtype::ComplexF32 => "Complex<f32>",
+            Dtype::ComplexF64 => "Complex<f64>",
+        }
+    }
+
+    fn code(&self) -> u8 {
+        match self {
+            Dtype::F32 => 1,
+            Dtype::F64 => 2,
+            Dtype::ComplexF32 => 3,
+            Dtype::ComplexF64 => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, SphrsError> {
+        match code {
+            1 => Ok(Dtype::F32),
+            2 => Ok(Dtype::F64),
+            3 => Ok(Dtype::ComplexF32),
+            4 => Ok(Dtype::ComplexF64),
+            c => Err(SphrsError::InvalidFormat(format!(
+                "unknown data type code {c}"
+            ))),
+        }
+    }
+}
+
+fn normalization_code(normalization: Normalization) -> u8 {
+    match normalization {
+        Normalization::Orthonormal => 0,
+        Normalization::FourPi => 1,
+        Normalization::Schmidt => 2,
+        Normalization::Unnormalized => 3,
+    }
+}
+
+fn normalization_from_code(code: u8) -> Result<Normalization, SphrsError> {
+    match code {
+        0 => Ok(Normalization::Orthonormal),
+        1 => Ok(Normalization::FourPi),
+        2 => Ok(Normalization::Schmidt),
+        3 => Ok(Normalization::Unnormalized),
+        c => Err(SphrsError::InvalidFormat(format!(
+            "unknown normalization code {c}"
+        ))),
+    }
+}
+
+/// Scalar types which can be stored in the binary coefficient format
+pub trait BinaryScalar: Sized {
+    /// Data type identifier
+    const DTYPE: Dtype;
+
+    /// Append the little-endian representation of `self` to `out`
+    fn write_le(&self, out: &mut Vec<u8>);
+
+    /// Read a value from its little-endian representation.
+    ///
+    /// `bytes` is guaranteed to have a length of `Self::DTYPE.size()`.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl BinaryScalar for f32 {
+    const DTYPE: Dtype = Dtype::F32;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl BinaryScalar for f64 {
+    const DTYPE: Dtype = Dtype::F64;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl BinaryScalar for Complex<f32> {
+    const DTYPE: Dtype = Dtype::ComplexF32;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        self.re.write_le(out);
+        self.im.write_le(out);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        Complex::new(f32::read_le(&bytes[..4]), f32::read_le(&bytes[4..]))
+    }
+}
+
+impl BinaryScalar for Complex<f64> {
+    const DTYPE: Dtype = Dtype::ComplexF64;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        self.re.write_le(out);
+        self.im.write_le(out);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        Complex::new(f64::read_le(&bytes[..8]), f64::read_le(&bytes[8..]))
+    }
+}
+
+/// Decoded header of a binary coefficient file
+#[derive(Clone, Copy, Debug)]
+struct Header {
+    normalization: Normalization,
+    dtype: Dtype,
+    degree: usize,
+    len: usize,
+}
+
+impl Header {
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut out = [0u8; HEADER_SIZE];
+        out[0..8].copy_from_slice(&MAGIC);
+        out[8..10].copy_from_slice(&VERSION.to_le_bytes());
+        out[10] = normalization_code(self.normalization);
+        out[11] = self.dtype.code();
+        out[16..24].copy_from_slice(&(self.degree as u64).to_le_bytes());
+        out[24..32].copy_from_slice(&(self.len as u64).to_le_bytes());
+        out
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self, SphrsError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected at least {HEADER_SIZE} bytes of header, found {}",
+                bytes.len()
+            )));
+        }
+        if bytes[0..8] != MAGIC {
+            return Err(SphrsError::InvalidFormat("missing magic bytes".to_string()));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(SphrsError::UnsupportedVersion(version));
+        }
+        let normalization = normalization_from_code(bytes[10])?;
+        let dtype = Dtype::from_code(bytes[11])?;
+        if bytes[12..16] != [0; 4] {
+            return Err(SphrsError::InvalidFormat(
+                "reserved header bytes are not zero".to_string(),
+            ));
+        }
+        let degree = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let len = u64::from_le_bytes(bytes[24..32].try_into().unwrap());
+        let degree = usize::try_from(degree)
+            .map_err(|_| SphrsError::InvalidFormat(format!("degree {degree} too large")))?;
+        let expected = degree
+            .checked_add(1)
+            .and_then(|d| d.checked_mul(d))
+            .ok_or_else(|| SphrsError::InvalidFormat(format!("degree {degree} too large")))?;
+        if len != expected as u64 {
+            return Err(SphrsError::InvalidFormat(format!(
+                "degree {degree} requires {expected} coefficients, header states {len}"
+            )));
+        }
+        Ok(Header {
+            normalization,
+            dtype,
+            degree,
+            len: expected,
+        })
+    }
+
+    /// Size of the payload in bytes
+    fn payload_size(&self) -> Result<usize, SphrsError> {
+        self.len
+            .checked_mul(self.dtype.size())
+            .ok_or_else(|| SphrsError::InvalidFormat("payload size too large".to_string()))
+    }
+}
+
+/// Zero-copy view of a binary coefficient file
+///
+/// The header is validated on construction, individual coefficients are decoded on access. This
+/// allows using the coefficients of a memory-mapped file without reading it entirely.
+///
+/// # Example
+///
+/// ```
+/// # use sphrs::SHCoefficients;
+/// use sphrs::io::BinaryView;
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+/// let bytes = coeffs.to_bytes();
+///
+/// let view = BinaryView::parse(&bytes).unwrap();
+/// assert_eq!(view.degree(), 1);
+/// assert_eq!(view.get::<f64>(1, 0).unwrap(), Some(0.2));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BinaryView<'a> {
+    /// Header
+    header: Header,
+    /// Payload (without header)
+    payload: &'a [u8],
+}
+
+impl<'a> BinaryView<'a> {
+    /// Validate the header of `bytes` and create a view of the payload.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SphrsError> {
+        let header = Header::parse(bytes)?;
+        let payload_size = header.payload_size()?;
+        let payload = &bytes[HEADER_SIZE..];
+        if payload.len() < payload_size {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected {payload_size} bytes of payload, found {}",
+                payload.len()
+            )));
+        }
+        Ok(BinaryView {
+            header,
+            payload: &payload[..payload_size],
+        })
+    }
+
+    /// Returns the maximum degree
+    pub fn degree(&self) -> usize {
+        self.header.degree
+    }
+
+    /// Returns the total number of coefficients
+    pub fn len(&self) -> usize {
+        self.header.len
+    }
+
+    /// Returns `true` if there are no coefficients
+    pub fn is_empty(&self) -> bool {
+        self.header.len == 0
+    }
+
+    /// Returns the normalization convention
+    pub fn normalization(&self) -> Normalization {
+        self.header.normalization
+    }
+
+    /// Returns the data type of the stored coefficients
+    pub fn dtype(&self) -> Dtype {
+        self.header.dtype
+    }
+
+    fn check_dtype<T: BinaryScalar>(&self) -> Result<(), SphrsError> {
+        if T::DTYPE != self.header.dtype {
+            return Err(SphrsError::DtypeMismatch {
+                expected: T::DTYPE.name(),
+                found: self.header.dtype.name(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Decode the coefficient at flat index `idx`.
+    ///
+    /// Returns an error if `T` does not match the stored data type and `None` if `idx` is out of
+    /// bounds.
+    pub fn get_index<T: BinaryScalar>(&self, idx: usize) -> Result<Option<T>, SphrsError> {
+        self.check_dtype::<T>()?;
+        if idx >= self.header.len {
+            return Ok(None);
+        }
+        let size = self.header.dtype.size();
+        Ok(Some(T::read_le(
+            &self.payload[idx * size..(idx + 1) * size],
+        )))
+    }
+
+    /// Decode the coefficient of degree `l` and order `m`.
+    ///
+    /// Returns an error if `T` does not match the stored data type and `None` if (l, m) is not
+    /// part of the set.
+    pub fn get<T: BinaryScalar>(&self, l: i64, m: i64) -> Result<Option<T>, SphrsError> {
+        if l < 0 || l as usize > self.header.degree || m.abs() > l {
+            self.check_dtype::<T>()?;
+            return Ok(None);
+        }
+        self.get_index(crate::coefficients::sh_index(l, m))
+    }
+
+    /// Decode all coefficients into an [`SHCoefficients`] struct.
+    pub fn to_coefficients<T: BinaryScalar>(&self) -> Result<SHCoefficients<T>, SphrsError> {
+        self.check_dtype::<T>()?;
+        let data = self
+            .payload
+            .chunks_exact(self.header.dtype.size())
+            .map(T::read_le)
+            .collect();
+        Ok(SHCoefficients::from_vec(self.header.degree, data)
+            .with_normalization(self.header.normalization))
+    }
+}
+
+impl<T> SHCoefficients<T>
+where
+    T: BinaryScalar,
+{
+    fn header(&self) -> Header {
+        Header {
+            normalization: self.normalization(),
+            dtype: T::DTYPE,
+            degree: self.degree(),
+            len: num_coefficients(self.degree()),
+        }
+    }
+
+    /// Serialize the coefficients into the sphrs binary format (see [`crate::io`]).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.len() * T::DTYPE.size());
+        out.extend_from_slice(&self.header().to_bytes());
+        for c in self.as_slice() {
+            c.write_le(&mut out);
+        }
+        out
+    }
+
+    /// Deserialize coefficients from the sphrs binary format (see [`crate::io`]).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SphrsError> {
+        BinaryView::parse(bytes)?.to_coefficients()
+    }
+
+    /// Write the coefficients in the sphrs binary format (see [`crate::io`]) to `writer`.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), SphrsError> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Read coefficients in the sphrs binary format (see [`crate::io`]) from `reader`.
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Self, SphrsError> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let parsed = Header::parse(&header)?;
+        if T::DTYPE != parsed.dtype {
+            return Err(SphrsError::DtypeMismatch {
+                expected: T::DTYPE.name(),
+                found: parsed.dtype.name(),
+            });
+        }
+        // The header is untrusted: let the buffer grow with the data actually read instead of
+        // allocating the size it states up front
+        let payload_size = parsed.payload_size()?;
+        let mut payload = Vec::with_capacity(payload_size.min(READ_CAPACITY));
+        reader.take(payload_size as u64).read_to_end(&mut payload)?;
+        if payload.len() < payload_size {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected {payload_size} bytes of payload, found {}",
+                payload.len()
+            )));
+        }
+        let data = payload
+            .chunks_exact(T::DTYPE.size())
+            .map(T::read_le)
+            .collect();
+        Ok(SHCoefficients::from_vec(parsed.degree, data).with_normalization(parsed.normalization))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_real() {
+        let coeffs = SHCoefficients::from_vec(2, (0..9).map(|x| x as f64 * 0.3).collect());
+        let bytes = coeffs.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 9 * 8);
+        assert_eq!(SHCoefficients::<f64>::from_bytes(&bytes).unwrap(), coeffs);
+
+        let mut buf = Vec::new();
+        coeffs.write_binary(&mut buf).unwrap();
+        assert_eq!(buf, bytes);
+        let read = SHCoefficients::<f64>::read_binary(buf.as_slice()).unwrap();
+        assert_eq!(read, coeffs);
+    }
+
+    #[test]
+    fn roundtrip_complex() {
+        let coeffs = SHCoefficients::from_vec(
+            1,
+            (0..4)
+                .map(|x| Complex::new(x as f32, -(x as f32) * 0.5))
+                .collect(),
+        );
+        let bytes = coeffs.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 4 * 8);
+        assert_eq!(SHCoefficients::from_bytes(&bytes).unwrap(), coeffs);
+        let view = BinaryView::parse(&bytes).unwrap();
+        assert_eq!(view.dtype(), Dtype::ComplexF32);
+        assert_eq!(
+            view.get::<Complex<f32>>(1, 1).unwrap(),
+            Some(Complex::new(3.0, -1.5))
+        );
+        assert_eq!(view.get::<Complex<f32>>(2, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn dtype_mismatch() {
+        let bytes = SHCoefficients::from_vec(0, vec![1.0f32]).to_bytes();
+        assert!(matches!(
+            SHCoefficients::<f64>::from_bytes(&bytes),
+            Err(SphrsError::DtypeMismatch { .. })
+        ));
+        assert!(matches!(
+            SHCoefficients::<f64>::read_binary(bytes.as_slice()),
+            Err(SphrsError::DtypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let bytes = SHCoefficients::from_vec(1, vec![1.0f64; 4]).to_bytes();
+
+        // truncated payload
+        assert!(matches!(
+            BinaryView::parse(&bytes[..bytes.len() - 1]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // truncated header
+        assert!(matches!(
+            BinaryView::parse(&bytes[..10]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // wrong magic
+        let mut wrong = bytes.clone();
+        wrong[0] = b'X';
+        assert!(matches!(
+            BinaryView::parse(&wrong),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // unsupported version
+        let mut wrong = bytes.clone();
+        wrong[8] = 2;
+        assert!(matches!(
+            BinaryView::parse(&wrong),
+            Err(SphrsError::UnsupportedVersion(2))
+        ));
+        // inconsistent number of coefficients
+        let mut wrong = bytes;
+        wrong[24] = 5;
+        assert!(matches!(
+            BinaryView::parse(&wrong),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn malicious_header() {
+        let header = |degree: u64| {
+            let mut bytes = SHCoefficients::from_vec(0, vec![Complex::new(1.0f64, 0.0)]).to_bytes();
+            let len = (degree + 1).wrapping_mul(degree + 1);
+            bytes[16..24].copy_from_slice(&degree.to_le_bytes());
+            bytes[24..32].copy_from_slice(&len.to_le_bytes());
+            bytes
+        };
+        // (L + 1)^2 fits into usize, but the payload size in bytes overflows
+        let overflowing = header((1 << 31) - 1);
+        assert!(matches!(
+            SHCoefficients::<Complex<f64>>::read_binary(overflowing.as_slice()),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            BinaryView::parse(&overflowing),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // Claims 16 TB of payload, but the stream ends after one coefficient
+        let huge = header((1 << 20) - 1);
+        assert!(matches!(
+            SHCoefficients::<Complex<f64>>::read_binary(huge.as_slice()),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+}
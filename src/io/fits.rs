@@ -0,0 +1,485 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io::{Read, Write};
+
+use num_complex::Complex;
+
+use crate::coefficients::sh_index;
+use crate::{Normalization, SHCoefficients, SphrsError, SphrsFloat};
+
+/// Size of FITS blocks in bytes, headers and data are padded to multiples of it
+const BLOCK: usize = 2880;
+/// Size of a header card in bytes
+const CARD: usize = 80;
+
+/// Header of a FITS unit as (keyword, value) pairs, with string values unquoted
+struct FitsHeader {
+    cards: Vec<(String, String)>,
+}
+
+impl FitsHeader {
+    /// Parse the header starting at `bytes[offset]` and return it with the offset of its data
+    fn parse(bytes: &[u8], offset: usize) -> Result<(Self, usize), SphrsError> {
+        let mut cards = Vec::new();
+        let mut pos = offset;
+        loop {
+            let card = bytes.get(pos..pos + CARD).ok_or_else(|| {
+                SphrsError::InvalidFormat("FITS header without END card".to_string())
+            })?;
+            pos += CARD;
+            if !card.is_ascii() {
+                return Err(SphrsError::InvalidFormat(
+                    "FITS header is not ASCII".to_string(),
+                ));
+            }
+            let card = std::str::from_utf8(card).unwrap();
+            let key = card[..8].trim_end();
+            if key == "END" {
+                break;
+            }
+            if &card[8..10] == "= " {
+                cards.push((key.to_string(), parse_value(&card[10..])));
+            }
+        }
+        Ok((FitsHeader { cards }, padded(pos)))
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.cards
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn int(&self, key: &str) -> Result<i64, SphrsError> {
+        let value = self
+            .get(key)
+            .ok_or_else(|| SphrsError::InvalidFormat(format!("missing FITS keyword {key}")))?;
+        value
+            .parse()
+            .map_err(|_| SphrsError::InvalidFormat(format!("invalid value {value} of {key}")))
+    }
+
+    fn usize(&self, key: &str) -> Result<usize, SphrsError> {
+        let value = self.int(key)?;
+        usize::try_from(value)
+            .map_err(|_| SphrsError::InvalidFormat(format!("invalid value {value} of {key}")))
+    }
+}
+
+/// Value of a header card without comment and quotes
+fn parse_value(field: &str) -> String {
+    let field = field.trim_start();
+    if let Some(rest) = field.strip_prefix('\'') {
+        // Quotes inside strings are doubled
+        let mut out = String::new();
+        let mut chars = rest.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\'' {
+                if chars.peek() == Some(&'\'') {
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            out.push(c);
+        }
+        out.trim_end().to_string()
+    } else {
+        field.split('/').next().unwrap_or("").trim().to_string()
+    }
+}
+
+/// `len` rounded up to a multiple of the block size
+fn padded(len: usize) -> usize {
+    len.div_ceil(BLOCK) * BLOCK
+}
+
+/// Header card with an integer or logical value, right-justified to column 30
+fn card(key: &str, value: impl std::fmt::Display) -> String {
+    format!("{key:<8}= {value:>20}")
+}
+
+/// Header card with a string value
+fn card_str(key: &str, value: &str) -> String {
+    format!("{key:<8}= '{value:<8}'")
+}
+
+/// Append the header `cards` to `out` and pad it to a full block
+fn push_header(out: &mut Vec<u8>, cards: &[String]) {
+    for c in cards.iter().map(String::as_str).chain(["END"]) {
+        out.extend(format!("{c:<80}").bytes());
+    }
+    out.resize(padded(out.len()), b' ');
+}
+
+/// Column of a binary table holding a single value per row
+#[derive(Clone, Copy)]
+struct Column {
+    /// Offset in the row in bytes
+    offset: usize,
+    /// Data type code of TFORM
+    code: u8,
+}
+
+impl Column {
+    fn read_int(&self, row: &[u8]) -> Option<i64> {
+        match self.code {
+            b'J' => Some(i32::from_be_bytes(self.bytes(row)?) as i64),
+            b'K' => Some(i64::from_be_bytes(self.bytes(row)?)),
+            _ => None,
+        }
+    }
+
+    fn read_float(&self, row: &[u8]) -> Option<f64> {
+        match self.code {
+            b'E' => Some(f32::from_be_bytes(self.bytes(row)?) as f64),
+            b'D' => Some(f64::from_be_bytes(self.bytes(row)?)),
+            _ => None,
+        }
+    }
+
+    /// The `N` bytes of the value in `row`, if the row is long enough
+    fn bytes<const N: usize>(&self, row: &[u8]) -> Option<[u8; N]> {
+        row.get(self.offset..self.offset.checked_add(N)?)?
+            .try_into()
+            .ok()
+    }
+}
+
+/// Columns of a binary table with the widths of their TFORMs
+fn table_columns(header: &FitsHeader) -> Result<Vec<Column>, SphrsError> {
+    let fields = header.usize("TFIELDS")?;
+    let mut offset = 0;
+    let mut columns = Vec::with_capacity(fields);
+    for i in 1..=fields {
+        let key = format!("TFORM{i}");
+        let form = header
+            .get(&key)
+            .ok_or_else(|| SphrsError::InvalidFormat(format!("missing FITS keyword {key}")))?;
+        let digits = form.bytes().take_while(u8::is_ascii_digit).count();
+        let repeat = if digits == 0 {
+            1
+        } else {
+            form[..digits].parse::<usize>().unwrap_or(usize::MAX)
+        };
+        let code = form.as_bytes().get(digits).copied().unwrap_or(b' ');
+        let size = match code {
+            b'L' | b'B' | b'A' => 1,
+            b'I' => 2,
+            b'J' | b'E' => 4,
+            b'K' | b'D' | b'C' | b'P' => 8,
+            b'M' | b'Q' => 16,
+            b'X' => {
+                columns.push(Column { offset, code });
+                offset += repeat.div_ceil(8);
+                continue;
+            }
+            _ => {
+                return Err(SphrsError::InvalidFormat(format!(
+                    "unsupported FITS column format {form}"
+                )))
+            }
+        };
+        if i <= 3 && repeat != 1 {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected a single value per row in column {i}, found format {form}"
+            )));
+        }
+        columns.push(Column { offset, code });
+        offset = repeat
+            .checked_mul(size)
+            .and_then(|s| s.checked_add(offset))
+            .ok_or_else(|| SphrsError::InvalidFormat("FITS row too large".to_string()))?;
+    }
+    Ok(columns)
+}
+
+impl<T: SphrsFloat> SHCoefficients<Complex<T>> {
+    /// Write the coefficients as a HEALPix alm FITS file (see [`crate::io`]) to `writer`
+    ///
+    /// Only the coefficients of the orders m >= 0 are written, as the ones of m < 0 follow from
+    /// them for real functions. Coefficients in other normalization conventions are converted to
+    /// orthonormal ones, the convention of HEALPix. The values are written in double precision.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::SHCoefficients;
+    ///
+    /// let real = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, -0.2, 0.3]);
+    /// let mut file = Vec::new();
+    /// real.to_complex().write_healpix_alm(&mut file).unwrap();
+    /// let alm = SHCoefficients::<num_complex::Complex<f64>>::read_healpix_alm(file.as_slice());
+    /// for (a, b) in alm.unwrap().to_real().as_slice().iter().zip(real.as_slice()) {
+    ///     assert!((a - b).abs() < 1e-15);
+    /// }
+    /// ```
+    pub fn write_healpix_alm<W: Write>(&self, mut writer: W) -> Result<(), SphrsError> {
+        let degree = self.degree();
+        let rows = (degree + 1) * (degree + 2) / 2;
+        let wide = (degree + 1) * (degree + 1) > i32::MAX as usize;
+        let (index_form, index_size) = if wide { ("K", 8) } else { ("J", 4) };
+
+        let mut out = Vec::new();
+        push_header(
+            &mut out,
+            &[
+                card("SIMPLE", "T"),
+                card("BITPIX", 8),
+                card("NAXIS", 0),
+                card("EXTEND", "T"),
+            ],
+        );
+        push_header(
+            &mut out,
+            &[
+                card_str("XTENSION", "BINTABLE"),
+                card("BITPIX", 8),
+                card("NAXIS", 2),
+                card("NAXIS1", index_size + 16),
+                card("NAXIS2", rows),
+                card("PCOUNT", 0),
+                card("GCOUNT", 1),
+                card("TFIELDS", 3),
+                card_str("TTYPE1", "index"),
+                card_str("TFORM1", index_form),
+                card_str("TTYPE2", "real"),
+                card_str("TFORM2", "D"),
+                card_str("TTYPE3", "imag"),
+                card_str("TFORM3", "D"),
+                card("MAX-LPOL", degree),
+                card("MAX-MPOL", degree),
+            ],
+        );
+
+        // Ordered by order and then by degree like the alm arrays of healpy
+        let normalization = self.normalization();
+        let data = self.as_slice();
+        for m in 0..=degree as i64 {
+            for l in m..=degree as i64 {
+                let index = (l * l + l + m + 1) as u64;
+                if wide {
+                    out.extend(index.to_be_bytes());
+                } else {
+                    out.extend((index as i32).to_be_bytes());
+                }
+                let c = data[sh_index(l, m)] * normalization.factor::<T>(l as usize, m);
+                out.extend(c.re.to_f64().unwrap().to_be_bytes());
+                out.extend(c.im.to_f64().unwrap().to_be_bytes());
+            }
+        }
+        out.resize(padded(out.len()), 0);
+        writer.write_all(&out)?;
+        Ok(())
+    }
+
+    /// Read coefficients from a HEALPix alm FITS file (see [`crate::io`]) from `reader`
+    ///
+    /// Reads the binary table of the first extension, e.g. the temperature alm of files with
+    /// polarization. The coefficients of the orders m < 0 are completed with
+    /// a_l,-m = (-1)^m conj(a_lm) of real functions, coefficients missing from the file are zero.
+    /// The maximum degree is taken from the keyword `MAX-LPOL`, or from the highest index if it is
+    /// missing. The returned coefficients are orthonormal.
+    pub fn read_healpix_alm<R: Read>(mut reader: R) -> Result<Self, SphrsError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let (primary, mut offset) = FitsHeader::parse(&bytes, 0)?;
+        if primary.get("SIMPLE") != Some("T") {
+            return Err(SphrsError::InvalidFormat(
+                "not a FITS file, SIMPLE = T missing".to_string(),
+            ));
+        }
+        let naxis = primary.usize("NAXIS")?;
+        if naxis > 0 {
+            let mut size = primary.int("BITPIX")?.unsigned_abs() as usize / 8;
+            for i in 1..=naxis {
+                size = size.saturating_mul(primary.usize(&format!("NAXIS{i}"))?);
+            }
+            offset = offset.saturating_add(padded(size));
+        }
+
+        let (table, offset) = FitsHeader::parse(&bytes, offset)?;
+        if table.get("XTENSION") != Some("BINTABLE") {
+            return Err(SphrsError::InvalidFormat(
+                "expected a binary table in the first extension".to_string(),
+            ));
+        }
+        let columns = table_columns(&table)?;
+        if columns.len() < 3 {
+            return Err(SphrsError::InvalidFormat(format!(
+                "expected the columns index, real and imag, found {} columns",
+                columns.len()
+            )));
+        }
+        let row_size = table.usize("NAXIS1")?;
+        let rows = table.usize("NAXIS2")?;
+        let data = row_size
+            .checked_mul(rows)
+            .and_then(|size| bytes.get(offset..offset.checked_add(size)?))
+            .ok_or_else(|| SphrsError::InvalidFormat("truncated FITS table".to_string()))?;
+        let invalid_column = |name: &str| {
+            SphrsError::InvalidFormat(format!("unsupported type or size of column {name}"))
+        };
+        let mut entries = Vec::with_capacity(data.len() / row_size.max(1));
+        for row in data.chunks_exact(row_size.max(1)).take(rows) {
+            let index = columns[0]
+                .read_int(row)
+                .ok_or_else(|| invalid_column("index"))?;
+            let re = columns[1]
+                .read_float(row)
+                .ok_or_else(|| invalid_column("real"))?;
+            let im = columns[2]
+                .read_float(row)
+                .ok_or_else(|| invalid_column("imag"))?;
+            // index = l^2 + l + m + 1
+            let i = index - 1;
+            let mut l = (i.max(0) as f64).sqrt() as i64;
+            while l * l > i {
+                l -= 1;
+            }
+            while (l + 1) * (l + 1) <= i {
+                l += 1;
+            }
+            let m = i - l * l - l;
+            if i < 0 || m < 0 {
+                return Err(SphrsError::InvalidFormat(format!(
+                    "invalid alm index {index}"
+                )));
+            }
+            entries.push((l, m, re, im));
+        }
+
+        let degree = match table.get("MAX-LPOL") {
+            Some(_) => table.usize("MAX-LPOL")?,
+            None => entries.iter().map(|e| e.0 as usize).max().unwrap_or(0),
+        };
+        // Every degree has at least the row of m = 0
+        if degree >= rows.max(1) {
+            return Err(SphrsError::InvalidFormat(format!(
+                "{rows} rows cannot hold the alm up to degree {degree}"
+            )));
+        }
+        let mut out = SHCoefficients::zeros(degree);
+        let c = out.as_mut_slice();
+        for (l, m, re, im) in entries {
+            if l as usize > degree {
+                return Err(SphrsError::InvalidFormat(format!(
+                    "alm of degree {l} exceeds MAX-LPOL = {degree}"
+                )));
+            }
+            let a = Complex::new(T::from_f64(re).unwrap(), T::from_f64(im).unwrap());
+            c[sh_index(l, m)] = a;
+            if m > 0 {
+                let sign = if m % 2 == 0 { T::one() } else { -T::one() };
+                c[sh_index(l, -m)] = a.conj() * sign;
+            }
+        }
+        Ok(out.with_normalization(Normalization::Orthonormal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, HarmonicsSet};
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+
+    fn real_alm(degree: usize) -> SHCoefficients<Complex<f64>> {
+        let n = (degree + 1) * (degree + 1);
+        SHCoefficients::from_vec(degree, (0..n).map(|i| (i as f64 * 0.7).sin()).collect())
+            .to_complex()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let alm = real_alm(6);
+        let mut file = Vec::new();
+        alm.write_healpix_alm(&mut file).unwrap();
+        assert_eq!(file.len() % BLOCK, 0);
+        let read = SHCoefficients::<Complex<f64>>::read_healpix_alm(file.as_slice()).unwrap();
+        assert_eq!(read.degree(), 6);
+        for (a, b) in read.as_slice().iter().zip(alm.as_slice()) {
+            assert_relative_eq!(*a, *b, epsilon = 1e-15);
+        }
+
+        // Real function
+        let sh = HarmonicsSet::new(6, ComplexSH::Spherical);
+        let p = Coordinates::spherical(1.0, 0.7, -2.0);
+        let v: Complex<f64> = sh.eval_sum(&p, read.as_slice()).unwrap();
+        assert!(v.im.abs() < 1e-14);
+    }
+
+    #[test]
+    fn layout() {
+        let mut alm = SHCoefficients::zeros(2);
+        *alm.get_mut(2, 1).unwrap() = Complex::new(0.5f64, -0.25);
+        *alm.get_mut(2, -1).unwrap() = Complex::new(-0.5f64, -0.25);
+        let mut file = Vec::new();
+        alm.write_healpix_alm(&mut file).unwrap();
+        let card = |i: usize| std::str::from_utf8(&file[i * CARD..(i + 1) * CARD]).unwrap();
+        assert_eq!(card(0).trim_end(), "SIMPLE  =                    T");
+        assert_eq!(card(36).trim_end(), "XTENSION= 'BINTABLE'");
+        assert_eq!(card(40).trim_end(), "NAXIS2  =                    6");
+        assert_eq!(card(45).trim_end(), "TFORM1  = 'J       '");
+
+        // Rows of m = 0 (l = 0, 1, 2), then m = 1 (l = 1, 2) and m = 2 (l = 2)
+        let data = &file[2 * BLOCK..];
+        let row = |i: usize| &data[i * 20..(i + 1) * 20];
+        let index = |i: usize| i32::from_be_bytes(row(i)[..4].try_into().unwrap());
+        assert_eq!((0..6).map(index).collect::<Vec<_>>(), [1, 3, 7, 4, 8, 9]);
+        assert_eq!(f64::from_be_bytes(row(4)[4..12].try_into().unwrap()), 0.5);
+        assert_eq!(
+            f64::from_be_bytes(row(4)[12..20].try_into().unwrap()),
+            -0.25
+        );
+    }
+
+    #[test]
+    fn normalization_is_converted() {
+        let real = SHCoefficients::from_vec(2, (0..9).map(|i| i as f64).collect());
+        let schmidt = real.to_normalization(Normalization::Schmidt);
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        real.to_complex().write_healpix_alm(&mut a).unwrap();
+        schmidt.to_complex().write_healpix_alm(&mut b).unwrap();
+        let a = SHCoefficients::<Complex<f64>>::read_healpix_alm(a.as_slice()).unwrap();
+        let b = SHCoefficients::<Complex<f64>>::read_healpix_alm(b.as_slice()).unwrap();
+        assert_eq!(b.normalization(), Normalization::Orthonormal);
+        for (x, y) in a.as_slice().iter().zip(b.as_slice()) {
+            assert_relative_eq!(*x, *y, epsilon = 1e-13);
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut file = Vec::new();
+        real_alm(3).write_healpix_alm(&mut file).unwrap();
+        let read = |bytes: &[u8]| SHCoefficients::<Complex<f32>>::read_healpix_alm(bytes);
+        assert!(read(&file).is_ok());
+        // truncated table
+        assert!(matches!(
+            read(&file[..2 * BLOCK + 40]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // missing END card
+        assert!(matches!(
+            read(&file[..CARD]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // not a FITS file
+        let mut wrong = file.clone();
+        wrong[29] = b'F';
+        assert!(matches!(read(&wrong), Err(SphrsError::InvalidFormat(_))));
+        // index of m < 0
+        let mut wrong = file;
+        wrong[2 * BLOCK..2 * BLOCK + 4].copy_from_slice(&2i32.to_be_bytes());
+        assert!(matches!(read(&wrong), Err(SphrsError::InvalidFormat(_))));
+    }
+}
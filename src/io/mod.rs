@@ -0,0 +1,68 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Import and export of coefficient sets
+//!
+//! # Binary format
+//!
+//! sphrs defines a small, versioned binary format for [`SHCoefficients`](`crate::SHCoefficients`).
+//! All values are stored in little-endian byte order. A file consists of a 32 byte header
+//! followed by the payload:
+//!
+//! | Offset | Size | Content                                                  |
+//! |--------|------|----------------------------------------------------------|
+//! | 0      | 8    | Magic bytes `SPHRSCOF`                                   |
+//! | 8      | 2    | Format version (`u16`, currently `1`)                    |
+//! | 10     | 1    | Normalization convention (see [`Normalization`](`crate::Normalization`)) |
+//! | 11     | 1    | Data type (see [`Dtype`])                                |
+//! | 12     | 4    | Reserved, must be zero                                   |
+//! | 16     | 8    | Maximum degree (`u64`)                                   |
+//! | 24     | 8    | Number of coefficients (`u64`), must equal `(degree + 1)^2` |
+//! | 32     | ...  | Coefficients ordered by degree and order                 |
+//!
+//! Complex values are stored as real part followed by imaginary part. Because the header has a
+//! fixed size, the payload of a memory-mapped file can be accessed directly via [`BinaryView`]
+//! without copying or parsing the whole file.
+//!
+//! # HEALPix alm files
+//!
+//! [`SHCoefficients::write_healpix_alm`](`crate::SHCoefficients::write_healpix_alm`) and
+//! [`SHCoefficients::read_healpix_alm`](`crate::SHCoefficients::read_healpix_alm`) exchange
+//! complex coefficients with HEALPix and healpy (`healpy.write_alm` and `healpy.read_alm`). These
+//! files are FITS files with a binary table in the first extension with the columns `index`,
+//! `real` and `imag`, one row per coefficient a_lm of order m >= 0 with index = l^2 + l + m + 1.
+//! The coefficients refer to the orthonormal complex harmonics with the Condon-Shortley phase,
+//! i.e. [`ComplexSH::Spherical`](`crate::basis::ComplexSH::Spherical`), such that no conversion
+//! is needed besides the completion of the orders m < 0. Real coefficients are converted with
+//! [`SHCoefficients::to_complex`](`crate::SHCoefficients::to_complex`) and
+//! [`SHCoefficients::to_real`](`crate::SHCoefficients::to_real`).
+//!
+//! # NumPy files
+//!
+//! Coefficients and arrays of evaluated harmonics or grid values are exchanged with Python in the
+//! NumPy `.npy` format with [`write_npy`] and [`read_npy`], and
+//! [`SHCoefficients::write_npy`](`crate::SHCoefficients::write_npy`) and
+//! [`SHCoefficients::read_npy`](`crate::SHCoefficients::read_npy`). Several arrays are bundled in
+//! `.npz` archives with [`NpzWriter`] and read with [`NpzArchive`]. The same
+//! [`BinaryScalar`] types as in the binary format are supported.
+//!
+//! # CSV and JSON tables
+//!
+//! For a quick look in spreadsheets and plotting tools,
+//! [`SHCoefficients::write_csv`](`crate::SHCoefficients::write_csv`) and
+//! [`SHCoefficients::write_json`](`crate::SHCoefficients::write_json`) export coefficients, and
+//! [`write_evaluations_csv`] and [`write_evaluations_json`] export harmonics evaluated at a list
+//! of points, as tables with one row per value and the columns degree, order and value.
+
+mod binary;
+mod fits;
+mod npy;
+mod table;
+
+pub use binary::{BinaryScalar, BinaryView, Dtype};
+pub use npy::{read_npy, write_npy, NpzArchive, NpzWriter};
+pub use table::{write_evaluations_csv, write_evaluations_json, TableScalar};
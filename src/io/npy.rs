@@ -0,0 +1,620 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io::{Read, Write};
+
+use crate::coefficients::num_coefficients;
+use crate::io::{BinaryScalar, Dtype};
+use crate::{SHCoefficients, SphrsError};
+
+/// Magic bytes at the start of every `.npy` file
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+/// Alignment of the data of `.npy` files, the header is padded with spaces to a multiple of it
+const NPY_ALIGN: usize = 64;
+
+/// Signatures of the records of zip archives
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+/// MS-DOS date of 1980-01-01, the earliest date zip archives can store
+const ZIP_DATE: u16 = (1 << 5) | 1;
+
+impl Dtype {
+    /// Type description of NumPy (little-endian)
+    fn npy_descr(&self) -> &'static str {
+        match self {
+            Dtype::F32 => "<f4",
+            Dtype::F64 => "<f8",
+            Dtype::ComplexF32 => "<c8",
+            Dtype::ComplexF64 => "<c16",
+        }
+    }
+}
+
+/// Write the array `data` of shape `shape` in the NumPy `.npy` format to `writer`
+///
+/// The elements are in row-major (C) order, i.e. the last index varies fastest, and stored
+/// little-endian. Returns [`SphrsError::LengthMismatch`] if the length of `data` is not the
+/// product of `shape`. Such files are read with `numpy.load`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::io::{read_npy, write_npy};
+/// use sphrs::sampling::fibonacci;
+///
+/// // Harmonics of degree 2 at 10 points, one row per point
+/// let sh = HarmonicsSet::new(2, RealSH::Spherical);
+/// let values: Vec<f64> = sh.eval_batch(&fibonacci(10, 0));
+/// let mut file = Vec::new();
+/// write_npy(&mut file, &[10, sh.num_sh()], &values).unwrap();
+/// let (shape, read) = read_npy::<f64, _>(file.as_slice()).unwrap();
+/// assert_eq!(shape, [10, 9]);
+/// assert_eq!(read, values);
+/// ```
+pub fn write_npy<T, W>(mut writer: W, shape: &[usize], data: &[T]) -> Result<(), SphrsError>
+where
+    T: BinaryScalar,
+    W: Write,
+{
+    writer.write_all(&npy_bytes(shape, data)?)?;
+    Ok(())
+}
+
+/// Read an array in the NumPy `.npy` format from `reader`
+///
+/// Returns the shape and the elements in row-major (C) order. The stored type has to be the
+/// little-endian type of `T`, otherwise [`SphrsError::DtypeMismatch`] is returned; arrays in
+/// Fortran order are not supported.
+pub fn read_npy<T, R>(mut reader: R) -> Result<(Vec<usize>, Vec<T>), SphrsError>
+where
+    T: BinaryScalar,
+    R: Read,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    parse_npy(&bytes)
+}
+
+/// `.npy` file of the array `data` of shape `shape`
+fn npy_bytes<T: BinaryScalar>(shape: &[usize], data: &[T]) -> Result<Vec<u8>, SphrsError> {
+    let len = shape.iter().product::<usize>();
+    if len != data.len() {
+        return Err(SphrsError::LengthMismatch {
+            expected: len,
+            found: data.len(),
+        });
+    }
+    let shape = match shape {
+        [n] => format!("({n},)"),
+        _ => {
+            let dims: Vec<String> = shape.iter().map(usize::to_string).collect();
+            format!("({})", dims.join(", "))
+        }
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {shape}, }}",
+        T::DTYPE.npy_descr()
+    );
+    // Magic, version and header length take 10 bytes, the header ends with a newline
+    let total = (10 + header.len() + 1).div_ceil(NPY_ALIGN) * NPY_ALIGN;
+    header.extend(std::iter::repeat_n(' ', total - 11 - header.len()));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(total + data.len() * T::DTYPE.size());
+    out.extend_from_slice(NPY_MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for x in data {
+        x.write_le(&mut out);
+    }
+    Ok(out)
+}
+
+/// Value of `key` in the header dictionary of a `.npy` file
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, SphrsError> {
+    let missing = || SphrsError::InvalidFormat(format!("missing {key} in .npy header"));
+    let start = header.find(&format!("'{key}'")).ok_or_else(missing)? + key.len() + 2;
+    let rest = header[start..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or_else(missing)?;
+    let rest = rest.trim_start();
+    let end = match rest.as_bytes().first() {
+        Some(b'(') => rest.find(')').map(|i| i + 1),
+        Some(b'\'') => rest[1..].find('\'').map(|i| i + 2),
+        _ => rest.find([',', '}']),
+    }
+    .ok_or_else(missing)?;
+    Ok(rest[..end].trim())
+}
+
+/// Decode a `.npy` file
+fn parse_npy<T: BinaryScalar>(bytes: &[u8]) -> Result<(Vec<usize>, Vec<T>), SphrsError> {
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(SphrsError::InvalidFormat(
+            "missing magic bytes of .npy file".to_string(),
+        ));
+    }
+    let (header_len, start): (usize, usize) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize,
+            12,
+        ),
+        v => return Err(SphrsError::UnsupportedVersion(v as u16)),
+    };
+    let data_start = start + header_len;
+    let header = bytes
+        .get(start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| SphrsError::InvalidFormat("truncated .npy header".to_string()))?;
+
+    let descr = header_value(header, "descr")?.trim_matches('\'');
+    let dtype = [Dtype::F32, Dtype::F64, Dtype::ComplexF32, Dtype::ComplexF64]
+        .into_iter()
+        .find(|d| d.npy_descr() == descr);
+    if dtype != Some(T::DTYPE) {
+        return Err(match dtype {
+            Some(found) => SphrsError::DtypeMismatch {
+                expected: T::DTYPE.name(),
+                found: found.name(),
+            },
+            None => SphrsError::InvalidFormat(format!("unsupported .npy type {descr}")),
+        });
+    }
+    if header_value(header, "fortran_order")? != "False" {
+        return Err(SphrsError::InvalidFormat(
+            "arrays in Fortran order are not supported".to_string(),
+        ));
+    }
+    let shape = header_value(header, "shape")?
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .map_err(|_| SphrsError::InvalidFormat(format!("invalid .npy shape entry {s}")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    let size = T::DTYPE.size();
+    let payload = shape
+        .iter()
+        .try_fold(size, |acc, &n| acc.checked_mul(n))
+        .and_then(|len| bytes.get(data_start..data_start.checked_add(len)?))
+        .ok_or_else(|| SphrsError::InvalidFormat("truncated .npy data".to_string()))?;
+    let data = payload.chunks_exact(size).map(T::read_le).collect();
+    Ok((shape, data))
+}
+
+impl<T> SHCoefficients<T>
+where
+    T: BinaryScalar,
+{
+    /// Write the coefficients as one-dimensional array in the NumPy `.npy` format to `writer`
+    ///
+    /// The coefficients are ordered by degree and order. The format stores neither the degree,
+    /// which follows from the length, nor the normalization convention.
+    pub fn write_npy<W: Write>(&self, writer: W) -> Result<(), SphrsError> {
+        write_npy(writer, &[self.len()], self.as_slice())
+    }
+
+    /// Read coefficients ordered by degree and order from a one-dimensional array in the NumPy
+    /// `.npy` format from `reader`
+    ///
+    /// The length of the array has to be (L + 1)^2 for a degree L. The normalization convention
+    /// is the default one, see [`with_normalization`](`SHCoefficients::with_normalization`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::SHCoefficients;
+    ///
+    /// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// let mut file = Vec::new();
+    /// coeffs.write_npy(&mut file).unwrap();
+    /// assert_eq!(SHCoefficients::read_npy(file.as_slice()).unwrap(), coeffs);
+    /// ```
+    pub fn read_npy<R: Read>(reader: R) -> Result<Self, SphrsError> {
+        let (shape, data) = read_npy::<T, R>(reader)?;
+        coefficients_from_array(&shape, data)
+    }
+}
+
+/// Coefficients from a one-dimensional array of length (L + 1)^2
+fn coefficients_from_array<T>(
+    shape: &[usize],
+    data: Vec<T>,
+) -> Result<SHCoefficients<T>, SphrsError> {
+    if shape.len() != 1 {
+        return Err(SphrsError::InvalidFormat(format!(
+            "expected a one-dimensional array of coefficients, found shape {shape:?}"
+        )));
+    }
+    let degree = (data.len() as f64).sqrt() as usize;
+    let degree = (degree.saturating_sub(1)..=degree)
+        .find(|&d| num_coefficients(d) == data.len())
+        .ok_or_else(|| {
+            SphrsError::InvalidFormat(format!(
+                "{} coefficients are not (L + 1)^2 for a degree L",
+                data.len()
+            ))
+        })?;
+    Ok(SHCoefficients::from_vec(degree, data))
+}
+
+/// CRC-32 checksum of zip archives (polynomial 0xedb88320)
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Writer of NumPy `.npz` archives
+///
+/// Each array is added as `.npy` file named after the array to an uncompressed zip archive, like
+/// `numpy.savez` does. `numpy.load` returns the arrays by these names.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::io::{NpzArchive, NpzWriter};
+/// use sphrs::SHCoefficients;
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+/// let mut npz = NpzWriter::new(Vec::new());
+/// npz.add_coefficients("coeffs", &coeffs).unwrap();
+/// npz.add("grid", &[2, 3], &[0.0f32, 1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+/// let file = npz.finish().unwrap();
+///
+/// let archive = NpzArchive::parse(&file).unwrap();
+/// assert_eq!(archive.names().collect::<Vec<_>>(), ["coeffs", "grid"]);
+/// assert_eq!(archive.coefficients::<f64>("coeffs").unwrap(), coeffs);
+/// assert_eq!(archive.array::<f32>("grid").unwrap().0, [2, 3]);
+/// ```
+#[derive(Debug)]
+pub struct NpzWriter<W> {
+    /// Destination of the archive
+    writer: W,
+    /// Bytes written so far
+    offset: usize,
+    /// Central directory records of the files written so far
+    directory: Vec<u8>,
+    /// Number of files written so far
+    entries: usize,
+}
+
+impl<W: Write> NpzWriter<W> {
+    /// Create an archive writing to `writer`
+    pub fn new(writer: W) -> Self {
+        NpzWriter {
+            writer,
+            offset: 0,
+            directory: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// Add the array `data` of shape `shape` named `name`, see [`write_npy`]
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if the archive would exceed the 4 GiB and 65535 files
+    /// of zip archives without the Zip64 extension.
+    pub fn add<T: BinaryScalar>(
+        &mut self,
+        name: &str,
+        shape: &[usize],
+        data: &[T],
+    ) -> Result<(), SphrsError> {
+        let file = npy_bytes(shape, data)?;
+        let name = format!("{name}.npy");
+        let too_large = || SphrsError::InvalidInput("npz archive exceeds 4 GiB".to_string());
+        let size = u32::try_from(file.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| SphrsError::InvalidInput(format!("array name {name} too long")))?;
+        if self.entries == u16::MAX as usize {
+            return Err(SphrsError::InvalidInput(
+                "npz archive exceeds 65535 arrays".to_string(),
+            ));
+        }
+        let crc = crc32(&file);
+
+        // Version 2.0, no flags, stored, no time, checksum and sizes
+        let common = |out: &mut Vec<u8>| {
+            for v in [20u16, 0, 0, 0, ZIP_DATE] {
+                out.extend(v.to_le_bytes());
+            }
+            for v in [crc, size, size] {
+                out.extend(v.to_le_bytes());
+            }
+            out.extend(name_len.to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+        };
+        let mut local = LOCAL_HEADER.to_le_bytes().to_vec();
+        common(&mut local);
+        local.extend(name.as_bytes());
+
+        self.directory.extend(CENTRAL_HEADER.to_le_bytes());
+        // Made by version 2.0
+        self.directory.extend(20u16.to_le_bytes());
+        common(&mut self.directory);
+        // No comment, disk 0, no attributes
+        self.directory.extend([0u8; 8]);
+        self.directory.extend([0u8; 2]);
+        self.directory.extend(offset.to_le_bytes());
+        self.directory.extend(name.as_bytes());
+
+        self.writer.write_all(&local)?;
+        self.writer.write_all(&file)?;
+        self.offset += local.len() + file.len();
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Add the coefficients `coeffs` named `name`, see
+    /// [`SHCoefficients::write_npy`](`crate::SHCoefficients::write_npy`)
+    pub fn add_coefficients<T: BinaryScalar>(
+        &mut self,
+        name: &str,
+        coeffs: &SHCoefficients<T>,
+    ) -> Result<(), SphrsError> {
+        self.add(name, &[coeffs.len()], coeffs.as_slice())
+    }
+
+    /// Write the directory of the archive and return the writer
+    pub fn finish(mut self) -> Result<W, SphrsError> {
+        let too_large = || SphrsError::InvalidInput("npz archive exceeds 4 GiB".to_string());
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        let size = u32::try_from(self.directory.len()).map_err(|_| too_large())?;
+        let mut end = END_OF_CENTRAL_DIRECTORY.to_le_bytes().to_vec();
+        // Disk 0, directory on disk 0
+        end.extend([0u8; 4]);
+        for _ in 0..2 {
+            end.extend((self.entries as u16).to_le_bytes());
+        }
+        end.extend(size.to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        // No comment
+        end.extend([0u8; 2]);
+        self.writer.write_all(&self.directory)?;
+        self.writer.write_all(&end)?;
+        Ok(self.writer)
+    }
+}
+
+/// Read access to the arrays of a NumPy `.npz` archive
+///
+/// Only uncompressed archives as written by `numpy.savez` are supported, not those of
+/// `numpy.savez_compressed`. The files of the archive are located when it is parsed, the arrays
+/// are decoded on access.
+#[derive(Clone, Debug)]
+pub struct NpzArchive<'a> {
+    /// Names of the arrays (without the `.npy` extension) and their `.npy` files
+    files: Vec<(String, &'a [u8])>,
+}
+
+impl<'a> NpzArchive<'a> {
+    /// Locate the arrays of the archive `bytes`
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SphrsError> {
+        let invalid = |msg: &str| SphrsError::InvalidFormat(format!("invalid npz archive: {msg}"));
+        let u16_at = |pos: usize| -> Result<usize, SphrsError> {
+            let b = bytes
+                .get(pos..pos + 2)
+                .ok_or_else(|| invalid("truncated"))?;
+            Ok(u16::from_le_bytes([b[0], b[1]]) as usize)
+        };
+        let u32_at = |pos: usize| -> Result<u32, SphrsError> {
+            let b = bytes
+                .get(pos..pos + 4)
+                .ok_or_else(|| invalid("truncated"))?;
+            Ok(u32::from_le_bytes(b.try_into().unwrap()))
+        };
+
+        // The end of central directory record is followed by a comment of up to 65535 bytes
+        let end = (0..bytes.len().saturating_sub(21))
+            .rev()
+            .take(u16::MAX as usize + 1)
+            .find(|&pos| u32_at(pos).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or_else(|| invalid("missing end of central directory"))?;
+        let entries = u16_at(end + 10)?;
+        let mut pos = u32_at(end + 16)? as usize;
+
+        let mut files = Vec::with_capacity(entries);
+        for _ in 0..entries {
+            if u32_at(pos)? != CENTRAL_HEADER {
+                return Err(invalid("corrupt central directory"));
+            }
+            let method = u16_at(pos + 10)?;
+            let crc = u32_at(pos + 16)?;
+            let size = u32_at(pos + 20)? as usize;
+            let name_len = u16_at(pos + 28)?;
+            let skip = name_len + u16_at(pos + 30)? + u16_at(pos + 32)?;
+            let local = u32_at(pos + 42)? as usize;
+            let name = bytes
+                .get(pos + 46..pos + 46 + name_len)
+                .ok_or_else(|| invalid("truncated"))?;
+            let name = String::from_utf8_lossy(name);
+            pos += 46 + skip;
+            if method != 0 {
+                return Err(SphrsError::InvalidFormat(format!(
+                    "{name} is compressed, only uncompressed npz archives (numpy.savez) are \
+                     supported"
+                )));
+            }
+
+            if u32_at(local)? != LOCAL_HEADER {
+                return Err(invalid("corrupt local header"));
+            }
+            let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+            let file = bytes
+                .get(start..start + size)
+                .ok_or_else(|| invalid("truncated"))?;
+            if crc32(file) != crc {
+                return Err(invalid(&format!("checksum mismatch of {name}")));
+            }
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            files.push((name, file));
+        }
+        Ok(NpzArchive { files })
+    }
+
+    /// Returns the names of the arrays in the order of the archive
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Decode the array `name`, see [`read_npy`]
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if there is no array of this name.
+    pub fn array<T: BinaryScalar>(&self, name: &str) -> Result<(Vec<usize>, Vec<T>), SphrsError> {
+        let file = self
+            .files
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, file)| *file)
+            .ok_or_else(|| SphrsError::InvalidInput(format!("no array {name} in npz archive")))?;
+        parse_npy(file)
+    }
+
+    /// Decode the coefficients `name`, see
+    /// [`SHCoefficients::read_npy`](`crate::SHCoefficients::read_npy`)
+    pub fn coefficients<T: BinaryScalar>(
+        &self,
+        name: &str,
+    ) -> Result<SHCoefficients<T>, SphrsError> {
+        let (shape, data) = self.array(name)?;
+        coefficients_from_array(&shape, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex;
+
+    #[test]
+    fn npy_layout() {
+        // Same bytes as numpy.save of numpy.arange(3.0)
+        let mut file = Vec::new();
+        write_npy(&mut file, &[3], &[0.0f64, 1.0, 2.0]).unwrap();
+        assert_eq!(file.len(), 128 + 24);
+        assert_eq!(&file[..10], b"\x93NUMPY\x01\x00\x76\x00");
+        let header = std::str::from_utf8(&file[10..128]).unwrap();
+        assert_eq!(
+            header.trim_end(),
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (3,), }"
+        );
+        assert!(header.ends_with(" \n"));
+        assert_eq!(&file[128 + 8..128 + 16], &1.0f64.to_le_bytes());
+    }
+
+    #[test]
+    fn npy_roundtrip() {
+        let data: Vec<Complex<f32>> = (0..24).map(|i| Complex::new(i as f32, -1.0)).collect();
+        let mut file = Vec::new();
+        write_npy(&mut file, &[2, 3, 4], &data).unwrap();
+        assert_eq!(file.len() % NPY_ALIGN, 24 * 8 % NPY_ALIGN);
+        let (shape, read) = read_npy::<Complex<f32>, _>(file.as_slice()).unwrap();
+        assert_eq!(shape, [2, 3, 4]);
+        assert_eq!(read, data);
+
+        // Scalars have the empty shape
+        let mut file = Vec::new();
+        write_npy(&mut file, &[], &[2.5f64]).unwrap();
+        assert_eq!(
+            read_npy::<f64, _>(file.as_slice()).unwrap(),
+            (vec![], vec![2.5])
+        );
+    }
+
+    #[test]
+    fn npy_errors() {
+        assert!(matches!(
+            write_npy(Vec::new(), &[2, 2], &[1.0f64; 3]),
+            Err(SphrsError::LengthMismatch {
+                expected: 4,
+                found: 3
+            })
+        ));
+        let mut file = Vec::new();
+        write_npy(&mut file, &[5], &[1.0f32; 5]).unwrap();
+        assert!(matches!(
+            read_npy::<f64, _>(file.as_slice()),
+            Err(SphrsError::DtypeMismatch { .. })
+        ));
+        // Not a square number of coefficients
+        assert!(matches!(
+            SHCoefficients::<f32>::read_npy(file.as_slice()),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            read_npy::<f32, _>(&file[..file.len() - 1]),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        let mut fortran = file.clone();
+        let pos = fortran.windows(5).position(|w| w == b"False").unwrap();
+        fortran[pos..pos + 5].copy_from_slice(b"True ");
+        assert!(matches!(
+            read_npy::<f32, _>(fortran.as_slice()),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn npz_roundtrip() {
+        let coeffs =
+            SHCoefficients::from_vec(2, (0..9).map(|i| Complex::new(i as f64, 1.0)).collect());
+        let grid: Vec<f32> = (0..12).map(|i| i as f32 * 0.5).collect();
+        let mut npz = NpzWriter::new(Vec::new());
+        npz.add_coefficients("alm", &coeffs).unwrap();
+        npz.add("values", &[3, 4], &grid).unwrap();
+        let file = npz.finish().unwrap();
+
+        let archive = NpzArchive::parse(&file).unwrap();
+        assert_eq!(archive.names().collect::<Vec<_>>(), ["alm", "values"]);
+        assert_eq!(archive.coefficients::<Complex<f64>>("alm").unwrap(), coeffs);
+        assert_eq!(archive.array::<f32>("values").unwrap(), (vec![3, 4], grid));
+        assert!(matches!(
+            archive.array::<f32>("missing"),
+            Err(SphrsError::InvalidInput(_))
+        ));
+
+        // Corrupt data
+        let mut wrong = file.clone();
+        wrong[100] ^= 1;
+        assert!(matches!(
+            NpzArchive::parse(&wrong),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+        // Compressed file
+        let mut wrong = file;
+        wrong[8] = 8;
+        assert!(NpzArchive::parse(&wrong).is_ok());
+        let directory = wrong.len() - 22 - 2 * 46 - "alm.npy".len() - "values.npy".len();
+        wrong[directory + 10] = 8;
+        assert!(matches!(
+            NpzArchive::parse(&wrong),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::io::Write;
+
+use num_complex::Complex;
+
+use crate::basis::{HarmonicsSet, SHEval};
+use crate::coefficients::sh_degree_order;
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Scalar types which can be exported as columns of CSV and JSON tables
+pub trait TableScalar {
+    /// Names of the columns of the value, `value` for real and `real` and `imag` for complex
+    /// values
+    const COLUMNS: &'static [&'static str];
+
+    /// Append the formatted columns of the value to `out`
+    ///
+    /// The values are formatted as the shortest decimal representation which reads back to the
+    /// same number, with `NaN`, `inf` and `-inf` for non-finite values.
+    fn push_columns(&self, out: &mut Vec<String>);
+}
+
+impl TableScalar for f32 {
+    const COLUMNS: &'static [&'static str] = &["value"];
+
+    fn push_columns(&self, out: &mut Vec<String>) {
+        out.push(format!("{self:?}"));
+    }
+}
+
+impl TableScalar for f64 {
+    const COLUMNS: &'static [&'static str] = &["value"];
+
+    fn push_columns(&self, out: &mut Vec<String>) {
+        out.push(format!("{self:?}"));
+    }
+}
+
+impl TableScalar for Complex<f32> {
+    const COLUMNS: &'static [&'static str] = &["real", "imag"];
+
+    fn push_columns(&self, out: &mut Vec<String>) {
+        self.re.push_columns(out);
+        self.im.push_columns(out);
+    }
+}
+
+impl TableScalar for Complex<f64> {
+    const COLUMNS: &'static [&'static str] = &["real", "imag"];
+
+    fn push_columns(&self, out: &mut Vec<String>) {
+        self.re.push_columns(out);
+        self.im.push_columns(out);
+    }
+}
+
+/// Writer of the rows of a table with the columns `columns` as CSV or JSON
+struct TableWriter<W> {
+    writer: W,
+    columns: Vec<&'static str>,
+    json: bool,
+    rows: usize,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// Start the table, with the header line of CSV or the opening bracket of the JSON array
+    fn new(mut writer: W, columns: Vec<&'static str>, json: bool) -> Result<Self, SphrsError> {
+        if json {
+            writer.write_all(b"[")?;
+        } else {
+            writeln!(writer, "{}", columns.join(","))?;
+        }
+        Ok(TableWriter {
+            writer,
+            columns,
+            json,
+            rows: 0,
+        })
+    }
+
+    /// Write a row of formatted values, one per column
+    fn row(&mut self, values: &[String]) -> Result<(), SphrsError> {
+        debug_assert_eq!(values.len(), self.columns.len());
+        if self.json {
+            let separator = if self.rows == 0 { "\n  " } else { ",\n  " };
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .zip(values)
+                .map(|(c, v)| format!("\"{c}\": {}", json_number(v)))
+                .collect();
+            write!(self.writer, "{separator}{{{}}}", fields.join(", "))?;
+        } else {
+            writeln!(self.writer, "{}", values.join(","))?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Finish the table and return the writer
+    fn finish(mut self) -> Result<W, SphrsError> {
+        if self.json {
+            let end = if self.rows == 0 { "]" } else { "\n]" };
+            self.writer.write_all(end.as_bytes())?;
+        }
+        Ok(self.writer)
+    }
+}
+
+/// `value` as JSON number, `null` for the non-finite values JSON cannot represent
+fn json_number(value: &str) -> &str {
+    if value.parse::<f64>().is_ok_and(f64::is_finite) {
+        value
+    } else {
+        "null"
+    }
+}
+
+/// Write the coefficients `coeffs` as table with the columns `degree`, `order` and those of the
+/// value
+fn write_coefficients<T, W>(
+    coeffs: &SHCoefficients<T>,
+    writer: W,
+    json: bool,
+) -> Result<W, SphrsError>
+where
+    T: TableScalar,
+    W: Write,
+{
+    let columns = ["degree", "order"]
+        .iter()
+        .chain(T::COLUMNS)
+        .copied()
+        .collect();
+    let mut table = TableWriter::new(writer, columns, json)?;
+    let mut values = Vec::new();
+    for (i, c) in coeffs.as_slice().iter().enumerate() {
+        let (l, m) = sh_degree_order(i);
+        values.clear();
+        values.extend([l.to_string(), m.to_string()]);
+        c.push_columns(&mut values);
+        table.row(&values)?;
+    }
+    table.finish()
+}
+
+impl<T> SHCoefficients<T>
+where
+    T: TableScalar,
+{
+    /// Write the coefficients as CSV table to `writer`
+    ///
+    /// The table has a header line and one row per coefficient, ordered by degree and order, with
+    /// the columns `degree`, `order` and `value` for real or `real` and `imag` for complex
+    /// coefficients (see [`TableScalar`](`crate::io::TableScalar`)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::SHCoefficients;
+    ///
+    /// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, -0.25, 2e-20]);
+    /// let mut csv = Vec::new();
+    /// coeffs.write_csv(&mut csv).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "degree,order,value\n0,0,1.0\n1,-1,0.5\n1,0,-0.25\n1,1,2e-20\n"
+    /// );
+    /// ```
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), SphrsError> {
+        write_coefficients(self, writer, false)?;
+        Ok(())
+    }
+
+    /// Write the coefficients as JSON object to `writer`
+    ///
+    /// The object holds the maximum `degree`, the `normalization` convention and the
+    /// `coefficients` as array of objects with the keys of the columns of
+    /// [`write_csv`](`SHCoefficients::write_csv`). Non-finite values are written as `null`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::SHCoefficients;
+    ///
+    /// let coeffs = SHCoefficients::from_vec(0, vec![num_complex::Complex::new(1.0f64, -0.5)]);
+    /// let mut json = Vec::new();
+    /// coeffs.write_json(&mut json).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(json).unwrap(),
+    ///     "{\"degree\": 0, \"normalization\": \"orthonormal\", \"coefficients\": [\n  \
+    ///      {\"degree\": 0, \"order\": 0, \"real\": 1.0, \"imag\": -0.5}\n]}\n"
+    /// );
+    /// ```
+    pub fn write_json<W: Write>(&self, mut writer: W) -> Result<(), SphrsError> {
+        write!(
+            writer,
+            "{{\"degree\": {}, \"normalization\": \"{}\", \"coefficients\": ",
+            self.degree(),
+            self.normalization()
+        )?;
+        let mut writer = write_coefficients(self, writer, true)?;
+        writer.write_all(b"}\n")?;
+        Ok(())
+    }
+}
+
+/// Write the harmonics of `sh` evaluated at `points` as table with one row per point and
+/// harmonic
+fn write_evaluations<T, E, C, W>(
+    sh: &HarmonicsSet<T, E>,
+    points: &[C],
+    writer: W,
+    json: bool,
+) -> Result<W, SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: TableScalar,
+    C: SHCoordinates<T>,
+    W: Write,
+{
+    let columns = ["point", "r", "theta", "phi", "degree", "order"]
+        .iter()
+        .chain(E::Output::COLUMNS)
+        .copied()
+        .collect();
+    let mut table = TableWriter::new(writer, columns, json)?;
+    let mut values = Vec::new();
+    let format = |x: T| format!("{x:?}");
+    for (i, p) in points.iter().enumerate() {
+        let position = [
+            i.to_string(),
+            format(p.r()),
+            format(p.theta()),
+            format(p.phi()),
+        ];
+        for ((l, m), y) in sh.harmonics().zip(sh.eval(p)) {
+            values.clear();
+            values.extend(position.iter().cloned());
+            values.extend([l.to_string(), m.to_string()]);
+            y.push_columns(&mut values);
+            table.row(&values)?;
+        }
+    }
+    table.finish()
+}
+
+/// Write the harmonics of `sh` evaluated at `points` as CSV table to `writer`
+///
+/// The table has a header line and one row per point and harmonic with the columns `point` (the
+/// index of the point), `r`, `theta` and `phi` (its spherical coordinates), `degree`, `order` and
+/// those of the value (see [`TableScalar`]). Such long tables are read directly by spreadsheets
+/// and plotting tools, e.g. with `pandas.read_csv`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::io::write_evaluations_csv;
+/// use sphrs::Coordinates;
+///
+/// let sh = HarmonicsSet::new(1, RealSH::Spherical);
+/// let points = [Coordinates::spherical(1.0f64, 0.0, 0.0)];
+/// let mut csv = Vec::new();
+/// write_evaluations_csv(&mut csv, &sh, &points).unwrap();
+/// let csv = String::from_utf8(csv).unwrap();
+/// let mut lines = csv.lines();
+/// assert_eq!(lines.next(), Some("point,r,theta,phi,degree,order,value"));
+/// assert!(lines.next().unwrap().starts_with("0,1.0,0.0,0.0,0,0,0.28209479177387"));
+/// assert_eq!(lines.count(), 3);
+/// ```
+pub fn write_evaluations_csv<T, E, C, W>(
+    writer: W,
+    sh: &HarmonicsSet<T, E>,
+    points: &[C],
+) -> Result<(), SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: TableScalar,
+    C: SHCoordinates<T>,
+    W: Write,
+{
+    write_evaluations(sh, points, writer, false)?;
+    Ok(())
+}
+
+/// Write the harmonics of `sh` evaluated at `points` as JSON array to `writer`
+///
+/// The array holds one object per point and harmonic with the keys of the columns of
+/// [`write_evaluations_csv`]. Non-finite values are written as `null`.
+pub fn write_evaluations_json<T, E, C, W>(
+    writer: W,
+    sh: &HarmonicsSet<T, E>,
+    points: &[C],
+) -> Result<(), SphrsError>
+where
+    T: SphrsFloat,
+    E: SHEval<T>,
+    E::Output: TableScalar,
+    C: SHCoordinates<T>,
+    W: Write,
+{
+    let mut writer = write_evaluations(sh, points, writer, true)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{ComplexSH, RealSH};
+    use crate::{Coordinates, Normalization};
+
+    #[test]
+    fn coefficients_json_is_valid() {
+        let coeffs = SHCoefficients::from_vec(2, (0..9).map(|i| i as f32 * 0.1).collect())
+            .with_normalization(Normalization::Schmidt);
+        let mut json = Vec::new();
+        coeffs.write_json(&mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["degree"], 2);
+        assert_eq!(value["normalization"], "schmidt");
+        let rows = value["coefficients"].as_array().unwrap();
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[5]["degree"], 2);
+        assert_eq!(rows[5]["order"], -1);
+        assert_eq!(rows[5]["value"].as_f64().unwrap() as f32, 0.5);
+    }
+
+    #[test]
+    fn evaluations() {
+        let sh = HarmonicsSet::new(2, ComplexSH::Spherical);
+        let points = [
+            Coordinates::spherical(1.0f64, 0.3, 0.2),
+            Coordinates::spherical(2.0, 1.3, -0.7),
+        ];
+        let mut csv = Vec::new();
+        write_evaluations_csv(&mut csv, &sh, &points).unwrap();
+        let mut rdr = csv::Reader::from_reader(csv.as_slice());
+        assert_eq!(
+            rdr.headers().unwrap(),
+            vec!["point", "r", "theta", "phi", "degree", "order", "real", "imag"]
+        );
+        let rows: Vec<csv::StringRecord> = rdr.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 18);
+        let values = sh.eval(&points[1]);
+        for (k, (row, y)) in rows[9..].iter().zip(values).enumerate() {
+            assert_eq!(&row[0], "1");
+            assert_eq!(row[1].parse::<f64>().unwrap(), 2.0);
+            let l: i64 = row[4].parse().unwrap();
+            let m: i64 = row[5].parse().unwrap();
+            assert_eq!(sh.index(l, m), Some(k));
+            assert_eq!(row[6].parse::<f64>().unwrap(), y.re);
+            assert_eq!(row[7].parse::<f64>().unwrap(), y.im);
+        }
+
+        let mut json = Vec::new();
+        let sh = HarmonicsSet::new(1, RealSH::Spherical);
+        write_evaluations_json(&mut json, &sh, &points).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let rows = value.as_array().unwrap();
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[7]["point"], 1);
+        assert_eq!(rows[7]["order"], 1);
+        assert_eq!(rows[7]["value"].as_f64().unwrap(), sh.eval(&points[1])[3]);
+    }
+
+    #[test]
+    fn non_finite_values() {
+        let coeffs = SHCoefficients::from_vec(0, vec![f64::NAN]);
+        let mut csv = Vec::new();
+        coeffs.write_csv(&mut csv).unwrap();
+        assert_eq!(csv, b"degree,order,value\n0,0,NaN\n");
+        let mut json = Vec::new();
+        coeffs.write_json(&mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert!(value["coefficients"][0]["value"].is_null());
+
+        let mut json = Vec::new();
+        let sh = HarmonicsSet::new(1, RealSH::Spherical);
+        write_evaluations_json(&mut json, &sh, &[] as &[Coordinates<f64>]).unwrap();
+        assert_eq!(json, b"[]\n");
+    }
+}
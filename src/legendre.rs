@@ -0,0 +1,671 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Associated Legendre functions
+//!
+//! The associated Legendre functions P_l^m(x) of this module include the Condon-Shortley phase
+//! (-1)^m, as do the spherical harmonics of this crate and e.g. SciPy's `lpmv`. Multiply by
+//! (-1)^m to obtain the functions without the phase, as commonly used in geodesy.
+//!
+//! * [`legendre_p_all`]: Legendre polynomials P_0(x), ..., P_lmax(x)
+//! * [`plm`]: unnormalized associated Legendre function P_l^m(x)
+//! * [`plm_bar`]: fully normalized (4 pi normalized) associated Legendre function
+//! * [`plm_bar_table`]: all fully normalized functions up to a given degree, indexed by [`index`]
+//! * [`plm_bar_dtheta_table`] and [`plm_bar_dx_table`]: the same and their derivatives with
+//!   respect to theta or x
+//!
+//! The unnormalized functions grow like (2 m - 1)!! and overflow `f64` for orders beyond about
+//! 150. The fully normalized functions are computed with recurrences of normalized quantities,
+//! which are stable for high degrees; their range is only limited by the underflow of
+//! sin(theta)^m for orders of a few thousand.
+
+use std::array::from_fn;
+
+use crate::SphrsFloat;
+
+/// Normalization factor sqrt((2l + 1) / (4 pi)) of the zonal harmonic Y_l0
+#[inline(always)]
+pub(crate) fn zonal_norm<T: SphrsFloat>(l: usize) -> T {
+    (T::from_usize(2 * l + 1).unwrap() / (T::from_f64(4.0).unwrap() * T::PI())).sqrt()
+}
+
+/// Legendre polynomials P_0(x), ..., P_lmax(x) via the three-term recurrence
+///
+/// Bonnet's recurrence l P_l(x) = (2 l - 1) x P_{l-1}(x) - (l - 1) P_{l-2}(x) is stable in the
+/// forward direction for -1 <= x <= 1. The Legendre polynomials are the zonal kernels of the
+/// addition theorem: the sum of Y_lm(p) conj(Y_lm(q)) over all orders m equals
+/// (2 l + 1) / (4 pi) P_l(cos gamma), where gamma is the angle between p and q.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::legendre_p_all;
+///
+/// let p = legendre_p_all(3, 0.5f64);
+/// assert_eq!(p.len(), 4);
+/// assert_eq!(p[..3], [1.0, 0.5, -0.125]);
+/// assert!((p[3] + 0.4375).abs() < 1e-15);
+/// ```
+pub fn legendre_p_all<T: SphrsFloat>(lmax: usize, x: T) -> Vec<T> {
+    let mut p = Vec::with_capacity(lmax + 1);
+    p.push(T::one());
+    if lmax >= 1 {
+        p.push(x);
+    }
+    for l in 2..=lmax {
+        let lf = T::from_usize(l).unwrap();
+        let next = ((lf + lf - T::one()) * x * p[l - 1] - (lf - T::one()) * p[l - 2]) / lf;
+        p.push(next);
+    }
+    p
+}
+
+/// Orthonormalized associated Legendre function K_lm P_l^m(x) (including the Condon-Shortley
+/// phase) for 0 <= m <= l, where K_lm is the normalization factor of the spherical harmonics
+///
+/// `x = cos(theta)` and `s = sin(theta)` are passed separately because computing `s` from `x`
+/// loses precision close to the poles. The normalization is folded into the recurrences instead
+/// of multiplying with K_lm at the end, which avoids the overflow of the factorials in K_lm and of
+/// P_l^m itself for high degrees.
+pub(crate) fn normalized_legendre<T: SphrsFloat>(l: usize, m: usize, x: T, s: T) -> T {
+    debug_assert!(m <= l);
+    let f = |v: usize| T::from_usize(v).unwrap();
+    let mut pmm = (T::one() / (f(4) * T::PI())).sqrt();
+    for k in 1..=m {
+        pmm = -(f(2 * k + 1) / f(2 * k)).sqrt() * s * pmm;
+    }
+    if l == m {
+        return pmm;
+    }
+    let mut prev = pmm;
+    let mut cur = f(2 * m + 3).sqrt() * x * pmm;
+    for ll in (m + 2)..=l {
+        let a = (f(4 * ll * ll - 1) / f(ll * ll - m * m)).sqrt();
+        let b = (f((ll - 1) * (ll - 1) - m * m) / f(4 * (ll - 1) * (ll - 1) - 1)).sqrt();
+        let next = a * (x * cur - b * prev);
+        prev = cur;
+        cur = next;
+    }
+    cur
+}
+
+/// Orthonormalized associated Legendre function as [`normalized_legendre`] and its derivative
+/// with respect to theta
+///
+/// For m >= 1 the recurrence is applied to P_lm / sin(theta), which keeps the derivative accurate
+/// at the poles.
+pub(crate) fn normalized_legendre_dtheta<T: SphrsFloat>(l: usize, m: usize, x: T, s: T) -> (T, T) {
+    debug_assert!(m <= l);
+    let f = |v: usize| T::from_usize(v).unwrap();
+    if m == 0 {
+        let p = normalized_legendre(l, 0, x, s);
+        if l == 0 {
+            return (p, T::zero());
+        }
+        return (p, f(l * (l + 1)).sqrt() * normalized_legendre(l, 1, x, s));
+    }
+    // P_mm / sin(theta)
+    let mut pmm = (T::one() / (f(4) * T::PI())).sqrt();
+    for k in 1..=m {
+        let c = -(f(2 * k + 1) / f(2 * k)).sqrt();
+        pmm = if k == 1 { c * pmm } else { c * s * pmm };
+    }
+    let mut prev = T::zero();
+    let mut cur = pmm;
+    for ll in (m + 1)..=l {
+        let next = if ll == m + 1 {
+            f(2 * m + 3).sqrt() * x * cur
+        } else {
+            let a = (f(4 * ll * ll - 1) / f(ll * ll - m * m)).sqrt();
+            let b = (f((ll - 1) * (ll - 1) - m * m) / f(4 * (ll - 1) * (ll - 1) - 1)).sqrt();
+            a * (x * cur - b * prev)
+        };
+        prev = cur;
+        cur = next;
+    }
+    let lower = (f((2 * l + 1) * (l * l - m * m)) / f(2 * l - 1)).sqrt() * prev;
+    (s * cur, f(l) * x * cur - lower)
+}
+
+/// `sqrt(1 - x^2)`, computed as `sqrt((1 - x) (1 + x))` to keep the precision close to |x| = 1
+#[inline(always)]
+fn sin_from_cos<T: SphrsFloat>(x: T) -> T {
+    ((T::one() - x) * (T::one() + x)).sqrt()
+}
+
+/// Associated Legendre function P_l^m(x) for 0 <= m <= l and -1 <= x <= 1, including the
+/// Condon-Shortley phase
+///
+/// Computed with the standard recurrence in l from the sectoral function P_m^m(x) =
+/// (-1)^m (2 m - 1)!! (1 - x^2)^(m/2).
+///
+/// # Panics
+///
+/// Panics if m > l.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::plm;
+///
+/// let x = 0.3f64;
+/// assert!((plm(2, 1, x) + 3.0 * x * (1.0 - x * x).sqrt()).abs() < 1e-15);
+/// assert!((plm(3, 0, x) - 0.5 * (5.0 * x.powi(3) - 3.0 * x)).abs() < 1e-15);
+/// ```
+pub fn plm<T: SphrsFloat>(l: usize, m: usize, x: T) -> T {
+    assert!(m <= l, "order {m} exceeds degree {l}");
+    let f = |v: usize| T::from_usize(v).unwrap();
+    let s = sin_from_cos(x);
+    let mut pmm = T::one();
+    for k in 1..=m {
+        pmm = -f(2 * k - 1) * s * pmm;
+    }
+    if l == m {
+        return pmm;
+    }
+    let mut prev = pmm;
+    let mut cur = f(2 * m + 1) * x * pmm;
+    for ll in (m + 2)..=l {
+        let next = (f(2 * ll - 1) * x * cur - f(ll + m - 1) * prev) / f(ll - m);
+        prev = cur;
+        cur = next;
+    }
+    cur
+}
+
+/// Fully normalized associated Legendre function for 0 <= m <= l and -1 <= x <= 1, including
+/// the Condon-Shortley phase
+///
+/// The normalization sqrt((2 - delta_m0) (2 l + 1) (l - m)! / (l + m)!) P_l^m(x) is the one of
+/// geodesy and geomagnetism, for which the mean square of P_lm(cos theta) cos(m phi) over the
+/// sphere is one.
+///
+/// # Panics
+///
+/// Panics if m > l.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::{plm, plm_bar};
+///
+/// let x = 0.3f64;
+/// assert!((plm_bar(2, 1, x) - (2.0 * 5.0f64 / 6.0).sqrt() * plm(2, 1, x)).abs() < 1e-15);
+/// // No overflow for high degrees
+/// assert!(plm_bar(2000, 1000, x).is_finite());
+/// ```
+pub fn plm_bar<T: SphrsFloat>(l: usize, m: usize, x: T) -> T {
+    assert!(m <= l, "order {m} exceeds degree {l}");
+    normalized_legendre(l, m, x, sin_from_cos(x)) * full_norm(m)
+}
+
+/// Ratio of the full normalization and the normalization of [`normalized_legendre`]
+#[inline(always)]
+fn full_norm<T: SphrsFloat>(m: usize) -> T {
+    let norm = (T::from_f64(4.0).unwrap() * T::PI()).sqrt();
+    if m == 0 {
+        norm
+    } else {
+        norm * T::SQRT_2()
+    }
+}
+
+/// Fully normalized associated Legendre functions (see [`plm_bar`]) of all degrees l <= `lmax`
+/// and orders 0 <= m <= l at `x`
+///
+/// The function of degree l and order m is stored at [`index(l, m)`](`index`).
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::{index, plm_bar, plm_bar_table};
+///
+/// let table = plm_bar_table(10, 0.6f64);
+/// assert_eq!(table.len(), index(10, 10) + 1);
+/// assert!((table[index(7, 3)] - plm_bar(7, 3, 0.6)).abs() < 1e-13);
+/// ```
+pub fn plm_bar_table<T: SphrsFloat>(lmax: usize, x: T) -> Vec<T> {
+    let table = LegendreTable::new(lmax, x, sin_from_cos(x));
+    let mut p = table.p;
+    for l in 0..=lmax {
+        for m in 0..=l {
+            p[index(l, m)] = p[index(l, m)] * full_norm(m);
+        }
+    }
+    p
+}
+
+/// Fully normalized associated Legendre functions (see [`plm_bar`]) of all degrees l <= `lmax`
+/// at `x = cos(theta)` and their derivatives with respect to theta, 0 <= theta <= pi
+///
+/// Returns the values and the derivatives, both indexed by [`index`]. The derivatives are
+/// computed with recurrences of the normalized quantities, which are stable for high degrees and
+/// also at the poles.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::{index, plm_bar_dtheta_table};
+///
+/// // P_10(cos theta) = sqrt(3) cos(theta), hence d/dtheta = -sqrt(3) sin(theta)
+/// let theta = 0.4f64;
+/// let (p, dp) = plm_bar_dtheta_table(5, theta.cos());
+/// assert!((p[index(1, 0)] - 3f64.sqrt() * theta.cos()).abs() < 1e-15);
+/// assert!((dp[index(1, 0)] + 3f64.sqrt() * theta.sin()).abs() < 1e-15);
+/// ```
+pub fn plm_bar_dtheta_table<T: SphrsFloat>(lmax: usize, x: T) -> (Vec<T>, Vec<T>) {
+    let table = LegendreTable::new(lmax, x, sin_from_cos(x));
+    let (mut p, mut dp) = (table.p, table.dp);
+    for l in 0..=lmax {
+        for m in 0..=l {
+            let i = index(l, m);
+            p[i] = p[i] * full_norm(m);
+            dp[i] = dp[i] * full_norm(m);
+        }
+    }
+    (p, dp)
+}
+
+/// Fully normalized associated Legendre functions (see [`plm_bar`]) of all degrees l <= `lmax`
+/// at `x` and their derivatives with respect to x
+///
+/// Returns the values and the derivatives, both indexed by [`index`]. Inside the interval the
+/// derivatives are -1 / sin(theta) times those of [`plm_bar_dtheta_table`]. At x = +-1 the
+/// limits are used: they are finite for all orders except m = 1, whose derivatives are infinite.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::legendre::{index, plm_bar_dx_table};
+///
+/// // P_20(x) = sqrt(5) (3 x^2 - 1) / 2, hence d/dx = 3 sqrt(5) x
+/// for x in [0.3f64, 1.0] {
+///     let (_, dp) = plm_bar_dx_table(2, x);
+///     assert!((dp[index(2, 0)] - 3.0 * 5f64.sqrt() * x).abs() < 1e-14);
+/// }
+/// ```
+pub fn plm_bar_dx_table<T: SphrsFloat>(lmax: usize, x: T) -> (Vec<T>, Vec<T>) {
+    let s = sin_from_cos(x);
+    let (p, mut dp) = plm_bar_dtheta_table(lmax, x);
+    if s > T::zero() {
+        for v in dp.iter_mut() {
+            *v = -*v / s;
+        }
+        return (p, dp);
+    }
+    // Limits at the poles. The functions behave like (1 - x^2)^(m / 2) towards the poles, such
+    // that only the derivatives of the orders m <= 2 do not vanish.
+    let f = |v: usize| T::from_usize(v).unwrap();
+    let x = x.signum();
+    for v in dp.iter_mut() {
+        *v = T::zero();
+    }
+    for l in 1..=lmax {
+        let sign = x.powi(l as i32);
+        // P_l'(+-1) = (+-1)^(l + 1) l (l + 1) / 2
+        dp[index(l, 0)] = sign * x * f(2 * l + 1).sqrt() * f(l * (l + 1)) / f(2);
+        dp[index(l, 1)] = sign * T::infinity();
+        if l >= 2 {
+            dp[index(l, 2)] =
+                -sign * x * (f(2 * (2 * l + 1)) * f((l - 1) * l * (l + 1) * (l + 2))).sqrt() / f(4);
+        }
+    }
+    (p, dp)
+}
+
+/// Index of degree l and order m, 0 <= m <= l, in the tables of this module, `l (l + 1) / 2 + m`
+#[inline(always)]
+pub fn index(l: usize, m: usize) -> usize {
+    l * (l + 1) / 2 + m
+}
+
+/// Degree and order (l, m) of the entry with index `idx` in the tables of this module, the
+/// inverse of [`index`]
+pub fn degree_order(idx: usize) -> (usize, usize) {
+    let mut l = 0;
+    while index(l + 1, 0) <= idx {
+        l += 1;
+    }
+    (l, idx - index(l, 0))
+}
+
+/// Index of (l, m), 0 <= m <= l, in a [`LegendreTable`]
+#[inline(always)]
+pub(crate) fn table_index(l: usize, m: usize) -> usize {
+    index(l, m)
+}
+
+/// Orthonormalized associated Legendre functions [`normalized_legendre`] of all degrees up to
+/// `lmax` and the orders 0 <= m <= min(l, `mmax`) for `x = cos(theta)` and `s = sin(theta)`,
+/// stored at [`table_index`]
+///
+/// One pass of the recurrences of [`normalized_legendre`] computes the whole table with
+/// O(lmax mmax) operations and the same results. The entries of higher orders are zero.
+pub(crate) fn normalized_legendre_table_orders<T: SphrsFloat>(
+    lmax: usize,
+    mmax: usize,
+    x: T,
+    s: T,
+) -> Vec<T> {
+    let mut p = vec![T::zero(); table_index(lmax, lmax) + 1];
+    normalized_legendre_lanes(lmax, mmax, &[x], &[s], |l, m, v| {
+        p[table_index(l, m)] = v[0];
+    });
+    p
+}
+
+/// Recurrences of [`normalized_legendre`] for all degrees up to `lmax` and the orders
+/// 0 <= m <= min(l, `mmax`), evaluated for `LANES` colatitudes in lockstep
+///
+/// `emit(l, m, p)` receives the functions of all lanes order by order, starting with the sectoral
+/// function of each order and continuing with increasing degree. The coefficients of the
+/// recurrences are computed once for all lanes, and the lanes are updated by independent
+/// operations on fixed-size arrays, which the compiler can vectorize.
+pub(crate) fn normalized_legendre_lanes<T: SphrsFloat, const LANES: usize>(
+    lmax: usize,
+    mmax: usize,
+    x: &[T; LANES],
+    s: &[T; LANES],
+    mut emit: impl FnMut(usize, usize, &[T; LANES]),
+) {
+    let f = |v: usize| T::from_usize(v).unwrap();
+    let mut pmm = [(T::one() / (f(4) * T::PI())).sqrt(); LANES];
+    for m in 0..=mmax.min(lmax) {
+        // Sectoral terms
+        if m > 0 {
+            let c = -(f(2 * m + 1) / f(2 * m)).sqrt();
+            pmm = from_fn(|k| c * s[k] * pmm[k]);
+        }
+        emit(m, m, &pmm);
+        if m == lmax {
+            break;
+        }
+        // Three-term recurrence in l for fixed m
+        let c = f(2 * m + 3).sqrt();
+        let (mut prev, mut cur) = (pmm, from_fn(|k| c * x[k] * pmm[k]));
+        emit(m + 1, m, &cur);
+        for l in (m + 2)..=lmax {
+            let a = (f(4 * l * l - 1) / f(l * l - m * m)).sqrt();
+            let b = (f((l - 1) * (l - 1) - m * m) / f(4 * (l - 1) * (l - 1) - 1)).sqrt();
+            let next = from_fn(|k| a * (x[k] * cur[k] - b * prev[k]));
+            (prev, cur) = (cur, next);
+            emit(l, m, &cur);
+        }
+    }
+}
+
+/// Orthonormalized associated Legendre functions and their derivatives at a given polar angle
+///
+/// `p` holds K_lm P_l^m(cos theta) (including the Condon-Shortley phase) for 0 <= m <= l, where
+/// K_lm is the normalization factor of the spherical harmonics, such that Y_lm = p e^{i m phi}.
+/// `p_sin` holds `p / sin(theta)` for m >= 1 (zero for m = 0) and `dp` holds the derivative of `p`
+/// with respect to theta. All values are computed with recurrences of normalized quantities, which
+/// are stable for high degrees and at the poles.
+pub(crate) struct LegendreTable<T> {
+    pub(crate) p: Vec<T>,
+    pub(crate) p_sin: Vec<T>,
+    pub(crate) dp: Vec<T>,
+}
+
+impl<T: SphrsFloat> LegendreTable<T> {
+    /// Compute the table up to degree `lmax` for `x = cos(theta)` and `s = sin(theta)`
+    pub(crate) fn new(lmax: usize, x: T, s: T) -> Self {
+        Self::with_orders(lmax, lmax, x, s)
+    }
+
+    /// Compute the table up to degree `lmax` and order `mmax`
+    ///
+    /// As in [`normalized_legendre_table_orders`], entries of higher orders are not computed, with
+    /// the exception of `p` of order `mmax + 1`, which the derivatives of order 0 need.
+    pub(crate) fn with_orders(lmax: usize, mmax: usize, x: T, s: T) -> Self {
+        let n = table_index(lmax, lmax) + 1;
+        let mmax = mmax.min(lmax);
+        let p = normalized_legendre_table_orders(lmax, mmax + 1, x, s);
+        let mut p_sin = vec![T::zero(); n];
+        let mut dp = vec![T::zero(); n];
+        let f = |v: usize| T::from_usize(v).unwrap();
+
+        // Same recurrences as for p, started from P_mm / sin(theta)
+        for m in 1..=mmax {
+            let c = -(f(2 * m + 1) / f(2 * m)).sqrt();
+            let prev = table_index(m - 1, m - 1);
+            p_sin[table_index(m, m)] = if m == 1 {
+                c * p[prev]
+            } else {
+                c * s * p_sin[prev]
+            };
+        }
+        for m in 1..=mmax {
+            if m < lmax {
+                p_sin[table_index(m + 1, m)] = f(2 * m + 3).sqrt() * x * p_sin[table_index(m, m)];
+            }
+            for l in (m + 2)..=lmax {
+                let a = (f(4 * l * l - 1) / f(l * l - m * m)).sqrt();
+                let b = (f((l - 1) * (l - 1) - m * m) / f(4 * (l - 1) * (l - 1) - 1)).sqrt();
+                let (i, i1, i2) = (
+                    table_index(l, m),
+                    table_index(l - 1, m),
+                    table_index(l - 2, m),
+                );
+                p_sin[i] = a * (x * p_sin[i1] - b * p_sin[i2]);
+            }
+        }
+        // Derivatives with respect to theta
+        for l in 1..=lmax {
+            dp[table_index(l, 0)] = f(l * (l + 1)).sqrt() * p[table_index(l, 1)];
+            for m in 1..=l.min(mmax) {
+                let lower = if l > m {
+                    (f((2 * l + 1) * (l * l - m * m)) / f(2 * l - 1)).sqrt()
+                        * p_sin[table_index(l - 1, m)]
+                } else {
+                    T::zero()
+                };
+                dp[table_index(l, m)] = f(l) * x * p_sin[table_index(l, m)] - lower;
+            }
+        }
+        LegendreTable { p, p_sin, dp }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn index_round_trip() {
+        for l in 0..=30 {
+            for m in 0..=l {
+                assert_eq!(degree_order(index(l, m)), (l, m));
+            }
+        }
+    }
+
+    #[test]
+    fn legendre_polynomials() {
+        for &x in &[-1.0f64, -0.7, 0.0, 0.3, 1.0] {
+            let p = legendre_p_all(4, x);
+            assert_relative_eq!(p[0], 1.0);
+            assert_relative_eq!(p[1], x);
+            assert_relative_eq!(p[2], 0.5 * (3.0 * x * x - 1.0), epsilon = 1e-15);
+            assert_relative_eq!(p[3], 0.5 * (5.0 * x.powi(3) - 3.0 * x), epsilon = 1e-15);
+            assert_relative_eq!(
+                p[4],
+                (35.0 * x.powi(4) - 30.0 * x * x + 3.0) / 8.0,
+                epsilon = 1e-15
+            );
+        }
+    }
+
+    #[test]
+    fn legendre_polynomials_high_degree() {
+        let p = legendre_p_all(1000, 1.0f64);
+        assert!(p.iter().all(|&v| (v - 1.0).abs() < 1e-12));
+        let p = legendre_p_all(1000, -1.0f64);
+        for (l, v) in p.into_iter().enumerate() {
+            assert!((v - (-1f64).powi(l as i32)).abs() < 1e-12);
+        }
+        // Agrees with the zonal associated Legendre functions
+        let p = legendre_p_all(200, 0.37f64);
+        for l in [0, 5, 60, 200] {
+            assert_relative_eq!(p[l], plm(l, 0, 0.37), epsilon = 1e-13);
+        }
+        assert!(legendre_p_all(0, 0.3f64) == vec![1.0]);
+    }
+
+    #[test]
+    fn associated_legendre_functions() {
+        for &x in &[-1.0f64, -0.8, 0.0, 0.45, 0.99, 1.0] {
+            let s = (1.0 - x * x).sqrt();
+            assert_relative_eq!(plm(0, 0, x), 1.0);
+            assert_relative_eq!(plm(1, 1, x), -s, epsilon = 1e-15);
+            assert_relative_eq!(plm(2, 2, x), 3.0 * s * s, epsilon = 1e-14);
+            assert_relative_eq!(plm(3, 2, x), 15.0 * x * s * s, epsilon = 1e-14);
+            assert_relative_eq!(
+                plm(4, 1, x),
+                -2.5 * (7.0 * x.powi(3) - 3.0 * x) * s,
+                epsilon = 1e-14
+            );
+            // Consistent with the unnormalized functions
+            for l in 0..=12 {
+                for m in 0..=l {
+                    let ratio: f64 = (l - m + 1..=l + m).map(|k| k as f64).product();
+                    let norm = (if m == 0 { 1.0 } else { 2.0 } * (2 * l + 1) as f64 / ratio).sqrt();
+                    assert_relative_eq!(
+                        plm_bar(l, m, x),
+                        norm * plm(l, m, x),
+                        epsilon = 1e-12,
+                        max_relative = 1e-12
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fully_normalized_table() {
+        // Addition theorem: the sum of the squares of all orders is 2 l + 1
+        let lmax = 300;
+        for &x in &[-0.95f64, 0.1, 0.7] {
+            let table = plm_bar_table(lmax, x);
+            assert_eq!(table.len(), index(lmax, lmax) + 1);
+            for l in [0, 1, 17, 150, 300] {
+                let sum: f64 = (0..=l).map(|m| table[index(l, m)].powi(2)).sum();
+                assert_relative_eq!(sum, (2 * l + 1) as f64, max_relative = 1e-12);
+                assert_relative_eq!(table[index(l, l / 3)], plm_bar(l, l / 3, x));
+            }
+        }
+    }
+
+    #[test]
+    fn derivatives() {
+        let lmax = 30;
+        let h = 1e-6;
+        for &x in &[-0.9f64, -0.2, 0.5, 0.8] {
+            let (p, dx) = plm_bar_dx_table(lmax, x);
+            let (_, dtheta) = plm_bar_dtheta_table(lmax, x);
+            let (pp, _) = plm_bar_dx_table(lmax, x + h);
+            let (pm, _) = plm_bar_dx_table(lmax, x - h);
+            assert_eq!(p, plm_bar_table(lmax, x));
+            for l in 0..=lmax {
+                for m in 0..=l {
+                    let i = index(l, m);
+                    let fd = (pp[i] - pm[i]) / (2.0 * h);
+                    let scale = (1.0 + (l * l) as f64) * (2 * l + 1) as f64;
+                    assert!((fd - dx[i]).abs() < 1e-7 * scale, "{l} {m} {x}");
+                    assert_relative_eq!(
+                        -dtheta[i] / (1.0 - x * x).sqrt(),
+                        dx[i],
+                        epsilon = 1e-13,
+                        max_relative = 1e-13
+                    );
+                }
+            }
+        }
+
+        // Limits at the poles against one-sided differences
+        let h = 1e-7;
+        for x in [1.0f64, -1.0] {
+            let (p, dx) = plm_bar_dx_table(lmax, x);
+            let (ph, _) = plm_bar_dx_table(lmax, x - x * h);
+            for l in 0..=lmax {
+                for m in 0..=l {
+                    let i = index(l, m);
+                    if m == 1 {
+                        assert!(dx[i].is_infinite(), "{l} {m} {x}");
+                        continue;
+                    }
+                    if m > 2 {
+                        // Vanishes like (1 - x^2)^((m - 2) / 2), too slowly for differences
+                        assert_eq!(dx[i], 0.0);
+                        continue;
+                    }
+                    let fd = x * (p[i] - ph[i]) / h;
+                    let scale = (1.0 + (l * l * l * l) as f64) * (2 * l + 1) as f64;
+                    assert!(
+                        (fd - dx[i]).abs() < 1e-6 * scale,
+                        "{l} {m} {x} {fd} {}",
+                        dx[i]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_derivatives() {
+        for &theta in &[0.0f64, 0.3, 1.2, 2.9, std::f64::consts::PI] {
+            let (x, s) = (theta.cos(), theta.sin());
+            let t = LegendreTable::new(25, x, s);
+            for l in 0..=25 {
+                for m in 0..=l {
+                    let (p, dp) = normalized_legendre_dtheta(l, m, x, s);
+                    let i = table_index(l, m);
+                    assert_relative_eq!(p, t.p[i], epsilon = 1e-13);
+                    assert_relative_eq!(dp, t.dp[i], epsilon = 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normalized_table() {
+        let lmax = 40;
+        for &theta in &[0.0f64, 0.3, 1.2, std::f64::consts::FRAC_PI_2, 2.9] {
+            let (x, s) = (theta.cos(), theta.sin());
+            let t = LegendreTable::new(lmax, x, s);
+            let h = 1e-6;
+            let tp = LegendreTable::new(lmax, (theta + h).cos(), (theta + h).sin());
+            let tm = LegendreTable::new(lmax, (theta - h).cos(), (theta - h).sin());
+            for l in 0..=lmax {
+                // Unsoeld's theorem for the values and the gradients
+                let mut sum = t.p[table_index(l, 0)].powi(2);
+                let mut grad = t.dp[table_index(l, 0)].powi(2);
+                for m in 1..=l {
+                    let i = table_index(l, m);
+                    sum += 2.0 * t.p[i].powi(2);
+                    grad += 2.0 * (t.dp[i].powi(2) + (m as f64 * t.p_sin[i]).powi(2));
+                    assert_relative_eq!(t.p_sin[i] * s, t.p[i], epsilon = 1e-12);
+                }
+                let lf = l as f64;
+                let norm = (2.0 * lf + 1.0) / (4.0 * std::f64::consts::PI);
+                assert_relative_eq!(sum, norm, epsilon = 1e-12);
+                assert_relative_eq!(
+                    grad,
+                    lf * (lf + 1.0) * norm,
+                    epsilon = 1e-9,
+                    max_relative = 1e-12
+                );
+                for m in 0..=l {
+                    let i = table_index(l, m);
+                    let fd = (tp.p[i] - tm.p[i]) / (2.0 * h);
+                    assert!((fd - t.dp[i]).abs() < 1e-6 * (1.0 + lf * lf), "{l} {m}");
+                }
+            }
+        }
+    }
+}
@@ -28,24 +28,28 @@
 //!
 //! # Tutorial
 //!
-//! There are two enums [`RealSH`] and [`ComplexSH`], each with the following variants:
+//! The harmonics live in the [`basis`] module. There are two enums [`RealSH`](`basis::RealSH`) and
+//! [`ComplexSH`](`basis::ComplexSH`), each with the following variants:
 //!
 //! * `Spherical`
 //! * `RegularSolid`
 //! * `IrregularSolid`
 //!
 //! These variants are used to define which kind of spherical/solid harmonic is to be computed.
-//! Each enum implements the [`SHEval`] trait, which provides an [`SHEval::eval`] method.
+//! Each enum implements the [`SHEval`](`basis::SHEval`) trait, which provides an
+//! [`eval`](`basis::SHEval::eval`) method.
 //! This method is used to compute the spherical/solid harmonic for [`Coordinates`].
 //! Coordinates define a position in space and can be constructed from Cartesian and spherical
 //! coordinates via [`Coordinates::cartesian`] and [`Coordinates::spherical`], respectively.
+//! [`CartesianCoordinates`] skip the angles for the closed-form expressions of the real harmonics.
 //!
 //! In order to compute real valued spherical harmonics, one needs to call the
-//! [`eval`](`SHEval::eval`) method on the `Spherical` variant of the `RealSH` enum.
-//! The `eval` method is part of the [`SHEval`] trait and as such this trait must be in scope.
+//! [`eval`](`basis::SHEval::eval`) method on the `Spherical` variant of the `RealSH` enum.
+//! The `eval` method is part of the `SHEval` trait and as such this trait must be in scope. The
+//! [`prelude`] brings it into scope together with the most commonly used types.
 //!
 //! ```rust
-//! use sphrs::{Coordinates, RealSH, SHEval};
+//! use sphrs::prelude::*;
 //!
 //! // l = 2
 //! let degree = 2;
@@ -62,14 +66,15 @@
 //! println!("SH ({}, {}): {:?}", degree, order, computed_sh);
 //! ```
 //!
-//! This library can also compute [`HarmonicsSet`]s which contains all spherical/solid harmonics
-//! up to a given order.
+//! This library can also compute [`HarmonicsSet`](`basis::HarmonicsSet`)s which contains all
+//! spherical/solid harmonics up to a given order.
 //!
 //! The following example shows how to compute complex spherical harmonics up to third order at
 //! the spherical coordinates (r, theta, phi) = (1.0, 0.8, 0.4):
 //!
 //! ```rust
-//! use sphrs::{ComplexSH, Coordinates, HarmonicsSet};
+//! use sphrs::basis::{ComplexSH, HarmonicsSet};
+//! use sphrs::Coordinates;
 //!
 //! // l = 3
 //! let degree = 3;
@@ -87,22 +92,91 @@
 //! ```
 //!
 //! The individual SH in the set can also be multiplied element-wise with a vector of coefficients
-//! with the function [`HarmonicsSet::eval_with_coefficients`]:
+//! with the function
+//! [`HarmonicsSet::eval_with_coefficients`](`basis::HarmonicsSet::eval_with_coefficients`):
 //!
 //! ```rust
-//! # use sphrs::{ComplexSH, HarmonicsSet, Coordinates};
+//! # use sphrs::basis::{ComplexSH, HarmonicsSet};
+//! # use sphrs::Coordinates;
 //! # let degree = 3;
 //! let sh = HarmonicsSet::new(degree, ComplexSH::Spherical);
 //! # let p = Coordinates::spherical(1.0, 0.8, 0.4);
-//! // Must be the same length as the set.
+//! // By default, the coefficients must be of the same length as the set.
 //! let coeff = vec![2.0; sh.num_sh()];
-//! let set = sh.eval_with_coefficients(&p, coeff.as_slice());
+//! let set = sh.eval_with_coefficients(&p, coeff.as_slice()).unwrap();
 //! println!("SH up to degree {}: {:?}", degree, set);
 //! ```
 //!
+//! [`HarmonicsSet::eval_sum`](`basis::HarmonicsSet::eval_sum`) directly returns the sum of the
+//! products, i.e. the value of the expansion at `p`. Coefficient slices of other lengths (e.g.
+//! of a model truncated at a different degree) are accepted with a
+//! [`LengthPolicy`](`basis::LengthPolicy`) set via the builder.
+//!
 //! # Advanced features
 //!
-//! Feel free to directly use the low level functions linked at the bottom of this page.
+//! Feel free to directly use the low level functions in [`basis`].
+//!
+//! Beyond evaluation, the crate is organized into the following modules:
+//!
+//! * [`transform`]: projection of functions on the sphere onto spherical harmonics
+//! * [`rotation`]: rotation of spherical harmonics expansions and estimation of rotations
+//! * [`translation`]: translation of solid harmonics expansions (multipole and local expansions)
+//! * [`fit`]: fitting of coefficients to scattered samples
+//! * [`sampling`]: quasi-Monte Carlo direction sequences
+//! * [`grid`]: spherical grids and resampling between them
+//! * [`sht`]: fast spherical harmonic transforms on sampling theorem grids
+//! * [`slepian`]: Slepian functions concentrated within spherical caps
+//! * [`so3`]: Wigner D-function transforms of functions on the rotation group
+//! * [`wavelet`]: needlet decomposition for multi-scale analysis
+//! * [`spectral`]: power and cross spectra, degree correlation, admittance, spectral filters,
+//!   zonal convolution and products of expansions
+//! * [`dmri`]: symmetric basis and utilities for diffusion MRI
+//! * [`angular_momentum`]: angular momentum operators acting on complex coefficients
+//! * [`antenna`]: far-field pattern expansion, directivity and beamwidth
+//! * [`io`]: binary (de)serialization of coefficients
+//! * [`legendre`]: associated Legendre functions
+//! * [`verify`]: accuracy self-check against an extended precision reference
+//!
+//! # Features
+//!
+//! * `compact`: Evaluate all harmonics with the recursive implementation. This removes the
+//!   hardcoded closed-form expressions and the unrolled evaluation of
+//!   [`HarmonicsSet`](`basis::HarmonicsSet`)s from the evaluation paths, which considerably
+//!   reduces the code size at the cost of speed. The closed-form functions such as
+//!   [`basis::sh2p1`] remain available, but are only compiled into a binary when called directly.
+//! * `serde`: Implement `Serialize` and `Deserialize` for the configuration enums
+//!   ([`RealSH`](`basis::RealSH`), [`ComplexSH`](`basis::ComplexSH`),
+//!   [`PhaseConvention`](`basis::PhaseConvention`), [`Backend`](`basis::Backend`),
+//!   [`LengthPolicy`](`basis::LengthPolicy`), [`Parallelism`](`basis::Parallelism`),
+//!   [`SHConvention`](`dmri::SHConvention`) and
+//!   [`Normalization`]). They are represented by the same names as used by their `Display` and
+//!   `FromStr` implementations, such that a basis can be configured from configuration files and
+//!   command line flags alike.
+//! * `xnumber`: Add [`Backend::Extended`](`basis::Backend::Extended`), which evaluates the
+//!   Legendre recurrences with extended exponent arithmetic for degrees beyond a few thousand.
+//! * `half`: Add [`HarmonicsSet::eval_f16`](`basis::HarmonicsSet::eval_f16`) and
+//!   [`HarmonicsSet::eval_table_f16`](`basis::HarmonicsSet::eval_table_f16`), which compute the
+//!   harmonics in `f32` or `f64` and store them as `half::f16`.
+//! * `ndarray`: Add [`fit::design_matrix`], which returns the harmonics at a set of points as an
+//!   `ndarray::Array2`, e.g. for use with other solvers.
+//! * `rayon`: Distribute the transforms over the rayon thread pool: the rings of the
+//!   [`sht`] transforms, the grid directions of the [`grid`] transforms, and the sample points of
+//!   projections such as [`transform::project`] and of batch evaluations such as
+//!   [`HarmonicsSet::eval_batch`](`basis::HarmonicsSet::eval_batch`) and
+//!   [`Interpolator::eval_many`](`fit::Interpolator::eval_many`). Results are bit-identical with
+//!   and without the feature and for any number of threads. Sets built with
+//!   [`Parallelism::Sequential`](`basis::Parallelism::Sequential`) always evaluate batches on the
+//!   calling thread.
+//! * `gpu`: Add the [`gpu`] module, which evaluates harmonics and expansions at buffers of `f32`
+//!   directions in the layout of GPU compute shaders. Only the CPU fallback device is
+//!   implemented, a wgpu backend is not included.
+//!
+//! # Closed-form expressions
+//!
+//! The real spherical harmonics up to degree 8 are evaluated with closed-form polynomials in the
+//! components of the unit vector, which are generated by the build script. The degree can be set
+//! at build time (between 3 and 20) with the environment variable `SPHRS_HARDCODED_DEGREE`, e.g.
+//! `SPHRS_HARDCODED_DEGREE=10 cargo build`. Higher degrees increase the code size.
 //!
 //! # Acknowledgements
 //!
@@ -137,10 +211,108 @@
 
 #![warn(missing_docs)]
 
+pub mod angular_momentum;
+pub mod antenna;
+pub mod basis;
+mod coefficients;
 mod coordinates;
+pub mod dmri;
+mod error;
+pub mod fit;
 mod float;
-mod sh;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod grid;
+pub mod io;
+pub mod legendre;
+mod linalg;
+mod names;
+mod parallel;
+mod quadrature;
+mod reduce;
+pub mod rotation;
+pub mod sampling;
+pub mod sht;
+pub mod slepian;
+pub mod so3;
+pub mod spectral;
+mod timevarying;
+pub mod transform;
+pub mod translation;
+pub mod verify;
+pub mod wavelet;
+#[cfg(feature = "xnumber")]
+mod xnumber;
 
-pub use crate::coordinates::{Coordinates, SHCoordinates};
+pub use crate::coefficients::{sh_degree_order, sh_index, Normalization, SHCoefficients};
+pub use crate::coordinates::{CartesianCoordinates, Coordinates, SHCoordinates};
+pub use crate::error::SphrsError;
 pub use crate::float::SphrsFloat;
-pub use crate::sh::*;
+pub use crate::timevarying::TimeVaryingExpansion;
+
+/// Commonly used types and traits
+///
+/// ```
+/// use sphrs::prelude::*;
+///
+/// let sh = HarmonicsSet::new(2, RealSH::Spherical);
+/// let set: Vec<f64> = sh.eval(&Coordinates::spherical(1.0, 0.8, 0.4));
+/// assert_eq!(set.len(), 9);
+/// ```
+pub mod prelude {
+    pub use crate::basis::{ComplexSH, HarmonicsSet, RealSH, SHEval, SHGrad};
+    pub use crate::{Coordinates, SHCoefficients, SHCoordinates, SphrsFloat};
+}
+
+// Flat re-exports of the contents of `basis` from before the introduction of the submodules.
+// These will be removed in the next release.
+
+/// Harmonics evaluation trait
+///
+/// Deprecated location, use [`basis::SHEval`] or the [`prelude`] instead.
+pub use crate::basis::SHEval;
+
+/// Deprecated alias of [`basis::RealSH`]
+#[deprecated(since = "0.3.0", note = "use `sphrs::basis::RealSH` instead")]
+pub type RealSH = basis::RealSH;
+
+/// Deprecated alias of [`basis::ComplexSH`]
+#[deprecated(since = "0.3.0", note = "use `sphrs::basis::ComplexSH` instead")]
+pub type ComplexSH = basis::ComplexSH;
+
+/// Deprecated alias of [`basis::HarmonicsSet`]
+#[deprecated(since = "0.3.0", note = "use `sphrs::basis::HarmonicsSet` instead")]
+pub type HarmonicsSet<T, E> = basis::HarmonicsSet<T, E>;
+
+macro_rules! deprecated_reexport {
+    ($($name:ident),*; $($lm_name:ident -> $out:ty),*) => {
+        $(
+            #[doc = concat!("Deprecated alias of [`basis::", stringify!($name), "`]")]
+            #[deprecated(since = "0.3.0", note = "use the function of the same name in `sphrs::basis` instead")]
+            #[inline(always)]
+            pub fn $name<T: SphrsFloat>(p: &impl SHCoordinates<T>) -> T {
+                basis::$name(p)
+            }
+        )*
+        $(
+            #[doc = concat!("Deprecated alias of [`basis::", stringify!($lm_name), "`]")]
+            #[deprecated(since = "0.3.0", note = "use the function of the same name in `sphrs::basis` instead")]
+            #[inline(always)]
+            pub fn $lm_name<T: SphrsFloat>(l: i64, m: i64, p: &impl SHCoordinates<T>) -> $out {
+                basis::$lm_name(l, m, p)
+            }
+        )*
+    };
+}
+
+deprecated_reexport!(
+    sh00, sh1n1, sh10, sh1p1, sh2n2, sh2n1, sh20, sh2p1, sh2p2, sh3n3, sh3n2, sh3n1, sh30, sh3p1,
+    sh3p2, sh3p3;
+    sh -> num_complex::Complex<T>,
+    real_sh -> T,
+    real_sh_hardcoded -> T,
+    regular_solid_sh -> num_complex::Complex<T>,
+    irregular_solid_sh -> num_complex::Complex<T>,
+    real_regular_solid_sh -> T,
+    real_irregular_solid_sh -> T
+);
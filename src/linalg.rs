@@ -0,0 +1,171 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Small dense linear algebra routines (row-major storage)
+
+use crate::SphrsFloat;
+
+/// In-place Cholesky decomposition of the symmetric positive definite `n x n` matrix `a`
+///
+/// On success, the lower triangle of `a` holds the factor L with A = L L^T. Returns `None` if the
+/// matrix is not (numerically) positive definite, i.e. if a pivot drops below `n * eps` times the
+/// largest diagonal element.
+pub(crate) fn cholesky<T: SphrsFloat>(a: &mut [T], n: usize) -> Option<()> {
+    assert_eq!(a.len(), n * n);
+    let max_diag = (0..n).fold(T::zero(), |acc, i| acc.max(a[i * n + i]));
+    let tol = max_diag * T::from_usize(n).unwrap() * T::epsilon();
+    for j in 0..n {
+        let mut d = a[j * n + j];
+        for k in 0..j {
+            d = d - a[j * n + k] * a[j * n + k];
+        }
+        if d.is_nan() || d <= tol {
+            return None;
+        }
+        let d = d.sqrt();
+        a[j * n + j] = d;
+        for i in (j + 1)..n {
+            let mut s = a[i * n + j];
+            for k in 0..j {
+                s = s - a[i * n + k] * a[j * n + k];
+            }
+            a[i * n + j] = s / d;
+        }
+    }
+    Some(())
+}
+
+/// Solve L L^T x = b in place, with L as computed by [`cholesky`]
+pub(crate) fn cholesky_solve<T: SphrsFloat>(l: &[T], n: usize, b: &mut [T]) {
+    assert_eq!(b.len(), n);
+    for i in 0..n {
+        let mut s = b[i];
+        for k in 0..i {
+            s = s - l[i * n + k] * b[k];
+        }
+        b[i] = s / l[i * n + i];
+    }
+    for i in (0..n).rev() {
+        let mut s = b[i];
+        for k in (i + 1)..n {
+            s = s - l[k * n + i] * b[k];
+        }
+        b[i] = s / l[i * n + i];
+    }
+}
+
+/// Solve the symmetric positive definite system A x = b. Returns `None` if A is not positive
+/// definite.
+pub(crate) fn solve_spd<T: SphrsFloat>(mut a: Vec<T>, n: usize, mut b: Vec<T>) -> Option<Vec<T>> {
+    cholesky(&mut a, n)?;
+    cholesky_solve(&a, n, &mut b);
+    Some(b)
+}
+
+/// Eigenvalues and eigenvectors of the symmetric `n x n` matrix `a` (cyclic Jacobi method)
+///
+/// Returns the eigenvalues in descending order and the matrix whose column j is the normalized
+/// eigenvector of eigenvalue j.
+pub(crate) fn symmetric_eigen<T: SphrsFloat>(mut a: Vec<T>, n: usize) -> (Vec<T>, Vec<T>) {
+    assert_eq!(a.len(), n * n);
+    let mut v = vec![T::zero(); n * n];
+    for i in 0..n {
+        v[i * n + i] = T::one();
+    }
+    let norm = a.iter().fold(T::zero(), |acc, &x| acc + x * x);
+    let tol = norm * T::epsilon() * T::epsilon();
+    for _ in 0..100 {
+        let off = (0..n).fold(T::zero(), |acc, i| {
+            (0..n)
+                .filter(|&j| j != i)
+                .fold(acc, |acc, j| acc + a[i * n + j] * a[i * n + j])
+        });
+        if off <= tol {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a[p * n + q];
+                if apq == T::zero() {
+                    continue;
+                }
+                // Rotation annihilating a_pq
+                let theta = (a[q * n + q] - a[p * n + p]) / (apq + apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + T::one()).sqrt());
+                let c = T::one() / (t * t + T::one()).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[j * n + j].partial_cmp(&a[i * n + i]).unwrap());
+    let values = order.iter().map(|&i| a[i * n + i]).collect();
+    let mut vectors = vec![T::zero(); n * n];
+    for (j, &i) in order.iter().enumerate() {
+        for k in 0..n {
+            vectors[k * n + j] = v[k * n + i];
+        }
+    }
+    (values, vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn solve_spd_system() {
+        let mut a = vec![4.0f64, 2.0, 0.4, 2.0, 5.0, 1.0, 0.4, 1.0, 3.0];
+        let x = [1.0, -2.0, 0.5];
+        let mut b: Vec<f64> = (0..3)
+            .map(|i| (0..3).map(|j| a[i * 3 + j] * x[j]).sum())
+            .collect();
+        cholesky(&mut a, 3).unwrap();
+        cholesky_solve(&a, 3, &mut b);
+        for (b, x) in b.iter().zip(x.iter()) {
+            assert_relative_eq!(b, x, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn not_positive_definite() {
+        let mut a = vec![1.0f64, 2.0, 2.0, 1.0];
+        assert!(cholesky(&mut a, 2).is_none());
+    }
+
+    #[test]
+    fn eigen_decomposition() {
+        let a = vec![4.0f64, 2.0, 0.4, 2.0, 5.0, 1.0, 0.4, 1.0, 3.0];
+        let (values, vectors) = symmetric_eigen(a.clone(), 3);
+        assert!(values[0] >= values[1] && values[1] >= values[2]);
+        for j in 0..3 {
+            for i in 0..3 {
+                let av: f64 = (0..3).map(|k| a[i * 3 + k] * vectors[k * 3 + j]).sum();
+                assert_relative_eq!(av, values[j] * vectors[i * 3 + j], epsilon = 1e-13);
+            }
+            let norm: f64 = (0..3).map(|k| vectors[k * 3 + j].powi(2)).sum();
+            assert_relative_eq!(norm, 1.0, epsilon = 1e-14);
+        }
+    }
+}
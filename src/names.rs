@@ -0,0 +1,129 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! String names of configuration enums
+
+/// Implement `Display`, `FromStr` and (with the `serde` feature) `Serialize`/`Deserialize` for a
+/// fieldless enum via a fixed name per variant
+///
+/// Parsing is case insensitive and accepts `-` in place of `_`. Serde uses the same names as
+/// `Display`. Variants may carry `cfg` attributes.
+macro_rules! named_enum {
+    ($ty:ident, $what:literal, { $($(#[$meta:meta])* $variant:ident => $name:literal),+ $(,)? }) => {
+        impl $ty {
+            /// Name of the variant as used by `Display` and `FromStr`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($(#[$meta])* $ty::$variant => $name,)+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl std::str::FromStr for $ty {
+            type Err = $crate::SphrsError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let normalized = s.trim().to_ascii_lowercase().replace('-', "_");
+                match normalized.as_str() {
+                    $($(#[$meta])* $name => Ok($ty::$variant),)+
+                    _ => Err($crate::SphrsError::InvalidFormat(format!(
+                        concat!("unknown ", $what, " \"{}\", expected one of {}"),
+                        s,
+                        [$($(#[$meta])* $name),+].join(", ")
+                    ))),
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use named_enum;
+
+#[cfg(test)]
+mod tests {
+    use crate::basis::{Backend, ComplexSH, LengthPolicy, Parallelism, RealSH};
+    use crate::dmri::SHConvention;
+    use crate::{Normalization, SphrsError};
+
+    #[test]
+    fn display_and_parse() {
+        for sh in [
+            RealSH::Spherical,
+            RealSH::RegularSolid,
+            RealSH::IrregularSolid,
+        ] {
+            assert_eq!(sh.to_string().parse::<RealSH>().unwrap(), sh);
+        }
+        assert_eq!(
+            "Regular-Solid".parse::<ComplexSH>().unwrap(),
+            ComplexSH::RegularSolid
+        );
+        assert_eq!(
+            ComplexSH::SphericalAcoustics.to_string(),
+            "spherical_acoustics"
+        );
+        assert_eq!(Backend::Recursive.to_string(), "recursive");
+        assert_eq!(
+            "allow_shorter".parse::<LengthPolicy>().unwrap(),
+            LengthPolicy::AllowShorter
+        );
+        assert_eq!(
+            "Sequential".parse::<Parallelism>().unwrap(),
+            Parallelism::Sequential
+        );
+        assert_eq!(
+            " MRtrix3 ".parse::<SHConvention>().unwrap(),
+            SHConvention::Mrtrix3
+        );
+        assert_eq!(
+            SHConvention::DipyTournier07Legacy.to_string(),
+            "dipy_tournier07_legacy"
+        );
+        assert_eq!(Normalization::Orthonormal.to_string(), "orthonormal");
+        assert_eq!(
+            "Four-Pi".parse::<Normalization>().unwrap(),
+            Normalization::FourPi
+        );
+        assert!(matches!(
+            "spherial".parse::<RealSH>(),
+            Err(SphrsError::InvalidFormat(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_uses_names() {
+        assert_eq!(
+            serde_json::to_string(&RealSH::IrregularSolid).unwrap(),
+            "\"irregular_solid\""
+        );
+        let backend: Backend = serde_json::from_str("\"Hardcoded\"").unwrap();
+        assert_eq!(backend, Backend::Hardcoded);
+        assert!(serde_json::from_str::<LengthPolicy>("\"any\"").is_err());
+    }
+}
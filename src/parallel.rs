@@ -0,0 +1,86 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Data parallel loops
+//!
+//! With the `rayon` feature, [`map`] and [`for_each_chunk_mut`] distribute the iterations over the
+//! rayon thread pool, otherwise they are plain sequential loops. The bounds are the same in both
+//! cases, such that enabling the feature does not change which code compiles. Each iteration
+//! computes its result independently of the others and results are returned in order, so the
+//! output does not depend on the number of threads.
+
+/// `f(i)` for i = 0, ..., `n` - 1
+#[cfg(feature = "rayon")]
+pub(crate) fn map<U, F>(n: usize, f: F) -> Vec<U>
+where
+    U: Send,
+    F: Fn(usize) -> U + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    (0..n).into_par_iter().map(f).collect()
+}
+
+/// `f(i)` for i = 0, ..., `n` - 1
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn map<U, F>(n: usize, f: F) -> Vec<U>
+where
+    U: Send,
+    F: Fn(usize) -> U + Sync + Send,
+{
+    (0..n).map(f).collect()
+}
+
+/// `f(i, chunk)` for the consecutive chunks of `chunk_len` elements of `data`, the last one
+/// possibly shorter
+#[cfg(feature = "rayon")]
+pub(crate) fn for_each_chunk_mut<U, F>(data: &mut [U], chunk_len: usize, f: F)
+where
+    U: Send,
+    F: Fn(usize, &mut [U]) + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    data.par_chunks_mut(chunk_len)
+        .enumerate()
+        .for_each(|(i, chunk)| f(i, chunk));
+}
+
+/// `f(i, chunk)` for the consecutive chunks of `chunk_len` elements of `data`, the last one
+/// possibly shorter
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn for_each_chunk_mut<U, F>(data: &mut [U], chunk_len: usize, f: F)
+where
+    U: Send,
+    F: Fn(usize, &mut [U]) + Sync + Send,
+{
+    data.chunks_mut(chunk_len)
+        .enumerate()
+        .for_each(|(i, chunk)| f(i, chunk));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserves_order() {
+        assert_eq!(map(5, |i| i * i), vec![0, 1, 4, 9, 16]);
+        assert!(map(0, |i| i).is_empty());
+    }
+
+    #[test]
+    fn chunks_in_place() {
+        let mut data = vec![0; 10];
+        for_each_chunk_mut(&mut data, 4, |i, chunk| {
+            for (j, v) in chunk.iter_mut().enumerate() {
+                *v = 4 * i + j;
+            }
+        });
+        assert_eq!(data, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::SphrsFloat;
+
+/// Nodes and weights of the `n`-point Gauss-Legendre quadrature on [-1, 1]
+///
+/// The nodes are returned in descending order (which corresponds to ascending colatitude
+/// `acos(x)`). Computed via Newton iterations on the Legendre recurrence. The quadrature
+/// integrates polynomials up to degree `2 n - 1` exactly.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::sht::gauss_legendre;
+///
+/// let (nodes, weights) = gauss_legendre::<f64>(3);
+/// // integral of x^4 over [-1, 1]
+/// let integral: f64 = nodes.iter().zip(&weights).map(|(x, w)| w * x.powi(4)).sum();
+/// assert!((integral - 0.4).abs() < 1e-15);
+/// ```
+pub fn gauss_legendre<T: SphrsFloat>(n: usize) -> (Vec<T>, Vec<T>) {
+    let mut nodes = vec![T::zero(); n];
+    let mut weights = vec![T::zero(); n];
+    let nf = T::from_usize(n).unwrap();
+    let two = T::from_f64(2.0).unwrap();
+    let eps = T::epsilon() * T::from_f64(4.0).unwrap();
+
+    // Legendre polynomial P_n(x) and its derivative
+    let legendre = |x: T| {
+        let mut p0 = T::one();
+        let mut p1 = x;
+        for k in 2..=n {
+            let kf = T::from_usize(k).unwrap();
+            let p2 = ((two * kf - T::one()) * x * p1 - (kf - T::one()) * p0) / kf;
+            p0 = p1;
+            p1 = p2;
+        }
+        (p1, nf * (x * p1 - p0) / (x * x - T::one()))
+    };
+
+    for i in 0..n.div_ceil(2) {
+        // Initial guess (Tricomi)
+        let mut x = (T::PI() * (T::from_usize(i).unwrap() + T::from_f64(0.75).unwrap())
+            / (nf + T::from_f64(0.5).unwrap()))
+        .cos();
+        for _ in 0..100 {
+            let (p, dp) = legendre(x);
+            let dx = p / dp;
+            x = x - dx;
+            if dx.abs() <= eps {
+                break;
+            }
+        }
+        let (_, dp) = legendre(x);
+        let w = two / ((T::one() - x * x) * dp * dp);
+        nodes[i] = x;
+        nodes[n - 1 - i] = -x;
+        weights[i] = w;
+        weights[n - 1 - i] = w;
+    }
+    if n % 2 == 1 {
+        nodes[n / 2] = T::zero();
+    }
+    (nodes, weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn integrates_polynomials_exactly() {
+        for n in 1..20 {
+            let (x, w) = gauss_legendre::<f64>(n);
+            assert_relative_eq!(w.iter().sum::<f64>(), 2.0, epsilon = 1e-13);
+            for k in 0..(2 * n) {
+                let integral: f64 = x
+                    .iter()
+                    .zip(w.iter())
+                    .map(|(x, w)| w * x.powi(k as i32))
+                    .sum();
+                let expected = if k % 2 == 0 {
+                    2.0 / (k as f64 + 1.0)
+                } else {
+                    0.0
+                };
+                assert_relative_eq!(integral, expected, epsilon = 1e-13);
+            }
+            for i in 1..n {
+                assert!(x[i] < x[i - 1]);
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Reductions with a fixed order of floating point operations
+//!
+//! Sums over many sample points (e.g. in projections and grid analysis) are split into blocks of
+//! [`BLOCK_SIZE`] consecutive terms, which are accumulated sequentially, and the partial sums of
+//! the blocks are combined by a pairwise tree whose shape only depends on the number of blocks.
+//! The result is therefore bit-identical no matter how the blocks are distributed over threads,
+//! and the pairwise combination reduces the accumulated rounding error compared to one long
+//! sequential sum. [`neumaier_sum`] provides compensated summation for single sums whose rounding
+//! error matters more than their speed.
+
+use std::ops::{Add, Range};
+
+use num::Zero;
+
+use crate::{parallel, SphrsFloat};
+
+/// Number of consecutive terms accumulated sequentially
+pub(crate) const BLOCK_SIZE: usize = 256;
+
+/// Sum of `n` terms, each a vector of length `len`
+///
+/// `accumulate(range, acc)` adds the terms with indices in `range` to `acc` in increasing order.
+pub(crate) fn blockwise_sum<T, F>(n: usize, len: usize, accumulate: F) -> Vec<T>
+where
+    T: Copy + Zero + Add<Output = T> + Send,
+    F: Fn(Range<usize>, &mut [T]) + Sync + Send,
+{
+    blockwise_sum_by(n, BLOCK_SIZE, len, accumulate)
+}
+
+/// Sum of `n` terms in blocks of `block_size` consecutive terms, see [`blockwise_sum`]
+///
+/// The blocks are accumulated in parallel with the `rayon` feature.
+pub(crate) fn blockwise_sum_by<T, F>(
+    n: usize,
+    block_size: usize,
+    len: usize,
+    accumulate: F,
+) -> Vec<T>
+where
+    T: Copy + Zero + Add<Output = T> + Send,
+    F: Fn(Range<usize>, &mut [T]) + Sync + Send,
+{
+    let partials = parallel::map(n.div_ceil(block_size), |block| {
+        let start = block * block_size;
+        let mut acc = vec![T::zero(); len];
+        accumulate(start..(start + block_size).min(n), &mut acc);
+        acc
+    });
+    tree_sum(partials, len)
+}
+
+/// Combine partial sums pairwise, level by level
+pub(crate) fn tree_sum<T>(mut partials: Vec<Vec<T>>, len: usize) -> Vec<T>
+where
+    T: Copy + Zero + Add<Output = T>,
+{
+    while partials.len() > 1 {
+        partials = partials
+            .chunks_mut(2)
+            .map(|pair| match pair {
+                [a, b] => a.iter().zip(b.iter()).map(|(&a, &b)| a + b).collect(),
+                [a] => std::mem::take(a),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    partials.pop().unwrap_or_else(|| vec![T::zero(); len])
+}
+
+/// Sum of `terms` with Neumaier's variant of Kahan's compensated summation
+///
+/// The rounding error of each addition is accumulated separately and added at the end, such that
+/// the error of the result does not grow with the number of terms.
+pub(crate) fn neumaier_sum<T: SphrsFloat>(terms: impl IntoIterator<Item = T>) -> T {
+    let (sum, compensation) =
+        terms
+            .into_iter()
+            .fold((T::zero(), T::zero()), |(sum, compensation), term| {
+                let next = sum + term;
+                let error = if sum.abs() >= term.abs() {
+                    (sum - next) + term
+                } else {
+                    (term - next) + sum
+                };
+                (next, compensation + error)
+            });
+    sum + compensation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential_sum() {
+        for n in [0, 1, BLOCK_SIZE - 1, BLOCK_SIZE, 5 * BLOCK_SIZE + 3] {
+            let sum = blockwise_sum(n, 2, |range, acc: &mut [u64]| {
+                for i in range {
+                    acc[0] += i as u64;
+                    acc[1] += 1;
+                }
+            });
+            let n64 = n as u64;
+            assert_eq!(sum, vec![n64 * n64.saturating_sub(1) / 2, n64]);
+        }
+    }
+
+    #[test]
+    fn pairwise_order() {
+        // ((a + b) + (c + d)) + e differs from the sequential sum in floating point
+        let partials = vec![vec![1e16], vec![1.0], vec![-1e16], vec![1.0], vec![0.5]];
+        assert_eq!(tree_sum(partials, 1), vec![0.5]);
+    }
+
+    #[test]
+    fn compensated_sum() {
+        let terms = [1.0, 1e100, 1.0, -1e100];
+        assert_eq!(terms.iter().sum::<f64>(), 0.0);
+        assert_eq!(neumaier_sum(terms), 2.0);
+        assert_eq!(neumaier_sum(Vec::<f32>::new()), 0.0);
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use num::Zero;
+use num_complex::Complex;
+
+use crate::basis::real_to_complex_coefficients;
+use crate::coefficients::{num_coefficients, sh_index};
+use crate::linalg::solve_spd;
+use crate::rotation::rotate;
+use crate::rotation::wigner::{matrix_index, small_d_matrix};
+use crate::{SHCoefficients, SphrsError, SphrsFloat};
+
+/// Maximum number of Newton iterations refining the estimate of the rotation
+const MAX_NEWTON_ITERATIONS: usize = 10;
+
+/// Rotation between two expansions, as estimated by [`estimate_rotation`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RotationEstimate<T> {
+    /// Euler angles
+    alpha: T,
+    beta: T,
+    gamma: T,
+    /// Normalized correlation at the estimate
+    correlation: T,
+}
+
+impl<T: SphrsFloat> RotationEstimate<T> {
+    /// Euler angles (alpha, beta, gamma) of the rotation, with alpha and gamma in [0, 2 pi) and
+    /// beta in [0, pi], in the convention of [`rotate`]
+    pub fn euler_angles(&self) -> (T, T, T) {
+        (self.alpha, self.beta, self.gamma)
+    }
+
+    /// Correlation of the rotated and the target expansion, normalized by their norms
+    ///
+    /// The correlation is 1 if the target is exactly a rotation of the source and smaller
+    /// otherwise, e.g. for noisy or partially overlapping data.
+    pub fn correlation(&self) -> T {
+        self.correlation
+    }
+}
+
+/// Copy of the degrees up to `degree` of `coeffs`
+fn truncate<T: SphrsFloat>(coeffs: &SHCoefficients<T>, degree: usize) -> SHCoefficients<T> {
+    SHCoefficients::from_vec(
+        degree,
+        coeffs.as_slice()[..num_coefficients(degree)].to_vec(),
+    )
+}
+
+/// `x` reduced to [0, `period`)
+fn wrap<T: SphrsFloat>(x: T, period: T) -> T {
+    let r = x - (x / period).floor() * period;
+    if r >= period {
+        r - period
+    } else {
+        r
+    }
+}
+
+/// Correlation of `g` with `f` rotated by the Euler angles `alpha`, `beta` and `gamma`
+///
+/// Returns the integral of g(p) f(R^-1 p) over the sphere, i.e. the inner product of `g` and
+/// [`rotate(f, alpha, beta, gamma)`](`rotate`), up to the lower of the degrees of `f` and `g`.
+pub fn so3_correlation<T: SphrsFloat>(
+    f: &SHCoefficients<T>,
+    g: &SHCoefficients<T>,
+    alpha: T,
+    beta: T,
+    gamma: T,
+) -> T {
+    let degree = f.degree().min(g.degree());
+    let rotated = rotate(&truncate(f, degree), alpha, beta, gamma);
+    rotated
+        .as_slice()
+        .iter()
+        .zip(g.as_slice())
+        .fold(T::zero(), |acc, (&a, &b)| acc + a * b)
+}
+
+/// Correlation of `g` with the rotations of `f` on a grid of n / 2 x n x n Euler angles
+///
+/// The values are stored with beta varying slowest and gamma fastest. The angles are
+/// beta_i = (i + 1/2) h, alpha_j = j h and gamma_k = k h with h = 2 pi / n.
+fn correlation_grid<T: SphrsFloat>(
+    f: &SHCoefficients<T>,
+    g: &SHCoefficients<T>,
+    n: usize,
+) -> Vec<T> {
+    let degree = f.degree();
+    let step = T::from_f64(2.0).unwrap() * T::PI() / T::from_usize(n).unwrap();
+    let (fc, gc) = (
+        real_to_complex_coefficients(f),
+        real_to_complex_coefficients(g),
+    );
+    let li = degree as i64;
+    let width = 2 * degree + 1;
+    // e^(-i m phi_k) for all orders and grid angles
+    let phase: Vec<Complex<T>> = (-li..=li)
+        .flat_map(|m| {
+            (0..n).map(move |k| {
+                Complex::from_polar(
+                    T::one(),
+                    -T::from_i64(m).unwrap() * T::from_usize(k).unwrap() * step,
+                )
+            })
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(n / 2 * n * n);
+    for i in 0..n / 2 {
+        let beta = (T::from_usize(i).unwrap() + T::from_f64(0.5).unwrap()) * step;
+        // S_m1m2 = sum_l conj(g_lm1) d^l_m1m2(beta) f_lm2
+        let mut s = vec![Complex::<T>::zero(); width * width];
+        for l in 0..=degree {
+            let d = small_d_matrix(l, beta);
+            let l64 = l as i64;
+            for m1 in -l64..=l64 {
+                let gm = gc.as_slice()[sh_index(l64, m1)].conj();
+                for m2 in -l64..=l64 {
+                    let idx = (m1 + li) as usize * width + (m2 + li) as usize;
+                    s[idx] =
+                        s[idx] + gm * fc.as_slice()[sh_index(l64, m2)] * d[matrix_index(l, m1, m2)];
+                }
+            }
+        }
+        // Sum over m2 for each gamma, then over m1 for each alpha
+        let mut t = vec![Complex::<T>::zero(); width * n];
+        for m1 in 0..width {
+            for m2 in 0..width {
+                let v = s[m1 * width + m2];
+                if v.is_zero() {
+                    continue;
+                }
+                for k in 0..n {
+                    t[m1 * n + k] = t[m1 * n + k] + v * phase[m2 * n + k];
+                }
+            }
+        }
+        for j in 0..n {
+            for k in 0..n {
+                out.push((0..width).fold(T::zero(), |acc, m1| {
+                    acc + (t[m1 * n + k] * phase[m1 * n + j]).re
+                }));
+            }
+        }
+    }
+    out
+}
+
+/// Estimate the rotation R which maps the expansion `f` onto `g`, i.e. g(p) = f(R^-1 p)
+///
+/// The correlation [`so3_correlation`] is maximized over SO(3): it is first evaluated on a grid
+/// of 2 (L + 1) samples per Euler angle, where L is the lower of the degrees of `f` and `g`,
+/// using C(alpha, beta, gamma) = sum_l sum_m1,m2 conj(g_lm1) D^l_m1m2(alpha, beta, gamma) f_lm2
+/// with the complex coefficients. Since the Wigner D-matrices are Fourier series in alpha and
+/// gamma, each beta costs a two-dimensional Fourier sum. The best grid point is then refined by
+/// a local pattern search and Newton iterations.
+///
+/// Functions with symmetries, e.g. zonal functions, are invariant under some rotations; the
+/// estimate is then one of several equally good rotations. Returns
+/// [`SphrsError::InvalidInput`] if `f` or `g` vanishes up to the common degree.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::{estimate_rotation, rotate};
+/// use sphrs::SHCoefficients;
+///
+/// let f = SHCoefficients::from_vec(2, vec![1.0f64, 0.3, -0.5, 0.8, 0.1, 0.7, -0.2, 0.4, 0.6]);
+/// let g = rotate(&f, 0.4, 1.1, 2.5);
+/// let estimate = estimate_rotation(&f, &g).unwrap();
+/// let (alpha, beta, gamma) = estimate.euler_angles();
+/// assert!((alpha - 0.4).abs() < 1e-6 && (beta - 1.1).abs() < 1e-6 && (gamma - 2.5).abs() < 1e-6);
+/// assert!((estimate.correlation() - 1.0).abs() < 1e-12);
+/// ```
+pub fn estimate_rotation<T: SphrsFloat>(
+    f: &SHCoefficients<T>,
+    g: &SHCoefficients<T>,
+) -> Result<RotationEstimate<T>, SphrsError> {
+    let degree = f.degree().min(g.degree());
+    let (f, g) = (truncate(f, degree), truncate(g, degree));
+    let norm = |c: &SHCoefficients<T>| {
+        c.as_slice()
+            .iter()
+            .fold(T::zero(), |acc, &x| acc + x * x)
+            .sqrt()
+    };
+    let norm = norm(&f) * norm(&g);
+    if norm == T::zero() {
+        return Err(SphrsError::InvalidInput(
+            "cannot correlate vanishing expansions".to_string(),
+        ));
+    }
+
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    let n = 2 * (degree + 1);
+    let step = two_pi / T::from_usize(n).unwrap();
+    let grid = correlation_grid(&f, &g, n);
+    let (index, &value) =
+        grid.iter().enumerate().fold(
+            (0, &T::neg_infinity()),
+            |best, c| if c.1 > best.1 { c } else { best },
+        );
+    let (i, j, k) = (index / (n * n), index / n % n, index % n);
+    let best = (
+        value,
+        T::from_usize(j).unwrap() * step,
+        (T::from_usize(i).unwrap() + T::from_f64(0.5).unwrap()) * step,
+        T::from_usize(k).unwrap() * step,
+    );
+
+    // Pattern search around the best grid point
+    let correlation = |a: [T; 3]| so3_correlation(&f, &g, a[0], a[1], a[2]);
+    let (mut value, mut angles) = (best.0, [best.1, best.2, best.3]);
+    let mut delta = step / T::from_f64(2.0).unwrap();
+    let h = T::from_f64(1e-4).unwrap();
+    while delta > h {
+        let mut improved = false;
+        for axis in 0..3 {
+            for sign in [T::one(), -T::one()] {
+                let mut trial = angles;
+                trial[axis] = trial[axis] + sign * delta;
+                let c = correlation(trial);
+                if c > value {
+                    value = c;
+                    angles = trial;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            delta = delta / T::from_f64(2.0).unwrap();
+        }
+    }
+
+    // Newton iterations with finite difference derivatives, which converge to the maximum much
+    // more precisely than the comparison of nearly equal correlations
+    for _ in 0..MAX_NEWTON_ITERATIONS {
+        let shifted = |i: usize, di: T, j: usize, dj: T| {
+            let mut a = angles;
+            a[i] = a[i] + di;
+            a[j] = a[j] + dj;
+            correlation(a)
+        };
+        let two = T::from_f64(2.0).unwrap();
+        let mut gradient = vec![T::zero(); 3];
+        let mut hessian = vec![T::zero(); 9];
+        for i in 0..3 {
+            let (plus, minus) = (shifted(i, h, i, T::zero()), shifted(i, -h, i, T::zero()));
+            gradient[i] = (plus - minus) / (two * h);
+            hessian[i * 3 + i] = -(plus - two * value + minus) / (h * h);
+            for j in 0..i {
+                let mixed = shifted(i, h, j, h) - shifted(i, h, j, -h) - shifted(i, -h, j, h)
+                    + shifted(i, -h, j, -h);
+                hessian[i * 3 + j] = -mixed / (T::from_f64(4.0).unwrap() * h * h);
+                hessian[j * 3 + i] = hessian[i * 3 + j];
+            }
+        }
+        // The negative Hessian is positive definite at a strict maximum
+        let Some(update) = solve_spd(hessian, 3, gradient) else {
+            break;
+        };
+        let size = update.iter().fold(T::zero(), |acc, &u| acc.max(u.abs()));
+        if size > step {
+            break;
+        }
+        for (a, u) in angles.iter_mut().zip(&update) {
+            *a = *a + *u;
+        }
+        value = correlation(angles);
+        if size < T::epsilon() {
+            break;
+        }
+    }
+
+    // Normalize to beta in [0, pi] and alpha, gamma in [0, 2 pi)
+    let [mut alpha, mut beta, mut gamma] = angles;
+    beta = wrap(beta, two_pi);
+    if beta > T::PI() {
+        beta = two_pi - beta;
+        alpha = alpha + T::PI();
+        gamma = gamma + T::PI();
+    }
+    Ok(RotationEstimate {
+        alpha: wrap(alpha, two_pi),
+        beta,
+        gamma: wrap(gamma, two_pi),
+        correlation: value / norm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn coefficients(degree: usize) -> SHCoefficients<f64> {
+        SHCoefficients::from_vec(
+            degree,
+            (0..num_coefficients(degree))
+                .map(|i| ((i * 7919) % 101) as f64 / 50.0 - 1.0)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn recovers_rotation() {
+        let f = coefficients(6);
+        for (alpha, beta, gamma) in [(0.3, 2.9, 5.9), (4.0, 0.2, 1.0), (2.0, 1.6, 3.1)] {
+            let g = rotate(&f, alpha, beta, gamma);
+            let estimate = estimate_rotation(&f, &g).unwrap();
+            let (a, b, c) = estimate.euler_angles();
+            assert_relative_eq!(a, alpha, epsilon = 1e-7);
+            assert_relative_eq!(b, beta, epsilon = 1e-7);
+            assert_relative_eq!(c, gamma, epsilon = 1e-7);
+            assert_relative_eq!(estimate.correlation(), 1.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn grid_matches_direct_correlation() {
+        let (f, g) = (coefficients(3), rotate(&coefficients(3), 1.0, 0.5, 2.0));
+        let n = 6;
+        let grid = correlation_grid(&f, &g, n);
+        let h = 2.0 * std::f64::consts::PI / n as f64;
+        for (index, &c) in grid.iter().enumerate() {
+            let (i, j, k) = (index / (n * n), index / n % n, index % n);
+            let direct = so3_correlation(&f, &g, j as f64 * h, (i as f64 + 0.5) * h, k as f64 * h);
+            assert_relative_eq!(c, direct, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn vanishing_expansion() {
+        let f = coefficients(2);
+        let zero = SHCoefficients::zeros(2);
+        assert!(matches!(
+            estimate_rotation(&f, &zero),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rotation matrices of the real spherical harmonics after Ivanic and Ruedenberg
+//!
+//! J. Ivanic and K. Ruedenberg, "Rotation Matrices for Real Spherical Harmonics. Direct
+//! Determination by Recursion", J. Phys. Chem. 100 (1996), 6342-6347, with the corrections in
+//! J. Phys. Chem. A 102 (1998), 9099-9100.
+//!
+//! The matrix of degree 1 is the rotation matrix itself with the axes in the order (y, z, x) of
+//! the orders -1, 0 and 1. The matrix of degree l follows from the one of degree l - 1 and the
+//! one of degree 1 with the recursion
+//!
+//! R^l_{mn} = u^l_{mn} U^l_{mn} + v^l_{mn} V^l_{mn} + w^l_{mn} W^l_{mn},
+//!
+//! which only takes real arithmetic and O(l^2) operations per degree.
+
+use super::wigner::matrix_index;
+use crate::SphrsFloat;
+
+/// Element (m, n) of the matrix `r` of degree `l`
+#[inline(always)]
+fn at<T: Copy>(r: &[T], l: usize, m: i64, n: i64) -> T {
+    r[matrix_index(l, m, n)]
+}
+
+/// Function P^l_{i a b} of the recursion, with `r1` of degree 1 and `prev` of degree l - 1
+fn p<T: SphrsFloat>(r1: &[T], prev: &[T], l: usize, i: i64, a: i64, b: i64) -> T {
+    let li = l as i64;
+    let (ri1, rim1) = (at(r1, 1, i, 1), at(r1, 1, i, -1));
+    if b == li {
+        ri1 * at(prev, l - 1, a, li - 1) - rim1 * at(prev, l - 1, a, -li + 1)
+    } else if b == -li {
+        ri1 * at(prev, l - 1, a, -li + 1) + rim1 * at(prev, l - 1, a, li - 1)
+    } else {
+        at(r1, 1, i, 0) * at(prev, l - 1, a, b)
+    }
+}
+
+/// Rotation matrices of the real harmonics for the degrees 0, ..., `degree`
+///
+/// `r` is the 3 x 3 rotation matrix acting on (x, y, z). The matrices are stored like the Wigner
+/// matrices, element (m, n) of degree l at [`matrix_index(l, m, n)`](`matrix_index`).
+pub(crate) fn real_rotation_matrices<T: SphrsFloat>(r: &[[T; 3]; 3], degree: usize) -> Vec<Vec<T>> {
+    let mut out = Vec::with_capacity(degree + 1);
+    out.push(vec![T::one()]);
+    if degree == 0 {
+        return out;
+    }
+
+    // (y, z, x) for the orders (-1, 0, 1)
+    let axis = [1, 2, 0];
+    out.push(
+        (0..9)
+            .map(|k| r[axis[k / 3]][axis[k % 3]])
+            .collect::<Vec<T>>(),
+    );
+
+    let (half, two) = (T::from_f64(0.5).unwrap(), T::from_f64(2.0).unwrap());
+    let sqrt2 = two.sqrt();
+    for l in 2..=degree {
+        let li = l as i64;
+        let (r1, prev) = (&out[1], &out[l - 1]);
+        let p = |i, a, b| p(r1, prev, l, i, a, b);
+        let mut m_l = vec![T::zero(); (2 * l + 1) * (2 * l + 1)];
+        for m in -li..=li {
+            let ma = m.abs();
+            for n in -li..=li {
+                let denom = if n.abs() == li {
+                    (2 * li) * (2 * li - 1)
+                } else {
+                    (li + n) * (li - n)
+                };
+                let denom = T::from_i64(denom).unwrap();
+                let ratio = |num: i64| (T::from_i64(num).unwrap() / denom).sqrt();
+
+                let mut value = if ma < li {
+                    ratio((li + m) * (li - m)) * p(0, m, n)
+                } else {
+                    T::zero()
+                };
+                let v = if m == 0 {
+                    -half * ratio(2 * (li - 1) * li) * (p(1, 1, n) + p(-1, -1, n))
+                } else if m > 0 {
+                    let s = if m == 1 {
+                        sqrt2 * p(1, 0, n)
+                    } else {
+                        p(1, m - 1, n) - p(-1, -m + 1, n)
+                    };
+                    half * ratio((li + ma - 1) * (li + ma)) * s
+                } else {
+                    let s = if m == -1 {
+                        sqrt2 * p(-1, 0, n)
+                    } else {
+                        p(1, m + 1, n) + p(-1, -m - 1, n)
+                    };
+                    half * ratio((li + ma - 1) * (li + ma)) * s
+                };
+                value = value + v;
+                if m != 0 && ma < li - 1 {
+                    let w = if m > 0 {
+                        p(1, m + 1, n) + p(-1, -m - 1, n)
+                    } else {
+                        p(1, m - 1, n) - p(-1, -m + 1, n)
+                    };
+                    value = value - half * ratio((li - ma - 1) * (li - ma)) * w;
+                }
+                m_l[matrix_index(l, m, n)] = value;
+            }
+        }
+        out.push(m_l);
+    }
+    out
+}
@@ -0,0 +1,431 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rotation of spherical harmonics expansions
+//!
+//! Coefficients are with respect to the real, orthonormal spherical harmonics as computed by
+//! [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`). The [`wigner`] module provides the
+//! Wigner d- and D-matrices.
+//!
+//! [`rotate`] rotates expansions by Euler angles and [`estimate_rotation`] recovers the rotation
+//! between two expansions of the same function from their correlation on SO(3).
+//! [`SHCoefficients::rotate`] rotates real expansions by a [`Rotation`] with the real rotation
+//! matrices of Ivanic and Ruedenberg, without the detour through the complex harmonics.
+
+mod correlation;
+mod ivanic;
+pub mod wigner;
+
+pub use self::correlation::{estimate_rotation, so3_correlation, RotationEstimate};
+
+use num::Zero;
+use num_complex::Complex;
+
+use crate::basis::{
+    complex_to_real_coefficients, real_to_complex_coefficients, HarmonicsSet, RealSH,
+};
+use crate::coefficients::sh_index;
+use crate::legendre::zonal_norm;
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Rotation of three-dimensional space, given by its 3 x 3 matrix
+///
+/// The matrix acts on column vectors (x, y, z), i.e. a point p is rotated to R p. Rotations are
+/// created from ZYZ Euler angles, unit quaternions or rotation matrices, and are used for
+/// [`SHCoefficients::rotate`] and [`wigner_d_matrix`](`Rotation::wigner_d_matrix`).
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::Rotation;
+///
+/// // 90 degrees about the y-axis turns the z-axis into the x-axis
+/// let r = Rotation::from_euler(0.0f64, std::f64::consts::FRAC_PI_2, 0.0);
+/// assert!((r.matrix()[0][2] - 1.0).abs() < 1e-15);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation<T> {
+    matrix: [[T; 3]; 3],
+}
+
+impl<T: SphrsFloat> Rotation<T> {
+    /// Active rotation by the Euler angles `alpha`, `beta` and `gamma`
+    ///
+    /// R = R_z(alpha) R_y(beta) R_z(gamma), the convention of [`rotate`] and of the [`wigner`]
+    /// matrices.
+    pub fn from_euler(alpha: T, beta: T, gamma: T) -> Self {
+        let (sa, ca) = alpha.sin_cos();
+        let (sb, cb) = beta.sin_cos();
+        let (sg, cg) = gamma.sin_cos();
+        Rotation {
+            matrix: [
+                [ca * cb * cg - sa * sg, -ca * cb * sg - sa * cg, ca * sb],
+                [sa * cb * cg + ca * sg, -sa * cb * sg + ca * cg, sa * sb],
+                [-sb * cg, sb * sg, cb],
+            ],
+        }
+    }
+
+    /// Rotation given by the unit quaternion w + x i + y j + z k
+    ///
+    /// The quaternion is normalized first, such that quaternions which have lost their unit norm
+    /// by rounding are fine. q and -q give the same rotation. Returns
+    /// [`SphrsError::InvalidInput`] for the zero quaternion and for non-finite components.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::rotation::Rotation;
+    ///
+    /// // 90 degrees about the y-axis
+    /// let h = std::f64::consts::FRAC_1_SQRT_2;
+    /// let q = Rotation::from_quaternion(h, 0.0, h, 0.0).unwrap();
+    /// let e = Rotation::from_euler(0.0, std::f64::consts::FRAC_PI_2, 0.0);
+    /// for (a, b) in q.matrix().iter().flatten().zip(e.matrix().iter().flatten()) {
+    ///     assert!((a - b).abs() < 1e-15);
+    /// }
+    /// ```
+    pub fn from_quaternion(w: T, x: T, y: T, z: T) -> Result<Self, SphrsError> {
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        if !norm.is_finite() || norm == T::zero() {
+            return Err(SphrsError::InvalidInput(format!(
+                "quaternion must be finite and non-zero, got ({w:?}, {x:?}, {y:?}, {z:?})"
+            )));
+        }
+        let (w, x, y, z) = (w / norm, x / norm, y / norm, z / norm);
+        let (one, two) = (T::one(), T::from_f64(2.0).unwrap());
+        Ok(Rotation {
+            matrix: [
+                [
+                    one - two * (y * y + z * z),
+                    two * (x * y - w * z),
+                    two * (x * z + w * y),
+                ],
+                [
+                    two * (x * y + w * z),
+                    one - two * (x * x + z * z),
+                    two * (y * z - w * x),
+                ],
+                [
+                    two * (x * z - w * y),
+                    two * (y * z + w * x),
+                    one - two * (x * x + y * y),
+                ],
+            ],
+        })
+    }
+
+    /// Rotation given by its 3 x 3 matrix, row by row
+    ///
+    /// Matrices of libraries which store them column by column, e.g. the arrays of
+    /// `glam::DMat3::to_cols_array_2d`, have to be transposed. Returns
+    /// [`SphrsError::InvalidInput`] unless the matrix is orthogonal with determinant one up to
+    /// about the square root of the machine epsilon, i.e. a rotation and not a reflection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::rotation::Rotation;
+    ///
+    /// // 90 degrees about the z-axis
+    /// let r = Rotation::from_matrix([[0.0f64, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+    /// assert!(r.is_ok());
+    /// // Reflections are not rotations
+    /// let r = Rotation::from_matrix([[-1.0f64, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    /// assert!(r.is_err());
+    /// ```
+    pub fn from_matrix(matrix: [[T; 3]; 3]) -> Result<Self, SphrsError> {
+        let tol = T::epsilon().sqrt();
+        let mut orthogonal = true;
+        for i in 0..3 {
+            for j in 0..3 {
+                let dot = (0..3).fold(T::zero(), |acc, k| acc + matrix[i][k] * matrix[j][k]);
+                let expected = if i == j { T::one() } else { T::zero() };
+                // Also catches NaN
+                orthogonal &= (dot - expected).abs() <= tol;
+            }
+        }
+        let m = &matrix;
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        if !orthogonal || det <= T::zero() {
+            return Err(SphrsError::InvalidInput(format!(
+                "matrix is not a rotation: {matrix:?}"
+            )));
+        }
+        Ok(Rotation { matrix })
+    }
+
+    /// Returns the rotation matrix, row by row
+    pub fn matrix(&self) -> [[T; 3]; 3] {
+        self.matrix
+    }
+
+    /// Euler angles (alpha, beta, gamma) of the rotation in the convention of
+    /// [`from_euler`](`Rotation::from_euler`)
+    ///
+    /// beta is in [0, pi], alpha and gamma in (-pi, pi]. For beta = 0 and beta = pi, where only
+    /// the sum or the difference of alpha and gamma is determined, gamma is zero.
+    pub fn euler_angles(&self) -> (T, T, T) {
+        let r = &self.matrix;
+        let sb = (r[0][2] * r[0][2] + r[1][2] * r[1][2]).sqrt();
+        let beta = sb.atan2(r[2][2]);
+        if sb > T::epsilon() {
+            (r[1][2].atan2(r[0][2]), beta, r[2][1].atan2(-r[2][0]))
+        } else if r[2][2] > T::zero() {
+            (r[1][0].atan2(r[0][0]), beta, T::zero())
+        } else {
+            ((-r[0][1]).atan2(r[1][1]), beta, T::zero())
+        }
+    }
+
+    /// Wigner D-matrix of degree `l` of the rotation, see [`wigner::big_d_matrix`]
+    pub fn wigner_d_matrix(&self, l: usize) -> Vec<Complex<T>> {
+        let (alpha, beta, gamma) = self.euler_angles();
+        wigner::big_d_matrix(l, alpha, beta, gamma)
+    }
+
+    /// Rotation matrices of the real harmonics of the degrees 0, ..., `degree`
+    ///
+    /// The coefficients of degree l of the rotated expansion f(R^-1 p) are the matrix of degree l
+    /// times those of f. The matrices are stored like the [`wigner`] matrices, element (m, n) of
+    /// degree l at [`wigner::matrix_index(l, m, n)`](`wigner::matrix_index`). Precomputing them
+    /// pays off when many expansions are rotated by the same rotation.
+    pub fn real_sh_matrices(&self, degree: usize) -> Vec<Vec<T>> {
+        ivanic::real_rotation_matrices(&self.matrix, degree)
+    }
+}
+
+/// Rotate a zonal function such that its symmetry axis points along `axis`
+///
+/// `zonal` contains the coefficients of Y_l0 for l = 0, ..., `zonal.len() - 1`. The returned
+/// coefficients of the rotated function are
+/// c_lm = sqrt(4 pi / (2l + 1)) z_l Y_lm(`axis`).
+pub fn rotate_zonal<T: SphrsFloat>(zonal: &[T], axis: &impl SHCoordinates<T>) -> SHCoefficients<T> {
+    assert!(!zonal.is_empty());
+    let degree = zonal.len() - 1;
+    let y = HarmonicsSet::new(degree, RealSH::Spherical).eval(axis);
+    let mut out = Vec::with_capacity(y.len());
+    for (l, &z) in zonal.iter().enumerate() {
+        let factor = z / zonal_norm::<T>(l);
+        let start = l * l;
+        out.extend(y[start..start + 2 * l + 1].iter().map(|&v| factor * v));
+    }
+    SHCoefficients::from_vec(degree, out)
+}
+
+/// Rotate an expansion by the Euler angles `alpha`, `beta` and `gamma`
+///
+/// Returns the coefficients of the rotated function f(R^-1 p), where R is the active rotation
+/// about the z-, y- and z-axis by gamma, beta and alpha (see [`wigner`] for the convention). The
+/// coefficients of each degree are transformed with the Wigner D-matrix of that degree.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::rotate;
+/// use sphrs::SHCoefficients;
+///
+/// // Rotating Y_1,0 (along z) by 90 degrees about the y-axis turns it into Y_1,1 (along x)
+/// let z = SHCoefficients::from_vec(1, vec![0.0f64, 0.0, 1.0, 0.0]);
+/// let x = rotate(&z, 0.0, std::f64::consts::FRAC_PI_2, 0.0);
+/// assert!((x.get(1, 1).unwrap() - 1.0).abs() < 1e-14);
+/// ```
+pub fn rotate<T: SphrsFloat>(
+    coeffs: &SHCoefficients<T>,
+    alpha: T,
+    beta: T,
+    gamma: T,
+) -> SHCoefficients<T> {
+    let c = real_to_complex_coefficients(coeffs);
+    let mut out: SHCoefficients<Complex<T>> = SHCoefficients::zeros(coeffs.degree());
+    for l in 0..=coeffs.degree() {
+        let d = wigner::big_d_matrix(l, alpha, beta, gamma);
+        let li = l as i64;
+        for m1 in -li..=li {
+            out.as_mut_slice()[sh_index(li, m1)] = (-li..=li).fold(Complex::zero(), |acc, m2| {
+                acc + d[wigner::matrix_index(l, m1, m2)] * c.as_slice()[sh_index(li, m2)]
+            });
+        }
+    }
+    complex_to_real_coefficients(&out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Coordinates, Normalization};
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn rotate_zonal_to_z_axis_is_identity() {
+        let zonal = vec![1.0f64, -0.5, 0.25, 0.125];
+        let axis = Coordinates::cartesian(0.0, 0.0, 1.0);
+        let coeffs = rotate_zonal(&zonal, &axis);
+        for l in 0..=3i64 {
+            for m in -l..=l {
+                let expected = if m == 0 { zonal[l as usize] } else { 0.0 };
+                assert_relative_eq!(*coeffs.get(l, m).unwrap(), expected, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_zonal_preserves_power() {
+        let zonal = vec![0.3f64, 1.0, -0.7, 0.2];
+        let axis = Coordinates::cartesian(0.4, -1.2, 0.3);
+        let coeffs = rotate_zonal(&zonal, &axis);
+        for l in 0..=3i64 {
+            let power: f64 = (-l..=l).map(|m| coeffs.get(l, m).unwrap().powi(2)).sum();
+            assert_relative_eq!(power, zonal[l as usize].powi(2), epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn ivanic_ruedenberg_matches_wigner() {
+        let coeffs =
+            SHCoefficients::from_vec(12, (0..169).map(|i| (i as f64 * 0.37).sin()).collect());
+        for (alpha, beta, gamma) in [(0.7f64, 1.2, -0.4), (-2.1, 0.05, 3.0), (0.3, 3.1, 1.1)] {
+            let expected = rotate(&coeffs, alpha, beta, gamma);
+            let rotated = coeffs.rotate(&Rotation::from_euler(alpha, beta, gamma));
+            for (a, b) in rotated.as_slice().iter().zip(expected.as_slice()) {
+                assert_relative_eq!(*a, *b, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn real_sh_matrices_are_orthogonal() {
+        let r = Rotation::from_euler(1.3f64, 2.2, -0.6);
+        for (l, m) in r.real_sh_matrices(8).iter().enumerate() {
+            let n = 2 * l + 1;
+            for i in 0..n {
+                for j in 0..n {
+                    let dot: f64 = (0..n).map(|k| m[i * n + k] * m[j * n + k]).sum();
+                    assert_relative_eq!(dot, if i == j { 1.0 } else { 0.0 }, epsilon = 1e-13);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_keeps_normalization() {
+        let r = Rotation::from_euler(0.4f64, 0.9, 2.0);
+        let coeffs = SHCoefficients::from_vec(4, (0..25).map(|i| (i as f64).cos()).collect());
+        let expected = coeffs.rotate(&r);
+        for n in [Normalization::Schmidt, Normalization::Unnormalized] {
+            let rotated = coeffs.to_normalization(n).rotate(&r);
+            assert_eq!(rotated.normalization(), n);
+            let rotated = rotated.to_normalization(Normalization::Orthonormal);
+            for (a, b) in rotated.as_slice().iter().zip(expected.as_slice()) {
+                assert_relative_eq!(*a, *b, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_z_matches_wigner() {
+        let coeffs =
+            SHCoefficients::from_vec(10, (0..121).map(|i| (i as f64 * 0.61).cos()).collect());
+        for alpha in [0.3f64, -1.7, 2.9] {
+            let expected = rotate(&coeffs, alpha, 0.0, 0.0);
+            let rotated = coeffs.rotate_z(alpha);
+            let general = coeffs.rotate(&Rotation::from_euler(alpha - 0.5, 0.0, 0.5));
+            for ((a, b), c) in rotated
+                .as_slice()
+                .iter()
+                .zip(general.as_slice())
+                .zip(expected.as_slice())
+            {
+                assert_relative_eq!(*a, *c, epsilon = 1e-12);
+                assert_relative_eq!(*b, *c, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_inputs_agree() {
+        for (alpha, beta, gamma) in [
+            (0.7f64, 1.2, -0.4),
+            (-2.1, 0.0, 1.0),
+            (0.3, PI, 1.1),
+            (3.0, 2.5, -3.0),
+        ] {
+            let e = Rotation::from_euler(alpha, beta, gamma);
+            // Product of the quaternions of the three elementary rotations
+            let (a, b, g) = (alpha / 2.0, beta / 2.0, gamma / 2.0);
+            let q = Rotation::from_quaternion(
+                2.0 * (b.cos() * (a + g).cos()),
+                2.0 * (-b.sin() * (a - g).sin()),
+                2.0 * (b.sin() * (a - g).cos()),
+                2.0 * (b.cos() * (a + g).sin()),
+            )
+            .unwrap();
+            let m = Rotation::from_matrix(e.matrix()).unwrap();
+            let (a, b, g) = e.euler_angles();
+            let angles = Rotation::from_euler(a, b, g);
+            for r in [q, m, angles] {
+                for (x, y) in r.matrix().iter().flatten().zip(e.matrix().iter().flatten()) {
+                    assert_relative_eq!(*x, *y, epsilon = 1e-14);
+                }
+            }
+            for l in 0..4 {
+                let expected = wigner::big_d_matrix(l, alpha, beta, gamma);
+                for (x, y) in e.wigner_d_matrix(l).iter().zip(&expected) {
+                    assert_relative_eq!(*x, *y, epsilon = 1e-13);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_rotation_inputs() {
+        assert!(Rotation::from_quaternion(0.0f64, 0.0, 0.0, 0.0).is_err());
+        assert!(Rotation::from_quaternion(f64::NAN, 0.0, 0.0, 1.0).is_err());
+        let scaled = [[2.0f64, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 2.0]];
+        assert!(Rotation::from_matrix(scaled).is_err());
+        let nan = [[f64::NAN, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert!(Rotation::from_matrix(nan).is_err());
+    }
+
+    #[test]
+    fn rotate_matches_rotated_function() {
+        let (alpha, beta, gamma) = (0.7f64, 1.2, -0.4);
+        let coeffs = SHCoefficients::from_vec(
+            3,
+            (0..16)
+                .map(|i| ((i * 37) % 11) as f64 / 5.0 - 1.0)
+                .collect(),
+        );
+        let rotated = rotate(&coeffs, alpha, beta, gamma);
+        let sh = HarmonicsSet::new(3, RealSH::Spherical);
+        let rz = |a: f64, p: [f64; 3]| {
+            [
+                a.cos() * p[0] - a.sin() * p[1],
+                a.sin() * p[0] + a.cos() * p[1],
+                p[2],
+            ]
+        };
+        let ry = |b: f64, p: [f64; 3]| {
+            [
+                b.cos() * p[0] + b.sin() * p[2],
+                p[1],
+                -b.sin() * p[0] + b.cos() * p[2],
+            ]
+        };
+        for p in crate::sampling::fibonacci::<f64>(10, 0) {
+            // R^-1 p with R = Rz(alpha) Ry(beta) Rz(gamma)
+            let q = rz(-gamma, ry(-beta, rz(-alpha, [p.x(), p.y(), p.z()])));
+            let q = Coordinates::cartesian(q[0], q[1], q[2]);
+            let expected: f64 = sh.eval_sum(&q, coeffs.as_slice()).unwrap();
+            let value: f64 = sh.eval_sum(&p, rotated.as_slice()).unwrap();
+            assert_relative_eq!(value, expected, epsilon = 1e-12);
+        }
+    }
+}
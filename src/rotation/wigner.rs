@@ -0,0 +1,270 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Wigner d- and D-matrices
+//!
+//! The Wigner D-matrix of degree l is
+//!
+//! D^l_{m1 m2}(alpha, beta, gamma) = e^{-i m1 alpha} d^l_{m1 m2}(beta) e^{-i m2 gamma},
+//!
+//! where alpha, beta and gamma are the Euler angles of an active rotation about the z-, y- and
+//! z-axis (in this order, about the fixed axes from right to left) and d^l is the real Wigner
+//! small d-matrix. This is the convention of Wikipedia and of most quantum mechanics textbooks.
+//! With it, d^l_{m0}(beta) = sqrt((l - m)! / (l + m)!) P_l^m(cos(beta)) with the associated
+//! Legendre functions of [`legendre::plm`](`crate::legendre::plm`).
+//!
+//! The elements are computed with the three-term recurrence in the degree for fixed orders,
+//! starting from the closed form of degree max(|m1|, |m2|). The recurrence is stable like the one
+//! of the associated Legendre functions; the starting values are computed in logarithms, such that
+//! the binomial coefficients do not overflow.
+//!
+//! Matrices are stored row-major with the orders running from -l to l, i.e. element (m1, m2) is
+//! at [`matrix_index(l, m1, m2)`](`matrix_index`).
+
+use num_complex::Complex;
+
+use crate::SphrsFloat;
+
+/// Index of element (m1, m2) in a matrix of degree `l`, `(m1 + l) (2 l + 1) + m2 + l`
+#[inline(always)]
+pub fn matrix_index(l: usize, m1: i64, m2: i64) -> usize {
+    let l = l as i64;
+    debug_assert!(m1.abs() <= l && m2.abs() <= l);
+    ((m1 + l) * (2 * l + 1) + m2 + l) as usize
+}
+
+/// Wigner small d-matrix element d^l_{m1 m2}(beta)
+///
+/// # Panics
+///
+/// Panics if |m1| > l or |m2| > l.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::wigner::small_d;
+///
+/// let beta = 0.8f64;
+/// assert!((small_d(1, 1, 0, beta) + beta.sin() / 2f64.sqrt()).abs() < 1e-15);
+/// assert!((small_d(1, -1, -1, beta) - (1.0 + beta.cos()) / 2.0).abs() < 1e-15);
+/// ```
+pub fn small_d<T: SphrsFloat>(l: usize, m1: i64, m2: i64, beta: T) -> T {
+    let li = l as i64;
+    assert!(
+        m1.abs() <= li && m2.abs() <= li,
+        "orders ({m1}, {m2}) exceed degree {l}"
+    );
+    let f = |v: i64| T::from_i64(v).unwrap();
+    let l0 = m1.abs().max(m2.abs());
+    let x = beta.cos();
+    let (m1f, m2f) = (f(m1), f(m2));
+    let mut prev = T::zero();
+    let mut cur = d_start(l0, m1, m2, beta);
+    for k in (l0 + 1)..=li {
+        let kf = f(k);
+        let scale = kf * f(2 * k - 1) / ((kf * kf - m1f * m1f) * (kf * kf - m2f * m2f)).sqrt();
+        let mixed = if k > 1 {
+            m1f * m2f / (kf * f(k - 1))
+        } else {
+            T::zero()
+        };
+        let lower = if k - 1 > l0 {
+            let k1 = f(k - 1);
+            ((k1 * k1 - m1f * m1f) * (k1 * k1 - m2f * m2f)).sqrt() / (k1 * f(2 * k - 1))
+        } else {
+            T::zero()
+        };
+        let next = scale * ((x - mixed) * cur - lower * prev);
+        prev = cur;
+        cur = next;
+    }
+    cur
+}
+
+/// d^l_{m1 m2}(beta) for l = max(|m1|, |m2|), reduced by symmetry to
+/// d^l_{l m} = (-1)^(l - m) sqrt(binomial(2 l, l + m)) cos(beta / 2)^(l + m) sin(beta / 2)^(l - m)
+fn d_start<T: SphrsFloat>(l: i64, m1: i64, m2: i64, beta: T) -> T {
+    // d_{m1 m2} = (-1)^(m1 - m2) d_{m2 m1} = d_{-m2 -m1}
+    let (m, sign) = if m1 == l {
+        (m2, 1)
+    } else if m1 == -l {
+        (-m2, if (l + m2) % 2 == 0 { 1 } else { -1 })
+    } else if m2 == l {
+        (m1, if (l - m1) % 2 == 0 { 1 } else { -1 })
+    } else {
+        (-m1, 1)
+    };
+    let sign = if (l - m) % 2 == 0 { sign } else { -sign };
+    let half = beta / T::from_f64(2.0).unwrap();
+    let (c, s) = (half.cos(), half.sin());
+    let ln_factorial =
+        |n: i64| (2..=n).fold(T::zero(), |acc, k| acc + T::from_i64(k).unwrap().ln());
+    let mut ln = (ln_factorial(2 * l) - ln_factorial(l + m) - ln_factorial(l - m))
+        / T::from_f64(2.0).unwrap();
+    // Powers with exponent zero are skipped, such that 0^0 = 1 at beta = 0 and beta = pi
+    for (base, exponent) in [(c, l + m), (s, l - m)] {
+        if exponent > 0 {
+            ln = ln + T::from_i64(exponent).unwrap() * base.abs().ln();
+        }
+    }
+    let mut value = ln.exp();
+    // cos(beta / 2) and sin(beta / 2) may be negative outside of 0 <= beta <= 2 pi
+    if (c < T::zero() && (l + m) % 2 != 0) != (s < T::zero() && (l - m) % 2 != 0) {
+        value = -value;
+    }
+    if sign < 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Wigner small d-matrix d^l(beta), stored as described in the [module](`self`) documentation
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::wigner::{matrix_index, small_d_matrix};
+///
+/// let d = small_d_matrix(1, 0.8f64);
+/// assert_eq!(d.len(), 9);
+/// assert!((d[matrix_index(1, 0, 0)] - 0.8f64.cos()).abs() < 1e-15);
+/// ```
+pub fn small_d_matrix<T: SphrsFloat>(l: usize, beta: T) -> Vec<T> {
+    let li = l as i64;
+    let mut out = Vec::with_capacity((2 * l + 1) * (2 * l + 1));
+    for m1 in -li..=li {
+        for m2 in -li..=li {
+            out.push(small_d(l, m1, m2, beta));
+        }
+    }
+    out
+}
+
+/// Wigner D-matrix element D^l_{m1 m2}(alpha, beta, gamma)
+///
+/// # Panics
+///
+/// Panics if |m1| > l or |m2| > l.
+pub fn big_d<T: SphrsFloat>(l: usize, m1: i64, m2: i64, alpha: T, beta: T, gamma: T) -> Complex<T> {
+    let phase = -(T::from_i64(m1).unwrap() * alpha + T::from_i64(m2).unwrap() * gamma);
+    Complex::from_polar(small_d(l, m1, m2, beta), phase)
+}
+
+/// Wigner D-matrix D^l(alpha, beta, gamma), stored as described in the [module](`self`)
+/// documentation
+///
+/// # Example
+///
+/// ```
+/// use sphrs::rotation::wigner::{big_d_matrix, matrix_index};
+///
+/// // A rotation about the z-axis only changes the phases
+/// let d = big_d_matrix(2, 0.3f64, 0.0, 0.2);
+/// let v = d[matrix_index(2, 1, 1)];
+/// assert!((v.re - 0.5f64.cos()).abs() < 1e-15 && (v.im + 0.5f64.sin()).abs() < 1e-15);
+/// assert!(d[matrix_index(2, 1, 0)].norm() < 1e-15);
+/// ```
+pub fn big_d_matrix<T: SphrsFloat>(l: usize, alpha: T, beta: T, gamma: T) -> Vec<Complex<T>> {
+    let li = l as i64;
+    let mut out = Vec::with_capacity((2 * l + 1) * (2 * l + 1));
+    for m1 in -li..=li {
+        for m2 in -li..=li {
+            out.push(big_d(l, m1, m2, alpha, beta, gamma));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legendre::plm;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn closed_forms() {
+        for &beta in &[0.0f64, 0.4, 1.3, 2.2, std::f64::consts::PI, 4.0, -0.7] {
+            let (c, s) = (beta.cos(), beta.sin());
+            assert_relative_eq!(small_d(0, 0, 0, beta), 1.0);
+            assert_relative_eq!(small_d(1, 1, 1, beta), (1.0 + c) / 2.0, epsilon = 1e-15);
+            assert_relative_eq!(small_d(1, 1, 0, beta), -s / 2f64.sqrt(), epsilon = 1e-15);
+            assert_relative_eq!(small_d(1, 0, 1, beta), s / 2f64.sqrt(), epsilon = 1e-15);
+            assert_relative_eq!(small_d(1, 1, -1, beta), (1.0 - c) / 2.0, epsilon = 1e-15);
+            assert_relative_eq!(small_d(1, 0, 0, beta), c, epsilon = 1e-15);
+            assert_relative_eq!(
+                small_d(2, 2, 1, beta),
+                -(1.0 + c) / 2.0 * s,
+                epsilon = 1e-15
+            );
+            assert_relative_eq!(
+                small_d(2, 1, 1, beta),
+                (1.0 + c) / 2.0 * (2.0 * c - 1.0),
+                epsilon = 1e-15
+            );
+            assert_relative_eq!(
+                small_d(2, 0, 0, beta),
+                (3.0 * c * c - 1.0) / 2.0,
+                epsilon = 1e-15
+            );
+            // Relation to the associated Legendre functions, which assume sin(beta) >= 0
+            if s < 0.0 {
+                continue;
+            }
+            for l in 0..=20usize {
+                for m in 0..=l {
+                    let ratio: f64 = (l - m + 1..=l + m).map(|k| k as f64).product();
+                    assert_relative_eq!(
+                        small_d(l, m as i64, 0, beta),
+                        plm(l, m, c) / ratio.sqrt(),
+                        epsilon = 1e-13
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetries_and_unitarity() {
+        let (alpha, beta, gamma) = (0.4f64, 1.1, -2.0);
+        for l in [0usize, 1, 4, 9, 60] {
+            let n = 2 * l + 1;
+            let li = l as i64;
+            let d = small_d_matrix(l, beta);
+            for m1 in -li..=li {
+                for m2 in -li..=li {
+                    let v = d[matrix_index(l, m1, m2)];
+                    let sign = if (m1 - m2) % 2 == 0 { 1.0 } else { -1.0 };
+                    assert_relative_eq!(d[matrix_index(l, m2, m1)], sign * v, epsilon = 1e-13);
+                    assert_relative_eq!(d[matrix_index(l, -m1, -m2)], sign * v, epsilon = 1e-13);
+                }
+            }
+            let big = big_d_matrix(l, alpha, beta, gamma);
+            for i in 0..n {
+                for j in 0..n {
+                    let dot: Complex<f64> =
+                        (0..n).map(|k| big[i * n + k] * big[j * n + k].conj()).sum();
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!((dot - expected).norm() < 1e-12, "{l} {i} {j}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn high_degree() {
+        // No overflow of the binomial coefficients, the rows stay normalized
+        let l = 1500;
+        let beta = 2.5f64;
+        for m1 in [-1500i64, -700, 0, 3, 1499] {
+            let norm: f64 = (-(l as i64)..=l as i64)
+                .map(|m2| small_d(l, m1, m2, beta).powi(2))
+                .sum();
+            assert_relative_eq!(norm, 1.0, epsilon = 1e-9);
+        }
+    }
+}
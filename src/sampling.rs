@@ -0,0 +1,260 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Quasi-Monte Carlo direction sequences on the sphere
+//!
+//! Low-discrepancy point sets in the unit square are mapped onto the unit sphere with the
+//! equal-area map `z = 1 - 2u`, `phi = 2 pi v`. Uniformly weighted sums over the resulting
+//! directions (such as in [`transform::project`](`crate::transform::project`)) converge
+//! considerably faster than sums over random directions.
+//!
+//! All sequences are deterministic. A `seed` of `0` yields the plain sequence, any other seed
+//! randomizes the sequence (Cranley-Patterson rotation for Halton and Hammersley points, digital
+//! shift for Sobol points) while preserving its low-discrepancy structure.
+
+use crate::{Coordinates, SphrsError, SphrsFloat};
+
+/// SplitMix64 pseudo random number generator, used to derive shifts from seeds
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Two shifts in [0, 1) derived from `seed` (zero for a seed of zero)
+fn shifts(seed: u64) -> (f64, f64) {
+    if seed == 0 {
+        return (0.0, 0.0);
+    }
+    let mut state = seed;
+    let a = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+    let b = (splitmix64(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+    (a, b)
+}
+
+/// Radical inverse of `i` in base `base` (van der Corput sequence)
+pub fn radical_inverse(base: u64, mut i: u64) -> f64 {
+    assert!(base >= 2);
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut out = 0.0;
+    while i > 0 {
+        out += (i % base) as f64 * factor;
+        i /= base;
+        factor *= inv_base;
+    }
+    out
+}
+
+/// Map a point of the unit square onto the unit sphere, preserving area
+pub fn square_to_sphere<T: SphrsFloat>(u: f64, v: f64) -> Coordinates<T> {
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * v;
+    Coordinates::cartesian(
+        T::from_f64(r * phi.cos()).unwrap(),
+        T::from_f64(r * phi.sin()).unwrap(),
+        T::from_f64(z).unwrap(),
+    )
+}
+
+/// Add a shift modulo one
+fn rotate(x: f64, shift: f64) -> f64 {
+    let y = x + shift;
+    if y >= 1.0 {
+        y - 1.0
+    } else {
+        y
+    }
+}
+
+/// First `n` directions of the Halton sequence (bases 2 and 3)
+///
+/// # Example
+///
+/// ```
+/// use sphrs::sampling::halton;
+///
+/// let directions = halton::<f64>(128, 0);
+/// assert_eq!(directions.len(), 128);
+/// ```
+pub fn halton<T: SphrsFloat>(n: usize, seed: u64) -> Vec<Coordinates<T>> {
+    let (su, sv) = shifts(seed);
+    // The first element of the sequence is skipped, it coincides with the north pole.
+    (1..=n as u64)
+        .map(|i| {
+            square_to_sphere(
+                rotate(radical_inverse(2, i), su),
+                rotate(radical_inverse(3, i), sv),
+            )
+        })
+        .collect()
+}
+
+/// Spherical Hammersley set with `n` directions
+///
+/// Unlike the other sequences, the Hammersley set depends on `n`, i.e. it is not possible to add
+/// further directions to an existing set.
+pub fn hammersley<T: SphrsFloat>(n: usize, seed: u64) -> Vec<Coordinates<T>> {
+    let (su, sv) = shifts(seed);
+    (0..n as u64)
+        .map(|i| {
+            square_to_sphere(
+                rotate((i as f64 + 0.5) / n as f64, su),
+                rotate(radical_inverse(2, i), sv),
+            )
+        })
+        .collect()
+}
+
+/// Direction numbers of the first two dimensions of the Sobol sequence
+fn sobol_direction_numbers() -> ([u32; 32], [u32; 32]) {
+    let mut v1 = [0u32; 32];
+    let mut v2 = [0u32; 32];
+    // Second dimension: primitive polynomial x + 1 with initial direction number m_1 = 1
+    let mut m = 1u32;
+    for k in 0..32 {
+        v1[k] = 1 << (31 - k);
+        if k > 0 {
+            m ^= m << 1;
+        }
+        v2[k] = m << (31 - k);
+    }
+    (v1, v2)
+}
+
+/// First `n` directions of the two-dimensional Sobol sequence
+///
+/// The sequence has 2^32 directions, larger `n` return [`SphrsError::InvalidInput`].
+pub fn sobol<T: SphrsFloat>(n: usize, seed: u64) -> Result<Vec<Coordinates<T>>, SphrsError> {
+    let Ok(count) = u32::try_from(n) else {
+        return Err(SphrsError::InvalidInput(format!(
+            "Sobol sequence has fewer than {n} directions"
+        )));
+    };
+    let (v1, v2) = sobol_direction_numbers();
+    let (shift1, shift2) = if seed == 0 {
+        (0u32, 0u32)
+    } else {
+        let mut state = seed;
+        let s = splitmix64(&mut state);
+        ((s >> 32) as u32, s as u32)
+    };
+    let scale = 1.0 / (1u64 << 32) as f64;
+    // Offset by half a grid cell in the first dimension to avoid the pole
+    let offset = 0.5 / n as f64;
+    Ok((0..count)
+        .map(|i| {
+            let gray = i ^ (i >> 1);
+            let (mut x1, mut x2) = (0u32, 0u32);
+            for k in 0..32 {
+                if gray & (1 << k) != 0 {
+                    x1 ^= v1[k];
+                    x2 ^= v2[k];
+                }
+            }
+            square_to_sphere(
+                rotate((x1 ^ shift1) as f64 * scale, offset),
+                (x2 ^ shift2) as f64 * scale,
+            )
+        })
+        .collect())
+}
+
+/// Spherical Fibonacci lattice with `n` directions
+pub fn fibonacci<T: SphrsFloat>(n: usize, seed: u64) -> Vec<Coordinates<T>> {
+    let (su, sv) = shifts(seed);
+    let golden = (5f64.sqrt() - 1.0) / 2.0;
+    (0..n)
+        .map(|i| {
+            square_to_sphere(
+                rotate((i as f64 + 0.5) / n as f64, su),
+                rotate((i as f64 * golden).fract(), sv),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHCoordinates;
+    use approx::assert_relative_eq;
+
+    type Generator = fn(usize, u64) -> Vec<Coordinates<f64>>;
+
+    const GENERATORS: [Generator; 4] = [
+        halton,
+        hammersley,
+        |n, seed| sobol(n, seed).unwrap(),
+        fibonacci,
+    ];
+
+    #[test]
+    fn radical_inverse_base_2() {
+        assert_relative_eq!(radical_inverse(2, 1), 0.5);
+        assert_relative_eq!(radical_inverse(2, 2), 0.25);
+        assert_relative_eq!(radical_inverse(2, 3), 0.75);
+        assert_relative_eq!(radical_inverse(3, 1), 1.0 / 3.0);
+        assert_relative_eq!(radical_inverse(3, 4), 1.0 / 3.0 + 1.0 / 9.0);
+    }
+
+    #[test]
+    fn sobol_first_points() {
+        let (v1, v2) = sobol_direction_numbers();
+        // Direction numbers m_1 = 1 and m_2 = 3 of the second dimension
+        let scale = 1.0 / (1u64 << 32) as f64;
+        assert_relative_eq!(v1[0] as f64 * scale, 0.5);
+        assert_relative_eq!(v2[0] as f64 * scale, 0.5);
+        assert_relative_eq!((v2[0] ^ v2[1]) as f64 * scale, 0.25);
+        assert_relative_eq!(v2[1] as f64 * scale, 0.75);
+        if let Ok(n) = usize::try_from(1u64 << 32) {
+            assert!(matches!(
+                sobol::<f64>(n, 0),
+                Err(SphrsError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn unit_directions_and_low_discrepancy() {
+        let n = 4096;
+        for generator in GENERATORS {
+            let directions = generator(n, 0);
+            assert_eq!(directions.len(), n);
+            let mut mean = [0.0; 3];
+            let mut zz = 0.0;
+            for p in &directions {
+                assert_relative_eq!(p.r(), 1.0, epsilon = 1e-12);
+                mean[0] += p.x() / n as f64;
+                mean[1] += p.y() / n as f64;
+                mean[2] += p.z() / n as f64;
+                zz += p.z() * p.z() / n as f64;
+            }
+            for m in mean {
+                assert!(m.abs() < 2e-3, "{m}");
+            }
+            assert!((zz - 1.0 / 3.0).abs() < 2e-3);
+        }
+    }
+
+    #[test]
+    fn deterministic_seeding() {
+        for generator in GENERATORS {
+            let a = generator(64, 42);
+            let b = generator(64, 42);
+            let c = generator(64, 7);
+            for (a, b) in a.iter().zip(b.iter()) {
+                assert_eq!(a.z(), b.z());
+                assert_eq!(a.phi(), b.phi());
+            }
+            assert!(a.iter().zip(c.iter()).any(|(a, c)| a.z() != c.z()));
+        }
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spherical harmonic transforms on iso-latitude sampling grids
+//!
+//! A function with bandlimit L, i.e. with harmonics of the degrees 0, ..., L - 1 only, is
+//! determined by a finite number of samples. The sampling theorems implemented here place the
+//! samples on rings of constant colatitude with equally spaced azimuths and compute the
+//! coefficients exactly (up to rounding). All coefficients refer to the real, orthonormal
+//! spherical harmonics as computed by [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`).
+//!
+//! The transform is separable: the samples of each ring are first reduced to their Fourier sums
+//! in the azimuth, which are then projected onto the associated Legendre functions of the ring.
+//! This needs O(L^3) operations instead of the O(L^4) of evaluating every harmonic at every
+//! sample.
+//!
+//! Samples are stored ring by ring from north to south, see [`Sampling`] for the layouts.
+//! [`forward`] analyzes samples into coefficients and [`inverse`] synthesizes the samples of an
+//! expansion. [`synthesize`] evaluates an expansion on arbitrary products of colatitudes and
+//! azimuths, e.g. on the pixel grid of an image.
+//!
+//! With the `rayon` feature, the rings are processed in parallel. The results do not depend on
+//! the number of threads.
+//!
+//! # Example
+//!
+//! ```
+//! use sphrs::basis::{HarmonicsSet, RealSH};
+//! use sphrs::sht::{forward, Sampling};
+//! use sphrs::Coordinates;
+//!
+//! let bandlimit = 4;
+//! let sampling = Sampling::DriscollHealy;
+//! let sh = HarmonicsSet::new(bandlimit - 1, RealSH::Spherical);
+//! let coeffs: Vec<f64> = (0..sh.num_sh()).map(|i| (i as f64).cos()).collect();
+//!
+//! // Sample the function on the grid
+//! let mut values = Vec::new();
+//! for theta in sampling.thetas::<f64>(bandlimit) {
+//!     for phi in sampling.phis::<f64>(bandlimit) {
+//!         let p = Coordinates::spherical(1.0, theta, phi);
+//!         values.push(sh.eval_sum(&p, &coeffs).unwrap());
+//!     }
+//! }
+//!
+//! let analyzed = forward(sampling, bandlimit, &values).unwrap();
+//! for (a, b) in analyzed.as_slice().iter().zip(&coeffs) {
+//!     assert!((a - b).abs() < 1e-12);
+//! }
+//! ```
+
+mod mw;
+mod sampling;
+
+pub use crate::quadrature::gauss_legendre;
+pub use sampling::Sampling;
+
+use crate::coefficients::{num_coefficients, sh_index};
+use crate::legendre::{table_index, LegendreTable};
+use crate::parallel;
+use crate::reduce::blockwise_sum_by;
+use crate::{SHCoefficients, SphrsError, SphrsFloat};
+
+/// Number of consecutive rings projected sequentially in [`forward`]
+///
+/// The projections of the blocks are added pairwise (see `reduce`), such that the coefficients
+/// do not depend on the number of threads.
+const RING_BLOCK_SIZE: usize = 16;
+
+/// Sign (-1)^m of the real harmonics relative to the Legendre table
+#[inline(always)]
+fn order_sign<T: SphrsFloat>(m: usize) -> T {
+    if m.is_multiple_of(2) {
+        T::one()
+    } else {
+        -T::one()
+    }
+}
+
+/// cos(m phi_k) and sin(m phi_k) for m < `bandlimit` and all azimuths, stored row-major per m
+fn trig_table<T: SphrsFloat>(bandlimit: usize, phis: &[T]) -> (Vec<T>, Vec<T>) {
+    let mut cos = Vec::with_capacity(bandlimit * phis.len());
+    let mut sin = Vec::with_capacity(bandlimit * phis.len());
+    for m in 0..bandlimit {
+        let mf = T::from_usize(m).unwrap();
+        for &phi in phis {
+            cos.push((mf * phi).cos());
+            sin.push((mf * phi).sin());
+        }
+    }
+    (cos, sin)
+}
+
+/// Check that the bandlimit is positive and `len` samples fit the grid of `sampling`
+fn check_samples(sampling: Sampling, bandlimit: usize, len: usize) -> Result<(), SphrsError> {
+    if bandlimit == 0 {
+        return Err(SphrsError::InvalidInput(
+            "bandlimit must be positive".to_string(),
+        ));
+    }
+    let (n_theta, n_phi) = sampling.shape(bandlimit);
+    if len != n_theta * n_phi {
+        return Err(SphrsError::InvalidInput(format!(
+            "{len} samples on a grid of {n_theta} x {n_phi} samples"
+        )));
+    }
+    Ok(())
+}
+
+/// Fourier sums (sum_k f_k cos(m phi_k), sum_k f_k sin(m phi_k)) of each ring for m < `bandlimit`,
+/// stored row-major per ring
+fn ring_sums<T: SphrsFloat>(bandlimit: usize, phis: &[T], values: &[T]) -> Vec<(T, T)> {
+    let n_phi = phis.len();
+    let (cos, sin) = trig_table(bandlimit, phis);
+    parallel::map(values.len() / n_phi, |j| {
+        let ring = &values[j * n_phi..(j + 1) * n_phi];
+        (0..bandlimit)
+            .map(|m| {
+                let trig = m * n_phi..(m + 1) * n_phi;
+                ring.iter()
+                    .zip(&cos[trig.clone()])
+                    .zip(&sin[trig])
+                    .fold((T::zero(), T::zero()), |(a, b), ((&f, &c), &s)| {
+                        (a + f * c, b + f * s)
+                    })
+            })
+            .collect::<Vec<_>>()
+    })
+    .concat()
+}
+
+/// Project weighted Fourier sums (see [`ring_sums`]) at the colatitudes `thetas` onto the
+/// associated Legendre functions
+///
+/// This is the quadrature of the integral over theta, the weights are already contained in
+/// `sums`.
+fn project_rings<T: SphrsFloat>(
+    bandlimit: usize,
+    n_phi: usize,
+    thetas: &[T],
+    sums: &[(T, T)],
+) -> SHCoefficients<T> {
+    let degree = bandlimit - 1;
+    let d_phi = T::from_f64(2.0).unwrap() * T::PI() / T::from_usize(n_phi).unwrap();
+    let coeffs = blockwise_sum_by(
+        thetas.len(),
+        RING_BLOCK_SIZE,
+        num_coefficients(degree),
+        |rings, c| {
+            for j in rings {
+                let theta = thetas[j];
+                let table = LegendreTable::new(degree, theta.cos(), theta.sin());
+                let sums = &sums[j * bandlimit..(j + 1) * bandlimit];
+                for (m, &(a, b)) in sums.iter().enumerate() {
+                    // Includes the factor sqrt(2) of the real harmonics
+                    let scale = if m == 0 { T::one() } else { T::SQRT_2() };
+                    let factor = order_sign::<T>(m) * scale * d_phi;
+                    for l in m..bandlimit {
+                        let x = factor * table.p[table_index(l, m)];
+                        let (l, mi) = (l as i64, m as i64);
+                        c[sh_index(l, mi)] = c[sh_index(l, mi)] + x * a;
+                        if m > 0 {
+                            c[sh_index(l, -mi)] = c[sh_index(l, -mi)] + x * b;
+                        }
+                    }
+                }
+            }
+        },
+    );
+    SHCoefficients::from_vec(degree, coeffs)
+}
+
+/// Analyze samples of a function with bandlimit `bandlimit` into real SH coefficients
+///
+/// `values` holds the samples on the grid of `sampling`, ring by ring from north to south.
+/// Returns the coefficients of the degrees 0, ..., `bandlimit` - 1, which are exact if the
+/// sampled function is bandlimited. Returns [`SphrsError::InvalidInput`] if the bandlimit is zero
+/// or the number of values does not match the grid.
+pub fn forward<T: SphrsFloat>(
+    sampling: Sampling,
+    bandlimit: usize,
+    values: &[T],
+) -> Result<SHCoefficients<T>, SphrsError> {
+    check_samples(sampling, bandlimit, values.len())?;
+    let phis = sampling.phis::<T>(bandlimit);
+    let sums = ring_sums(bandlimit, &phis, values);
+    let coeffs = match sampling.weights::<T>(bandlimit) {
+        Some(weights) => {
+            let weighted: Vec<(T, T)> = sums
+                .chunks(bandlimit)
+                .zip(&weights)
+                .flat_map(|(ring, &w)| ring.iter().map(move |&(a, b)| (a * w, b * w)))
+                .collect();
+            project_rings(
+                bandlimit,
+                phis.len(),
+                &sampling.thetas(bandlimit),
+                &weighted,
+            )
+        }
+        None => {
+            // McEwen-Wiaux: no quadrature on the rings, the sums are resampled exactly
+            let (thetas, weighted) = mw::resample(bandlimit, &sums);
+            project_rings(bandlimit, phis.len(), &thetas, &weighted)
+        }
+    };
+    Ok(coeffs)
+}
+
+/// Synthesize the samples of the expansion `coeffs` on the grid of `sampling`
+///
+/// The bandlimit of the grid is the degree of `coeffs` plus one. The samples are returned ring by
+/// ring from north to south, such that [`forward`] recovers the coefficients.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::sht::{forward, inverse, Sampling};
+/// use sphrs::SHCoefficients;
+///
+/// let coeffs = SHCoefficients::from_vec(2, vec![1.0f64, 0.5, -0.3, 0.2, 0.1, 0.0, -0.7, 0.4, 0.9]);
+/// let values = inverse(Sampling::GaussLegendre, &coeffs);
+/// assert_eq!(values.len(), 3 * 5);
+/// let back = forward(Sampling::GaussLegendre, 3, &values).unwrap();
+/// for (a, b) in back.as_slice().iter().zip(coeffs.as_slice()) {
+///     assert!((a - b).abs() < 1e-14);
+/// }
+/// ```
+pub fn inverse<T: SphrsFloat>(sampling: Sampling, coeffs: &SHCoefficients<T>) -> Vec<T> {
+    let bandlimit = coeffs.degree() + 1;
+    synthesize(
+        coeffs,
+        &sampling.thetas::<T>(bandlimit),
+        &sampling.phis::<T>(bandlimit),
+    )
+}
+
+/// Synthesize the values of the expansion `coeffs` on the product grid of the colatitudes
+/// `thetas` and the azimuths `phis`
+///
+/// The values are returned row by row, i.e. the value at (`thetas[j]`, `phis[k]`) is at index
+/// `j * phis.len() + k`. The associated Legendre functions are computed once per colatitude and
+/// the sums over the degrees once per order, such that the cost is O(L^2) per colatitude plus
+/// O(L) per sample instead of O(L^2) per sample for evaluating a
+/// [`HarmonicsSet`](`crate::basis::HarmonicsSet`) at every sample. Neither the colatitudes nor
+/// the azimuths need to be equally spaced.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::basis::{HarmonicsSet, RealSH};
+/// use sphrs::sht::synthesize;
+/// use sphrs::{Coordinates, SHCoefficients};
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![1.0f64, -0.5, 0.25, 2.0]);
+/// let (thetas, phis) = ([0.1, 1.2, 2.9], [0.0, 0.3, 4.0, 5.5]);
+/// let values = synthesize(&coeffs, &thetas, &phis);
+///
+/// let sh = HarmonicsSet::new(1, RealSH::Spherical);
+/// let p = Coordinates::spherical(1.0, thetas[1], phis[2]);
+/// let expected: f64 = sh.eval_sum(&p, coeffs.as_slice()).unwrap();
+/// assert!((values[4 + 2] - expected).abs() < 1e-14);
+/// ```
+pub fn synthesize<T: SphrsFloat>(coeffs: &SHCoefficients<T>, thetas: &[T], phis: &[T]) -> Vec<T> {
+    let degree = coeffs.degree();
+    let bandlimit = degree + 1;
+    let n_phi = phis.len();
+    let (cos, sin) = trig_table(bandlimit, phis);
+    let c = coeffs.as_slice();
+
+    parallel::map(thetas.len(), |j| {
+        let theta = thetas[j];
+        let table = LegendreTable::new(degree, theta.cos(), theta.sin());
+        let mut ring = vec![T::zero(); n_phi];
+        for m in 0..bandlimit {
+            // Sums over the degrees of the cos(m phi) and sin(m phi) parts
+            let (mut a, mut b) = (T::zero(), T::zero());
+            for l in m..bandlimit {
+                let p = table.p[table_index(l, m)];
+                let (l, mi) = (l as i64, m as i64);
+                a = a + c[sh_index(l, mi)] * p;
+                if m > 0 {
+                    b = b + c[sh_index(l, -mi)] * p;
+                }
+            }
+            let scale = if m == 0 { T::one() } else { T::SQRT_2() };
+            let factor = order_sign::<T>(m) * scale;
+            let trig = m * n_phi..(m + 1) * n_phi;
+            for ((v, &cm), &sm) in ring.iter_mut().zip(&cos[trig.clone()]).zip(&sin[trig]) {
+                *v = *v + factor * (a * cm + b * sm);
+            }
+        }
+        ring
+    })
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::Coordinates;
+
+    /// Samples of the expansion `coeffs` on the grid of `sampling`
+    fn sample(sampling: Sampling, bandlimit: usize, coeffs: &[f64]) -> Vec<f64> {
+        let sh = HarmonicsSet::new(bandlimit - 1, RealSH::Spherical);
+        let mut values = Vec::new();
+        for theta in sampling.thetas::<f64>(bandlimit) {
+            for phi in sampling.phis::<f64>(bandlimit) {
+                let p = Coordinates::spherical(1.0, theta, phi);
+                values.push(sh.eval_sum(&p, coeffs).unwrap());
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn driscoll_healy_round_trip() {
+        for bandlimit in [1, 2, 5, 16] {
+            let n = bandlimit * bandlimit;
+            let coeffs: Vec<f64> = (0..n).map(|i| (i as f64 * 0.83).sin()).collect();
+            let values = sample(Sampling::DriscollHealy, bandlimit, &coeffs);
+            let analyzed = forward(Sampling::DriscollHealy, bandlimit, &values).unwrap();
+            assert_eq!(analyzed.degree(), bandlimit - 1);
+            for (i, (a, b)) in analyzed.as_slice().iter().zip(&coeffs).enumerate() {
+                assert!((a - b).abs() < 1e-12, "{bandlimit} {i}: {a} {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn gauss_legendre_round_trip() {
+        for bandlimit in [1, 2, 7, 20] {
+            let n = bandlimit * bandlimit;
+            let coeffs: Vec<f64> = (0..n).map(|i| (i as f64 * 1.37).cos()).collect();
+            let values = sample(Sampling::GaussLegendre, bandlimit, &coeffs);
+            assert_eq!(values.len(), bandlimit * (2 * bandlimit - 1));
+            let analyzed = forward(Sampling::GaussLegendre, bandlimit, &values).unwrap();
+            for (i, (a, b)) in analyzed.as_slice().iter().zip(&coeffs).enumerate() {
+                assert!((a - b).abs() < 1e-12, "{bandlimit} {i}: {a} {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_matches_pointwise_evaluation() {
+        let bandlimit = 9;
+        let coeffs: Vec<f64> = (0..81).map(|i| (i as f64 * 0.29).sin()).collect();
+        let expansion = SHCoefficients::from_vec(bandlimit - 1, coeffs.clone());
+        for sampling in [
+            Sampling::DriscollHealy,
+            Sampling::GaussLegendre,
+            Sampling::McEwenWiaux,
+        ] {
+            let values = inverse(sampling, &expansion);
+            let expected = sample(sampling, bandlimit, &coeffs);
+            assert_eq!(values.len(), expected.len());
+            for (a, b) in values.iter().zip(&expected) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn mcewen_wiaux_round_trip() {
+        for bandlimit in [1, 2, 3, 8, 24] {
+            let n = bandlimit * bandlimit;
+            let coeffs: Vec<f64> = (0..n).map(|i| (i as f64 * 0.61).sin() + 0.2).collect();
+            let values = sample(Sampling::McEwenWiaux, bandlimit, &coeffs);
+            assert_eq!(values.len(), bandlimit * (2 * bandlimit - 1));
+            let analyzed = forward(Sampling::McEwenWiaux, bandlimit, &values).unwrap();
+            for (i, (a, b)) in analyzed.as_slice().iter().zip(&coeffs).enumerate() {
+                assert!((a - b).abs() < 1e-11, "{bandlimit} {i}: {a} {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn synthesize_on_irregular_grid() {
+        let degree = 12;
+        let n = (degree + 1) * (degree + 1);
+        let coeffs: Vec<f64> = (0..n).map(|i| (i as f64 * 0.47).cos()).collect();
+        let expansion = SHCoefficients::from_vec(degree, coeffs.clone());
+        let thetas = [0.0, 0.05, 0.8, 1.9, 3.0, std::f64::consts::PI];
+        let phis = [-1.0, 0.0, 0.2, 2.5, 6.0];
+        let values = synthesize(&expansion, &thetas, &phis);
+        let sh = HarmonicsSet::new(degree, RealSH::Spherical);
+        for (j, &theta) in thetas.iter().enumerate() {
+            for (k, &phi) in phis.iter().enumerate() {
+                let p = Coordinates::spherical(1.0, theta, phi);
+                let expected: f64 = sh.eval_sum(&p, &coeffs).unwrap();
+                assert!((values[j * phis.len() + k] - expected).abs() < 1e-12);
+            }
+        }
+        assert!(synthesize(&expansion, &thetas, &[]).is_empty());
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(matches!(
+            forward(Sampling::DriscollHealy, 3, &[0.0f64; 35]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            forward::<f64>(Sampling::DriscollHealy, 0, &[]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
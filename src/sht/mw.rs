@@ -0,0 +1,123 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Integration over theta on McEwen-Wiaux grids
+//!
+//! For a fixed order m, the Fourier sums F_m(theta) of the rings are a linear combination of the
+//! associated Legendre functions X_lm(theta), l < L. Continued to theta in [0, 2 pi) with
+//! sin(theta) changing its sign, X_lm is a trigonometric polynomial of degree l with
+//! X_lm(2 pi - theta) = (-1)^m X_lm(theta). The L rings at theta_t = pi (2 t + 1) / (2 L - 1)
+//! together with their reflections are 2 L - 1 equally spaced points, which determine F_m
+//! exactly by trigonometric interpolation.
+//!
+//! The integral of F_m X_lm sin(theta) over [0, pi] is then computed in the Fourier domain. With
+//! F_m = sum_k f_k e^(i k theta) and X_lm sin(theta) = sum_j x_j e^(i j theta), it is
+//! sum_j x_j b_j with b_j = sum_k f_k E(k + j) and E(n) the integral of e^(i n theta) over
+//! [0, pi]. Computing x_j by a discrete Fourier transform on N = 2 L + 1 points s_n turns this
+//! into the weighted sum (1 / N) sum_n X_lm(s_n) sin(s_n) g(s_n), g(s) = sum_j b_j e^(-i j s),
+//! i.e. a quadrature with weights depending on F_m, which [`resample`] returns.
+
+use num::Zero;
+use num_complex::Complex;
+
+use crate::SphrsFloat;
+
+/// Integral of e^(i n theta) over [0, pi]
+fn half_period_integral<T: SphrsFloat>(n: i64) -> Complex<T> {
+    if n == 0 {
+        Complex::new(T::PI(), T::zero())
+    } else if n % 2 == 0 {
+        Complex::zero()
+    } else {
+        // (e^(i n pi) - 1) / (i n) = 2 i / n
+        Complex::new(
+            T::zero(),
+            T::from_f64(2.0).unwrap() / T::from_i64(n).unwrap(),
+        )
+    }
+}
+
+/// Nodes and weighted Fourier sums replacing the rings of a McEwen-Wiaux grid
+///
+/// `sums` holds the Fourier sums of the L rings (see `ring_sums`). Returns the 2 L + 1 equally
+/// spaced nodes on [0, 2 pi) and the sums at those nodes, weighted such that the projection onto
+/// the Legendre functions yields the exact integrals over theta.
+pub(crate) fn resample<T: SphrsFloat>(bandlimit: usize, sums: &[(T, T)]) -> (Vec<T>, Vec<(T, T)>) {
+    let l = bandlimit as i64;
+    let n_t = 2 * bandlimit - 1;
+    let n_s = 2 * bandlimit + 1;
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    let thetas: Vec<T> = (0..n_t)
+        .map(|t| T::PI() * T::from_usize(2 * t + 1).unwrap() / T::from_usize(n_t).unwrap())
+        .collect();
+    let nodes: Vec<T> = (0..n_s)
+        .map(|s| two_pi * T::from_usize(s).unwrap() / T::from_usize(n_s).unwrap())
+        .collect();
+    let e =
+        |n: i64, x: T| -> Complex<T> { Complex::from_polar(T::one(), T::from_i64(n).unwrap() * x) };
+
+    // Weights g(s_n) / N of one function F_m, given on the L rings
+    let weights = |m: usize, f: &dyn Fn(usize) -> T| -> Vec<T> {
+        let sign = if m.is_multiple_of(2) {
+            T::one()
+        } else {
+            -T::one()
+        };
+        // Values on all 2 L - 1 points, using the reflection symmetry
+        let ext: Vec<T> = (0..n_t)
+            .map(|t| {
+                if t < bandlimit {
+                    f(t)
+                } else {
+                    sign * f(n_t - 1 - t)
+                }
+            })
+            .collect();
+        let norm = T::one() / T::from_usize(n_t).unwrap();
+        let coeffs: Vec<Complex<T>> = (1 - l..l)
+            .map(|k| {
+                ext.iter()
+                    .zip(&thetas)
+                    .fold(Complex::zero(), |acc, (&v, &t)| acc + e(-k, t) * v)
+                    * norm
+            })
+            .collect();
+        let b: Vec<Complex<T>> = (-l..=l)
+            .map(|j| {
+                (1 - l..l)
+                    .zip(&coeffs)
+                    .fold(Complex::zero(), |acc, (k, &c)| {
+                        acc + c * half_period_integral::<T>(k + j)
+                    })
+            })
+            .collect();
+        let norm = T::one() / T::from_usize(n_s).unwrap();
+        nodes
+            .iter()
+            .map(|&s| {
+                let g: Complex<T> = (-l..=l)
+                    .zip(&b)
+                    .fold(Complex::zero(), |acc, (j, &b)| acc + b * e(-j, s));
+                g.re * s.sin() * norm
+            })
+            .collect()
+    };
+
+    let mut out = vec![(T::zero(), T::zero()); n_s * bandlimit];
+    for m in 0..bandlimit {
+        let a = weights(m, &|t| sums[t * bandlimit + m].0);
+        let b = if m > 0 {
+            weights(m, &|t| sums[t * bandlimit + m].1)
+        } else {
+            vec![T::zero(); n_s]
+        };
+        for (n, (a, b)) in a.into_iter().zip(b).enumerate() {
+            out[n * bandlimit + m] = (a, b);
+        }
+    }
+    (nodes, out)
+}
@@ -0,0 +1,141 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::quadrature::gauss_legendre;
+use crate::SphrsFloat;
+
+/// Sampling scheme of the transforms in [`sht`](`crate::sht`)
+///
+/// Each scheme defines, for a bandlimit L, rings of constant colatitude theta_j with n_phi
+/// equally spaced azimuths phi_k = 2 pi k / n_phi each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Sampling {
+    /// Equiangular Driscoll-Healy grid of 2 L x 2 L samples
+    ///
+    /// The rings are at theta_j = pi j / (2 L), j = 0, ..., 2 L - 1, i.e. the north pole is
+    /// sampled (2 L times) and the south pole is not.
+    DriscollHealy,
+    /// Gauss-Legendre grid of L x (2 L - 1) samples
+    ///
+    /// The rings are at the L Gauss-Legendre nodes in cos(theta), see
+    /// [`gauss_legendre`](`crate::sht::gauss_legendre`). This is the smallest of the grids and
+    /// its quadrature is the most accurate one.
+    GaussLegendre,
+    /// McEwen-Wiaux grid of L x (2 L - 1) samples, as used by ssht and s2let
+    ///
+    /// The rings are at theta_t = pi (2 t + 1) / (2 L - 1), t = 0, ..., L - 1, i.e. the south
+    /// pole is sampled (2 L - 1 times) and the north pole is not. The rings have no quadrature
+    /// of their own; the transform extends the samples periodically in theta instead.
+    McEwenWiaux,
+}
+
+impl Sampling {
+    /// Number of rings and number of samples per ring for bandlimit `bandlimit`
+    pub fn shape(&self, bandlimit: usize) -> (usize, usize) {
+        match self {
+            Sampling::DriscollHealy => (2 * bandlimit, 2 * bandlimit),
+            Sampling::GaussLegendre | Sampling::McEwenWiaux => {
+                (bandlimit, (2 * bandlimit).saturating_sub(1))
+            }
+        }
+    }
+
+    /// Colatitudes of the rings, from north to south
+    pub fn thetas<T: SphrsFloat>(&self, bandlimit: usize) -> Vec<T> {
+        let (n_theta, _) = self.shape(bandlimit);
+        match self {
+            Sampling::DriscollHealy => {
+                let n = T::from_usize(n_theta).unwrap();
+                (0..n_theta)
+                    .map(|j| T::PI() * T::from_usize(j).unwrap() / n)
+                    .collect()
+            }
+            Sampling::GaussLegendre => {
+                let (x, _) = gauss_legendre::<T>(n_theta);
+                x.into_iter().map(|x| x.acos()).collect()
+            }
+            Sampling::McEwenWiaux => {
+                let n = T::from_usize(2 * n_theta - 1).unwrap();
+                (0..n_theta)
+                    .map(|t| T::PI() * T::from_usize(2 * t + 1).unwrap() / n)
+                    .collect()
+            }
+        }
+    }
+
+    /// Azimuths of the samples of each ring
+    pub fn phis<T: SphrsFloat>(&self, bandlimit: usize) -> Vec<T> {
+        let (_, n_phi) = self.shape(bandlimit);
+        let n = T::from_usize(n_phi).unwrap();
+        let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+        (0..n_phi)
+            .map(|k| two_pi * T::from_usize(k).unwrap() / n)
+            .collect()
+    }
+
+    /// Quadrature weights of the rings for integrals over cos(theta)
+    ///
+    /// The weights integrate g(theta) sin(theta) over [0, pi] exactly for all trigonometric
+    /// polynomials g up to the degree needed by the product of two harmonics below the
+    /// bandlimit. Returns `None` for [`Sampling::McEwenWiaux`], whose rings have no such
+    /// quadrature.
+    pub(crate) fn weights<T: SphrsFloat>(&self, bandlimit: usize) -> Option<Vec<T>> {
+        let weights = match self {
+            Sampling::DriscollHealy => {
+                // w_j = 4 / N sin(theta_j) sum_k sin((2 k + 1) theta_j) / (2 k + 1) with N = 2 L
+                let (n_theta, _) = self.shape(bandlimit);
+                let n = T::from_usize(n_theta).unwrap();
+                self.thetas::<T>(bandlimit)
+                    .into_iter()
+                    .map(|theta| {
+                        let sum = (0..bandlimit).fold(T::zero(), |acc, k| {
+                            let odd = T::from_usize(2 * k + 1).unwrap();
+                            acc + (odd * theta).sin() / odd
+                        });
+                        T::from_f64(4.0).unwrap() / n * theta.sin() * sum
+                    })
+                    .collect()
+            }
+            Sampling::GaussLegendre => gauss_legendre::<T>(bandlimit).1,
+            Sampling::McEwenWiaux => return None,
+        };
+        Some(weights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_integrate_polynomials() {
+        let bandlimit = 6;
+        for sampling in [Sampling::DriscollHealy, Sampling::GaussLegendre] {
+            check_weights(sampling, bandlimit);
+        }
+    }
+
+    fn check_weights(sampling: Sampling, bandlimit: usize) {
+        let thetas = sampling.thetas::<f64>(bandlimit);
+        let weights = sampling.weights::<f64>(bandlimit).unwrap();
+        // integral of cos(theta)^k over [-1, 1]
+        for k in 0..2 * bandlimit - 1 {
+            let sum: f64 = thetas
+                .iter()
+                .zip(&weights)
+                .map(|(t, w)| w * t.cos().powi(k as i32))
+                .sum();
+            let expected = if k % 2 == 0 {
+                2.0 / (k as f64 + 1.0)
+            } else {
+                0.0
+            };
+            assert!((sum - expected).abs() < 1e-14, "{k} {sum}");
+        }
+    }
+}
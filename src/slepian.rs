@@ -0,0 +1,227 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spherical Slepian functions
+//!
+//! Slepian functions are the bandlimited functions which are optimally concentrated within a
+//! region of the sphere. For a region R and a maximum degree L, they are the eigenvectors of the
+//! concentration matrix D_lm,l'm' = integral over R of Y_lm Y_l'm' in coefficient space, and the
+//! eigenvalues 0 < lambda < 1 are the fractions of the energy of the functions within R. The sum
+//! of all eigenvalues is the Shannon number N = (L + 1)^2 A / (4 pi) of a region with area A;
+//! roughly N functions are well concentrated, the remaining ones are concentrated outside of R.
+//!
+//! For a spherical cap around the north pole the concentration matrix decouples into one block
+//! per order m, which is solved by [`cap_slepian`]. Caps around other axes are obtained by
+//! rotating the coefficients.
+//!
+//! # Example
+//!
+//! ```
+//! use sphrs::slepian::{cap_slepian, shannon_number};
+//!
+//! let (degree, half_angle) = (10, 0.5f64);
+//! let functions = cap_slepian(degree, half_angle);
+//! assert_eq!(functions.len(), 121);
+//! // The eigenvalues add up to the Shannon number
+//! let sum: f64 = functions.iter().map(|f| f.concentration).sum();
+//! assert!((sum - shannon_number(degree, half_angle)).abs() < 1e-12);
+//! // The best concentrated function has almost all of its energy within the cap
+//! assert!(functions[0].concentration > 0.99);
+//! ```
+//!
+//! # References
+//!
+//! * Simons, F. J., Dahlen, F. A. and Wieczorek, M. A., "Spatiospectral concentration on a
+//!   sphere", SIAM Review 48(3), 2006
+
+use crate::coefficients::sh_index;
+use crate::legendre::{table_index, LegendreTable};
+use crate::linalg::symmetric_eigen;
+use crate::quadrature::gauss_legendre;
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Slepian function, given by its coefficients in real spherical harmonics
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slepian<T> {
+    /// Order m of the harmonics the function is composed of
+    pub order: i64,
+    /// Fraction of the energy within the region (eigenvalue of the concentration problem)
+    pub concentration: T,
+    /// Coefficients with respect to
+    /// [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`), normalized to unit energy on
+    /// the sphere
+    pub coefficients: SHCoefficients<T>,
+}
+
+/// Shannon number (L + 1)^2 (1 - cos(half_angle)) / 2 of a spherical cap
+///
+/// This is the sum of the concentration eigenvalues of all Slepian functions up to degree
+/// `degree` and an estimate of the number of well concentrated functions.
+pub fn shannon_number<T: SphrsFloat>(degree: usize, half_angle: T) -> T {
+    let n = T::from_usize((degree + 1) * (degree + 1)).unwrap();
+    n * (T::one() - half_angle.cos()) / T::from_f64(2.0).unwrap()
+}
+
+/// Slepian functions up to degree `degree` concentrated within a spherical cap around the z-axis
+///
+/// The cap contains all directions with a colatitude smaller than `half_angle` (in radians).
+/// Returns all (L + 1)^2 functions sorted by decreasing concentration. Each function consists of
+/// harmonics of a single order m; the functions of the orders m and -m share the same
+/// concentration. The coefficients of each function are orthonormal, with the sign chosen such
+/// that the coefficient of largest magnitude is positive.
+///
+/// The concentration matrix of order m is the integral over the cap of the products of the
+/// normalized associated Legendre functions, which is computed exactly with a Gauss-Legendre
+/// quadrature.
+pub fn cap_slepian<T: SphrsFloat>(degree: usize, half_angle: T) -> Vec<Slepian<T>> {
+    let x0 = half_angle.cos();
+    let half = T::from_f64(0.5).unwrap();
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+
+    // Quadrature on [cos(half_angle), 1], exact for the polynomials of degree 2 L
+    let (nodes, weights) = gauss_legendre::<T>(degree + 2);
+    let tables: Vec<(T, LegendreTable<T>)> = nodes
+        .iter()
+        .zip(&weights)
+        .map(|(&t, &w)| {
+            let x = x0 + half * (T::one() - x0) * (t + T::one());
+            let s = (T::one() - x * x).max(T::zero()).sqrt();
+            (
+                two_pi * half * (T::one() - x0) * w,
+                LegendreTable::new(degree, x, s),
+            )
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity((degree + 1) * (degree + 1));
+    for m in 0..=degree {
+        // Concentration matrix of the degrees m..=L
+        let n = degree - m + 1;
+        let mut d = vec![T::zero(); n * n];
+        for (w, table) in &tables {
+            let p: Vec<T> = (m..=degree).map(|l| table.p[table_index(l, m)]).collect();
+            for i in 0..n {
+                for j in i..n {
+                    d[i * n + j] = d[i * n + j] + *w * p[i] * p[j];
+                }
+            }
+        }
+        for i in 0..n {
+            for j in 0..i {
+                d[i * n + j] = d[j * n + i];
+            }
+        }
+
+        let (values, vectors) = symmetric_eigen(d, n);
+        for (j, &concentration) in values.iter().enumerate() {
+            let column: Vec<T> = (0..n).map(|i| vectors[i * n + j]).collect();
+            let largest = column.iter().fold(
+                T::zero(),
+                |acc, &v| if v.abs() > acc.abs() { v } else { acc },
+            );
+            let sign = if largest < T::zero() {
+                -T::one()
+            } else {
+                T::one()
+            };
+            let orders: &[i64] = if m == 0 {
+                &[0]
+            } else {
+                &[m as i64, -(m as i64)]
+            };
+            for &order in orders {
+                let mut coefficients = SHCoefficients::zeros(degree);
+                for (i, &v) in column.iter().enumerate() {
+                    coefficients.as_mut_slice()[sh_index((m + i) as i64, order)] = sign * v;
+                }
+                out.push(Slepian {
+                    order,
+                    concentration,
+                    coefficients,
+                });
+            }
+        }
+    }
+    out.sort_by(|a, b| {
+        b.concentration
+            .partial_cmp(&a.concentration)
+            .unwrap()
+            .then(a.order.abs().cmp(&b.order.abs()))
+            .then(b.order.cmp(&a.order))
+    });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::Coordinates;
+
+    #[test]
+    fn concentration_matches_spatial_integral() {
+        let (degree, half_angle) = (8, 0.7f64);
+        let functions = cap_slepian(degree, half_angle);
+        assert_eq!(functions.len(), 81);
+        let sh = HarmonicsSet::new(degree, RealSH::Spherical);
+
+        // Energy within the cap by brute force quadrature in theta and phi
+        let (nodes, weights) = gauss_legendre::<f64>(40);
+        let n_phi = 40;
+        for f in functions.iter().take(12) {
+            let mut energy = 0.0;
+            for (&t, &w) in nodes.iter().zip(&weights) {
+                let x = half_angle.cos() + 0.5 * (1.0 - half_angle.cos()) * (t + 1.0);
+                for k in 0..n_phi {
+                    let phi = 2.0 * std::f64::consts::PI * k as f64 / n_phi as f64;
+                    let p = Coordinates::spherical(1.0, x.acos(), phi);
+                    let v: f64 = sh.eval_sum(&p, f.coefficients.as_slice()).unwrap();
+                    energy +=
+                        v * v * w * 0.5 * (1.0 - half_angle.cos()) * 2.0 * std::f64::consts::PI
+                            / n_phi as f64;
+                }
+            }
+            assert!(
+                (energy - f.concentration).abs() < 1e-12,
+                "{} {energy} {}",
+                f.order,
+                f.concentration
+            );
+        }
+    }
+
+    #[test]
+    fn orthonormal_and_sorted() {
+        let functions = cap_slepian(6, 1.2f64);
+        for w in functions.windows(2) {
+            assert!(w[0].concentration >= w[1].concentration);
+        }
+        for a in &functions {
+            assert!(a.concentration > -1e-14 && a.concentration < 1.0 + 1e-14);
+            for b in &functions {
+                let dot: f64 = a
+                    .coefficients
+                    .as_slice()
+                    .iter()
+                    .zip(b.coefficients.as_slice())
+                    .map(|(x, y)| x * y)
+                    .sum();
+                let expected = if std::ptr::eq(a, b) { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-13);
+            }
+        }
+    }
+
+    #[test]
+    fn whole_sphere() {
+        // A cap covering the sphere concentrates all functions completely
+        let functions = cap_slepian(4, std::f64::consts::PI);
+        for f in functions {
+            assert!((f.concentration - 1.0).abs() < 1e-13);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Harmonic analysis on the rotation group SO(3)
+//!
+//! A function on SO(3) with bandlimit L is a linear combination of the Wigner D-functions of the
+//! degrees 0, ..., L - 1,
+//!
+//! f(alpha, beta, gamma) = sum_l sum_m,n F^l_mn D^l_mn(alpha, beta, gamma),
+//!
+//! with the Euler angles and the D-functions of [`wigner`](`crate::rotation::wigner`). The
+//! D-functions are orthogonal with respect to the Haar measure sin(beta) d alpha d beta d gamma,
+//! each with the squared norm 8 pi^2 / (2 l + 1), such that
+//! F^l_mn = (2 l + 1) / (8 pi^2) integral of f conj(D^l_mn).
+//!
+//! [`forward`] computes the coefficients from samples on the grid of [`angles`] exactly (up to
+//! rounding), and [`inverse`] synthesizes the samples. The integrals over alpha and gamma are
+//! discrete Fourier sums over 2 L - 1 equally spaced angles each; the integral over beta is a
+//! Gauss-Legendre quadrature in cos(beta) with L nodes, which is exact since the product of two
+//! d-functions of the same orders is a polynomial in cos(beta).
+//!
+//! # Example
+//!
+//! ```
+//! use num_complex::Complex;
+//! use sphrs::so3::{forward, inverse, WignerCoefficients};
+//!
+//! let mut coeffs = WignerCoefficients::<f64>::zeros(3);
+//! *coeffs.get_mut(2, 1, -2).unwrap() = Complex::new(0.5, -1.0);
+//! *coeffs.get_mut(0, 0, 0).unwrap() = Complex::new(2.0, 0.0);
+//! let values = inverse(&coeffs);
+//! let back = forward(3, &values).unwrap();
+//! for (a, b) in back.as_slice().iter().zip(coeffs.as_slice()) {
+//!     assert!((a - b).norm() < 1e-13);
+//! }
+//! ```
+
+use num::Zero;
+use num_complex::Complex;
+
+use crate::quadrature::gauss_legendre;
+use crate::rotation::wigner::{matrix_index, small_d_matrix};
+use crate::{SphrsError, SphrsFloat};
+
+/// Offset of degree `l` in the coefficients, the number of coefficients of the lower degrees
+#[inline(always)]
+fn degree_offset(l: usize) -> usize {
+    // sum of (2 k + 1)^2 for k < l
+    (4 * l * l * l - l) / 3
+}
+
+/// Number of coefficients of a function with bandlimit `bandlimit`
+#[inline(always)]
+fn num_wigner_coefficients(bandlimit: usize) -> usize {
+    degree_offset(bandlimit)
+}
+
+/// Coefficients F^l_mn of a function on SO(3) with respect to the Wigner D-functions
+///
+/// The coefficients are ordered by degree; the coefficients of each degree form a matrix which is
+/// stored as the Wigner matrices, see [`matrix_index`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WignerCoefficients<T> {
+    /// Bandlimit, one more than the maximum degree
+    bandlimit: usize,
+    /// Coefficients
+    data: Vec<Complex<T>>,
+}
+
+impl<T: SphrsFloat> WignerCoefficients<T> {
+    /// Create `WignerCoefficients` with bandlimit `bandlimit`, i.e. for the degrees
+    /// 0, ..., `bandlimit` - 1, from a vector of coefficients.
+    ///
+    /// Panics if the length of `data` does not equal sum_l (2 l + 1)^2.
+    pub fn from_vec(bandlimit: usize, data: Vec<Complex<T>>) -> Self {
+        assert_eq!(data.len(), num_wigner_coefficients(bandlimit));
+        WignerCoefficients { bandlimit, data }
+    }
+
+    /// Create `WignerCoefficients` with bandlimit `bandlimit` with all coefficients set to zero.
+    pub fn zeros(bandlimit: usize) -> Self {
+        WignerCoefficients::from_vec(
+            bandlimit,
+            vec![Complex::zero(); num_wigner_coefficients(bandlimit)],
+        )
+    }
+
+    /// Returns the bandlimit, one more than the maximum degree
+    pub fn bandlimit(&self) -> usize {
+        self.bandlimit
+    }
+
+    /// Returns the coefficient F^l_mn, if present
+    pub fn get(&self, l: usize, m: i64, n: i64) -> Option<&Complex<T>> {
+        let li = l as i64;
+        if l >= self.bandlimit || m.abs() > li || n.abs() > li {
+            return None;
+        }
+        self.data.get(degree_offset(l) + matrix_index(l, m, n))
+    }
+
+    /// Returns a mutable reference to the coefficient F^l_mn, if present
+    pub fn get_mut(&mut self, l: usize, m: i64, n: i64) -> Option<&mut Complex<T>> {
+        let li = l as i64;
+        if l >= self.bandlimit || m.abs() > li || n.abs() > li {
+            return None;
+        }
+        self.data.get_mut(degree_offset(l) + matrix_index(l, m, n))
+    }
+
+    /// Returns the coefficients as a slice
+    pub fn as_slice(&self) -> &[Complex<T>] {
+        &self.data
+    }
+
+    /// Returns the coefficients as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [Complex<T>] {
+        &mut self.data
+    }
+
+    /// Consumes `self` and returns the underlying vector of coefficients
+    pub fn into_vec(self) -> Vec<Complex<T>> {
+        self.data
+    }
+}
+
+/// Number of samples of alpha, beta and gamma for bandlimit `bandlimit`
+pub fn shape(bandlimit: usize) -> (usize, usize, usize) {
+    let n = (2 * bandlimit).saturating_sub(1);
+    (n, bandlimit, n)
+}
+
+/// Euler angles (alphas, betas, gammas) of the sampling grid for bandlimit `bandlimit`
+///
+/// alpha and gamma are equally spaced in [0, 2 pi) starting at zero, beta are the colatitudes of
+/// the Gauss-Legendre nodes in cos(beta), from 0 to pi. The samples are stored with beta varying
+/// slowest and gamma fastest, i.e. the sample at (`alphas[j]`, `betas[i]`, `gammas[k]`) is at
+/// index (i n_alpha + j) n_gamma + k.
+pub fn angles<T: SphrsFloat>(bandlimit: usize) -> (Vec<T>, Vec<T>, Vec<T>) {
+    let (n_alpha, n_beta, _) = shape(bandlimit);
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    let alphas: Vec<T> = (0..n_alpha)
+        .map(|j| two_pi * T::from_usize(j).unwrap() / T::from_usize(n_alpha).unwrap())
+        .collect();
+    let betas = gauss_legendre::<T>(n_beta)
+        .0
+        .into_iter()
+        .map(|x| x.acos())
+        .collect();
+    (alphas.clone(), betas, alphas)
+}
+
+/// e^(i sign m phi) for the orders -L < m < L (rows) and the angles `phis` (columns)
+fn phases<T: SphrsFloat>(bandlimit: usize, phis: &[T], sign: T) -> Vec<Complex<T>> {
+    let li = bandlimit as i64;
+    (1 - li..li)
+        .flat_map(|m| {
+            phis.iter().map(move |&phi| {
+                Complex::from_polar(T::one(), sign * T::from_i64(m).unwrap() * phi)
+            })
+        })
+        .collect()
+}
+
+/// Compute the Wigner D-coefficients of a function with bandlimit `bandlimit` from its samples
+///
+/// `values` holds the samples on the grid of [`angles`]. Returns the coefficients of the degrees
+/// 0, ..., `bandlimit` - 1, which are exact if the sampled function is bandlimited. Returns
+/// [`SphrsError::InvalidInput`] if the bandlimit is zero or the number of values does not match
+/// the grid.
+pub fn forward<T: SphrsFloat>(
+    bandlimit: usize,
+    values: &[Complex<T>],
+) -> Result<WignerCoefficients<T>, SphrsError> {
+    if bandlimit == 0 {
+        return Err(SphrsError::InvalidInput(
+            "bandlimit must be positive".to_string(),
+        ));
+    }
+    let (n_alpha, n_beta, n_gamma) = shape(bandlimit);
+    if values.len() != n_alpha * n_beta * n_gamma {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} samples on a grid of {n_alpha} x {n_beta} x {n_gamma} samples",
+            values.len()
+        )));
+    }
+    let (alphas, betas, gammas) = angles::<T>(bandlimit);
+    let weights = gauss_legendre::<T>(n_beta).1;
+    let (e_alpha, e_gamma) = (
+        phases(bandlimit, &alphas, T::one()),
+        phases(bandlimit, &gammas, T::one()),
+    );
+    let width = 2 * bandlimit - 1;
+    let li = bandlimit as i64 - 1;
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    // (2 pi / n_alpha) (2 pi / n_gamma) / (8 pi^2)
+    let scale = two_pi * two_pi
+        / (T::from_f64(8.0).unwrap()
+            * T::PI()
+            * T::PI()
+            * T::from_usize(n_alpha * n_gamma).unwrap());
+
+    let mut coeffs = WignerCoefficients::zeros(bandlimit);
+    for (i, (&beta, &w)) in betas.iter().zip(&weights).enumerate() {
+        // G_mn = sum_jk f_ijk e^(i m alpha_j) e^(i n gamma_k), first over gamma
+        let slab = &values[i * n_alpha * n_gamma..(i + 1) * n_alpha * n_gamma];
+        let mut h = vec![Complex::<T>::zero(); n_alpha * width];
+        for (j, row) in slab.chunks(n_gamma).enumerate() {
+            for n in 0..width {
+                h[j * width + n] = row
+                    .iter()
+                    .zip(&e_gamma[n * n_gamma..(n + 1) * n_gamma])
+                    .fold(Complex::zero(), |acc, (&f, &e)| acc + f * e);
+            }
+        }
+        let mut g = vec![Complex::<T>::zero(); width * width];
+        for m in 0..width {
+            for j in 0..n_alpha {
+                let e = e_alpha[m * n_alpha + j];
+                for n in 0..width {
+                    g[m * width + n] = g[m * width + n] + e * h[j * width + n];
+                }
+            }
+        }
+        for l in 0..bandlimit {
+            let d = small_d_matrix(l, beta);
+            let factor = T::from_usize(2 * l + 1).unwrap() * scale * w;
+            let l64 = l as i64;
+            for m in -l64..=l64 {
+                for n in -l64..=l64 {
+                    let v = g[(m + li) as usize * width + (n + li) as usize]
+                        * (factor * d[matrix_index(l, m, n)]);
+                    let c = coeffs.get_mut(l, m, n).unwrap();
+                    *c = *c + v;
+                }
+            }
+        }
+    }
+    Ok(coeffs)
+}
+
+/// Synthesize the samples of the function with the Wigner D-coefficients `coeffs` on the grid of
+/// [`angles`]
+///
+/// The samples are returned in the order described at [`angles`], such that [`forward`] recovers
+/// the coefficients.
+pub fn inverse<T: SphrsFloat>(coeffs: &WignerCoefficients<T>) -> Vec<Complex<T>> {
+    let bandlimit = coeffs.bandlimit();
+    let (n_alpha, n_beta, n_gamma) = shape(bandlimit);
+    let (alphas, betas, gammas) = angles::<T>(bandlimit);
+    let (e_alpha, e_gamma) = (
+        phases(bandlimit, &alphas, -T::one()),
+        phases(bandlimit, &gammas, -T::one()),
+    );
+    let width = (2 * bandlimit).saturating_sub(1);
+    let li = bandlimit as i64 - 1;
+
+    let mut values = Vec::with_capacity(n_alpha * n_beta * n_gamma);
+    for &beta in &betas {
+        // S_mn = sum_l F^l_mn d^l_mn(beta)
+        let mut s = vec![Complex::<T>::zero(); width * width];
+        for l in 0..bandlimit {
+            let d = small_d_matrix(l, beta);
+            let l64 = l as i64;
+            for m in -l64..=l64 {
+                for n in -l64..=l64 {
+                    let idx = (m + li) as usize * width + (n + li) as usize;
+                    s[idx] = s[idx] + *coeffs.get(l, m, n).unwrap() * d[matrix_index(l, m, n)];
+                }
+            }
+        }
+        // Sum over n for each gamma, then over m for each alpha
+        let mut t = vec![Complex::<T>::zero(); width * n_gamma];
+        for m in 0..width {
+            for n in 0..width {
+                let v = s[m * width + n];
+                for k in 0..n_gamma {
+                    t[m * n_gamma + k] = t[m * n_gamma + k] + v * e_gamma[n * n_gamma + k];
+                }
+            }
+        }
+        for j in 0..n_alpha {
+            for k in 0..n_gamma {
+                values.push((0..width).fold(Complex::zero(), |acc, m| {
+                    acc + t[m * n_gamma + k] * e_alpha[m * n_alpha + j]
+                }));
+            }
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotation::wigner::big_d;
+
+    #[test]
+    fn d_functions_have_unit_coefficients() {
+        let bandlimit = 4;
+        let (alphas, betas, gammas) = angles::<f64>(bandlimit);
+        for (l, m, n) in [(0, 0, 0), (1, 1, -1), (2, 0, 1), (3, -3, 2), (3, 1, 1)] {
+            let mut values = Vec::new();
+            for &beta in &betas {
+                for &alpha in &alphas {
+                    for &gamma in &gammas {
+                        values.push(big_d(l, m, n, alpha, beta, gamma));
+                    }
+                }
+            }
+            let coeffs = forward(bandlimit, &values).unwrap();
+            for l2 in 0..bandlimit {
+                let li = l2 as i64;
+                for m2 in -li..=li {
+                    for n2 in -li..=li {
+                        let expected = if (l2, m2, n2) == (l, m, n) { 1.0 } else { 0.0 };
+                        let c = coeffs.get(l2, m2, n2).unwrap();
+                        assert!((c - expected).norm() < 1e-13, "{l} {m} {n}: {l2} {m2} {n2}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_evaluates_expansion() {
+        let bandlimit = 3;
+        let data = (0..num_wigner_coefficients(bandlimit))
+            .map(|i| Complex::new((i as f64).sin(), (i as f64 * 0.7).cos()))
+            .collect();
+        let coeffs = WignerCoefficients::from_vec(bandlimit, data);
+        let values = inverse(&coeffs);
+        let (alphas, betas, gammas) = angles::<f64>(bandlimit);
+        let (n_alpha, _, n_gamma) = shape(bandlimit);
+        let (i, j, k) = (1, 3, 2);
+        let mut expected = Complex::zero();
+        for l in 0..bandlimit {
+            let li = l as i64;
+            for m in -li..=li {
+                for n in -li..=li {
+                    expected += coeffs.get(l, m, n).unwrap()
+                        * big_d(l, m, n, alphas[j], betas[i], gammas[k]);
+                }
+            }
+        }
+        assert!((values[(i * n_alpha + j) * n_gamma + k] - expected).norm() < 1e-13);
+    }
+
+    #[test]
+    fn invalid_samples() {
+        assert!(matches!(
+            forward::<f64>(0, &[]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            forward(2, &[Complex::new(1.0f64, 0.0); 10]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::ops::Mul;
+
+use crate::legendre::{legendre_p_all, zonal_norm};
+use crate::quadrature::gauss_legendre;
+use crate::spectral::filter_degrees;
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Axially symmetric convolution kernel k(cos gamma), which depends only on the angle gamma
+/// between two directions
+///
+/// By the Funk-Hecke theorem, the harmonics are eigenfunctions of the convolution with such a
+/// kernel: the integral of k(p . q) Y_lm(q) over all directions q is lambda_l Y_lm(p) with
+/// lambda_l = 2 pi integral over [-1, 1] of k(t) P_l(t) dt. [`Kernel::eigenvalues`] computes
+/// lambda_l.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Kernel<T> {
+    /// Gaussian (heat) kernel with an angular scale of sigma radians and unit integral
+    ///
+    /// The eigenvalues are lambda_l = exp(-l (l + 1) sigma^2 / 2), the same as of
+    /// [`Filter::Gaussian`](`crate::spectral::Filter::Gaussian`). For small sigma the kernel
+    /// approaches a Gaussian of standard deviation sigma in the angle gamma.
+    Gaussian(T),
+    /// Clamped cosine lobe k(t) = max(t, 0)^n with the exponent n
+    ///
+    /// The exponent 1 is the Lambertian kernel, which maps radiance to irradiance. The kernel is
+    /// not normalized, its integral is lambda_0 = 2 pi / (n + 1).
+    CosineLobe(u32),
+    /// Top-hat kernel averaging over a spherical cap with the given half angle in radians
+    ///
+    /// The kernel is 1 / A within the cap of area A = 2 pi (1 - cos(half_angle)) and zero
+    /// outside, i.e. it has unit integral.
+    Cap(T),
+    /// Kernel given by the coefficients r_l of its expansion into the zonal harmonics Y_l0
+    ///
+    /// `r[l]` is the coefficient of Y_l0 of the kernel oriented along the z-axis, k(cos(theta)) =
+    /// sum_l r_l Y_l0(theta), and lambda_l = sqrt(4 pi / (2 l + 1)) r_l. The degrees beyond the
+    /// end of the vector have a coefficient of zero. See [`Kernel::from_fn`] for kernels given as
+    /// functions.
+    Zonal(Vec<T>),
+}
+
+impl<T: SphrsFloat> Kernel<T> {
+    /// Expand the kernel `k(t)`, t = cos(gamma), into the zonal harmonics up to degree `degree`
+    ///
+    /// The coefficients are computed with a Gauss-Legendre quadrature of `nodes` nodes, which is
+    /// exact for polynomial kernels of degree up to 2 `nodes` - 1 - `degree`. Kernels with sharp
+    /// features or discontinuities need correspondingly more nodes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::spectral::Kernel;
+    ///
+    /// // k(t) = t^2 has eigenvalues 4 pi / 3 and 8 pi / 15 at the degrees 0 and 2
+    /// let kernel = Kernel::from_fn(3, 4, |t: f64| t * t);
+    /// let lambda = kernel.eigenvalues(3);
+    /// let pi = std::f64::consts::PI;
+    /// assert!((lambda[0] - 4.0 * pi / 3.0).abs() < 1e-14);
+    /// assert!(lambda[1].abs() < 1e-14);
+    /// assert!((lambda[2] - 8.0 * pi / 15.0).abs() < 1e-14);
+    /// ```
+    pub fn from_fn<F: Fn(T) -> T>(degree: usize, nodes: usize, k: F) -> Self {
+        let lambda = legendre_integrals(degree, &gauss_legendre::<T>(nodes), k);
+        Kernel::Zonal(
+            lambda
+                .into_iter()
+                .enumerate()
+                .map(|(l, lambda)| lambda * zonal_norm::<T>(l))
+                .collect(),
+        )
+    }
+
+    /// Eigenvalues lambda_0, ..., lambda_degree of the convolution with the kernel
+    pub fn eigenvalues(&self, degree: usize) -> Vec<T> {
+        match self {
+            Kernel::Gaussian(sigma) => (0..=degree)
+                .map(|l| {
+                    let ll = T::from_usize(l * (l + 1)).unwrap();
+                    (-ll * *sigma * *sigma / T::from_f64(2.0).unwrap()).exp()
+                })
+                .collect(),
+            Kernel::CosineLobe(n) => {
+                // Polynomial of degree n + l on [0, 1]
+                let (x, w) = gauss_legendre::<T>((*n as usize + degree) / 2 + 1);
+                let half = T::from_f64(0.5).unwrap();
+                let x = x.into_iter().map(|x| half * (x + T::one())).collect();
+                let w = w.into_iter().map(|w| half * w).collect();
+                legendre_integrals(degree, &(x, w), |t| t.powi(*n as i32))
+            }
+            Kernel::Cap(half_angle) => {
+                let x0 = half_angle.cos();
+                let area = T::one() - x0;
+                if area <= T::zero() {
+                    // Delta kernel
+                    return vec![T::one(); degree + 1];
+                }
+                // integral of P_l over [x0, 1] is (P_{l-1}(x0) - P_{l+1}(x0)) / (2 l + 1)
+                let p = legendre_p_all(degree + 1, x0);
+                (0..=degree)
+                    .map(|l| {
+                        if l == 0 {
+                            T::one()
+                        } else {
+                            (p[l - 1] - p[l + 1]) / (T::from_usize(2 * l + 1).unwrap() * area)
+                        }
+                    })
+                    .collect()
+            }
+            Kernel::Zonal(r) => (0..=degree)
+                .map(|l| r.get(l).map_or(T::zero(), |&r| r / zonal_norm::<T>(l)))
+                .collect(),
+        }
+    }
+}
+
+/// 2 pi times the integrals of k(t) P_l(t), l = 0, ..., degree, with the quadrature `rule`
+fn legendre_integrals<T, F>(degree: usize, rule: &(Vec<T>, Vec<T>), k: F) -> Vec<T>
+where
+    T: SphrsFloat,
+    F: Fn(T) -> T,
+{
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    let (nodes, weights) = rule;
+    let mut lambda = vec![T::zero(); degree + 1];
+    for (&t, &w) in nodes.iter().zip(weights) {
+        let kw = k(t) * w * two_pi;
+        for (lambda, p) in lambda.iter_mut().zip(legendre_p_all(degree, t)) {
+            *lambda = *lambda + kw * p;
+        }
+    }
+    lambda
+}
+
+/// Spherical convolution of the function with the coefficients `coeffs` with `kernel`
+///
+/// Returns the coefficients of the integral of k(p . q) f(q) over all directions q, which are
+/// the coefficients of f multiplied by the eigenvalues lambda_l of the kernel (see [`Kernel`]).
+/// The coefficients may be real or complex.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::{convolve, Kernel};
+/// use sphrs::SHCoefficients;
+///
+/// // Irradiance of a constant environment with radiance 1
+/// let radiance = SHCoefficients::from_vec(1, vec![(4.0 * std::f64::consts::PI).sqrt(), 0.0, 0.0, 0.0]);
+/// let irradiance = convolve(&radiance, &Kernel::<f64>::CosineLobe(1));
+/// let value = irradiance.get(0, 0).unwrap() / (4.0 * std::f64::consts::PI).sqrt();
+/// assert!((value - std::f64::consts::PI).abs() < 1e-14);
+/// ```
+pub fn convolve<T, C>(coeffs: &SHCoefficients<C>, kernel: &Kernel<T>) -> SHCoefficients<C>
+where
+    T: SphrsFloat,
+    C: Copy + Mul<T, Output = C>,
+{
+    let lambda = kernel.eigenvalues(coeffs.degree());
+    let mut out = coeffs.clone();
+    filter_degrees(&mut out, |l| lambda[l]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::{Coordinates, SHCoordinates};
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn lambertian_kernel() {
+        // Ramamoorthi and Hanrahan: pi, 2 pi / 3, pi / 4, 0, -pi / 24
+        let lambda = Kernel::<f64>::CosineLobe(1).eigenvalues(4);
+        let expected = [PI, 2.0 * PI / 3.0, PI / 4.0, 0.0, -PI / 24.0];
+        for (a, b) in lambda.iter().zip(expected) {
+            assert_relative_eq!(*a, b, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn cap_matches_quadrature() {
+        let half_angle = 0.6f64;
+        let exact = Kernel::Cap(half_angle).eigenvalues(8);
+        let area = 2.0 * PI * (1.0 - half_angle.cos());
+        let k = |t: f64| {
+            if t >= half_angle.cos() {
+                1.0 / area
+            } else {
+                0.0
+            }
+        };
+        let numeric = Kernel::from_fn(8, 4000, k).eigenvalues(8);
+        assert_relative_eq!(exact[0], 1.0);
+        for (a, b) in exact.iter().zip(&numeric) {
+            assert_relative_eq!(a, b, epsilon = 1e-3);
+        }
+        assert_eq!(Kernel::Cap(0.0).eigenvalues(2), vec![1.0; 3]);
+    }
+
+    #[test]
+    fn gaussian_kernel() {
+        // Numerically integrated heat kernel sum_l (2 l + 1) / (4 pi) lambda_l P_l(t)
+        let sigma = 0.3f64;
+        let lambda = Kernel::Gaussian(sigma).eigenvalues(60);
+        let k = |t: f64| {
+            legendre_p_all(60, t)
+                .iter()
+                .zip(&lambda)
+                .enumerate()
+                .map(|(l, (p, lambda))| (2 * l + 1) as f64 / (4.0 * PI) * lambda * p)
+                .sum::<f64>()
+        };
+        let numeric = Kernel::from_fn(10, 80, k).eigenvalues(10);
+        for (a, b) in lambda.iter().zip(&numeric) {
+            assert_relative_eq!(a, b, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn convolution_is_spatial_integral() {
+        // Convolve a random expansion with k(t) = t^3 + t and compare to a direct quadrature
+        let degree = 3;
+        let set = HarmonicsSet::new(degree, RealSH::Spherical);
+        let coeffs = SHCoefficients::from_vec(
+            degree,
+            (0..16).map(|i| ((i * 7) % 5) as f64 - 2.0).collect(),
+        );
+        let k = |t: f64| t * t * t + t;
+        let out = convolve(&coeffs, &Kernel::from_fn(degree, 4, k));
+
+        let p = Coordinates::spherical(1.0, 0.7, 1.9);
+        let (nodes, weights) = gauss_legendre::<f64>(8);
+        let n_phi = 16;
+        let mut expected = 0.0;
+        for (&x, &w) in nodes.iter().zip(&weights) {
+            for j in 0..n_phi {
+                let phi = 2.0 * PI * j as f64 / n_phi as f64;
+                let q = Coordinates::spherical(1.0, x.acos(), phi);
+                let dot = p.x() * q.x() + p.y() * q.y() + p.z() * q.z();
+                let f: f64 = set.eval_sum(&q, coeffs.as_slice()).unwrap();
+                expected += w * 2.0 * PI / n_phi as f64 * k(dot) * f;
+            }
+        }
+        let value: f64 = set.eval_sum(&p, out.as_slice()).unwrap();
+        assert_relative_eq!(value, expected, epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::ops::Mul;
+
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Isotropic filter acting on coefficient sets
+///
+/// Each variant defines a transfer function h_l per degree l, by which all coefficients of that
+/// degree are multiplied. Since the transfer function does not depend on the order, filtering
+/// commutes with rotations.
+///
+/// The tapers [`Cosine`](`Filter::Cosine`), [`Hann`](`Filter::Hann`) and
+/// [`Lanczos`](`Filter::Lanczos`) decrease smoothly to zero instead of cutting the expansion off.
+/// They suppress the Gibbs ringing of truncated expansions of discontinuous functions at the cost
+/// of some resolution.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Filter<T> {
+    /// Keep the degrees up to and including the cutoff, h_l = 1 for l <= cutoff and 0 otherwise
+    LowPass(usize),
+    /// Keep the degrees from the cutoff on, h_l = 1 for l >= cutoff and 0 otherwise
+    HighPass(usize),
+    /// Keep the degrees from the first to (and including) the second bound
+    BandPass(usize, usize),
+    /// Gaussian smoothing with an angular scale of sigma radians, h_l = exp(-l (l + 1) sigma^2 / 2)
+    ///
+    /// For small sigma this is the smoothing with a Gaussian kernel of standard deviation sigma
+    /// (in angle on the sphere), i.e. a full width at half maximum of sqrt(8 ln 2) sigma. See
+    /// [`Filter::gaussian_fwhm`].
+    Gaussian(T),
+    /// Transfer function h_l = `transfer[l]` given per degree
+    ///
+    /// The degrees beyond the end of the vector are removed (h_l = 0).
+    Transfer(Vec<T>),
+    /// Cosine taper from the first to the second degree
+    ///
+    /// h_l = 1 up to the first degree l1, (1 + cos(pi (l - l1) / (l2 - l1))) / 2 in between and 0
+    /// from the second degree l2 on.
+    Cosine(usize, usize),
+    /// Hann taper of the degrees up to and including L, h_l = (1 + cos(pi l / (L + 1))) / 2 for
+    /// l <= L and 0 otherwise
+    Hann(usize),
+    /// Lanczos sigma factors of the degrees up to and including L,
+    /// h_l = sin(pi l / (L + 1)) / (pi l / (L + 1)) for l <= L and 0 otherwise
+    Lanczos(usize),
+}
+
+impl<T: SphrsFloat> Filter<T> {
+    /// Gaussian smoothing with a full width at half maximum of `fwhm` radians
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::spectral::Filter;
+    ///
+    /// let filter = Filter::gaussian_fwhm(1f64.to_radians());
+    /// assert_eq!(filter.transfer(0), 1.0);
+    /// assert!(filter.transfer(200) < 0.5);
+    /// ```
+    pub fn gaussian_fwhm(fwhm: T) -> Self {
+        let ln2 = T::from_f64(std::f64::consts::LN_2).unwrap();
+        Filter::Gaussian(fwhm / (T::from_f64(8.0).unwrap() * ln2).sqrt())
+    }
+
+    /// Transfer function h_l of degree `l`
+    pub fn transfer(&self, l: usize) -> T {
+        let keep = |keep: bool| if keep { T::one() } else { T::zero() };
+        match self {
+            Filter::LowPass(cutoff) => keep(l <= *cutoff),
+            Filter::HighPass(cutoff) => keep(l >= *cutoff),
+            Filter::BandPass(low, high) => keep(*low <= l && l <= *high),
+            Filter::Gaussian(sigma) => {
+                let ll = T::from_usize(l * (l + 1)).unwrap();
+                (-ll * *sigma * *sigma / T::from_f64(2.0).unwrap()).exp()
+            }
+            Filter::Transfer(h) => h.get(l).copied().unwrap_or_else(T::zero),
+            Filter::Cosine(start, end) => {
+                if l <= *start {
+                    T::one()
+                } else if l >= *end {
+                    T::zero()
+                } else {
+                    hann(l - start, end - start)
+                }
+            }
+            Filter::Hann(degree) => {
+                if l <= *degree {
+                    hann(l, degree + 1)
+                } else {
+                    T::zero()
+                }
+            }
+            Filter::Lanczos(degree) => {
+                if l == 0 {
+                    T::one()
+                } else if l <= *degree {
+                    let x =
+                        T::PI() * T::from_usize(l).unwrap() / T::from_usize(degree + 1).unwrap();
+                    x.sin() / x
+                } else {
+                    T::zero()
+                }
+            }
+        }
+    }
+
+    /// Multiply the coefficients of each degree l of `coeffs` by the transfer function h_l
+    ///
+    /// The coefficients may be real or complex.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sphrs::spectral::Filter;
+    /// use sphrs::SHCoefficients;
+    ///
+    /// let mut coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 0.5, 0.2, 0.1]);
+    /// Filter::<f64>::LowPass(0).apply(&mut coeffs);
+    /// assert_eq!(coeffs.as_slice(), &[1.0, 0.0, 0.0, 0.0]);
+    /// ```
+    pub fn apply<C>(&self, coeffs: &mut SHCoefficients<C>)
+    where
+        C: Copy + Mul<T, Output = C>,
+    {
+        filter_degrees(coeffs, |l| self.transfer(l));
+    }
+}
+
+/// Hann window (1 + cos(pi k / n)) / 2
+fn hann<T: SphrsFloat>(k: usize, n: usize) -> T {
+    let x = T::PI() * T::from_usize(k).unwrap() / T::from_usize(n).unwrap();
+    (T::one() + x.cos()) / T::from_f64(2.0).unwrap()
+}
+
+/// Multiply the coefficients of each degree l of `coeffs` by `transfer(l)`
+///
+/// This applies an arbitrary transfer function given as a closure; see [`Filter`] for the common
+/// ones.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::filter_degrees;
+/// use sphrs::SHCoefficients;
+///
+/// // Upward continuation of a potential from radius 1 to radius 2
+/// let mut coeffs = SHCoefficients::from_vec(1, vec![1.0f64, 1.0, 1.0, 1.0]);
+/// filter_degrees(&mut coeffs, |l| 0.5f64.powi(l as i32 + 1));
+/// assert_eq!(coeffs.as_slice(), &[0.5, 0.25, 0.25, 0.25]);
+/// ```
+pub fn filter_degrees<T, C, F>(coeffs: &mut SHCoefficients<C>, transfer: F)
+where
+    T: Copy,
+    C: Copy + Mul<T, Output = C>,
+    F: Fn(usize) -> T,
+{
+    let degree = coeffs.degree();
+    let data = coeffs.as_mut_slice();
+    for l in 0..=degree {
+        let h = transfer(l);
+        for c in &mut data[l * l..(l + 1) * (l + 1)] {
+            *c = *c * h;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectral::power_spectrum;
+    use approx::assert_relative_eq;
+    use num_complex::Complex;
+
+    #[test]
+    fn pass_filters() {
+        let coeffs = SHCoefficients::from_vec(3, vec![1.0f64; 16]);
+        let cases = [
+            (Filter::LowPass(1), [1.0, 3.0, 0.0, 0.0]),
+            (Filter::HighPass(2), [0.0, 0.0, 5.0, 7.0]),
+            (Filter::BandPass(1, 2), [0.0, 3.0, 5.0, 0.0]),
+            (Filter::Transfer(vec![2.0, 1.0]), [4.0, 3.0, 0.0, 0.0]),
+        ];
+        for (filter, expected) in cases {
+            let mut c = coeffs.clone();
+            filter.apply(&mut c);
+            assert_eq!(power_spectrum(&c), expected);
+        }
+    }
+
+    #[test]
+    fn gaussian() {
+        let sigma = 0.1f64;
+        let filter = Filter::Gaussian(sigma);
+        assert_eq!(filter.transfer(0), 1.0);
+        assert_relative_eq!(filter.transfer(10), (-110.0 * sigma * sigma / 2.0).exp());
+        // Half maximum of the kernel at fwhm / 2 for small widths
+        let fwhm = 0.02f64;
+        let Filter::Gaussian(s) = Filter::gaussian_fwhm(fwhm) else {
+            unreachable!()
+        };
+        assert_relative_eq!((-(fwhm / 2.0).powi(2) / (2.0 * s * s)).exp(), 0.5);
+    }
+
+    #[test]
+    fn tapers() {
+        let cosine = Filter::<f64>::Cosine(2, 6);
+        let hann = Filter::<f64>::Hann(3);
+        let lanczos = Filter::<f64>::Lanczos(3);
+        assert_eq!(cosine.transfer(2), 1.0);
+        assert_relative_eq!(cosine.transfer(4), 0.5, epsilon = 1e-15);
+        assert_eq!(cosine.transfer(6), 0.0);
+        assert_eq!(hann.transfer(0), 1.0);
+        assert_relative_eq!(hann.transfer(2), 0.5, epsilon = 1e-15);
+        assert_eq!(hann.transfer(4), 0.0);
+        assert_eq!(lanczos.transfer(0), 1.0);
+        assert_relative_eq!(
+            lanczos.transfer(2),
+            2.0 / std::f64::consts::PI,
+            epsilon = 1e-15
+        );
+        assert_eq!(lanczos.transfer(4), 0.0);
+        for filter in [cosine, hann, lanczos] {
+            for l in 0..8 {
+                let (a, b) = (filter.transfer(l), filter.transfer(l + 1));
+                assert!((0.0..=1.0).contains(&b) && b <= a);
+            }
+        }
+    }
+
+    #[test]
+    fn tapers_suppress_ringing() {
+        // Zonal expansion of the step function sign(cos theta) up to degree 31,
+        // a_l0 = 2 sqrt((2l + 1) pi) P_{l-1}(0) / (l + 1) for odd l
+        let degree = 31;
+        let mut coeffs = SHCoefficients::zeros(degree);
+        let mut p = 1.0f64;
+        for l in (1..=degree).step_by(2) {
+            *coeffs.get_mut(l as i64, 0).unwrap() =
+                2.0 * (((2 * l + 1) as f64) * std::f64::consts::PI).sqrt() * p / (l + 1) as f64;
+            p *= -(l as f64) / (l + 1) as f64;
+        }
+        let sh = crate::basis::HarmonicsSet::new(degree, crate::basis::RealSH::Spherical);
+        let max = |c: &SHCoefficients<f64>| {
+            (1..200)
+                .map(|i| {
+                    let p = crate::Coordinates::spherical(1.0, i as f64 * 0.0075, 0.0);
+                    sh.eval_sum(&p, c.as_slice()).unwrap()
+                })
+                .fold(f64::MIN, f64::max)
+        };
+        // Gibbs overshoot of about 9% of the jump of 2
+        assert!(max(&coeffs) > 1.15);
+        for filter in [
+            Filter::<f64>::Hann(degree),
+            Filter::Lanczos(degree),
+            Filter::Cosine(0, degree + 1),
+        ] {
+            let mut c = coeffs.clone();
+            filter.apply(&mut c);
+            assert!(max(&c) < 1.04);
+        }
+    }
+
+    #[test]
+    fn complex_coefficients() {
+        let mut coeffs = SHCoefficients::from_vec(1, vec![Complex::new(1.0f64, 2.0); 4]);
+        Filter::Transfer(vec![1.0, 0.5]).apply(&mut coeffs);
+        assert_eq!(coeffs.get(0, 0), Some(&Complex::new(1.0, 2.0)));
+        assert_eq!(coeffs.get(1, -1), Some(&Complex::new(0.5, 1.0)));
+    }
+}
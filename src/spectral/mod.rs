@@ -0,0 +1,52 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spectral analysis of coefficient sets
+//!
+//! The functions of this module operate on coefficients of orthonormal real spherical harmonics
+//! degree by degree. By Parseval's theorem, the power sum_m a_lm^2 of degree l is the
+//! contribution of that degree to the integral of f^2 over the sphere, and the cross power
+//! sum_m a_lm b_lm is the contribution to the integral of f g. Both are invariant under
+//! rotations.
+//!
+//! Besides the [`power_spectrum`] and the [`cross_spectrum`], the [`total_power`], the
+//! [`degree_rms`] and, for two sets, the [`degree_correlation`], the [`admittance`] and the
+//! [`coherence`] are computed from them.
+//!
+//! Isotropic filters, which multiply all coefficients of a degree by the same factor, are applied
+//! with [`Filter`] or [`filter_degrees`]; among them are tapers against Gibbs ringing. The
+//! convolution with an axially symmetric [`Kernel`] is such a filter by the Funk-Hecke theorem and
+//! is computed by [`convolve`]. [`product`] computes the coefficients of the pointwise product of
+//! two expansions.
+//!
+//! # Example
+//!
+//! ```
+//! use sphrs::spectral::{admittance, degree_correlation};
+//! use sphrs::SHCoefficients;
+//!
+//! // "Gravity" responding to "topography" with a degree dependent transfer function
+//! let topography = SHCoefficients::from_vec(1, vec![0.0f64, 0.3, -0.2, 0.5]);
+//! let gravity = SHCoefficients::from_vec(1, vec![0.0, 0.6, -0.4, 1.0]);
+//! let z = admittance(&gravity, &topography);
+//! assert!((z[1] - 2.0).abs() < 1e-12);
+//! let c = degree_correlation(&gravity, &topography);
+//! assert!((c[1] - 1.0).abs() < 1e-12);
+//! ```
+
+mod convolution;
+mod filter;
+mod product;
+mod spectrum;
+
+pub use self::convolution::{convolve, Kernel};
+pub use self::filter::{filter_degrees, Filter};
+pub use self::product::product;
+pub use self::spectrum::{
+    admittance, coherence, cross_spectrum, degree_correlation, degree_rms, power_spectrum,
+    total_power,
+};
@@ -0,0 +1,107 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::coefficients::num_coefficients;
+use crate::sht::{forward, synthesize, Sampling};
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Coefficients up to degree `output_degree` of the pointwise product f g of the expansions `a`
+/// and `b`
+///
+/// The product of expansions of the degrees L_a and L_b has the degree L_a + L_b. Both
+/// expansions are synthesized on a Gauss-Legendre grid with bandlimit
+/// max(L_a + L_b, `output_degree`) + 1, multiplied and analyzed again with [`forward`], which is
+/// exact for this bandlimit. The coefficients are therefore the exact integrals of f g Y_lm, as
+/// given by the Gaunt coefficients, at a cost of O(L^3) instead of O(L^5) for summing over the
+/// Gaunt coefficients. An `output_degree` below L_a + L_b truncates the product without
+/// aliasing, as needed e.g. for the nonlinear terms of spectral solvers, and a higher one pads
+/// it with zeros.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::product;
+/// use sphrs::SHCoefficients;
+///
+/// // Y_0,0 = 1 / sqrt(4 pi) times any function scales its coefficients
+/// let y00 = SHCoefficients::from_vec(0, vec![1.0f64]);
+/// let g = SHCoefficients::from_vec(1, vec![0.3, -0.2, 0.5, 0.1]);
+/// let fg = product(&y00, &g, 1);
+/// let scale = 1.0 / (4.0 * std::f64::consts::PI).sqrt();
+/// for (a, b) in fg.as_slice().iter().zip(g.as_slice()) {
+///     assert!((a - scale * b).abs() < 1e-14);
+/// }
+/// ```
+pub fn product<T: SphrsFloat>(
+    a: &SHCoefficients<T>,
+    b: &SHCoefficients<T>,
+    output_degree: usize,
+) -> SHCoefficients<T> {
+    let bandlimit = (a.degree() + b.degree()).max(output_degree) + 1;
+    let sampling = Sampling::GaussLegendre;
+    let thetas = sampling.thetas::<T>(bandlimit);
+    let phis = sampling.phis::<T>(bandlimit);
+    let values: Vec<T> = synthesize(a, &thetas, &phis)
+        .into_iter()
+        .zip(synthesize(b, &thetas, &phis))
+        .map(|(f, g)| f * g)
+        .collect();
+    let coeffs = forward(sampling, bandlimit, &values).expect("grid and values have the same size");
+    let mut data = coeffs.into_vec();
+    data.truncate(num_coefficients(output_degree));
+    SHCoefficients::from_vec(output_degree, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH};
+    use crate::grid::map_pointwise;
+    use crate::sampling::fibonacci;
+    use approx::assert_relative_eq;
+
+    fn coefficients(degree: usize, seed: usize) -> SHCoefficients<f64> {
+        SHCoefficients::from_vec(
+            degree,
+            (0..num_coefficients(degree))
+                .map(|i| (((i + seed) * 7919) % 101) as f64 / 50.0 - 1.0)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn pointwise_values() {
+        let (a, b) = (coefficients(3, 1), coefficients(4, 2));
+        let fg = product(&a, &b, 7);
+        assert_eq!(fg.degree(), 7);
+        let (sh3, sh4) = (
+            HarmonicsSet::new(3, RealSH::Spherical),
+            HarmonicsSet::new(4, RealSH::Spherical),
+        );
+        let sh7 = HarmonicsSet::new(7, RealSH::Spherical);
+        for p in fibonacci::<f64>(20, 0) {
+            let f: f64 = sh3.eval_sum(&p, a.as_slice()).unwrap();
+            let g: f64 = sh4.eval_sum(&p, b.as_slice()).unwrap();
+            let v: f64 = sh7.eval_sum(&p, fg.as_slice()).unwrap();
+            assert_relative_eq!(v, f * g, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn truncation_and_padding() {
+        let a = coefficients(3, 5);
+        let square = map_pointwise(&a, 4, 6, |v| v * v);
+        let truncated = product(&a, &a, 4);
+        for (x, y) in truncated.as_slice().iter().zip(square.as_slice()) {
+            assert_relative_eq!(x, y, epsilon = 1e-12);
+        }
+        let padded = product(&a, &a, 8);
+        assert!(padded.as_slice()[num_coefficients(6)..]
+            .iter()
+            .all(|c| c.abs() < 1e-14));
+    }
+}
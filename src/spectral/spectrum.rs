@@ -0,0 +1,203 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::{SHCoefficients, SphrsFloat};
+
+/// Power sum_m a_lm^2 of each degree l of `a`
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::power_spectrum;
+/// use sphrs::SHCoefficients;
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![2.0f64, 1.0, 0.0, 1.0]);
+/// assert_eq!(power_spectrum(&coeffs), vec![4.0, 2.0]);
+/// ```
+pub fn power_spectrum<T: SphrsFloat>(a: &SHCoefficients<T>) -> Vec<T> {
+    cross_spectrum(a, a)
+}
+
+/// Total power sum_l sum_m a_lm^2 of `a`
+///
+/// By Parseval's theorem this is the integral of f^2 over the sphere, i.e. 4 pi times the mean
+/// square of f.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::total_power;
+/// use sphrs::SHCoefficients;
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![2.0f64, 1.0, 0.0, 1.0]);
+/// assert_eq!(total_power(&coeffs), 6.0);
+/// ```
+pub fn total_power<T: SphrsFloat>(a: &SHCoefficients<T>) -> T {
+    a.as_slice().iter().fold(T::zero(), |acc, &x| acc + x * x)
+}
+
+/// Root mean square sqrt(S_l / (2l + 1)) of the coefficients of each degree l of `a`
+///
+/// This is the degree RMS of geodesy, the typical magnitude of a single coefficient of degree l,
+/// as commonly plotted against the degree to compare gravity field models and their errors.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::spectral::degree_rms;
+/// use sphrs::SHCoefficients;
+///
+/// let coeffs = SHCoefficients::from_vec(1, vec![-2.0f64, 1.0, 1.0, 1.0]);
+/// assert_eq!(degree_rms(&coeffs), vec![2.0, 1.0]);
+/// ```
+pub fn degree_rms<T: SphrsFloat>(a: &SHCoefficients<T>) -> Vec<T> {
+    power_spectrum(a)
+        .into_iter()
+        .enumerate()
+        .map(|(l, s)| (s / T::from_usize(2 * l + 1).unwrap()).sqrt())
+        .collect()
+}
+
+/// Cross power sum_m a_lm b_lm of each degree l of `a` and `b`
+///
+/// The sets may differ in degree; the spectrum is computed up to the lower of both degrees.
+pub fn cross_spectrum<T: SphrsFloat>(a: &SHCoefficients<T>, b: &SHCoefficients<T>) -> Vec<T> {
+    let degree = a.degree().min(b.degree());
+    (0..=degree)
+        .map(|l| {
+            let range = l * l..(l + 1) * (l + 1);
+            a.as_slice()[range.clone()]
+                .iter()
+                .zip(&b.as_slice()[range])
+                .fold(T::zero(), |acc, (&x, &y)| acc + x * y)
+        })
+        .collect()
+}
+
+/// Degree correlation S_ab / sqrt(S_aa S_bb) of `a` and `b`
+///
+/// The correlation of degree l lies in [-1, 1] and is 1 if the coefficients of both sets are
+/// proportional with a positive factor within that degree. Degrees in which either set has no
+/// power have a correlation of zero. As for [`cross_spectrum`], the correlation is computed up
+/// to the lower of both degrees.
+pub fn degree_correlation<T: SphrsFloat>(a: &SHCoefficients<T>, b: &SHCoefficients<T>) -> Vec<T> {
+    let (saa, sbb) = (power_spectrum(a), power_spectrum(b));
+    cross_spectrum(a, b)
+        .into_iter()
+        .zip(saa.into_iter().zip(sbb))
+        .map(|(sab, (saa, sbb))| {
+            let norm = (saa * sbb).sqrt();
+            if norm > T::zero() {
+                // Clamp rounding errors for (anti)proportional coefficients
+                (sab / norm).max(-T::one()).min(T::one())
+            } else {
+                T::zero()
+            }
+        })
+        .collect()
+}
+
+/// Coherence S_ab^2 / (S_aa S_bb) of `a` and `b`
+///
+/// The coherence of degree l is the square of the [`degree_correlation`] and lies in [0, 1]. It is
+/// the fraction of the power of degree l of either set which is explained by a linear relation
+/// to the other one, e.g. by the [`admittance`]. Degrees in which either set has no power have a
+/// coherence of zero. As for [`cross_spectrum`], the coherence is computed up to the lower of
+/// both degrees.
+pub fn coherence<T: SphrsFloat>(a: &SHCoefficients<T>, b: &SHCoefficients<T>) -> Vec<T> {
+    degree_correlation(a, b)
+        .into_iter()
+        .map(|c| c * c)
+        .collect()
+}
+
+/// Admittance S_ab / S_bb of `a` with respect to `b`
+///
+/// The admittance of degree l is the least squares estimate of the factor z_l in the linear
+/// model a_lm = z_l b_lm, e.g. the response of the gravity field `a` to the topography `b` in
+/// geophysics. Degrees in which `b` has no power have an admittance of zero. As for
+/// [`cross_spectrum`], the admittance is computed up to the lower of both degrees.
+pub fn admittance<T: SphrsFloat>(a: &SHCoefficients<T>, b: &SHCoefficients<T>) -> Vec<T> {
+    cross_spectrum(a, b)
+        .into_iter()
+        .zip(power_spectrum(b))
+        .map(|(sab, sbb)| {
+            if sbb > T::zero() {
+                sab / sbb
+            } else {
+                T::zero()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::project_quadrature;
+    use crate::{Coordinates, SHCoordinates};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn cross_spectrum_sums_to_inner_product() {
+        // sum_l S_ab(l) is the integral of f g over the sphere
+        let f = |p: &Coordinates<f64>| 1.0 + p.x() * p.z() - 0.5 * p.y();
+        let g = |p: &Coordinates<f64>| p.z() * p.z() + 0.2 * p.y();
+        let a = project_quadrature(3, f).into_coefficients();
+        let b = project_quadrature(3, g).into_coefficients();
+        let s: f64 = cross_spectrum(&a, &b).iter().sum();
+        assert_relative_eq!(s, 0.9 * 4.0 * std::f64::consts::PI / 3.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn total_power_is_mean_square() {
+        let f = |p: &Coordinates<f64>| 0.5 + p.x() * p.y() - p.z();
+        let a = project_quadrature(2, f).into_coefficients();
+        // Mean of f^2 = 1/4 + <x^2 y^2> + <z^2> = 1/4 + 1/15 + 1/3
+        let mean_square = 0.25 + 1.0 / 15.0 + 1.0 / 3.0;
+        assert_relative_eq!(
+            total_power(&a),
+            4.0 * std::f64::consts::PI * mean_square,
+            epsilon = 1e-12
+        );
+        let rms = degree_rms(&a);
+        for (l, (r, s)) in rms.iter().zip(power_spectrum(&a)).enumerate() {
+            assert_relative_eq!(r * r * (2 * l + 1) as f64, s, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn lower_degree() {
+        let a = SHCoefficients::from_vec(2, (1..=9).map(f64::from).collect());
+        let b = SHCoefficients::from_vec(1, vec![1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(cross_spectrum(&a, &b), vec![1.0, 9.0]);
+        assert_eq!(cross_spectrum(&b, &a), vec![1.0, 9.0]);
+        assert_eq!(power_spectrum(&a).len(), 3);
+    }
+
+    #[test]
+    fn correlation_and_admittance() {
+        let b = SHCoefficients::from_vec(2, vec![0.0, 1.0, 2.0, -1.0, 0.5, 0.1, 0.3, -0.2, 0.7]);
+        let mut a = b.clone();
+        // Degree 1 scaled by -3, degree 2 orthogonal to b
+        for c in &mut a.as_mut_slice()[1..4] {
+            *c *= -3.0;
+        }
+        a.as_mut_slice()[4..9].copy_from_slice(&[0.1, -0.5, 0.0, 0.0, 0.0]);
+        let c = degree_correlation(&a, &b);
+        let z = admittance(&a, &b);
+        for (g, c) in coherence(&a, &b).into_iter().zip(&c) {
+            assert_relative_eq!(g, c * c);
+        }
+        assert_eq!(c[0], 0.0);
+        assert_eq!(z[0], 0.0);
+        assert_relative_eq!(c[1], -1.0);
+        assert_relative_eq!(z[1], -3.0, epsilon = 1e-14);
+        assert_relative_eq!(c[2], 0.0);
+        assert_relative_eq!(z[2], 0.0);
+    }
+}
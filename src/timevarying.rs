@@ -0,0 +1,236 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::coefficients::num_coefficients;
+use crate::{SHCoefficients, SphrsError, SphrsFloat};
+
+/// Expansion whose coefficients vary polynomially in time around a reference epoch
+///
+/// This is the form in which models of slowly varying fields are commonly distributed, e.g. the
+/// IGRF and WMM geomagnetic models: main field coefficients at a reference epoch t0 together with
+/// their secular variation (first time derivative) and optionally a secular acceleration (second
+/// time derivative). The coefficients at epoch t are
+///
+/// c(t) = c + (t - t0) c' + (t - t0)^2 c'' / 2.
+///
+/// The time unit is that of the derivatives, usually years. The derivatives may be given to a
+/// lower degree than the main field, missing coefficients are treated as zero.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::{SHCoefficients, TimeVaryingExpansion};
+///
+/// let main = SHCoefficients::from_vec(1, vec![0.0, 2.0, -30.0, 5.0]);
+/// let secular = SHCoefficients::from_vec(1, vec![0.0, 0.1, 0.5, 0.0]);
+/// let model = TimeVaryingExpansion::new(2020.0, main, secular).unwrap();
+/// let coeffs = model.at(2022.0);
+/// assert_eq!(coeffs.get(1, 0), Some(&-29.0));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeVaryingExpansion<T> {
+    /// Reference epoch
+    epoch: T,
+    /// Coefficients at the reference epoch
+    main: SHCoefficients<T>,
+    /// First time derivative of the coefficients
+    secular: SHCoefficients<T>,
+    /// Second time derivative of the coefficients
+    acceleration: Option<SHCoefficients<T>>,
+    /// Interval of epochs for which the model is valid
+    validity: Option<(T, T)>,
+}
+
+/// Check that the derivative coefficients `coeffs` fit into the main field
+fn check_derivative<T>(
+    main: &SHCoefficients<T>,
+    coeffs: &SHCoefficients<T>,
+    what: &str,
+) -> Result<(), SphrsError> {
+    if coeffs.degree() > main.degree() {
+        return Err(SphrsError::InvalidInput(format!(
+            "{what} of degree {} exceeds main field of degree {}",
+            coeffs.degree(),
+            main.degree()
+        )));
+    }
+    if coeffs.normalization() != main.normalization() {
+        return Err(SphrsError::InvalidInput(format!(
+            "{what} uses {} normalization, main field uses {}",
+            coeffs.normalization(),
+            main.normalization()
+        )));
+    }
+    Ok(())
+}
+
+impl<T: SphrsFloat> TimeVaryingExpansion<T> {
+    /// Create a model from the `main` coefficients at `epoch` and their `secular` variation
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if the secular variation has a higher degree or a
+    /// different normalization than the main field.
+    pub fn new(
+        epoch: T,
+        main: SHCoefficients<T>,
+        secular: SHCoefficients<T>,
+    ) -> Result<Self, SphrsError> {
+        check_derivative(&main, &secular, "secular variation")?;
+        Ok(TimeVaryingExpansion {
+            epoch,
+            main,
+            secular,
+            acceleration: None,
+            validity: None,
+        })
+    }
+
+    /// Add the secular acceleration, the second time derivative of the coefficients
+    ///
+    /// Returns [`SphrsError::InvalidInput`] if the acceleration has a higher degree or a
+    /// different normalization than the main field.
+    pub fn with_acceleration(
+        mut self,
+        acceleration: SHCoefficients<T>,
+    ) -> Result<Self, SphrsError> {
+        check_derivative(&self.main, &acceleration, "secular acceleration")?;
+        self.acceleration = Some(acceleration);
+        Ok(self)
+    }
+
+    /// Set the interval `[start, end]` of epochs for which the model is valid
+    ///
+    /// The interval is informational, [`at`](`TimeVaryingExpansion::at`) extrapolates to any
+    /// epoch.
+    #[must_use]
+    pub fn with_validity(mut self, start: T, end: T) -> Self {
+        self.validity = Some((start, end));
+        self
+    }
+
+    /// Returns the reference epoch
+    pub fn epoch(&self) -> T {
+        self.epoch
+    }
+
+    /// Returns the coefficients at the reference epoch
+    pub fn main(&self) -> &SHCoefficients<T> {
+        &self.main
+    }
+
+    /// Returns the secular variation
+    pub fn secular(&self) -> &SHCoefficients<T> {
+        &self.secular
+    }
+
+    /// Returns the secular acceleration, if present
+    pub fn acceleration(&self) -> Option<&SHCoefficients<T>> {
+        self.acceleration.as_ref()
+    }
+
+    /// Returns the interval of epochs for which the model is valid, if set
+    pub fn validity(&self) -> Option<(T, T)> {
+        self.validity
+    }
+
+    /// Returns `true` if `epoch` lies within the validity interval or if none is set
+    pub fn is_valid_at(&self, epoch: T) -> bool {
+        self.validity
+            .is_none_or(|(start, end)| start <= epoch && epoch <= end)
+    }
+
+    /// Coefficients at `epoch`
+    pub fn at(&self, epoch: T) -> SHCoefficients<T> {
+        let dt = epoch - self.epoch;
+        let mut out = self.main.clone();
+        add_scaled(&mut out, &self.secular, dt);
+        if let Some(acceleration) = &self.acceleration {
+            add_scaled(&mut out, acceleration, T::from_f64(0.5).unwrap() * dt * dt);
+        }
+        out
+    }
+
+    /// Multiply the coefficients of each degree l by the transfer function `transfer(l)` at all
+    /// epochs
+    ///
+    /// The transfer function is applied to the main field and its derivatives, see
+    /// [`SHCoefficients::apply_transfer`].
+    pub fn apply_transfer<F: Fn(usize) -> T>(&mut self, transfer: F) {
+        self.main.apply_transfer(&transfer);
+        self.secular.apply_transfer(&transfer);
+        if let Some(acceleration) = &mut self.acceleration {
+            acceleration.apply_transfer(&transfer);
+        }
+    }
+}
+
+/// Add `scale` times `coeffs` to the leading coefficients of `out`
+fn add_scaled<T: SphrsFloat>(out: &mut SHCoefficients<T>, coeffs: &SHCoefficients<T>, scale: T) {
+    let n = num_coefficients(coeffs.degree());
+    for (o, &c) in out.as_mut_slice()[..n].iter_mut().zip(coeffs.as_slice()) {
+        *o = *o + scale * c;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn secular_variation_of_lower_degree() {
+        let main = SHCoefficients::from_vec(2, (0..9).map(f64::from).collect());
+        let secular = SHCoefficients::from_vec(1, vec![0.0, 1.0, -2.0, 0.5]);
+        let model = TimeVaryingExpansion::new(2025.0, main.clone(), secular)
+            .unwrap()
+            .with_validity(2025.0, 2030.0);
+        assert_eq!(model.at(2025.0), main);
+        let c = model.at(2027.5);
+        assert_relative_eq!(*c.get(1, -1).unwrap(), 3.5);
+        assert_relative_eq!(*c.get(1, 0).unwrap(), -3.0);
+        assert_relative_eq!(*c.get(2, 2).unwrap(), 8.0);
+        assert!(model.is_valid_at(2030.0));
+        assert!(!model.is_valid_at(2031.0));
+    }
+
+    #[test]
+    fn acceleration() {
+        let main = SHCoefficients::from_vec(1, vec![1.0, 0.0, 0.0, 0.0]);
+        let secular = SHCoefficients::zeros(0);
+        let acceleration = SHCoefficients::from_vec(1, vec![2.0, 0.0, 1.0, 0.0]);
+        let model = TimeVaryingExpansion::new(0.0, main, secular)
+            .unwrap()
+            .with_acceleration(acceleration)
+            .unwrap();
+        let c = model.at(-3.0);
+        assert_relative_eq!(*c.get(0, 0).unwrap(), 10.0);
+        assert_relative_eq!(*c.get(1, 0).unwrap(), 4.5);
+
+        let too_high = SHCoefficients::zeros(2);
+        assert!(matches!(
+            model.with_acceleration(too_high),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn transfer_at_all_epochs() {
+        let main = SHCoefficients::from_vec(1, vec![1.0, 2.0, 3.0, 4.0]);
+        let secular = SHCoefficients::from_vec(1, vec![0.5, -1.0, 0.0, 1.0]);
+        let acceleration = SHCoefficients::from_vec(0, vec![2.0]);
+        let mut model = TimeVaryingExpansion::new(2020.0, main, secular)
+            .unwrap()
+            .with_acceleration(acceleration)
+            .unwrap();
+        let h = |l: usize| 1.0 / (l + 1) as f64;
+        let mut expected = model.at(2023.0);
+        expected.apply_transfer(h);
+        model.apply_transfer(h);
+        for (a, b) in model.at(2023.0).as_slice().iter().zip(expected.as_slice()) {
+            assert_relative_eq!(*a, *b, epsilon = 1e-14);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::reduce::blockwise_sum;
+use crate::{Coordinates, SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Projection of a fisheye lens, relating the angle theta to the optical axis to the distance r
+/// of the image point from the image center for the focal length f
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LensModel {
+    /// r = f theta
+    Equidistant,
+    /// r = 2 f sin(theta / 2) (equal-area)
+    Equisolid,
+    /// r = 2 f tan(theta / 2)
+    Stereographic,
+    /// r = f sin(theta)
+    Orthographic,
+}
+
+impl LensModel {
+    /// Angle to the optical axis of an image point at distance `r = rho f` from the center, if
+    /// the point lies within the domain of the projection
+    fn theta<T: SphrsFloat>(&self, rho: T) -> Option<T> {
+        let two = T::from_f64(2.0).unwrap();
+        match self {
+            LensModel::Equidistant => Some(rho),
+            LensModel::Equisolid if rho <= two => Some(two * (rho / two).asin()),
+            LensModel::Stereographic => Some(two * (rho / two).atan()),
+            LensModel::Orthographic if rho < T::one() => Some(rho.asin()),
+            _ => None,
+        }
+    }
+
+    /// Solid angle per image area at angle `theta`, times f^2
+    fn density<T: SphrsFloat>(&self, theta: T) -> T {
+        let half = theta / T::from_f64(2.0).unwrap();
+        match self {
+            LensModel::Equidistant if theta == T::zero() => T::one(),
+            LensModel::Equidistant => theta.sin() / theta,
+            LensModel::Equisolid => T::one(),
+            LensModel::Stereographic => half.cos().powi(4),
+            LensModel::Orthographic => T::one() / theta.cos(),
+        }
+    }
+}
+
+/// Geometry of a fisheye camera
+///
+/// The image point (x, y) in pixels, with the origin at the top left corner of the image and y
+/// pointing down, is at the distance r from the image center (`cx`, `cy`) and the azimuth psi
+/// from the x axis. The lens model relates r to the angle theta to the optical axis, such that the
+/// direction in the camera frame is (sin(theta) cos(psi), sin(theta) sin(psi), cos(theta)), i.e.
+/// the camera looks along z with x pointing right and y pointing down. The orientation rotates
+/// camera directions into world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fisheye<T> {
+    /// Lens model
+    model: LensModel,
+    /// Image center in pixels
+    center: (T, T),
+    /// Focal length in pixels
+    focal: T,
+    /// Maximum angle to the optical axis
+    max_angle: T,
+    /// Rotation from camera to world coordinates (rows)
+    rotation: [[T; 3]; 3],
+}
+
+impl<T: SphrsFloat> Fisheye<T> {
+    /// Create a fisheye camera with image center (`cx`, `cy`) and focal length `focal` in pixels
+    ///
+    /// Defaults to a field of view of one hemisphere (a maximum angle of pi / 2 to the optical
+    /// axis) and a camera frame equal to the world frame.
+    pub fn new(model: LensModel, cx: T, cy: T, focal: T) -> Self {
+        let (o, l) = (T::zero(), T::one());
+        Fisheye {
+            model,
+            center: (cx, cy),
+            focal,
+            max_angle: T::FRAC_PI_2(),
+            rotation: [[l, o, o], [o, l, o], [o, o, l]],
+        }
+    }
+
+    /// The two cameras of a dual-fisheye image of size `width` x `height` with one fisheye
+    /// circle in each half of the image
+    ///
+    /// The fisheye of the left half looks along +x, the one of the right half along -x, and
+    /// the image up direction of both is +z. The focal length is chosen such that the field of
+    /// view of `fov` (in radians, e.g. 195 degrees for typical consumer cameras) fills the
+    /// height of the image. Each camera covers one hemisphere (see
+    /// [`max_angle`](`Fisheye::max_angle`)) such that the overlap of the lenses is not counted
+    /// twice.
+    pub fn dual(model: LensModel, width: usize, height: usize, fov: T) -> [Self; 2] {
+        let (o, l) = (T::zero(), T::one());
+        let two = T::from_f64(2.0).unwrap();
+        let h = T::from_usize(height).unwrap();
+        let radius = h / two;
+        let theta = fov / two;
+        let focal = radius
+            / match model {
+                LensModel::Equidistant => theta,
+                LensModel::Equisolid => two * (theta / two).sin(),
+                LensModel::Stereographic => two * (theta / two).tan(),
+                LensModel::Orthographic => theta.min(T::FRAC_PI_2()).sin(),
+            };
+        let cx = T::from_usize(width).unwrap() / T::from_f64(4.0).unwrap();
+        let front =
+            Fisheye::new(model, cx, radius, focal).orientation([[o, o, l], [-l, o, o], [o, -l, o]]);
+        let back = Fisheye::new(model, T::from_f64(3.0).unwrap() * cx, radius, focal)
+            .orientation([[o, o, -l], [l, o, o], [o, -l, o]]);
+        [front, back]
+    }
+
+    /// Maximum angle to the optical axis; pixels beyond are ignored
+    #[must_use]
+    pub fn max_angle(mut self, max_angle: T) -> Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    /// Rotation matrix from camera to world coordinates, given by its rows
+    #[must_use]
+    pub fn orientation(mut self, rotation: [[T; 3]; 3]) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Direction (in world coordinates) and solid angle per pixel area of the image point
+    /// (`x`, `y`), if the point lies within the field of view
+    pub fn direction(&self, x: T, y: T) -> Option<(Coordinates<T>, T)> {
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        let r = (dx * dx + dy * dy).sqrt();
+        let theta = self.model.theta(r / self.focal)?;
+        if theta > self.max_angle {
+            return None;
+        }
+        let (st, ct) = theta.sin_cos();
+        let (cp, sp) = if r > T::zero() {
+            (dx / r, dy / r)
+        } else {
+            (T::one(), T::zero())
+        };
+        let d = [st * cp, st * sp, ct];
+        let w = self
+            .rotation
+            .map(|row| row[0] * d[0] + row[1] * d[1] + row[2] * d[2]);
+        let density = self.model.density(theta) / (self.focal * self.focal);
+        Some((Coordinates::cartesian(w[0], w[1], w[2]), density))
+    }
+}
+
+/// Project samples with known solid angles onto real SH
+///
+/// `solid_angles` are the solid angles covered by the samples at `directions`, e.g. the pixels of
+/// a camera with a per-pixel direction map. Returns [`SphrsError::InvalidInput`] if the lengths
+/// of the inputs differ.
+pub fn project_pixels<T, C>(
+    degree: usize,
+    directions: &[C],
+    solid_angles: &[T],
+    values: &[T],
+) -> Result<SHCoefficients<T>, SphrsError>
+where
+    T: SphrsFloat,
+    C: SHCoordinates<T> + Sync,
+{
+    if solid_angles.len() != directions.len() || values.len() != directions.len() {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} directions, {} solid angles and {} values",
+            directions.len(),
+            solid_angles.len(),
+            values.len()
+        )));
+    }
+    let set = HarmonicsSet::new(degree, RealSH::Spherical);
+    let out = blockwise_sum(directions.len(), set.num_sh(), |range, acc| {
+        for i in range {
+            let v = values[i] * solid_angles[i];
+            for (a, y) in acc.iter_mut().zip(set.eval(&directions[i])) {
+                *a = *a + v * y;
+            }
+        }
+    });
+    Ok(SHCoefficients::from_vec(degree, out))
+}
+
+/// Project an image of size `width` x `height`, stored row by row, taken by one or more fisheye
+/// `cameras` onto real SH
+///
+/// Each pixel is assigned to the first camera whose field of view contains the pixel center and
+/// is weighted with its solid angle. Pixels outside the field of view of all cameras are ignored.
+/// Returns [`SphrsError::InvalidInput`] if the number of pixels does not equal
+/// `width * height`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::transform::{project_fisheye, Fisheye, LensModel};
+///
+/// let (width, height) = (400, 200);
+/// let cameras = Fisheye::dual(LensModel::Equisolid, width, height, 200f64.to_radians());
+/// let image = vec![1.0; width * height];
+/// let coeffs = project_fisheye(2, &cameras, width, height, &image).unwrap();
+/// // A constant image of one covers the whole sphere
+/// let expected = (4.0 * std::f64::consts::PI).sqrt();
+/// assert!((coeffs.get(0, 0).unwrap() - expected).abs() < 1e-2);
+/// ```
+pub fn project_fisheye<T: SphrsFloat>(
+    degree: usize,
+    cameras: &[Fisheye<T>],
+    width: usize,
+    height: usize,
+    pixels: &[T],
+) -> Result<SHCoefficients<T>, SphrsError> {
+    if pixels.len() != width * height {
+        return Err(SphrsError::InvalidInput(format!(
+            "{} pixels in an image of size {width} x {height}",
+            pixels.len()
+        )));
+    }
+    let half = T::from_f64(0.5).unwrap();
+    let mut directions = Vec::new();
+    let mut solid_angles = Vec::new();
+    let mut values = Vec::new();
+    for j in 0..height {
+        for i in 0..width {
+            let (x, y) = (
+                T::from_usize(i).unwrap() + half,
+                T::from_usize(j).unwrap() + half,
+            );
+            if let Some((p, w)) = cameras.iter().find_map(|c| c.direction(x, y)) {
+                directions.push(p);
+                solid_angles.push(w);
+                values.push(pixels[j * width + i]);
+            }
+        }
+    }
+    project_pixels(degree, &directions, &solid_angles, &values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::SHEval;
+    use std::f64::consts::PI;
+
+    const MODELS: [LensModel; 4] = [
+        LensModel::Equidistant,
+        LensModel::Equisolid,
+        LensModel::Stereographic,
+        LensModel::Orthographic,
+    ];
+
+    #[test]
+    fn solid_angle_density() {
+        // Solid angle of a small ring around the optical axis relative to its image area
+        let camera = |model| Fisheye::new(model, 0.0, 0.0, 100.0f64);
+        for model in MODELS {
+            for &r in &[0.0, 20.0, 60.0] {
+                let h = 1e-3;
+                let theta = |r: f64| model.theta(r / 100.0).unwrap();
+                let (t0, t1) = (theta(r), theta(r + h));
+                let solid_angle = 2.0 * PI * (t0.cos() - t1.cos());
+                let area = PI * ((r + h).powi(2) - r * r);
+                let (_, density) = camera(model).direction(r + h / 2.0, 0.0).unwrap();
+                assert!(
+                    (solid_angle / area - density).abs() < 1e-6 * density,
+                    "{model:?} {r}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dual_fisheye_orientation() {
+        let [front, back] = Fisheye::dual(LensModel::Equidistant, 400, 200, PI);
+        let (p, _) = front.direction(100.0, 100.0).unwrap();
+        assert!((p.x() - 1.0).abs() < 1e-12);
+        let (p, _) = back.direction(300.0, 100.0).unwrap();
+        assert!((p.x() + 1.0).abs() < 1e-12);
+        // Top of the image points up
+        let (p, _) = front.direction(100.0, 10.0).unwrap();
+        assert!(p.z() > 0.9);
+        // Right of the front image points towards -y, right of the back image towards +y
+        let (p, _) = front.direction(190.0, 100.0).unwrap();
+        assert!(p.y() < -0.9);
+        let (p, _) = back.direction(390.0, 100.0).unwrap();
+        assert!(p.y() > 0.9);
+    }
+
+    #[test]
+    fn project_dual_fisheye() {
+        let (width, height) = (800, 400);
+        let f = |p: &Coordinates<f64>| {
+            RealSH::Spherical.eval(1, 1, p) + 0.5 * RealSH::Spherical.eval(2, -2, p)
+        };
+        for model in MODELS {
+            let cameras = Fisheye::dual(model, width, height, 190f64.to_radians());
+            let mut image = vec![0.0; width * height];
+            for j in 0..height {
+                for i in 0..width {
+                    let (x, y) = (i as f64 + 0.5, j as f64 + 0.5);
+                    if let Some((p, _)) = cameras.iter().find_map(|c| c.direction(x, y)) {
+                        image[j * width + i] = f(&p);
+                    }
+                }
+            }
+            let coeffs = project_fisheye(2, &cameras, width, height, &image).unwrap();
+            for l in 0..=2i64 {
+                for m in -l..=l {
+                    let expected = match (l, m) {
+                        (1, 1) => 1.0,
+                        (2, -2) => 0.5,
+                        _ => 0.0,
+                    };
+                    let c = coeffs.get(l, m).unwrap();
+                    assert!((c - expected).abs() < 1e-2, "{model:?} ({l}, {m}): {c}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        let cameras = Fisheye::dual(LensModel::Equisolid, 4, 2, PI);
+        assert!(matches!(
+            project_fisheye(1, &cameras, 4, 2, &[1.0; 7]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::legendre::{legendre_p_all, zonal_norm};
+use crate::quadrature::gauss_legendre;
+use crate::rotation::rotate_zonal;
+use crate::{SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Zonal coefficients of the indicator function of a spherical cap around the z-axis
+///
+/// Returns the coefficients of Y_l0 for l = 0, ..., `degree` of the function which is one for all
+/// directions with a colatitude smaller than `half_angle` (in radians) and zero otherwise.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::transform::cap_zonal;
+///
+/// // A cap with half angle pi covers the entire sphere
+/// let zonal = cap_zonal(3, std::f64::consts::PI);
+/// assert!((zonal[0] - (4.0 * std::f64::consts::PI).sqrt()).abs() < 1e-12);
+/// ```
+pub fn cap_zonal<T: SphrsFloat>(degree: usize, half_angle: T) -> Vec<T> {
+    let x = half_angle.cos();
+    let p = legendre_p_all(degree + 1, x);
+    let two_pi = T::from_f64(2.0).unwrap() * T::PI();
+    (0..=degree)
+        .map(|l| {
+            // integral of P_l over [cos(half_angle), 1]
+            let integral = if l == 0 {
+                T::one() - x
+            } else {
+                (p[l - 1] - p[l + 1]) / T::from_usize(2 * l + 1).unwrap()
+            };
+            two_pi * zonal_norm::<T>(l) * integral
+        })
+        .collect()
+}
+
+/// Zonal coefficients of a smooth-edged cone around the z-axis
+///
+/// The cone is one for colatitudes smaller than `inner` and zero for colatitudes larger than
+/// `outer`. In between, it falls off smoothly following a raised cosine. Angles are in radians.
+/// Returns [`SphrsError::InvalidInput`] unless 0 <= `inner` < `outer` <= pi.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::transform::cone_zonal;
+///
+/// let zonal = cone_zonal(4, 0.2f64, 0.6).unwrap();
+/// assert_eq!(zonal.len(), 5);
+/// assert!(cone_zonal(4, 0.6f64, 0.2).is_err());
+/// ```
+pub fn cone_zonal<T: SphrsFloat>(degree: usize, inner: T, outer: T) -> Result<Vec<T>, SphrsError> {
+    if !(inner >= T::zero() && inner < outer && outer <= T::PI()) {
+        return Err(SphrsError::InvalidInput(format!(
+            "cone angles {inner:?} and {outer:?} do not satisfy 0 <= inner < outer <= pi"
+        )));
+    }
+    let mut zonal = cap_zonal(degree, inner);
+
+    // The falloff is smooth, a Gauss-Legendre quadrature over the transition region converges
+    // quickly.
+    let (nodes, weights) = gauss_legendre::<T>(degree + 32);
+    let half = T::from_f64(0.5).unwrap();
+    let width = outer - inner;
+    for (&t, &w) in nodes.iter().zip(weights.iter()) {
+        let theta = inner + half * width * (t + T::one());
+        let falloff = half * (T::one() + (T::PI() * (theta - inner) / width).cos());
+        let weight = T::from_f64(2.0).unwrap() * T::PI() * half * width * w * falloff * theta.sin();
+        let p = legendre_p_all(degree, theta.cos());
+        for (l, z) in zonal.iter_mut().enumerate() {
+            *z = *z + weight * zonal_norm::<T>(l) * p[l];
+        }
+    }
+    Ok(zonal)
+}
+
+/// Coefficients of the indicator function of a spherical cap around `axis`
+///
+/// The cap contains all directions enclosing an angle smaller than `half_angle` (in radians) with
+/// `axis`.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::Coordinates;
+/// use sphrs::transform::cap;
+///
+/// let axis = Coordinates::cartesian(1.0, 1.0, 0.0);
+/// let coeffs = cap(4, &axis, 0.3f64);
+/// assert_eq!(coeffs.len(), 25);
+/// ```
+pub fn cap<T: SphrsFloat>(
+    degree: usize,
+    axis: &impl SHCoordinates<T>,
+    half_angle: T,
+) -> SHCoefficients<T> {
+    rotate_zonal(&cap_zonal(degree, half_angle), axis)
+}
+
+/// Coefficients of a smooth-edged cone around `axis`
+///
+/// See [`cone_zonal`] for the definition of the cone and the errors.
+pub fn cone<T: SphrsFloat>(
+    degree: usize,
+    axis: &impl SHCoordinates<T>,
+    inner: T,
+    outer: T,
+) -> Result<SHCoefficients<T>, SphrsError> {
+    Ok(rotate_zonal(&cone_zonal(degree, inner, outer)?, axis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{HarmonicsSet, RealSH, SHEval};
+    use crate::Coordinates;
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    /// Numerically project `f` onto the real SH up to degree `degree`
+    fn project<F: Fn(&Coordinates<f64>) -> f64>(degree: usize, f: F) -> Vec<f64> {
+        let n_theta = 200;
+        let n_phi = 400;
+        let (x, w) = gauss_legendre::<f64>(n_theta);
+        let set = HarmonicsSet::new(degree, RealSH::Spherical);
+        let mut out = vec![0.0; set.num_sh()];
+        for (&x, &w) in x.iter().zip(w.iter()) {
+            for j in 0..n_phi {
+                let phi = 2.0 * PI * j as f64 / n_phi as f64;
+                let p = Coordinates::spherical(1.0, x.acos(), phi);
+                let v = f(&p) * w * 2.0 * PI / n_phi as f64;
+                for (o, y) in out.iter_mut().zip(set.eval(&p)) {
+                    *o += v * y;
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn cap_monopole_is_solid_angle() {
+        for &alpha in &[0.1f64, 0.5, 1.2, PI / 2.0, 2.5, PI] {
+            let zonal = cap_zonal(5, alpha);
+            assert_relative_eq!(
+                zonal[0],
+                2.0 * PI * (1.0 - alpha.cos()) / (4.0 * PI).sqrt(),
+                epsilon = 1e-14
+            );
+        }
+        // the full sphere is constant
+        let zonal = cap_zonal(5, PI);
+        for z in &zonal[1..] {
+            assert!(z.abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn cap_matches_numerical_projection() {
+        let axis = Coordinates::cartesian(0.3, -0.5, 0.8);
+        let alpha = 0.7f64;
+        let dir = [
+            axis.x() / axis.r(),
+            axis.y() / axis.r(),
+            axis.z() / axis.r(),
+        ];
+        let numerical = project(4, |p| {
+            let c = (p.x() * dir[0] + p.y() * dir[1] + p.z() * dir[2]) / p.r();
+            if c > alpha.cos() {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        let analytic = cap(4, &axis, alpha);
+        for (a, n) in analytic.as_slice().iter().zip(numerical.iter()) {
+            assert!((a - n).abs() < 5e-3, "{a} vs {n}");
+        }
+    }
+
+    #[test]
+    fn rotated_zonal_evaluates_consistently() {
+        // A rotated zonal function evaluated at the axis must equal the zonal function evaluated
+        // at the north pole.
+        let zonal = cone_zonal(6, 0.4, 0.9).unwrap();
+        let axis = Coordinates::spherical(1.0, 1.1, -0.4);
+        let coeffs = rotate_zonal(&zonal, &axis);
+        let set = HarmonicsSet::new(6, RealSH::Spherical);
+        let at_axis: f64 = set
+            .eval(&axis)
+            .iter()
+            .zip(coeffs.as_slice())
+            .map(|(y, c)| y * c)
+            .sum();
+        let pole = Coordinates::spherical(1.0, 0.0, 0.0);
+        let at_pole: f64 = zonal
+            .iter()
+            .enumerate()
+            .map(|(l, z)| z * RealSH::Spherical.eval(l as i64, 0, &pole))
+            .sum();
+        assert_relative_eq!(at_axis, at_pole, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn cone_matches_numerical_projection() {
+        let axis = Coordinates::cartesian(0.0, 0.0, 1.0);
+        let (inner, outer) = (0.3, 0.8);
+        let numerical = project(6, |p| {
+            let theta = p.theta();
+            if theta <= inner {
+                1.0
+            } else if theta >= outer {
+                0.0
+            } else {
+                0.5 * (1.0 + (PI * (theta - inner) / (outer - inner)).cos())
+            }
+        });
+        let analytic = cone(6, &axis, inner, outer).unwrap();
+        for (a, n) in analytic.as_slice().iter().zip(numerical.iter()) {
+            assert!((a - n).abs() < 1e-5, "{a} vs {n}");
+        }
+    }
+
+    #[test]
+    fn cone_rejects_invalid_angles() {
+        for (inner, outer) in [
+            (-0.1, 0.5),
+            (0.5, 0.5),
+            (0.7, 0.3),
+            (0.2, 4.0),
+            (f64::NAN, 1.0),
+        ] {
+            assert!(matches!(
+                cone_zonal(3, inner, outer),
+                Err(SphrsError::InvalidInput(_))
+            ));
+        }
+    }
+}
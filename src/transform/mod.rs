@@ -0,0 +1,26 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Projection of functions on the sphere onto real spherical harmonics
+//!
+//! All functions in this module return coefficients with respect to the real, orthonormal
+//! spherical harmonics as computed by [`RealSH::Spherical`](`crate::basis::RealSH::Spherical`).
+//!
+//! Images of omnidirectional cameras are projected with [`project_fisheye`] for (dual-)fisheye
+//! lenses or with [`project_pixels`] for arbitrary per-pixel direction maps.
+
+mod camera;
+mod cap;
+mod polygon;
+mod quadrature;
+mod samples;
+
+pub use camera::{project_fisheye, project_pixels, Fisheye, LensModel};
+pub use cap::{cap, cap_zonal, cone, cone_zonal};
+pub use polygon::polygon;
+pub use quadrature::{project_quadrature, QuadratureProjection};
+pub use samples::project;
@@ -0,0 +1,261 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::quadrature::gauss_legendre;
+use crate::{Coordinates, SHCoefficients, SHCoordinates, SphrsError, SphrsFloat};
+
+/// Spherical triangles with an edge longer than this (in radians) are subdivided before
+/// integration.
+const MAX_EDGE_ANGLE: f64 = std::f64::consts::FRAC_PI_4;
+
+type Vec3<T> = [T; 3];
+
+fn normalize<T: SphrsFloat>(v: Vec3<T>) -> Vec3<T> {
+    let n = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    [v[0] / n, v[1] / n, v[2] / n]
+}
+
+fn dot<T: SphrsFloat>(a: Vec3<T>, b: Vec3<T>) -> T {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn det<T: SphrsFloat>(a: Vec3<T>, b: Vec3<T>, c: Vec3<T>) -> T {
+    a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+        + a[2] * (b[0] * c[1] - b[1] * c[0])
+}
+
+fn midpoint<T: SphrsFloat>(a: Vec3<T>, b: Vec3<T>) -> Vec3<T> {
+    normalize([a[0] + b[0], a[1] + b[1], a[2] + b[2]])
+}
+
+/// Integrates the real SH over spherical triangles using a Duffy-transformed Gauss-Legendre rule
+/// on the gnomonic projection of the triangle.
+struct TriangleIntegrator<T> {
+    set: HarmonicsSet<T, RealSH>,
+    nodes: Vec<T>,
+    weights: Vec<T>,
+}
+
+impl<T: SphrsFloat> TriangleIntegrator<T> {
+    fn new(degree: usize) -> Self {
+        let (x, w) = gauss_legendre::<T>(degree + 16);
+        let half = T::from_f64(0.5).unwrap();
+        // Map nodes and weights from [-1, 1] to [0, 1]
+        let nodes = x.iter().map(|&x| half * (x + T::one())).collect();
+        let weights = w.iter().map(|&w| half * w).collect();
+        TriangleIntegrator {
+            set: HarmonicsSet::new(degree, RealSH::Spherical),
+            nodes,
+            weights,
+        }
+    }
+
+    /// Adds the (signed) integrals over the triangle (a, b, c) to `out`
+    fn integrate(&self, a: Vec3<T>, b: Vec3<T>, c: Vec3<T>, out: &mut [T]) {
+        let max_edge = T::from_f64(MAX_EDGE_ANGLE.cos()).unwrap();
+        if dot(a, b) < max_edge || dot(b, c) < max_edge || dot(c, a) < max_edge {
+            let ab = midpoint(a, b);
+            let bc = midpoint(b, c);
+            let ca = midpoint(c, a);
+            self.integrate(a, ab, ca, out);
+            self.integrate(ab, b, bc, out);
+            self.integrate(ca, bc, c, out);
+            self.integrate(ab, bc, ca, out);
+            return;
+        }
+
+        // The gnomonic projection maps the planar triangle q(u, v) = a + u (b - a) + v (c - a)
+        // onto the spherical triangle with a solid angle element of det(a, b, c) / |q|^3 du dv.
+        let d = det(a, b, c);
+        for (&s, &ws) in self.nodes.iter().zip(self.weights.iter()) {
+            for (&t, &wt) in self.nodes.iter().zip(self.weights.iter()) {
+                // Duffy transformation of the unit square onto the triangle
+                let u = s * (T::one() - t);
+                let v = s * t;
+                let q = [
+                    a[0] + u * (b[0] - a[0]) + v * (c[0] - a[0]),
+                    a[1] + u * (b[1] - a[1]) + v * (c[1] - a[1]),
+                    a[2] + u * (b[2] - a[2]) + v * (c[2] - a[2]),
+                ];
+                let norm = dot(q, q).sqrt();
+                let w = ws * wt * s * d / (norm * norm * norm);
+                let p = Coordinates::cartesian(q[0], q[1], q[2]);
+                for (o, y) in out.iter_mut().zip(self.set.eval(&p)) {
+                    *o = *o + w * y;
+                }
+            }
+        }
+    }
+}
+
+/// Coefficients of the indicator function of a spherical polygon
+///
+/// The polygon is defined by its `vertices`, connected by great circle arcs and given in
+/// counterclockwise order when seen from outside of the sphere. Only the directions of the
+/// vertices matter. The polygon must be simple and lie strictly within a hemisphere, which holds
+/// for the projection of any planar polygonal area light which does not intersect the plane
+/// through the origin. Returns [`SphrsError::InvalidInput`] if there are fewer than three
+/// vertices or if they do not lie strictly within a hemisphere.
+///
+/// The polygon is split into spherical triangles around the normalized vertex centroid. Each
+/// triangle is integrated exactly regarding its geometry via its gnomonic projection, with a
+/// Gauss-Legendre rule adapted to `degree`. Vertices given in clockwise order yield the negated
+/// coefficients.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::Coordinates;
+/// use sphrs::transform::polygon;
+///
+/// // Octant of the sphere
+/// let vertices = [
+///     Coordinates::cartesian(1.0f64, 0.0, 0.0),
+///     Coordinates::cartesian(0.0, 1.0, 0.0),
+///     Coordinates::cartesian(0.0, 0.0, 1.0),
+/// ];
+/// let coeffs = polygon(2, &vertices).unwrap();
+/// let solid_angle = coeffs.as_slice()[0] * (4.0 * std::f64::consts::PI).sqrt();
+/// assert!((solid_angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+/// ```
+pub fn polygon<T: SphrsFloat>(
+    degree: usize,
+    vertices: &[impl SHCoordinates<T>],
+) -> Result<SHCoefficients<T>, SphrsError> {
+    if vertices.len() < 3 {
+        return Err(SphrsError::InvalidInput(format!(
+            "polygon needs at least 3 vertices, found {}",
+            vertices.len()
+        )));
+    }
+    let v: Vec<Vec3<T>> = vertices
+        .iter()
+        .map(|p| normalize([p.x(), p.y(), p.z()]))
+        .collect();
+    let sum = v.iter().fold([T::zero(); 3], |acc, p| {
+        [acc[0] + p[0], acc[1] + p[1], acc[2] + p[2]]
+    });
+    let center = normalize(sum);
+    if !v.iter().all(|&p| dot(p, center) > T::zero()) {
+        return Err(SphrsError::InvalidInput(
+            "polygon must lie strictly within a hemisphere".to_string(),
+        ));
+    }
+
+    let integrator = TriangleIntegrator::new(degree);
+    let mut out = vec![T::zero(); integrator.set.num_sh()];
+    for i in 0..v.len() {
+        integrator.integrate(center, v[i], v[(i + 1) % v.len()], &mut out);
+    }
+    Ok(SHCoefficients::from_vec(degree, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    /// Solid angle of a spherical triangle (Van Oosterom and Strackee)
+    fn triangle_solid_angle(a: Vec3<f64>, b: Vec3<f64>, c: Vec3<f64>) -> f64 {
+        2.0 * det(a, b, c).atan2(1.0 + dot(a, b) + dot(b, c) + dot(c, a))
+    }
+
+    #[test]
+    fn octant() {
+        let vertices = [
+            Coordinates::cartesian(1.0f64, 0.0, 0.0),
+            Coordinates::cartesian(0.0, 1.0, 0.0),
+            Coordinates::cartesian(0.0, 0.0, 1.0),
+        ];
+        let coeffs = polygon(3, &vertices).unwrap();
+        let c = coeffs.as_slice();
+        assert_relative_eq!(c[0], PI / 2.0 / (4.0 * PI).sqrt(), epsilon = 1e-13);
+        // integral of x, y and z over the octant is pi / 4
+        let dipole = (3.0 / (4.0 * PI)).sqrt() * PI / 4.0;
+        for &v in &c[1..4] {
+            assert_relative_eq!(v, dipole, epsilon = 1e-13);
+        }
+
+        // reversed orientation negates the coefficients
+        let reversed = [
+            vertices[2].clone(),
+            vertices[1].clone(),
+            vertices[0].clone(),
+        ];
+        let rev = polygon(3, &reversed).unwrap();
+        for (a, b) in coeffs.as_slice().iter().zip(rev.as_slice()) {
+            assert_relative_eq!(*a, -*b, epsilon = 1e-13);
+        }
+    }
+
+    #[test]
+    fn area_of_non_convex_polygon() {
+        // An L-shaped polygon around the north pole
+        let vertices: Vec<Coordinates<f64>> = [
+            (0.0, 0.0),
+            (0.4, 0.0),
+            (0.4, 0.2),
+            (0.2, 0.2),
+            (0.2, 0.4),
+            (0.0, 0.4),
+        ]
+        .iter()
+        .map(|&(x, y)| Coordinates::cartesian(x - 0.15, y - 0.15, 1.0))
+        .collect();
+        let coeffs = polygon(4, &vertices).unwrap();
+        let v: Vec<Vec3<f64>> = vertices
+            .iter()
+            .map(|p| normalize([p.x(), p.y(), p.z()]))
+            .collect();
+        let area: f64 = (1..v.len() - 1)
+            .map(|i| triangle_solid_angle(v[0], v[i], v[i + 1]))
+            .sum();
+        assert_relative_eq!(
+            coeffs.as_slice()[0] * (4.0 * PI).sqrt(),
+            area,
+            epsilon = 1e-13
+        );
+    }
+
+    #[test]
+    fn regular_polygon_approaches_cap() {
+        let alpha = 0.6f64;
+        let n = 256;
+        let vertices: Vec<Coordinates<f64>> = (0..n)
+            .map(|i| Coordinates::spherical(1.0, alpha, 2.0 * PI * i as f64 / n as f64))
+            .collect();
+        let poly = polygon(6, &vertices).unwrap();
+        let axis = Coordinates::cartesian(0.0, 0.0, 1.0);
+        let cap = crate::transform::cap(6, &axis, alpha);
+        for (a, b) in poly.as_slice().iter().zip(cap.as_slice()) {
+            assert!((a - b).abs() < 1e-4, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn invalid_polygons() {
+        let c = |x, y, z| Coordinates::cartesian(x, y, z);
+        let too_few = [c(1.0, 0.0, 0.0), c(0.0, 1.0, 0.0)];
+        assert!(matches!(
+            polygon(2, &too_few),
+            Err(SphrsError::InvalidInput(_))
+        ));
+        // Contains antipodal vertices, so no open hemisphere contains it
+        let wide = [
+            c(1.0, 0.0, 0.0),
+            c(0.0, 1.0, 0.1),
+            c(-1.0, 0.0, 0.0),
+            c(0.0, -1.0, 0.1),
+        ];
+        assert!(matches!(
+            polygon(2, &wide),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::sht::{forward, Sampling};
+use crate::{Coordinates, SHCoefficients, SphrsFloat};
+
+/// Result of [`project_quadrature`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuadratureProjection<T> {
+    /// Projected coefficients
+    coefficients: SHCoefficients<T>,
+    /// Estimated aliasing error per degree
+    error: Vec<T>,
+    /// Norm of the expansion beyond the degree of the coefficients
+    truncation: T,
+}
+
+impl<T: SphrsFloat> QuadratureProjection<T> {
+    /// Returns the projected coefficients
+    pub fn coefficients(&self) -> &SHCoefficients<T> {
+        &self.coefficients
+    }
+
+    /// Consumes `self` and returns the projected coefficients
+    pub fn into_coefficients(self) -> SHCoefficients<T> {
+        self.coefficients
+    }
+
+    /// Returns the estimated error sqrt(sum_m e_lm^2) of the coefficients of each degree l
+    ///
+    /// The estimate is the difference to the projection on a grid of half the resolution, which
+    /// is exact for functions up to the requested degree. It hence measures how much the
+    /// frequencies above the requested degree alias into the coefficients, and usually
+    /// overestimates the error of the returned coefficients.
+    pub fn error(&self) -> &[T] {
+        &self.error
+    }
+
+    /// Returns the L2 norm of the part of the function between the requested degree and twice
+    /// that degree, i.e. the error of truncating the expansion (as far as the finer grid
+    /// resolves it)
+    pub fn truncation_error(&self) -> T {
+        self.truncation
+    }
+}
+
+/// Samples of `f` on the Gauss-Legendre grid of bandlimit `bandlimit`
+fn sample<T, F>(bandlimit: usize, f: &F) -> Vec<T>
+where
+    T: SphrsFloat,
+    F: Fn(&Coordinates<T>) -> T,
+{
+    let sampling = Sampling::GaussLegendre;
+    let phis = sampling.phis::<T>(bandlimit);
+    let mut values = Vec::with_capacity(bandlimit * phis.len());
+    for theta in sampling.thetas::<T>(bandlimit) {
+        for &phi in &phis {
+            values.push(f(&Coordinates::spherical(T::one(), theta, phi)));
+        }
+    }
+    values
+}
+
+/// Project a function onto real SH up to degree `degree` with Gauss-Legendre quadrature and
+/// estimate the error
+///
+/// Contrary to [`project`](`crate::transform::project`), the quadrature is deterministic and
+/// exact for bandlimited functions. `f` is sampled on two Gauss-Legendre grids: one which is
+/// exact for functions up to degree `degree` and one with twice the bandlimit, which provides the
+/// returned coefficients. The difference of both results estimates the aliasing error per degree
+/// and the coefficients of the finer grid above `degree` the truncation error.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::transform::project_quadrature;
+/// use sphrs::{Coordinates, SHCoordinates};
+///
+/// // Bandlimited: projected exactly, no error
+/// let projection = project_quadrature(3, |p: &Coordinates<f64>| p.z() * p.x());
+/// assert!(projection.error().iter().all(|&e| e < 1e-13));
+/// assert!(projection.truncation_error() < 1e-13);
+///
+/// // Not bandlimited: the estimates reveal the missing degrees
+/// let projection = project_quadrature(3, |p: &Coordinates<f64>| (5.0 * p.z()).exp());
+/// assert!(projection.truncation_error() > 1.0);
+/// ```
+pub fn project_quadrature<T, F>(degree: usize, f: F) -> QuadratureProjection<T>
+where
+    T: SphrsFloat,
+    F: Fn(&Coordinates<T>) -> T,
+{
+    let coarse_bandlimit = degree + 1;
+    let fine_bandlimit = 2 * coarse_bandlimit;
+    let coarse = forward(
+        Sampling::GaussLegendre,
+        coarse_bandlimit,
+        &sample(coarse_bandlimit, &f),
+    )
+    .unwrap();
+    let fine = forward(
+        Sampling::GaussLegendre,
+        fine_bandlimit,
+        &sample(fine_bandlimit, &f),
+    )
+    .unwrap();
+
+    let n = coarse.len();
+    let (lower, upper) = fine.as_slice().split_at(n);
+    let error = (0..=degree)
+        .map(|l| {
+            let range = l * l..(l + 1) * (l + 1);
+            coarse.as_slice()[range.clone()]
+                .iter()
+                .zip(&lower[range])
+                .fold(T::zero(), |acc, (&a, &b)| acc + (a - b) * (a - b))
+                .sqrt()
+        })
+        .collect();
+    let truncation = upper.iter().fold(T::zero(), |acc, &c| acc + c * c).sqrt();
+    QuadratureProjection {
+        coefficients: SHCoefficients::from_vec(degree, lower.to_vec()),
+        error,
+        truncation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::{RealSH, SHEval};
+
+    #[test]
+    fn exact_for_bandlimited_functions() {
+        let f = |p: &Coordinates<f64>| {
+            RealSH::Spherical.eval(4, -3, p) - 0.5 * RealSH::Spherical.eval(1, 1, p)
+        };
+        let projection = project_quadrature(4, f);
+        for l in 0..=4i64 {
+            for m in -l..=l {
+                let expected = match (l, m) {
+                    (4, -3) => 1.0,
+                    (1, 1) => -0.5,
+                    _ => 0.0,
+                };
+                let c = projection.coefficients().get(l, m).unwrap();
+                assert!((c - expected).abs() < 1e-13, "({l}, {m}): {c}");
+            }
+        }
+        assert!(projection.error().iter().all(|&e| e < 1e-13));
+        assert!(projection.truncation_error() < 1e-13);
+    }
+
+    #[test]
+    fn estimates_aliasing() {
+        // A degree 7 component aliases into the coarse projection up to degree 3
+        let f = |p: &Coordinates<f64>| RealSH::Spherical.eval(7, 2, p);
+        let projection = project_quadrature(3, f);
+        assert!((projection.truncation_error() - 1.0).abs() < 1e-12);
+        assert!(projection.error().iter().any(|&e| e > 1e-3));
+        // The finer grid is still exact
+        for c in projection.coefficients().as_slice() {
+            assert!(c.abs() < 1e-13);
+        }
+    }
+}
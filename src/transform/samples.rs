@@ -0,0 +1,78 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::reduce::blockwise_sum;
+use crate::{SHCoefficients, SHCoordinates, SphrsFloat};
+
+/// Project a function onto real SH by a uniformly weighted sum over `directions`
+///
+/// Each direction is assigned the weight `4 pi / directions.len()`, hence the directions are
+/// expected to be (quasi-)uniformly distributed over the sphere, e.g. generated with one of the
+/// sequences of the [`sampling`](`crate::sampling`) module.
+///
+/// # Example
+///
+/// ```
+/// use sphrs::transform::project;
+/// use sphrs::sampling::sobol;
+/// use sphrs::SHCoordinates;
+///
+/// let directions = sobol::<f64>(4096, 0).unwrap();
+/// let coeffs = project(2, &directions, |p| p.z() / p.r());
+/// // z is proportional to Y_10
+/// let expected = (4.0 * std::f64::consts::PI / 3.0).sqrt();
+/// assert!((coeffs.get(1, 0).unwrap() - expected).abs() < 1e-3);
+/// ```
+pub fn project<T, C, F>(degree: usize, directions: &[C], f: F) -> SHCoefficients<T>
+where
+    T: SphrsFloat,
+    C: SHCoordinates<T> + Sync,
+    F: Fn(&C) -> T + Sync,
+{
+    assert!(!directions.is_empty());
+    let set = HarmonicsSet::new(degree, RealSH::Spherical);
+    let weight = T::from_f64(4.0).unwrap() * T::PI() / T::from_usize(directions.len()).unwrap();
+    let out = blockwise_sum(directions.len(), set.num_sh(), |range, acc| {
+        for p in &directions[range] {
+            let v = f(p) * weight;
+            for (a, y) in acc.iter_mut().zip(set.eval(p)) {
+                *a = *a + v * y;
+            }
+        }
+    });
+    SHCoefficients::from_vec(degree, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::SHEval;
+    use crate::sampling::{halton, sobol};
+
+    #[test]
+    fn qmc_projection_converges() {
+        // Project Y_2,1 + 0.5 Y_3,-2
+        let f = |p: &crate::Coordinates<f64>| {
+            RealSH::Spherical.eval(2, 1, p) + 0.5 * RealSH::Spherical.eval(3, -2, p)
+        };
+        for directions in [sobol(8192, 3).unwrap(), halton(8192, 3)] {
+            let coeffs = project(3, &directions, f);
+            for l in 0..=3i64 {
+                for m in -l..=l {
+                    let expected = match (l, m) {
+                        (2, 1) => 1.0,
+                        (3, -2) => 0.5,
+                        _ => 0.0,
+                    };
+                    let c = coeffs.get(l, m).unwrap();
+                    assert!((c - expected).abs() < 5e-3, "({l}, {m}): {c}");
+                }
+            }
+        }
+    }
+}
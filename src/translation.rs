@@ -0,0 +1,331 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Translation of solid harmonics expansions
+//!
+//! The addition theorems of the solid harmonics shift expansions of harmonic functions between
+//! centers, as needed by fast multipole methods. Expansions are with respect to the complex
+//! solid harmonics [`ComplexSH::RegularSolid`](`crate::basis::ComplexSH::RegularSolid`) R_lm
+//! and [`ComplexSH::IrregularSolid`](`crate::basis::ComplexSH::IrregularSolid`) I_lm:
+//!
+//! * local expansions phi(r) = sum_lm L_lm R_lm(r - c), valid close to the center c,
+//! * multipole expansions phi(r) = sum_lm M_lm I_lm(r - c), valid far from the center c.
+//!
+//! For example, the potential 1 / |r - s| of a unit charge at s has the multipole coefficients
+//! M_lm = conj(R_lm(s - c)).
+//!
+//! With the binomial coefficients C(n, k), the regular addition theorem is
+//!
+//! R_lm(r + a) = sum_{j <= l} sum_k sqrt(C(l + m, j + k) C(l - m, j - k)) R_jk(r) R_{l-j, m-k}(a)
+//!
+//! and for |r| < |a| the irregular addition theorem is
+//!
+//! I_lm(r + a) = sum_j sum_k (-1)^(j + k) sqrt(C(l + j - m + k, j + k) C(l + j + m - k, j - k))
+//! R_jk(r) I_{l+j, m-k}(a).
+//!
+//! [`local_to_local`] (L2L) and [`multipole_to_multipole`] (M2M) are exact, i.e. the translated
+//! expansion of the same degree represents the same function. [`multipole_to_local`] (M2L)
+//! truncates an infinite series, its error decays geometrically with the degree for well
+//! separated centers.
+//!
+//! In all functions `shift` is the new center minus the old center.
+
+use num_complex::Complex;
+
+use crate::basis::{ComplexSH, HarmonicsSet};
+use crate::coefficients::sh_index;
+use crate::{SHCoefficients, SHCoordinates, SphrsFloat};
+
+/// Square roots of binomial coefficients, computed from a table of ln(n!)
+struct SqrtBinomial<T> {
+    ln_factorial: Vec<T>,
+}
+
+impl<T: SphrsFloat> SqrtBinomial<T> {
+    /// Table for binomial coefficients C(n, k) with n <= `n_max`
+    fn new(n_max: usize) -> Self {
+        let mut ln_factorial = Vec::with_capacity(n_max + 1);
+        let mut acc = T::zero();
+        ln_factorial.push(acc);
+        for n in 1..=n_max {
+            acc = acc + T::from_usize(n).unwrap().ln();
+            ln_factorial.push(acc);
+        }
+        SqrtBinomial { ln_factorial }
+    }
+
+    /// sqrt(C(n, k)), zero if k < 0 or k > n
+    #[inline(always)]
+    fn get(&self, n: i64, k: i64) -> T {
+        if k < 0 || k > n {
+            return T::zero();
+        }
+        let f = |i: i64| self.ln_factorial[i as usize];
+        ((f(n) - f(k) - f(n - k)) / T::from_f64(2.0).unwrap()).exp()
+    }
+}
+
+/// Coefficient of R_jk(r) I_{l+j, m-k}(a) in the expansion of I_lm(r + a)
+#[inline(always)]
+fn irregular_factor<T: SphrsFloat>(b: &SqrtBinomial<T>, l: i64, m: i64, j: i64, k: i64) -> T {
+    let v = b.get(l + j - m + k, j + k) * b.get(l + j + m - k, j - k);
+    if (j + k) % 2 == 0 {
+        v
+    } else {
+        -v
+    }
+}
+
+/// Shift a local expansion by `shift` (L2L)
+///
+/// Returns the coefficients L'_jk = sum_lm L_lm sqrt(C(l + m, j + k) C(l - m, j - k))
+/// R_{l-j, m-k}(`shift`) of the expansion about the new center, of the same degree as `coeffs`.
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::translation::local_to_local;
+/// use sphrs::{Coordinates, SHCoefficients};
+///
+/// // phi(r) = z, shifted by one unit along z: phi = 1 + z'
+/// let mut coeffs = SHCoefficients::zeros(1);
+/// *coeffs.get_mut(1, 0).unwrap() = Complex::new(1.0f64, 0.0);
+/// let shifted = local_to_local(&coeffs, &Coordinates::cartesian(0.0, 0.0, 1.0));
+/// assert!((shifted.get(0, 0).unwrap().re - 1.0).abs() < 1e-15);
+/// assert!((shifted.get(1, 0).unwrap().re - 1.0).abs() < 1e-15);
+/// ```
+pub fn local_to_local<T: SphrsFloat>(
+    coeffs: &SHCoefficients<Complex<T>>,
+    shift: &impl SHCoordinates<T>,
+) -> SHCoefficients<Complex<T>> {
+    let degree = coeffs.degree();
+    let p = degree as i64;
+    let r: Vec<Complex<T>> = HarmonicsSet::new(degree, ComplexSH::RegularSolid).eval(shift);
+    let b = SqrtBinomial::<T>::new(2 * degree);
+    let mut out = SHCoefficients::zeros(degree);
+    for j in 0..=p {
+        for k in -j..=j {
+            let mut acc = Complex::new(T::zero(), T::zero());
+            for l in j..=p {
+                let n = l - j;
+                for m in (k - n).max(-l)..=(k + n).min(l) {
+                    let factor = b.get(l + m, j + k) * b.get(l - m, j - k);
+                    acc = acc + coeffs.as_slice()[sh_index(l, m)] * r[sh_index(n, m - k)] * factor;
+                }
+            }
+            out.as_mut_slice()[sh_index(j, k)] = acc;
+        }
+    }
+    out
+}
+
+/// Shift a multipole expansion by `shift` (M2M)
+///
+/// Returns the coefficients M'_LM = sum_lm M_lm (-1)^(j + k) sqrt(C(L - M, j + k) C(L + M, j - k))
+/// R_jk(`shift`) with j = L - l and k = m - M, of the same degree as `coeffs`. The translated
+/// expansion is valid outside of the sphere about the new center that encloses the sphere of
+/// convergence of the original expansion.
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::basis::{ComplexSH, HarmonicsSet};
+/// use sphrs::translation::multipole_to_multipole;
+/// use sphrs::{Coordinates, SHCoefficients};
+///
+/// // Unit charge at the origin, expanded about the new center (0, 0, 1)
+/// let mut coeffs = SHCoefficients::zeros(8);
+/// *coeffs.get_mut(0, 0).unwrap() = Complex::new(1.0f64, 0.0);
+/// let shifted = multipole_to_multipole(&coeffs, &Coordinates::cartesian(0.0, 0.0, 1.0));
+///
+/// // Potential at (0, 0, 5), which is at (0, 0, 4) relative to the new center
+/// let sh = HarmonicsSet::new(8, ComplexSH::IrregularSolid);
+/// let phi = sh.eval_sum(&Coordinates::cartesian(0.0, 0.0, 4.0), shifted.as_slice()).unwrap();
+/// assert!((phi.re - 0.2).abs() < 1e-6);
+/// ```
+pub fn multipole_to_multipole<T: SphrsFloat>(
+    coeffs: &SHCoefficients<Complex<T>>,
+    shift: &impl SHCoordinates<T>,
+) -> SHCoefficients<Complex<T>> {
+    let degree = coeffs.degree();
+    let p = degree as i64;
+    // I_lm(r + a) for |r| > |a| is the irregular addition theorem with r and a exchanged
+    let r: Vec<Complex<T>> = HarmonicsSet::new(degree, ComplexSH::RegularSolid).eval(shift);
+    let b = SqrtBinomial::<T>::new(2 * degree);
+    let mut out = SHCoefficients::zeros(degree);
+    for big_l in 0..=p {
+        for big_m in -big_l..=big_l {
+            let mut acc = Complex::new(T::zero(), T::zero());
+            for l in 0..=big_l {
+                let j = big_l - l;
+                for m in (big_m - j).max(-l)..=(big_m + j).min(l) {
+                    let k = m - big_m;
+                    let factor = irregular_factor(&b, l, m, j, k);
+                    acc = acc + coeffs.as_slice()[sh_index(l, m)] * r[sh_index(j, k)] * factor;
+                }
+            }
+            out.as_mut_slice()[sh_index(big_l, big_m)] = acc;
+        }
+    }
+    out
+}
+
+/// Convert a multipole expansion into a local expansion of degree `degree` about the center
+/// displaced by `shift` (M2L)
+///
+/// Returns the coefficients L_jk = sum_lm M_lm (-1)^(j + k) sqrt(C(l + j - m + k, j + k)
+/// C(l + j + m - k, j - k)) I_{l+j, m-k}(`shift`). The local expansion converges inside of the
+/// sphere about the new center that does not intersect the sphere of convergence of the
+/// multipole expansion; the error of the truncation at `degree` decays geometrically with the
+/// ratio of the radii to |`shift`|.
+///
+/// # Example
+///
+/// ```
+/// use num_complex::Complex;
+/// use sphrs::basis::{ComplexSH, HarmonicsSet};
+/// use sphrs::translation::multipole_to_local;
+/// use sphrs::{Coordinates, SHCoefficients};
+///
+/// // Unit charge at the origin, local expansion about (0, 0, 4)
+/// let mut coeffs = SHCoefficients::zeros(0);
+/// *coeffs.get_mut(0, 0).unwrap() = Complex::new(1.0f64, 0.0);
+/// let local = multipole_to_local(&coeffs, &Coordinates::cartesian(0.0, 0.0, 4.0), 12);
+///
+/// // Potential at (0, 0, 5), which is at (0, 0, 1) relative to the new center
+/// let sh = HarmonicsSet::new(12, ComplexSH::RegularSolid);
+/// let phi = sh.eval_sum(&Coordinates::cartesian(0.0, 0.0, 1.0), local.as_slice()).unwrap();
+/// assert!((phi.re - 0.2).abs() < 1e-8);
+/// ```
+pub fn multipole_to_local<T: SphrsFloat>(
+    coeffs: &SHCoefficients<Complex<T>>,
+    shift: &impl SHCoordinates<T>,
+    degree: usize,
+) -> SHCoefficients<Complex<T>> {
+    let p_in = coeffs.degree() as i64;
+    let p_out = degree as i64;
+    let irr: Vec<Complex<T>> =
+        HarmonicsSet::new(coeffs.degree() + degree, ComplexSH::IrregularSolid).eval(shift);
+    let b = SqrtBinomial::<T>::new(2 * (coeffs.degree() + degree));
+    let mut out = SHCoefficients::zeros(degree);
+    for j in 0..=p_out {
+        for k in -j..=j {
+            let mut acc = Complex::new(T::zero(), T::zero());
+            for l in 0..=p_in {
+                for m in -l..=l {
+                    let factor = irregular_factor(&b, l, m, j, k);
+                    acc = acc
+                        + coeffs.as_slice()[sh_index(l, m)] * irr[sh_index(l + j, m - k)] * factor;
+                }
+            }
+            out.as_mut_slice()[sh_index(j, k)] = acc;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::SHEval;
+    use crate::Coordinates;
+
+    fn eval(
+        sh: ComplexSH,
+        coeffs: &SHCoefficients<Complex<f64>>,
+        p: &Coordinates<f64>,
+    ) -> Complex<f64> {
+        HarmonicsSet::new(coeffs.degree(), sh)
+            .eval_sum(p, coeffs.as_slice())
+            .unwrap()
+    }
+
+    fn sub(a: &Coordinates<f64>, b: &Coordinates<f64>) -> Coordinates<f64> {
+        Coordinates::cartesian(a.x() - b.x(), a.y() - b.y(), a.z() - b.z())
+    }
+
+    /// Multipole coefficients of point charges `q` at `s` about `center`
+    fn multipole(
+        degree: usize,
+        charges: &[(f64, Coordinates<f64>)],
+        center: &Coordinates<f64>,
+    ) -> SHCoefficients<Complex<f64>> {
+        let mut out = SHCoefficients::zeros(degree);
+        for (q, s) in charges {
+            let d = sub(s, center);
+            for l in 0..=degree as i64 {
+                for m in -l..=l {
+                    let r: Complex<f64> = ComplexSH::RegularSolid.eval(l, m, &d);
+                    *out.get_mut(l, m).unwrap() += r.conj() * *q;
+                }
+            }
+        }
+        out
+    }
+
+    fn potential(charges: &[(f64, Coordinates<f64>)], p: &Coordinates<f64>) -> f64 {
+        charges.iter().map(|(q, s)| q / sub(p, s).r()).sum()
+    }
+
+    #[test]
+    fn regular_addition_theorem() {
+        let degree = 6;
+        let mut coeffs = SHCoefficients::zeros(degree);
+        for (i, c) in coeffs.as_mut_slice().iter_mut().enumerate() {
+            *c = Complex::new((i as f64 * 0.37).sin(), (i as f64 * 0.81).cos());
+        }
+        let shift = Coordinates::cartesian(0.3, -0.7, 0.4);
+        let shifted = local_to_local(&coeffs, &shift);
+        for p in [
+            Coordinates::cartesian(0.2, 0.1, -0.5),
+            Coordinates::cartesian(-1.3, 0.4, 0.9),
+        ] {
+            let expected = eval(ComplexSH::RegularSolid, &coeffs, &p);
+            let value = eval(ComplexSH::RegularSolid, &shifted, &sub(&p, &shift));
+            assert!((value - expected).norm() < 1e-12 * expected.norm().max(1.0));
+        }
+    }
+
+    #[test]
+    fn point_charges() {
+        let charges = [
+            (1.0, Coordinates::cartesian(0.1, 0.2, -0.1)),
+            (-0.5, Coordinates::cartesian(-0.2, 0.1, 0.25)),
+            (0.8, Coordinates::cartesian(0.05, -0.3, 0.0)),
+        ];
+        let degree = 14;
+        let c1 = Coordinates::cartesian(0.0, 0.0, 0.0);
+        let c2 = Coordinates::cartesian(0.2, -0.1, 0.1);
+        let m1 = multipole(degree, &charges, &c1);
+
+        // M2M: exact, the coefficients equal those computed directly about the new center
+        let m2 = multipole_to_multipole(&m1, &c2);
+        let direct = multipole(degree, &charges, &c2);
+        for (a, b) in m2.as_slice().iter().zip(direct.as_slice()) {
+            assert!((a - b).norm() < 1e-14, "{a} {b}");
+        }
+
+        // M2L and L2L: potential close to a distant center
+        let c3 = Coordinates::cartesian(4.0, 3.0, -2.0);
+        let local = multipole_to_local(&m2, &sub(&c3, &c2), degree);
+        let c4 = Coordinates::cartesian(4.3, 2.8, -1.8);
+        let local2 = local_to_local(&local, &sub(&c4, &c3));
+        for p in [
+            Coordinates::cartesian(4.1, 3.2, -2.3),
+            Coordinates::cartesian(3.8, 2.9, -1.7),
+        ] {
+            let expected = potential(&charges, &p);
+            let value = eval(ComplexSH::RegularSolid, &local, &sub(&p, &c3));
+            assert!((value.re - expected).abs() < 1e-12, "{value} {expected}");
+            assert!(value.im.abs() < 1e-12);
+            let value = eval(ComplexSH::RegularSolid, &local2, &sub(&p, &c4));
+            assert!((value.re - expected).abs() < 1e-12, "{value} {expected}");
+        }
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Accuracy self-check against an extended precision reference
+//!
+//! [`max_error`] evaluates the real spherical harmonics of a [`HarmonicsSet`] in the float type
+//! `T` and compares them to a slow reference implementation in double-double arithmetic (about 32
+//! significant digits), which is independent of the float type of the set. The reference computes
+//! all angles directly from the Cartesian coordinates of the sample directions, such that both
+//! implementations see exactly the same input.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::basis::{HarmonicsSet, RealSH};
+use crate::sampling::fibonacci;
+use crate::{Coordinates, SHCoordinates, SphrsFloat};
+
+/// Maximum error of the real spherical harmonics of each degree up to `degree` in the float type
+/// `T`, sampled at `n_samples` directions of a Fibonacci lattice
+///
+/// The error of each degree l is the largest absolute error of the harmonics of that degree
+/// relative to their maximum value sqrt((2 l + 1) / (4 pi)). Entry l of the returned vector
+/// holds the error of degree l.
+///
+/// # Example
+///
+/// ```
+/// let errors = sphrs::verify::max_error::<f32>(20, 32);
+/// assert_eq!(errors.len(), 21);
+/// assert!(errors.iter().all(|&e| e < 1e-5));
+/// ```
+pub fn max_error<T: SphrsFloat>(degree: usize, n_samples: usize) -> Vec<f64> {
+    let set = HarmonicsSet::new(degree, RealSH::Spherical);
+    let mut errors = vec![0.0f64; degree + 1];
+    for q in fibonacci::<f64>(n_samples, 0) {
+        // Round the direction to T first, such that both implementations use the same input
+        let [x, y, z] = [q.x(), q.y(), q.z()].map(|v| T::from_f64(v).unwrap());
+        let values = set.eval(&Coordinates::cartesian(x, y, z));
+        let reference = reference_real_sh(
+            degree,
+            x.to_f64().unwrap(),
+            y.to_f64().unwrap(),
+            z.to_f64().unwrap(),
+        );
+        let mut i = 0;
+        for (l, error) in errors.iter_mut().enumerate() {
+            let max = ((2 * l + 1) as f64 / (4.0 * std::f64::consts::PI)).sqrt();
+            for _ in 0..(2 * l + 1) {
+                let diff = DoubleDouble::from(values[i].to_f64().unwrap()) - reference[i];
+                *error = error.max(diff.hi.abs() / max);
+                i += 1;
+            }
+        }
+    }
+    errors
+}
+
+/// Real spherical harmonics up to degree `degree` at the direction (x, y, z) in double-double
+/// arithmetic, ordered as the output of [`HarmonicsSet::eval`]
+fn reference_real_sh(degree: usize, x: f64, y: f64, z: f64) -> Vec<DoubleDouble> {
+    let (x, y, z) = (
+        DoubleDouble::from(x),
+        DoubleDouble::from(y),
+        DoubleDouble::from(z),
+    );
+    let zero = DoubleDouble::from(0.0);
+    let one = DoubleDouble::from(1.0);
+    let rho = (x * x + y * y).sqrt();
+    let r = (rho * rho + z * z).sqrt();
+    let (cos_theta, sin_theta) = (z / r, rho / r);
+    let (cos_phi, sin_phi) = if rho.hi == 0.0 {
+        (one, zero)
+    } else {
+        (x / rho, y / rho)
+    };
+    let f = |v: usize| DoubleDouble::from(v as f64);
+
+    // Orthonormalized associated Legendre functions, indexed by l (l + 1) / 2 + m
+    let mut p = vec![zero; (degree + 1) * (degree + 2) / 2];
+    let index = |l: usize, m: usize| l * (l + 1) / 2 + m;
+    p[0] = (one / (f(4) * DoubleDouble::PI)).sqrt();
+    for m in 0..=degree {
+        if m > 0 {
+            let c = -(f(2 * m + 1) / f(2 * m)).sqrt();
+            p[index(m, m)] = c * sin_theta * p[index(m - 1, m - 1)];
+        }
+        if m < degree {
+            p[index(m + 1, m)] = f(2 * m + 3).sqrt() * cos_theta * p[index(m, m)];
+        }
+        for l in (m + 2)..=degree {
+            let a = (f(4 * l * l - 1) / f(l * l - m * m)).sqrt();
+            let b = (f((l - 1) * (l - 1) - m * m) / f(4 * (l - 1) * (l - 1) - 1)).sqrt();
+            p[index(l, m)] = a * (cos_theta * p[index(l - 1, m)] - b * p[index(l - 2, m)]);
+        }
+    }
+
+    // cos(m phi) and sin(m phi) by repeated complex multiplication
+    let mut trig = vec![(one, zero)];
+    for m in 1..=degree {
+        let (c, s) = trig[m - 1];
+        trig.push((c * cos_phi - s * sin_phi, s * cos_phi + c * sin_phi));
+    }
+
+    let sqrt2 = f(2).sqrt();
+    let mut out = Vec::with_capacity((degree + 1) * (degree + 1));
+    for l in 0..=degree {
+        for m in -(l as i64)..=(l as i64) {
+            let am = m.unsigned_abs() as usize;
+            // The Condon-Shortley phase of p cancels with the (-1)^m of the real harmonics
+            let v = if am.is_multiple_of(2) {
+                p[index(l, am)]
+            } else {
+                -p[index(l, am)]
+            };
+            out.push(match m {
+                0 => v,
+                m if m > 0 => sqrt2 * trig[am].0 * v,
+                _ => sqrt2 * trig[am].1 * v,
+            });
+        }
+    }
+    out
+}
+
+/// Unevaluated sum `hi + lo` of two `f64` with |lo| <= ulp(hi) / 2
+#[derive(Clone, Copy, Debug)]
+struct DoubleDouble {
+    /// Leading part
+    hi: f64,
+    /// Trailing part
+    lo: f64,
+}
+
+impl DoubleDouble {
+    /// pi rounded to double-double precision
+    const PI: DoubleDouble = DoubleDouble {
+        hi: std::f64::consts::PI,
+        lo: 1.224_646_799_147_353_2e-16,
+    };
+
+    /// Exact sum of `a` and `b` with |a| >= |b|
+    fn quick_two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        DoubleDouble {
+            hi,
+            lo: b - (hi - a),
+        }
+    }
+
+    /// Exact sum of `a` and `b`
+    fn two_sum(a: f64, b: f64) -> (f64, f64) {
+        let s = a + b;
+        let bb = s - a;
+        (s, (a - (s - bb)) + (b - bb))
+    }
+
+    /// Square root
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 {
+            return DoubleDouble::from(0.0);
+        }
+        let q = DoubleDouble::from(self.hi.sqrt());
+        q + (self - q * q) / (q + q)
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(hi: f64) -> Self {
+        DoubleDouble { hi, lo: 0.0 }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (s, e) = DoubleDouble::two_sum(self.hi, other.hi);
+        let (t, f) = DoubleDouble::two_sum(self.lo, other.lo);
+        let s = DoubleDouble::quick_two_sum(s, e + t);
+        DoubleDouble::quick_two_sum(s.hi, s.lo + f)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let p = self.hi * other.hi;
+        let e = self.hi.mul_add(other.hi, -p);
+        DoubleDouble::quick_two_sum(p, e + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let q1 = self.hi / other.hi;
+        let r = self - other * DoubleDouble::from(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * DoubleDouble::from(q2);
+        let q3 = r.hi / other.hi;
+        DoubleDouble::quick_two_sum(q1, q2) + DoubleDouble::from(q3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_double_arithmetic() {
+        let third = DoubleDouble::from(1.0) / DoubleDouble::from(3.0);
+        let residual = DoubleDouble::from(1.0) - third * DoubleDouble::from(3.0);
+        assert!(residual.hi.abs() < 1e-31);
+        let sqrt2 = DoubleDouble::from(2.0).sqrt();
+        let residual = sqrt2 * sqrt2 - DoubleDouble::from(2.0);
+        assert!(residual.hi.abs() < 1e-31);
+        assert_eq!(sqrt2.hi, std::f64::consts::SQRT_2);
+    }
+
+    #[test]
+    fn errors_by_precision() {
+        let double = max_error::<f64>(30, 40);
+        let single = max_error::<f32>(30, 40);
+        assert_eq!(double.len(), 31);
+        for (l, (&d, &s)) in double.iter().zip(&single).enumerate() {
+            assert!(d < 1e-14, "{l} {d}");
+            assert!(s < 1e-5, "{l} {s}");
+            assert!(d < s, "{l}");
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Needlet (spherical wavelet) decomposition
+//!
+//! Needlets split a function on the sphere into scales j = 0, 1, ..., J by isotropic band-pass
+//! filters h_j(l) whose squares form a partition of unity, sum_j h_j(l)^2 = 1. Scale j covers
+//! the degrees B^(j - 2) < l < B^j for a base B > 1, such that the scales are localized both in
+//! frequency and, since the windows are smooth, in space. Scale 0 only contains the mean.
+//!
+//! Following Narcowich, Petrushev and Ward, the windows are h_j(l)^2 = phi(l / B^j) -
+//! phi(l / B^(j - 1)) with a smooth (C-infinity) function phi, which is 1 on [0, 1 / B] and 0 on
+//! [1, infinity). [`Needlets::analyze`] filters an expansion with each window and samples the
+//! result on the [`sht`](`crate::sht`) grid of the bandlimit of the scale, the needlet
+//! coefficients. [`Needlets::synthesize`] analyzes the maps, filters them again and adds the
+//! scales, which reconstructs the expansion exactly.
+//!
+//! # Example
+//!
+//! ```
+//! use sphrs::sht::Sampling;
+//! use sphrs::wavelet::Needlets;
+//! use sphrs::SHCoefficients;
+//!
+//! let coeffs = SHCoefficients::from_vec(3, (0..16).map(|i| (i as f64).sin()).collect());
+//! let needlets = Needlets::new(2.0, 4).unwrap();
+//! let maps = needlets.analyze(Sampling::GaussLegendre, &coeffs);
+//! assert_eq!(maps.len(), needlets.num_scales());
+//! let back = needlets.synthesize(Sampling::GaussLegendre, &maps).unwrap();
+//! for (a, b) in back.as_slice().iter().zip(coeffs.as_slice()) {
+//!     assert!((a - b).abs() < 1e-13);
+//! }
+//! ```
+//!
+//! # References
+//!
+//! * Narcowich, F. J., Petrushev, P. and Ward, J. D., "Localized tight frames on spheres", SIAM
+//!   Journal on Mathematical Analysis 38(2), 2006
+//! * Marinucci, D. et al., "Spherical needlets for cosmic microwave background data analysis",
+//!   Monthly Notices of the Royal Astronomical Society 383(2), 2008
+
+use crate::coefficients::num_coefficients;
+use crate::quadrature::gauss_legendre;
+use crate::sht::{forward, inverse, Sampling};
+use crate::spectral::filter_degrees;
+use crate::{SHCoefficients, SphrsError, SphrsFloat};
+
+/// Number of Gauss-Legendre nodes integrating the bump function of the windows
+const WINDOW_NODES: usize = 40;
+
+/// Smooth bump exp(-1 / (1 - u^2)) on (-1, 1)
+fn bump<T: SphrsFloat>(u: T) -> T {
+    let s = T::one() - u * u;
+    if s <= T::zero() {
+        T::zero()
+    } else {
+        (-T::one() / s).exp()
+    }
+}
+
+/// Integral of the bump over [-1, `u`]
+fn bump_integral<T: SphrsFloat>(u: T, rule: &(Vec<T>, Vec<T>)) -> T {
+    let half = (u + T::one()) / T::from_f64(2.0).unwrap();
+    rule.0.iter().zip(&rule.1).fold(T::zero(), |acc, (&x, &w)| {
+        acc + w * half * bump(-T::one() + half * (x + T::one()))
+    })
+}
+
+/// Axisymmetric needlet frame with base B up to a bandlimit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Needlets<T> {
+    /// Base B > 1 of the scales
+    base: T,
+    /// Bandlimit of the decomposed expansions
+    bandlimit: usize,
+    /// Squared windows h_j(l)^2, row-major per scale
+    windows: Vec<T>,
+}
+
+impl<T: SphrsFloat> Needlets<T> {
+    /// Create the needlets with base `base` for expansions with bandlimit `bandlimit`, i.e. up to
+    /// degree `bandlimit` - 1
+    ///
+    /// The number of scales is the smallest J + 1 with B^(J - 1) >= `bandlimit` - 1, such that
+    /// the windows add up to one for all degrees. Smaller bases give more scales with narrower
+    /// frequency bands and wider needlets; B = 2 is a common choice. Returns
+    /// [`SphrsError::InvalidInput`] if the base is not larger than one or the bandlimit is zero.
+    pub fn new(base: T, bandlimit: usize) -> Result<Self, SphrsError> {
+        if base.is_nan() || base <= T::one() {
+            return Err(SphrsError::InvalidInput(format!(
+                "needlet base must be larger than one, got {base:?}"
+            )));
+        }
+        if bandlimit == 0 {
+            return Err(SphrsError::InvalidInput(
+                "bandlimit must be positive".to_string(),
+            ));
+        }
+        let max_degree = T::from_usize(bandlimit - 1).unwrap();
+        let mut scales = 1;
+        while base.powi(scales as i32 - 2) < max_degree {
+            scales += 1;
+        }
+
+        let rule = gauss_legendre::<T>(WINDOW_NODES);
+        let total = bump_integral(T::one(), &rule);
+        let phi = |t: T| {
+            if t <= T::one() / base {
+                T::one()
+            } else if t >= T::one() {
+                T::zero()
+            } else {
+                let u = T::one()
+                    - T::from_f64(2.0).unwrap() * base * (t - T::one() / base) / (base - T::one());
+                bump_integral(u, &rule) / total
+            }
+        };
+        let mut windows = Vec::with_capacity(scales * bandlimit);
+        for j in 0..scales {
+            let scale = base.powi(j as i32);
+            for l in 0..bandlimit {
+                let l = T::from_usize(l).unwrap();
+                let lower = if j == 0 {
+                    T::zero()
+                } else {
+                    phi(l / (scale / base))
+                };
+                windows.push((phi(l / scale) - lower).max(T::zero()));
+            }
+        }
+        // The last scale takes the remainder, which is zero up to rounding
+        for l in 0..bandlimit {
+            let sum = (0..scales - 1).fold(T::zero(), |acc, j| acc + windows[j * bandlimit + l]);
+            windows[(scales - 1) * bandlimit + l] = (T::one() - sum).max(T::zero());
+        }
+        Ok(Needlets {
+            base,
+            bandlimit,
+            windows,
+        })
+    }
+
+    /// Returns the base B of the scales
+    pub fn base(&self) -> T {
+        self.base
+    }
+
+    /// Returns the bandlimit of the decomposed expansions
+    pub fn bandlimit(&self) -> usize {
+        self.bandlimit
+    }
+
+    /// Returns the number of scales J + 1
+    pub fn num_scales(&self) -> usize {
+        self.windows.len() / self.bandlimit
+    }
+
+    /// Window h_j(l) of scale `j` at degree `l`
+    ///
+    /// Panics if `j` is not a scale or `l` is not below the bandlimit.
+    pub fn window(&self, j: usize, l: usize) -> T {
+        assert!(j < self.num_scales() && l < self.bandlimit);
+        self.windows[j * self.bandlimit + l].sqrt()
+    }
+
+    /// Bandlimit of scale `j`, one more than the highest degree with a nonzero window
+    pub fn scale_bandlimit(&self, j: usize) -> usize {
+        let row = &self.windows[j * self.bandlimit..(j + 1) * self.bandlimit];
+        row.iter()
+            .rposition(|&w| w > T::zero())
+            .map_or(1, |l| l + 1)
+    }
+
+    /// Needlet coefficients of the expansion `coeffs`
+    ///
+    /// Returns one map per scale j with the samples of sum_lm h_j(l) c_lm Y_lm on the grid of
+    /// `sampling` for the bandlimit of the scale (see [`Sampling::shape`]). Degrees of `coeffs`
+    /// at or beyond the bandlimit of the needlets are ignored.
+    pub fn analyze(&self, sampling: Sampling, coeffs: &SHCoefficients<T>) -> Vec<Vec<T>> {
+        (0..self.num_scales())
+            .map(|j| {
+                let degree = self.scale_bandlimit(j) - 1;
+                let mut filtered = SHCoefficients::zeros(degree);
+                let n = num_coefficients(degree.min(coeffs.degree()));
+                filtered.as_mut_slice()[..n].copy_from_slice(&coeffs.as_slice()[..n]);
+                filter_degrees(&mut filtered, |l| self.window(j, l));
+                inverse(sampling, &filtered)
+            })
+            .collect()
+    }
+
+    /// Reconstruct an expansion from its needlet coefficients
+    ///
+    /// `maps` holds the maps of all scales as returned by [`Needlets::analyze`]. Returns the
+    /// coefficients up to degree `bandlimit` - 1, or [`SphrsError::InvalidInput`] if the number of
+    /// maps or the size of a map does not match.
+    pub fn synthesize(
+        &self,
+        sampling: Sampling,
+        maps: &[Vec<T>],
+    ) -> Result<SHCoefficients<T>, SphrsError> {
+        if maps.len() != self.num_scales() {
+            return Err(SphrsError::InvalidInput(format!(
+                "{} maps for {} scales",
+                maps.len(),
+                self.num_scales()
+            )));
+        }
+        let mut out = SHCoefficients::zeros(self.bandlimit - 1);
+        for (j, map) in maps.iter().enumerate() {
+            let mut scale = forward(sampling, self.scale_bandlimit(j), map)?;
+            filter_degrees(&mut scale, |l| self.window(j, l));
+            for (a, &b) in out.as_mut_slice().iter_mut().zip(scale.as_slice()) {
+                *a = *a + b;
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn partition_of_unity() {
+        for (base, bandlimit) in [(2.0f64, 40), (1.5, 17), (3.0, 2), (2.0, 1)] {
+            let needlets = Needlets::new(base, bandlimit).unwrap();
+            for l in 0..bandlimit {
+                let sum: f64 = (0..needlets.num_scales())
+                    .map(|j| needlets.window(j, l).powi(2))
+                    .sum();
+                assert_relative_eq!(sum, 1.0, epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn windows_are_band_pass() {
+        let base = 2.0f64;
+        let needlets = Needlets::new(base, 64).unwrap();
+        assert_eq!(needlets.num_scales(), 8);
+        assert_eq!(needlets.window(0, 0), 1.0);
+        for j in 1..needlets.num_scales() {
+            for l in 0..64 {
+                let lf = l as f64;
+                let w = needlets.window(j, l);
+                if lf <= base.powi(j as i32 - 2) || lf >= base.powi(j as i32) {
+                    assert_eq!(w, 0.0, "{j} {l}");
+                }
+            }
+            assert!(needlets.scale_bandlimit(j) <= base.powi(j as i32).ceil() as usize);
+        }
+        // Degree 2^(j - 1) lies in the flat part of the window of scale j
+        assert_relative_eq!(needlets.window(4, 8), 1.0);
+    }
+
+    #[test]
+    fn reconstruction() {
+        let bandlimit = 12;
+        let coeffs = SHCoefficients::from_vec(
+            bandlimit - 1,
+            (0..bandlimit * bandlimit)
+                .map(|i| ((i * 13) % 7) as f64 - 3.0)
+                .collect(),
+        );
+        let needlets = Needlets::new(1.7, bandlimit).unwrap();
+        for sampling in [
+            Sampling::DriscollHealy,
+            Sampling::GaussLegendre,
+            Sampling::McEwenWiaux,
+        ] {
+            let maps = needlets.analyze(sampling, &coeffs);
+            let back = needlets.synthesize(sampling, &maps).unwrap();
+            for (a, b) in back.as_slice().iter().zip(coeffs.as_slice()) {
+                assert_relative_eq!(a, b, epsilon = 1e-11);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(Needlets::new(1.0f64, 8).is_err());
+        assert!(Needlets::new(2.0f64, 0).is_err());
+        let needlets = Needlets::new(2.0f64, 8).unwrap();
+        assert!(matches!(
+            needlets.synthesize(Sampling::GaussLegendre, &[vec![0.0]]),
+            Err(SphrsError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Extended exponent arithmetic for associated Legendre functions of very high degree
+//!
+//! For degrees beyond a few thousand, the sectoral terms sin(theta)^m of the Legendre recurrences
+//! underflow the exponent range of `f64` long before the recurrence in the degree brings the
+//! values back to order one. X-numbers (Fukushima, "Numerical computation of spherical harmonics
+//! of arbitrary degree and order by extending exponent of floating point numbers", J. Geod.,
+//! 2012) store a value as `x * BIG^e` with an `f64` mantissa and an integer exponent in units of
+//! `BIG = 2^960`, which extends the range far enough for any practical degree.
+
+use crate::SphrsFloat;
+
+/// Base 2^960 of the exponent
+const BIG: f64 = f64::from_bits((1023 + 960) << 52);
+/// Inverse of the base
+const BIG_INV: f64 = f64::from_bits((1023 - 960) << 52);
+/// Square root of the base, the upper bound of normalized mantissas
+const BIG_SQRT: f64 = f64::from_bits((1023 + 480) << 52);
+/// Inverse square root of the base, the lower bound of normalized mantissas
+const BIG_SQRT_INV: f64 = f64::from_bits((1023 - 480) << 52);
+
+/// Number `x * BIG^e`
+#[derive(Clone, Copy, Debug)]
+struct XNumber {
+    /// Mantissa
+    x: f64,
+    /// Exponent in units of `BIG`
+    e: i32,
+}
+
+impl XNumber {
+    /// Create an X-number with exponent zero
+    fn new(x: f64) -> Self {
+        XNumber { x, e: 0 }
+    }
+
+    /// Bring the mantissa into `[BIG_SQRT_INV, BIG_SQRT)` (unless it is zero)
+    fn normalize(mut self) -> Self {
+        let w = self.x.abs();
+        if w >= BIG_SQRT {
+            self.x *= BIG_INV;
+            self.e += 1;
+        } else if w < BIG_SQRT_INV && w != 0.0 {
+            self.x *= BIG;
+            self.e -= 1;
+        }
+        self
+    }
+
+    /// Multiply by the `f64` `f`
+    fn scale(self, f: f64) -> Self {
+        XNumber {
+            x: self.x * f,
+            e: self.e,
+        }
+        .normalize()
+    }
+
+    /// Linear combination `f * a + g * b`
+    fn combine(f: f64, a: XNumber, g: f64, b: XNumber) -> Self {
+        let d = a.e - b.e;
+        let out = if d == 0 {
+            XNumber {
+                x: f * a.x + g * b.x,
+                e: a.e,
+            }
+        } else if d == 1 {
+            XNumber {
+                x: f * a.x + g * b.x * BIG_INV,
+                e: a.e,
+            }
+        } else if d == -1 {
+            XNumber {
+                x: f * a.x * BIG_INV + g * b.x,
+                e: b.e,
+            }
+        } else if d > 1 {
+            XNumber { x: f * a.x, e: a.e }
+        } else {
+            XNumber { x: g * b.x, e: b.e }
+        };
+        out.normalize()
+    }
+
+    /// Convert to `f64`, underflowing to zero and overflowing to infinity
+    fn to_f64(self) -> f64 {
+        match self.e {
+            0 => self.x,
+            -1 => self.x * BIG_INV,
+            e if e < -1 => 0.0,
+            _ => self.x * BIG,
+        }
+    }
+}
+
+/// Orthonormalized associated Legendre function K_lm P_l^m(x) (including the Condon-Shortley
+/// phase) for 0 <= m <= l, computed in X-number arithmetic
+///
+/// Same recurrences as [`normalized_legendre`](`crate::legendre::normalized_legendre`), with
+/// `x = cos(theta)` and `s = sin(theta)`. The recurrences are evaluated in `f64` regardless of
+/// `T`.
+pub(crate) fn normalized_legendre_x<T: SphrsFloat>(l: usize, m: usize, x: T, s: T) -> T {
+    debug_assert!(m <= l);
+    let x = x.to_f64().unwrap();
+    let s = s.to_f64().unwrap();
+    let f = |v: usize| v as f64;
+    let mut pmm = XNumber::new((1.0 / (4.0 * std::f64::consts::PI)).sqrt());
+    for k in 1..=m {
+        pmm = pmm.scale(-(f(2 * k + 1) / f(2 * k)).sqrt() * s);
+    }
+    if l == m {
+        return T::from_f64(pmm.to_f64()).unwrap();
+    }
+    let mut prev = pmm;
+    let mut cur = pmm.scale(f(2 * m + 3).sqrt() * x);
+    for ll in (m + 2)..=l {
+        let a = (f(4 * ll * ll - 1) / f(ll * ll - m * m)).sqrt();
+        let b = (f((ll - 1) * (ll - 1) - m * m) / f(4 * (ll - 1) * (ll - 1) - 1)).sqrt();
+        let next = XNumber::combine(a * x, cur, -a * b, prev);
+        prev = cur;
+        cur = next;
+    }
+    T::from_f64(cur.to_f64()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legendre::normalized_legendre;
+
+    #[test]
+    fn constants() {
+        assert_eq!(BIG, 2f64.powi(960));
+        assert_eq!(BIG_SQRT, 2f64.powi(480));
+        let mut x = XNumber::new(1.0);
+        for _ in 0..10 {
+            x = x.scale(1e-100);
+        }
+        assert!(x.e < -1);
+        assert_eq!(x.to_f64(), 0.0);
+        for _ in 0..10 {
+            x = x.scale(1e100);
+        }
+        assert!((x.to_f64() - 1.0).abs() < 1e-14);
+    }
+
+    #[test]
+    fn matches_f64_recurrence() {
+        for &theta in &[0.0f64, 0.4, 1.5, 2.9] {
+            let (x, s) = (theta.cos(), theta.sin());
+            for (l, m) in [(0, 0), (1, 1), (7, 3), (120, 0), (120, 77), (300, 300)] {
+                let expected = normalized_legendre(l, m, x, s);
+                let value = normalized_legendre_x(l, m, x, s);
+                assert!((value - expected).abs() < 1e-13, "{l} {m} {theta}");
+            }
+        }
+    }
+
+    #[test]
+    fn beyond_f64_exponent_range() {
+        // Reference value computed with mpmath. The sectoral term sin(0.3)^800 is about 1e-423.
+        let (x, s) = (0.3f64.cos(), 0.3f64.sin());
+        assert_eq!(normalized_legendre(3000, 800, x, s), 0.0);
+        let value = normalized_legendre_x(3000, 800, x, s);
+        assert!((value - 0.573_000_236_011_602).abs() < 1e-10, "{value}");
+    }
+}
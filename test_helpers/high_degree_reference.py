@@ -0,0 +1,33 @@
+"""
+Computes spherical harmonics of high degree with `spherharm` of `mpmath` in extended precision
+and prints them to the terminal as CSV (same columns and conventions as `scipy_comparison.py`).
+"""
+
+from math import pi
+import mpmath
+
+
+def main():
+    """
+    Computes a selection of high degree spherical harmonics for comparison
+    """
+    mpmath.mp.dps = 50
+    degrees = [11, 15, 20, 35, 50, 75, 100]
+    thetas = [0.0, 0.7, 2.0, 4.5]
+    phis = [0.0, 0.01, 0.4, pi / 3, pi / 2, 2.0, 3.0, pi]
+    print("n,m,theta,phi,sph_re,sph_im")
+    for n in degrees:
+        orders = sorted({-n, -n // 2, -3, -1, 0, 1, 2, n // 3, n - 1, n})
+        for m in orders:
+            for phi in phis:
+                for theta in thetas:
+                    res = mpmath.spherharm(n, m, phi, theta)
+                    print(
+                        "{},{},{},{},{},{}".format(
+                            n, m, theta, phi, float(res.real), float(res.imag)
+                        )
+                    )
+
+
+if __name__ == "__main__":
+    main()
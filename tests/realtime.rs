@@ -0,0 +1,108 @@
+// Copyright 2018-2023 Stefan Kroboth
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `HarmonicsSet::eval_realtime` does not allocate and rejects the backends it cannot serve
+//!
+//! The allocations are counted by a global allocator, which is why this test has its own binary.
+
+use sphrs::basis::{Backend, ComplexSH, HarmonicsSet, RealSH, SHEval};
+use sphrs::{Coordinates, SphrsError};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// System allocator counting the allocations of each thread
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations of the current thread in `f`
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn check<E: SHEval<f64>>(sh: HarmonicsSet<f64, E>)
+where
+    E::Output: Clone + Default,
+{
+    let mut out = vec![E::Output::default(); sh.num_sh()];
+    let points = [
+        Coordinates::spherical(1.3, 0.4, 2.2),
+        Coordinates::spherical(0.7, 0.0, 0.0),
+        Coordinates::cartesian(-0.2, 0.7, -0.4),
+        Coordinates::cartesian(0.0, 0.0, 0.0),
+    ];
+    for p in &points {
+        let n = allocations(|| sh.eval_realtime(p, &mut out).unwrap());
+        assert_eq!(n, 0);
+    }
+    let n = allocations(|| {
+        assert!(matches!(
+            sh.eval_realtime(&points[0], &mut out[1..]),
+            Err(SphrsError::LengthMismatch { .. })
+        ));
+    });
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn realtime_evaluation_does_not_allocate() {
+    check(HarmonicsSet::new(3, RealSH::Spherical));
+    check(HarmonicsSet::new(12, RealSH::RegularSolid));
+    check(HarmonicsSet::new(7, ComplexSH::Spherical));
+    check(
+        HarmonicsSet::builder()
+            .degree_range(2, 15)
+            .max_order(4)
+            .backend(Backend::Recursive)
+            .sh_type(RealSH::IrregularSolid)
+            .build()
+            .unwrap(),
+    );
+    check(HarmonicsSet::from_indices(&[(9, -3), (1, 0), (4, 4)], RealSH::Spherical).unwrap());
+}
+
+#[test]
+fn realtime_evaluation_rejects_other_backends() {
+    let backends = [
+        Backend::RecursiveF64,
+        Backend::Tabulated,
+        #[cfg(feature = "xnumber")]
+        Backend::Extended,
+    ];
+    for backend in backends {
+        let sh: HarmonicsSet<f64, RealSH> = HarmonicsSet::builder()
+            .degree(4)
+            .backend(backend)
+            .sh_type(RealSH::Spherical)
+            .build()
+            .unwrap();
+        let mut out = vec![0.0; sh.num_sh()];
+        let p = Coordinates::spherical(1.0, 0.4, 2.2);
+        assert!(matches!(
+            sh.eval_realtime(&p, &mut out),
+            Err(SphrsError::InvalidConfiguration(_))
+        ));
+    }
+}